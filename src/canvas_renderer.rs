@@ -1,10 +1,13 @@
-use std::{cell::RefCell, rc::Rc, sync::{Arc, Mutex}};
+use std::{cell::{Cell, RefCell}, rc::Rc, sync::{atomic::{AtomicBool, AtomicUsize, Ordering}, Arc, Mutex, Weak}};
 
-use log::info;
+use log::{info, warn};
+use std::time::Duration;
 use stunts_engine::{
+    animations::Sequence,
     camera::{Camera, CameraBinding},
     dot::RingDot,
     editor::{rgb_to_wgpu, ControlMode, Editor, Point, WebGpuResources, WindowSize, WindowSizeShader},
+    st_image::StImage,
     vertex::Vertex,
 };
 use wasm_bindgen::prelude::Closure;
@@ -12,21 +15,130 @@ use web_sys::{window, HtmlCanvasElement};
 use winit::{dpi::LogicalSize, event_loop, window::WindowBuilder};
 use leptos::{attr::label, wasm_bindgen::JsCast};
 use wgpu::{util::DeviceExt, StoreOp};
+use web_time::Instant;
+
+use crate::helpers::groups::SceneGroups;
 
 pub struct CanvasRenderer {
     pub editor: Arc<Mutex<Editor>>,
     pub gpu_resources: Arc<WebGpuResources>,
     pub render_pipeline: Arc<wgpu::RenderPipeline>,
     pub depth_view: Option<Arc<wgpu::TextureView>>,
-    pub multisampled_view: Option<Arc<wgpu::TextureView>>
+    pub multisampled_view: Option<Arc<wgpu::TextureView>>,
+    pub window_width: u32,
+    pub window_height: u32,
+    pub redraw: RedrawFlag,
+    /// rasterized gradient or image background, drawn before the scene
+    pub background: Arc<Mutex<Option<StImage>>>,
+    /// groups of the open sequence, drawn around their children
+    pub groups: Arc<Mutex<SceneGroups>>,
+    render_loop: Option<RenderLoop>,
+    // cancel flags of the captures handed out, teardown stops any still running
+    captures: Vec<Weak<AtomicBool>>,
+}
+
+/// Set by anything outside the render loop that changes what's on screen.
+/// The loop only redraws when this is set, or while something is animating.
+#[derive(Clone, Default, Debug)]
+pub struct RedrawFlag {
+    requested: Arc<AtomicBool>,
+    // exports in progress, the loop doesn't draw while any are
+    parked: Arc<AtomicUsize>,
+}
+
+impl RedrawFlag {
+    pub fn request(&self) {
+        self.requested.store(true, Ordering::Relaxed);
+    }
+
+    fn take(&self) -> bool {
+        !self.is_parked() && self.requested.swap(false, Ordering::Relaxed)
+    }
+
+    fn is_parked(&self) -> bool {
+        self.parked.load(Ordering::SeqCst) > 0
+    }
+
+    /// Keeps the render loop from drawing until the guard is dropped, so an export
+    /// has the editor's animation state and the shared buffers to itself
+    pub fn park(&self) -> ParkedRendering {
+        self.parked.fetch_add(1, Ordering::SeqCst);
+
+        ParkedRendering(self.clone())
+    }
+}
+
+pub struct ParkedRendering(RedrawFlag);
+
+impl Drop for ParkedRendering {
+    fn drop(&mut self) {
+        self.0.parked.fetch_sub(1, Ordering::SeqCst);
+        // the scene was left at an export time
+        self.0.request();
+    }
+}
+
+/// Puts the editor's playback back the way the export found it, see FrameCapture::begin
+pub struct CaptureSession {
+    editor: Arc<Mutex<Editor>>,
+    was_playing: bool,
+    start_playing_time: Option<Instant>,
+    // dropped after the editor is restored, so the redraw it asks for sees the restored scene
+    _parked: ParkedRendering,
+}
+
+impl Drop for CaptureSession {
+    fn drop(&mut self) {
+        let mut editor = self.editor.lock().unwrap();
+
+        editor.is_playing = self.was_playing;
+        editor.start_playing_time = self.start_playing_time;
+
+        if !self.was_playing {
+            // objects were left at the last exported time
+            editor.reset_sequence_objects();
+        }
+    }
+}
+
+// number of live render loops, more than one means a renderer was never torn down
+static ACTIVE_RENDER_LOOPS: AtomicUsize = AtomicUsize::new(0);
+
+pub fn active_render_loops() -> usize {
+    ACTIVE_RENDER_LOOPS.load(Ordering::SeqCst)
+}
+
+// handles kept around so the loop can be cancelled
+struct RenderLoop {
+    frame: Rc<RefCell<Option<Closure<dyn FnMut()>>>>,
+    frame_id: Rc<Cell<Option<i32>>>,
+    on_visibility_change: Closure<dyn FnMut()>,
+}
+
+/// Everything needed to render and read back frames outside the rAF loop.
+/// Cloned out of the renderer so exports don't hold the renderer lock across awaits.
+#[derive(Clone)]
+pub struct FrameCapture {
+    pub editor: Arc<Mutex<Editor>>,
+    pub gpu_resources: Arc<WebGpuResources>,
+    pub render_pipeline: Arc<wgpu::RenderPipeline>,
+    pub depth_view: Arc<wgpu::TextureView>,
+    pub multisampled_view: Arc<wgpu::TextureView>,
+    pub width: u32,
+    pub height: u32,
+    pub redraw: RedrawFlag,
+    pub background: Arc<Mutex<Option<StImage>>>,
+    pub groups: Arc<Mutex<SceneGroups>>,
+    /// set to stop the export at its next frame, by the user or by teardown
+    pub cancelled: Arc<AtomicBool>,
 }
 
 /// Call in this order:
-/// new(editor)
+/// new(editor, redraw)
 /// recreate_depth_view(window_width, window_height)
 /// begin_rendering()
 impl CanvasRenderer {
-    pub async fn new(editor_m: Arc<Mutex<Editor>>) -> CanvasRenderer {
+    pub async fn new(editor_m: Arc<Mutex<Editor>>, redraw: RedrawFlag) -> CanvasRenderer {
         println!("Initializing Canvas Renderer...");
 
         let window = web_sys::window().unwrap();
@@ -162,6 +274,26 @@ impl CanvasRenderer {
 
         let group_bind_group_layout = Arc::new(group_bind_group_layout);
 
+        // opacity of the group being drawn, every draw inherits the default one otherwise
+        let group_style_bind_group_layout =
+            gpu_resources
+                .device
+                .create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                    entries: &[wgpu::BindGroupLayoutEntry {
+                        binding: 0,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Uniform,
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    }],
+                    label: Some("group_style_bind_group_layout"),
+                });
+
+        let group_style_bind_group_layout = Arc::new(group_style_bind_group_layout);
+
         let window_size_buffer =
             gpu_resources
                 .device
@@ -219,6 +351,7 @@ impl CanvasRenderer {
                         &model_bind_group_layout,
                         &window_size_bind_group_layout,
                         &group_bind_group_layout,
+                        &group_style_bind_group_layout,
                     ], // No bind group layouts
                     push_constant_ranges: &[],
                 });
@@ -327,6 +460,8 @@ impl CanvasRenderer {
 
         editor.cursor_dot = Some(cursor_ring_dot);
 
+        let groups = SceneGroups::new(&gpu_resources.device, group_style_bind_group_layout);
+
         // gpu_helper.gpu_resources = Some(Arc::clone(&gpu_resources));
         editor.gpu_resources = Some(Arc::clone(&gpu_resources));
         editor.model_bind_group_layout = Some(model_bind_group_layout);
@@ -345,7 +480,14 @@ impl CanvasRenderer {
             gpu_resources,
             // gpu_helper
             depth_view: None,
-            multisampled_view: None
+            multisampled_view: None,
+            window_width: width,
+            window_height: height,
+            redraw,
+            background: Arc::new(Mutex::new(None)),
+            groups: Arc::new(Mutex::new(groups)),
+            render_loop: None,
+            captures: Vec::new(),
         }
     }
 
@@ -357,8 +499,6 @@ impl CanvasRenderer {
         window_width: u32,
         window_height: u32,
     ) {
-        let texture_format = wgpu::TextureFormat::Bgra8Unorm;
-
         // let config = wgpu::SurfaceConfiguration {
         //     usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
         //     format: texture_format,
@@ -377,110 +517,445 @@ impl CanvasRenderer {
 
         surface.configure(&self.gpu_resources.device, &config);
 
-        let multisampled_texture = self.gpu_resources
-            .device
-            .create_texture(&wgpu::TextureDescriptor {
-                size: wgpu::Extent3d {
-                    width: window_width,
-                    height: window_height,
-                    depth_or_array_layers: 1,
-                },
-                mip_level_count: 1,
-                sample_count: 4,
-                dimension: wgpu::TextureDimension::D2,
-                format: texture_format,
-                usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
-                label: Some("Multisampled render texture"),
-                view_formats: &[],
-            });
-
-        let multisampled_texture = Arc::new(multisampled_texture);
-
-        let multisampled_view =
-            multisampled_texture.create_view(&wgpu::TextureViewDescriptor::default());
-
-        let multisampled_view = Arc::new(multisampled_view);
-
-        let depth_texture = self.gpu_resources
-            .device
-            .create_texture(&wgpu::TextureDescriptor {
-                size: wgpu::Extent3d {
-                    width: window_width.clone(),
-                    height: window_height.clone(),
-                    depth_or_array_layers: 1,
-                },
-                mip_level_count: 1,
-                sample_count: 4, // used in a multisampled environment
-                dimension: wgpu::TextureDimension::D2,
-                format: wgpu::TextureFormat::Depth24Plus,
-                usage: wgpu::TextureUsages::RENDER_ATTACHMENT
-                    | wgpu::TextureUsages::TEXTURE_BINDING,
-                label: Some("Depth Texture"),
-                view_formats: &[],
-            });
-
-        let depth_view = depth_texture.create_view(&wgpu::TextureViewDescriptor::default());
-
-        let depth_view = Arc::new(depth_view);
+        let (multisampled_view, depth_view) =
+            create_target_views(&self.gpu_resources.device, window_width, window_height);
 
         self.depth_view = Some(depth_view);
         self.multisampled_view = Some(multisampled_view);
+        self.window_width = window_width;
+        self.window_height = window_height;
+
+        self.redraw.request();
 
         // (depth_texture, depth_view)
     }
 
-    pub fn begin_rendering(&self) {
+    pub fn begin_rendering(&mut self) {
+        // never run two loops for the same renderer
+        self.stop_rendering();
+
         let editor = self.editor.clone();
         let gpu_resources = self.gpu_resources.clone();
         let render_pipeline = self.render_pipeline.clone();
         let depth_view = self.depth_view.as_ref().expect("Couldn't get depth view").clone();
         let multisampled_view = self.multisampled_view.as_ref().expect("Couldn't get depth view").clone();
+        let redraw = self.redraw.clone();
+        let background = self.background.clone();
+        let groups = self.groups.clone();
+
+        let document = window().unwrap().document().unwrap();
 
         // web-based rendering loop
-        let f = Rc::new(RefCell::new(None));
-        let g = f.clone();
-
-        let closure = Closure::wrap(Box::new(move || {
-            // if !is_rendering_paused() {
-                // let device = device.clone();
-                // let state_guard = state.lock().unwrap();
-
-                render_frame(
-                    &editor,
-                    &gpu_resources,
-                    &render_pipeline,
-                    &depth_view,
-                    &multisampled_view
-                    // &camera_bind_group,
-                    // &camera_uniform_buffer,
-                );
+        let f: Rc<RefCell<Option<Closure<dyn FnMut()>>>> = Rc::new(RefCell::new(None));
+        let frame_id = Rc::new(Cell::new(None));
+
+        let closure = Closure::wrap(Box::new({
+            let f = f.clone();
+            let frame_id = frame_id.clone();
+            let redraw = redraw.clone();
+            let document = document.clone();
+
+            move || {
+                frame_id.set(None);
+
+                // park the loop while the tab is hidden, visibilitychange picks it back up
+                if document.hidden() {
+                    return;
+                }
 
-                // drop(state_guard);
-            // }
+                if redraw.take() {
+                    let animating = render_frame(
+                        &editor,
+                        &background,
+                        &groups,
+                        &gpu_resources,
+                        &render_pipeline,
+                        &depth_view,
+                        &multisampled_view
+                    );
+
+                    // playback and drags change the scene every frame
+                    if animating {
+                        redraw.request();
+                    }
+                }
+
+                // Schedule the next frame
+                frame_id.set(Some(request_animation_frame(f.borrow().as_ref().unwrap())));
+            }
+        }) as Box<dyn FnMut()>);
+
+        *f.borrow_mut() = Some(closure);
 
-            // Schedule the next frame
-            request_animation_frame(f.borrow().as_ref().unwrap());
+        let on_visibility_change = Closure::wrap(Box::new({
+            let f = f.clone();
+            let frame_id = frame_id.clone();
+            let document = document.clone();
+
+            move || {
+                if !document.hidden() && frame_id.get().is_none() {
+                    // anything that changed while hidden still needs drawing
+                    redraw.request();
+
+                    if let Some(closure) = f.borrow().as_ref() {
+                        frame_id.set(Some(request_animation_frame(closure)));
+                    }
+                }
+            }
         }) as Box<dyn FnMut()>);
 
-        *g.borrow_mut() = Some(closure);
+        document
+            .add_event_listener_with_callback(
+                "visibilitychange",
+                on_visibility_change.as_ref().unchecked_ref(),
+            )
+            .expect("Couldn't add visibilitychange listener");
+
+        self.redraw.request();
 
         // Start the rendering loop
-        request_animation_frame(g.borrow().as_ref().unwrap());
+        frame_id.set(Some(request_animation_frame(f.borrow().as_ref().unwrap())));
+
+        self.render_loop = Some(RenderLoop {
+            frame: f,
+            frame_id,
+            on_visibility_change,
+        });
+
+        ACTIVE_RENDER_LOOPS.fetch_add(1, Ordering::SeqCst);
+    }
+
+    /// Cancels the pending animation frame and drops the loop closure.
+    /// Rendering can be started again with begin_rendering()
+    pub fn stop_rendering(&mut self) {
+        let Some(render_loop) = self.render_loop.take() else {
+            return;
+        };
+
+        let window = window().unwrap();
+
+        if let Some(id) = render_loop.frame_id.take() {
+            window
+                .cancel_animation_frame(id)
+                .expect("Couldn't cancel animation frame");
+        }
+
+        window
+            .document()
+            .unwrap()
+            .remove_event_listener_with_callback(
+                "visibilitychange",
+                render_loop.on_visibility_change.as_ref().unchecked_ref(),
+            )
+            .expect("Couldn't remove visibilitychange listener");
+
+        // the frame closure holds a handle to itself, clear it to break the cycle
+        render_loop.frame.borrow_mut().take();
+
+        ACTIVE_RENDER_LOOPS.fetch_sub(1, Ordering::SeqCst);
+    }
+
+    pub fn is_rendering(&self) -> bool {
+        self.render_loop.is_some()
+    }
+
+    /// Picks up the groups of a sequence after it's opened or edited
+    pub fn set_groups(&self, sequence: &Sequence) {
+        let editor = self.editor.lock().unwrap();
+        self.groups.lock().unwrap().sync(&editor, sequence);
+
+        self.redraw.request();
+    }
+
+    /// Starts or stops previewing the current sequence, returns whether it's playing now
+    pub fn toggle_playback(&self) -> bool {
+        let mut editor = self.editor.lock().unwrap();
+
+        if editor.is_playing {
+            editor.is_playing = false;
+            editor.start_playing_time = None;
+            // put objects back where the sequence starts them
+            editor.reset_sequence_objects();
+        } else {
+            editor.start_playing_time = Some(Instant::now());
+            editor.is_playing = true;
+        }
+
+        self.redraw.request();
+
+        editor.is_playing
+    }
+
+    /// Stops the loop and frees the GPU device along with everything allocated on it.
+    /// The renderer and its editor can't draw again afterwards.
+    /// Running exports are cancelled, they share the device and the scene so both are
+    /// left for them to release once they stop.
+    pub fn teardown(&mut self) {
+        self.stop_rendering();
+
+        let running = self
+            .captures
+            .drain(..)
+            .filter_map(|cancelled| cancelled.upgrade())
+            .inspect(|cancelled| cancelled.store(true, Ordering::SeqCst))
+            .count();
+
+        if running > 0 {
+            warn!(
+                "{} exports still running, leaving the gpu device to them",
+                running
+            );
+            return;
+        }
+
+        {
+            // drop the scene's buffers and textures so only the (now empty) editor is left
+            let mut editor = self.editor.lock().unwrap();
+            editor.is_playing = false;
+            editor.polygons.clear();
+            editor.text_items.clear();
+            editor.image_items.clear();
+            editor.video_items.clear();
+            editor.motion_paths.clear();
+            editor.static_polygons.clear();
+            editor.gpu_resources = None;
+        }
+
+        self.background.lock().unwrap().take();
+        self.groups.lock().unwrap().clear();
+        self.depth_view = None;
+        self.multisampled_view = None;
+
+        self.gpu_resources.device.destroy();
+
+        // the surface keeps the canvas configured
+        match Arc::get_mut(&mut self.gpu_resources) {
+            Some(gpu_resources) => drop(gpu_resources.surface.take()),
+            None => warn!("Gpu resources still shared, keeping the surface"),
+        }
+    }
+
+    /// Hands out a capture that renders the scene at `width` x `height`.
+    /// The scene is laid out for the canvas, so keep the canvas aspect ratio.
+    pub fn frame_capture(&mut self, width: u32, height: u32) -> FrameCapture {
+        let (multisampled_view, depth_view) =
+            create_target_views(&self.gpu_resources.device, width, height);
+
+        let cancelled = Arc::new(AtomicBool::new(false));
+        self.captures.retain(|capture| capture.strong_count() > 0);
+        self.captures.push(Arc::downgrade(&cancelled));
+
+        FrameCapture {
+            editor: self.editor.clone(),
+            gpu_resources: self.gpu_resources.clone(),
+            render_pipeline: self.render_pipeline.clone(),
+            depth_view,
+            multisampled_view,
+            width,
+            height,
+            redraw: self.redraw.clone(),
+            background: self.background.clone(),
+            groups: self.groups.clone(),
+            cancelled,
+        }
+    }
+}
+
+impl Drop for CanvasRenderer {
+    fn drop(&mut self) {
+        self.stop_rendering();
+    }
+}
+
+impl FrameCapture {
+    /// Hold the returned session for as long as frames are being captured.
+    /// It parks the render loop and plays the editor, objects only upload their
+    /// stepped transforms while playing.
+    pub fn begin(&self) -> CaptureSession {
+        let parked = self.redraw.park();
+
+        let mut editor = self.editor.lock().unwrap();
+        let session = CaptureSession {
+            editor: self.editor.clone(),
+            was_playing: editor.is_playing,
+            start_playing_time: editor.start_playing_time.take(),
+            _parked: parked,
+        };
+        editor.is_playing = true;
+
+        session
+    }
+
+    /// Renders the scene at `current_time_s` into an offscreen texture and reads it back
+    /// as tightly packed RGBA rows (`width * height * 4` bytes).
+    /// Only call while a session from `begin()` is held.
+    pub async fn capture_frame(&self, current_time_s: f32) -> Result<Vec<u8>, String> {
+        let device = &self.gpu_resources.device;
+        let queue = &self.gpu_resources.queue;
+
+        let size = wgpu::Extent3d {
+            width: self.width,
+            height: self.height,
+            depth_or_array_layers: 1,
+        };
+
+        // must match the pipeline's target format
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            size,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::Bgra8Unorm,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+            label: Some("Export Frame Texture"),
+            view_formats: &[],
+        });
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+        // rows copied out of a texture must be padded to 256 bytes
+        let unpadded_bytes_per_row = self.width * 4;
+        let align = wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+        let padded_bytes_per_row = (unpadded_bytes_per_row + align - 1) / align * align;
+
+        let output_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Export Frame Buffer"),
+            size: (padded_bytes_per_row * self.height) as u64,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Export Encoder"),
+        });
+
+        {
+            let mut editor = self.editor.lock().unwrap();
+            let background = self.background.lock().unwrap();
+            let groups = self.groups.lock().unwrap();
+
+            encode_scene(
+                &mut editor,
+                background.as_ref(),
+                &groups,
+                &mut encoder,
+                &self.gpu_resources,
+                &self.render_pipeline,
+                &self.depth_view,
+                &self.multisampled_view,
+                &view,
+                Some(current_time_s),
+                RenderMode::Capture,
+            );
+        }
+
+        encoder.copy_texture_to_buffer(
+            wgpu::ImageCopyTexture {
+                texture: &texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d::ZERO,
+                aspect: wgpu::TextureAspect::All,
+            },
+            wgpu::ImageCopyBuffer {
+                buffer: &output_buffer,
+                layout: wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(padded_bytes_per_row),
+                    rows_per_image: Some(self.height),
+                },
+            },
+            size,
+        );
+
+        queue.submit(Some(encoder.finish()));
+
+        let buffer_slice = output_buffer.slice(..);
+        let (sender, receiver) = futures::channel::oneshot::channel();
+
+        buffer_slice.map_async(wgpu::MapMode::Read, move |result| {
+            let _ = sender.send(result);
+        });
+
+        // no-op on the web, the callback fires once the queue work is done
+        device.poll(wgpu::Maintain::Wait);
+
+        // a lost device drops the callback or fails the mapping
+        receiver
+            .await
+            .map_err(|_| "Couldn't receive frame mapping".to_string())?
+            .map_err(|e| format!("Couldn't map frame buffer: {}", e))?;
 
+        let mapped = buffer_slice.get_mapped_range();
 
+        let mut pixels = Vec::with_capacity((unpadded_bytes_per_row * self.height) as usize);
 
+        for row in mapped.chunks(padded_bytes_per_row as usize) {
+            // BGRA -> RGBA
+            for pixel in row[..unpadded_bytes_per_row as usize].chunks(4) {
+                pixels.extend_from_slice(&[pixel[2], pixel[1], pixel[0], pixel[3]]);
+            }
+        }
+
+        drop(mapped);
+        output_buffer.unmap();
+
+        // the editor was stepped to an export time, the live canvas is stale now
+        self.redraw.request();
+
+        Ok(pixels)
     }
 }
 
-fn request_animation_frame(f: &Closure<dyn FnMut()>) {
+// multisampled color and depth targets for the scene pass, the color format must match the pipeline's
+fn create_target_views(
+    device: &wgpu::Device,
+    width: u32,
+    height: u32,
+) -> (Arc<wgpu::TextureView>, Arc<wgpu::TextureView>) {
+    let size = wgpu::Extent3d {
+        width,
+        height,
+        depth_or_array_layers: 1,
+    };
+
+    let multisampled_texture = device.create_texture(&wgpu::TextureDescriptor {
+        size,
+        mip_level_count: 1,
+        sample_count: 4,
+        dimension: wgpu::TextureDimension::D2,
+        format: wgpu::TextureFormat::Bgra8Unorm,
+        usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+        label: Some("Multisampled render texture"),
+        view_formats: &[],
+    });
+
+    let depth_texture = device.create_texture(&wgpu::TextureDescriptor {
+        size,
+        mip_level_count: 1,
+        sample_count: 4, // used in a multisampled environment
+        dimension: wgpu::TextureDimension::D2,
+        format: wgpu::TextureFormat::Depth24Plus,
+        usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+        label: Some("Depth Texture"),
+        view_formats: &[],
+    });
+
+    (
+        Arc::new(multisampled_texture.create_view(&wgpu::TextureViewDescriptor::default())),
+        Arc::new(depth_texture.create_view(&wgpu::TextureViewDescriptor::default())),
+    )
+}
+
+fn request_animation_frame(f: &Closure<dyn FnMut()>) -> i32 {
     window()
         .unwrap()
         .request_animation_frame(f.as_ref().unchecked_ref())
-        .expect("should register `requestAnimationFrame` OK");
+        .expect("should register `requestAnimationFrame` OK")
 }
 
 fn render_frame(
     editor: &Arc<Mutex<Editor>>,
+    background: &Arc<Mutex<Option<StImage>>>,
+    groups: &Arc<Mutex<SceneGroups>>,
     // surface: &wgpu::Surface,
     // device: &wgpu::Device,
     // queue: &wgpu::Queue,
@@ -490,10 +965,10 @@ fn render_frame(
     multisampled_view: &Arc<wgpu::TextureView>,
     // camera_bind_group: &wgpu::BindGroup,
     // camera_uniform_buffer: &wgpu::Buffer,
-) {
+) -> bool {
     let mut editor  = editor.lock().unwrap();
-
-    let camera = editor.camera.expect("Couldn't get camera");
+    let background = background.lock().unwrap();
+    let groups = groups.lock().unwrap();
 
     let surface = &gpu_resources.surface.as_ref().expect("Couldn't get surface");
     let device = &gpu_resources.device;
@@ -511,6 +986,121 @@ fn render_frame(
         label: Some("Render Encoder"),
     });
 
+    encode_scene(
+        &mut editor,
+        background.as_ref(),
+        &groups,
+        &mut encoder,
+        gpu_resources,
+        render_pipeline,
+        depth_view,
+        multisampled_view,
+        &view,
+        None,
+        RenderMode::Editor,
+    );
+
+    queue.submit(Some(encoder.finish()));
+    device.poll(wgpu::Maintain::Poll);
+    frame.present();
+
+    // true while the scene keeps changing without outside input
+    editor.is_playing
+        || editor.is_panning
+        || editor.dragging_polygon.is_some()
+        || editor.dragging_text.is_some()
+        || editor.dragging_image.is_some()
+        || editor.dragging_video.is_some()
+        || editor.dragging_path.is_some()
+        || editor.dragging_path_handle.is_some()
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum RenderMode {
+    /// the live canvas, with motion paths, handles and the cursor dot
+    Editor,
+    /// exported frames, only what the sequence shows
+    Capture,
+}
+
+/// Records the scene render pass, resolving into `view`.
+/// `provided_current_time_s` pins animations to a sequence time (used by exports),
+/// otherwise the editor's own playback clock is used.
+fn encode_scene(
+    editor: &mut Editor,
+    background: Option<&StImage>,
+    groups: &SceneGroups,
+    encoder: &mut wgpu::CommandEncoder,
+    gpu_resources: &Arc<WebGpuResources>,
+    render_pipeline: &Arc<wgpu::RenderPipeline>,
+    depth_view: &Arc<wgpu::TextureView>,
+    multisampled_view: &Arc<wgpu::TextureView>,
+    view: &wgpu::TextureView,
+    provided_current_time_s: Option<f32>,
+    mode: RenderMode,
+) {
+    let camera = editor.camera.expect("Couldn't get camera");
+
+    // groups follow the same clock as the motion paths
+    let group_time = match provided_current_time_s {
+        Some(current_time_s) => Some(current_time_s),
+        None if editor.is_playing => editor
+            .start_playing_time
+            .map(|start| start.elapsed().as_secs_f32()),
+        None => None,
+    };
+    groups.update(&gpu_resources.queue, group_time.map(Duration::from_secs_f32));
+
+    // gradient and image backgrounds get their own pass so the scene's depth can't bury them
+    if let Some(background) = background {
+        let mut background_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Stunts Web Background Pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: &multisampled_view,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(wgpu::Color::WHITE),
+                    store: StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                view: &depth_view,
+                depth_ops: Some(wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(1.0),
+                    store: StoreOp::Discard,
+                }),
+                stencil_ops: None,
+            }),
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+
+        let camera_binding = editor
+            .camera_binding
+            .as_ref()
+            .expect("Couldn't get camera binding");
+
+        background_pass.set_pipeline(&render_pipeline);
+        background_pass.set_bind_group(0, &camera_binding.bind_group, &[]);
+        background_pass.set_bind_group(
+            2,
+            editor
+                .window_size_bind_group
+                .as_ref()
+                .expect("Couldn't get window size group"),
+            &[],
+        );
+        background_pass.set_bind_group(1, &background.bind_group, &[]);
+        background_pass.set_bind_group(3, &background.group_bind_group, &[]);
+        background_pass.set_bind_group(4, groups.default_style(), &[]);
+        background_pass.set_vertex_buffer(0, background.vertex_buffer.slice(..));
+        background_pass.set_index_buffer(
+            background.index_buffer.slice(..),
+            wgpu::IndexFormat::Uint32,
+        );
+        background_pass.draw_indexed(0..background.indices.len() as u32, 0, 0..1);
+    }
+
     {
         let color = wgpu::Color {
             r: 0.1,
@@ -545,9 +1135,13 @@ fn render_frame(
             label: Some("Stunts Web Render Pass"),
             color_attachments: &[Some(wgpu::RenderPassColorAttachment {
                 view: &multisampled_view,       // Use the multisampled view here
-                resolve_target: Some(&view), // Resolve to the swapchain texture
+                resolve_target: Some(view), // Resolve to the swapchain (or export) texture
                 ops: wgpu::Operations {
-                    load: wgpu::LoadOp::Clear(wgpu::Color::WHITE),
+                    // keep the background pass output if there was one
+                    load: match background {
+                        Some(_) => wgpu::LoadOp::Load,
+                        None => wgpu::LoadOp::Clear(wgpu::Color::WHITE),
+                    },
                     // store: StoreOp::Store,
                     // load: wgpu::LoadOp::Load,
                     // store: wgpu::StoreOp::Store,
@@ -572,8 +1166,8 @@ fn render_frame(
         render_pass.set_pipeline(&render_pipeline);
 
         // draw objects
-        editor.step_video_animations(&camera, None);
-        editor.step_motion_path_animations(&camera, None);
+        editor.step_video_animations(&camera, provided_current_time_s);
+        editor.step_motion_path_animations(&camera, provided_current_time_s);
 
         let camera_binding = editor
             .camera_binding
@@ -589,9 +1183,20 @@ fn render_frame(
                 .expect("Couldn't get window size group"),
             &[],
         );
+        render_pass.set_bind_group(4, groups.default_style(), &[]);
+
+        // editor chrome, exports leave it out
+        let static_polygons = match mode {
+            RenderMode::Editor => &editor.static_polygons[..],
+            RenderMode::Capture => &[],
+        };
+        let motion_paths = match mode {
+            RenderMode::Editor => &editor.motion_paths[..],
+            RenderMode::Capture => &[],
+        };
 
         // draw static (internal) polygons
-        for (poly_index, polygon) in editor.static_polygons.iter().enumerate() {
+        for (poly_index, polygon) in static_polygons.iter().enumerate() {
             // uniform buffers are pricier, no reason to over-update when idle
             if let Some(dragging_id) = editor.dragging_path_handle {
                 if dragging_id == polygon.id {
@@ -612,7 +1217,7 @@ fn render_frame(
         }
 
         // draw motion path static polygons, using motion path transform
-        for (path_index, path) in editor.motion_paths.iter().enumerate() {
+        for (path_index, path) in motion_paths.iter().enumerate() {
             // uniform buffers are pricier, no reason to over-update when idle
             if let Some(dragging_id) = editor.dragging_path {
                 if dragging_id == path.id {
@@ -663,8 +1268,12 @@ fn render_frame(
                         .update_uniform_buffer(&gpu_resources.queue, &camera.window_size);
                 }
 
+                let (group, style) =
+                    groups.bindings_for(&polygon.id.to_string(), &polygon.group_bind_group);
+
                 render_pass.set_bind_group(1, &polygon.bind_group, &[]);
-                render_pass.set_bind_group(3, &polygon.group_bind_group, &[]);
+                render_pass.set_bind_group(3, group, &[]);
+                render_pass.set_bind_group(4, style, &[]);
                 render_pass.set_vertex_buffer(0, polygon.vertex_buffer.slice(..));
                 render_pass.set_index_buffer(
                     polygon.index_buffer.slice(..),
@@ -677,6 +1286,8 @@ fn render_frame(
         // draw text items
         for (text_index, text_item) in editor.text_items.iter().enumerate() {
             if !text_item.hidden {
+                let text_id = text_item.id.to_string();
+
                 if !text_item.background_polygon.hidden {
                     // uniform buffers are pricier, no reason to over-update when idle
                     // also need to remember to update uniform buffers after changes like scale, rotation, position
@@ -706,11 +1317,11 @@ fn render_frame(
                         &text_item.background_polygon.bind_group,
                         &[],
                     );
-                    render_pass.set_bind_group(
-                        3,
-                        &text_item.background_polygon.group_bind_group,
-                        &[],
-                    );
+                    let (group, style) = groups
+                        .bindings_for(&text_id, &text_item.background_polygon.group_bind_group);
+
+                    render_pass.set_bind_group(3, group, &[]);
+                    render_pass.set_bind_group(4, style, &[]);
                     render_pass.set_vertex_buffer(
                         0,
                         text_item.background_polygon.vertex_buffer.slice(..),
@@ -741,8 +1352,11 @@ fn render_frame(
                         .update_uniform_buffer(&gpu_resources.queue, &camera.window_size);
                 }
 
+                let (group, style) = groups.bindings_for(&text_id, &text_item.group_bind_group);
+
                 render_pass.set_bind_group(1, &text_item.bind_group, &[]);
-                render_pass.set_bind_group(3, &text_item.group_bind_group, &[]);
+                render_pass.set_bind_group(3, group, &[]);
+                render_pass.set_bind_group(4, style, &[]);
                 render_pass.set_vertex_buffer(0, text_item.vertex_buffer.slice(..));
                 render_pass.set_index_buffer(
                     text_item.index_buffer.slice(..),
@@ -770,8 +1384,12 @@ fn render_frame(
                         .update_uniform_buffer(&gpu_resources.queue, &camera.window_size);
                 }
 
+                let (group, style) =
+                    groups.bindings_for(&st_image.id.to_string(), &st_image.group_bind_group);
+
                 render_pass.set_bind_group(1, &st_image.bind_group, &[]);
-                render_pass.set_bind_group(3, &st_image.group_bind_group, &[]);
+                render_pass.set_bind_group(3, group, &[]);
+                render_pass.set_bind_group(4, style, &[]);
                 render_pass.set_vertex_buffer(0, st_image.vertex_buffer.slice(..));
                 render_pass.set_index_buffer(
                     st_image.index_buffer.slice(..),
@@ -799,8 +1417,12 @@ fn render_frame(
                         .update_uniform_buffer(&gpu_resources.queue, &camera.window_size);
                 }
 
+                let (group, style) =
+                    groups.bindings_for(&st_video.id.to_string(), &st_video.group_bind_group);
+
                 render_pass.set_bind_group(1, &st_video.bind_group, &[]);
-                render_pass.set_bind_group(3, &st_video.group_bind_group, &[]);
+                render_pass.set_bind_group(3, group, &[]);
+                render_pass.set_bind_group(4, style, &[]);
                 render_pass.set_vertex_buffer(0, st_video.vertex_buffer.slice(..));
                 render_pass.set_index_buffer(
                     st_video.index_buffer.slice(..),
@@ -810,11 +1432,12 @@ fn render_frame(
             }
         }
 
-        if let Some(dot) = &editor.cursor_dot {
+        if let (RenderMode::Editor, Some(dot)) = (mode, &editor.cursor_dot) {
             dot.transform
                 .update_uniform_buffer(&gpu_resources.queue, &camera.window_size);
             render_pass.set_bind_group(1, &dot.bind_group, &[]);
             render_pass.set_bind_group(3, &dot.group_bind_group, &[]);
+            render_pass.set_bind_group(4, groups.default_style(), &[]);
             render_pass.set_vertex_buffer(0, dot.vertex_buffer.slice(..));
             render_pass
                 .set_index_buffer(dot.index_buffer.slice(..), wgpu::IndexFormat::Uint32);
@@ -826,8 +1449,175 @@ fn render_frame(
             editor.update_camera_binding();
         }
     }
+}
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use stunts_engine::animations::{
+        AnimationData, AnimationProperty, EasingType, KeyType, KeyframeValue, ObjectType,
+        UIKeyframe,
+    };
+    use stunts_engine::editor::{init_editor_with_model, PathType, Viewport};
+    use stunts_engine::polygon::{SavedPoint, SavedPolygonConfig, SavedStroke};
+    use uuid::Uuid;
+    use wasm_bindgen_test::*;
+
+    use super::*;
+    use crate::helpers::media::restore_sequence_objects;
+
+    // needs a browser with WebGPU, e.g. `wasm-pack test --chrome`
+    wasm_bindgen_test_configure!(run_in_browser);
+
+    fn position_keyframe(time_ms: u64, position: [i32; 2]) -> UIKeyframe {
+        UIKeyframe {
+            id: Uuid::new_v4().to_string(),
+            time: Duration::from_millis(time_ms),
+            value: KeyframeValue::Position(position),
+            easing: EasingType::EaseInOut,
+            path_type: PathType::Linear,
+            key_type: KeyType::Frame,
+        }
+    }
 
-    queue.submit(Some(encoder.finish()));
-    device.poll(wgpu::Maintain::Poll);
-    frame.present();
-}
\ No newline at end of file
+    // one square sliding across the canvas over a second
+    fn moving_square() -> Sequence {
+        let polygon_id = Uuid::new_v4().to_string();
+
+        Sequence {
+            id: Uuid::new_v4().to_string(),
+            name: "Sequence".to_string(),
+            background_fill: None,
+            duration_ms: 1000,
+            active_polygons: vec![SavedPolygonConfig {
+                id: polygon_id.clone(),
+                name: "Square".to_string(),
+                points: Vec::new(),
+                rotation: 0,
+                shape: None,
+                dimensions: (100, 100),
+                fill: [0, 0, 0, 255],
+                border_radius: 0,
+                position: SavedPoint { x: 100, y: 100 },
+                stroke: SavedStroke {
+                    thickness: 2,
+                    fill: [0, 0, 0, 255],
+                },
+                layer: -2,
+            }],
+            polygon_motion_paths: vec![AnimationData {
+                id: Uuid::new_v4().to_string(),
+                object_type: ObjectType::Polygon,
+                polygon_id,
+                duration: Duration::from_secs(1),
+                start_time_ms: 0,
+                position: [0, 0],
+                properties: vec![AnimationProperty {
+                    name: "Position".to_string(),
+                    property_path: "position".to_string(),
+                    children: Vec::new(),
+                    keyframes: vec![
+                        position_keyframe(0, [100, 100]),
+                        position_keyframe(1000, [600, 300]),
+                    ],
+                    depth: 0,
+                }],
+            }],
+            active_text_items: Vec::new(),
+            active_image_items: Vec::new(),
+            active_video_items: Vec::new(),
+            groups: Vec::new(),
+        }
+    }
+
+    async fn renderer_with(sequence: &Sequence) -> CanvasRenderer {
+        let document = window().unwrap().document().unwrap();
+
+        if document.get_element_by_id("scene-canvas").is_none() {
+            let canvas = document.create_element("canvas").unwrap();
+            canvas.set_id("scene-canvas");
+            canvas.set_attribute("width", "900").unwrap();
+            canvas.set_attribute("height", "450").unwrap();
+            document.body().unwrap().append_child(&canvas).unwrap();
+        }
+
+        let viewport = Arc::new(Mutex::new(Viewport::new(900.0, 450.0)));
+        let editor = Arc::new(Mutex::new(init_editor_with_model(viewport)));
+
+        let mut renderer = CanvasRenderer::new(editor.clone(), RedrawFlag::default()).await;
+        renderer.recreate_depth_view(900, 450);
+
+        let warnings = restore_sequence_objects(
+            editor.clone(),
+            vec![sequence.clone()],
+            false,
+            String::new(),
+            |_| {},
+        )
+        .await;
+        assert!(warnings.is_empty(), "{:?}", warnings);
+
+        editor.lock().unwrap().current_sequence_data = Some(sequence.clone());
+
+        renderer
+    }
+
+    #[wasm_bindgen_test]
+    async fn captured_frames_follow_the_sequence_time() {
+        let mut renderer = renderer_with(&moving_square()).await;
+        let capture = renderer.frame_capture(900, 450);
+
+        {
+            let _session = capture.begin();
+
+            let start = capture
+                .capture_frame(0.0)
+                .await
+                .expect("Couldn't capture start");
+            let end = capture
+                .capture_frame(1.0)
+                .await
+                .expect("Couldn't capture end");
+
+            assert_eq!(start.len(), 900 * 450 * 4);
+            assert_ne!(start, end, "The square didn't move between frames");
+        }
+
+        // the session hands playback back once the export is done
+        assert!(!renderer.editor.lock().unwrap().is_playing);
+
+        renderer.teardown();
+    }
+
+    #[wasm_bindgen_test]
+    async fn captures_render_at_their_own_size() {
+        let mut renderer = renderer_with(&moving_square()).await;
+        let capture = renderer.frame_capture(1800, 900);
+
+        {
+            let _session = capture.begin();
+
+            let frame = capture
+                .capture_frame(0.0)
+                .await
+                .expect("Couldn't capture frame");
+
+            assert_eq!(frame.len(), 1800 * 900 * 4);
+        }
+
+        renderer.teardown();
+    }
+
+    #[wasm_bindgen_test]
+    async fn teardown_cancels_exports_and_leaves_them_the_device() {
+        let mut renderer = renderer_with(&moving_square()).await;
+        let capture = renderer.frame_capture(900, 450);
+        let _session = capture.begin();
+
+        renderer.teardown();
+
+        assert!(capture.cancelled.load(Ordering::SeqCst));
+        // the frame it was rendering still finishes
+        assert!(capture.capture_frame(0.0).await.is_ok());
+    }
+}
@@ -0,0 +1,201 @@
+use leptos::prelude::*;
+use reactive_stores::Store;
+use std::sync::{Arc, Mutex};
+use stunts_engine::animations::ObjectType;
+use uuid::Uuid;
+
+use crate::canvas_renderer::CanvasRenderer;
+use crate::editor_state::EditorState;
+use crate::helpers::alignment::{
+    align_translations, distribute_translations, Alignment, Distribution, SnapSettings,
+};
+use crate::helpers::object_transform::object_bounds;
+use crate::helpers::utilities::{SavedState, SavedStateStoreFields};
+
+#[derive(Clone, Copy)]
+enum Arrangement {
+    Align(Alignment),
+    Distribute(Distribution),
+}
+
+/// Aligns and distributes the selection, and sets how dragging snaps.
+/// A single selected object aligns to the canvas.
+#[component]
+pub fn ArrangePanel(
+    renderer: LocalResource<(Arc<Mutex<CanvasRenderer>>, Arc<Mutex<EditorState>>)>,
+    sequence_id: String,
+    selection: RwSignal<Vec<(Uuid, ObjectType)>>,
+    snapping: RwSignal<SnapSettings>,
+) -> impl IntoView {
+    let state = expect_context::<Store<SavedState>>();
+    let sequences = state.sequences();
+
+    let (arrange_error, set_arrange_error) = signal(None::<String>);
+
+    // moves the selected objects into place as one undo step
+    let arrange = Callback::new(move |arrangement: Arrangement| {
+        let renderer = renderer.get_untracked().expect("Couldn't get renderer");
+        let (canvas_renderer, editor_state) = renderer.take();
+        let canvas_renderer = canvas_renderer.lock().unwrap();
+        let editor_m = canvas_renderer.editor.clone();
+
+        let objects = {
+            let editor = editor_m.lock().unwrap();
+
+            selection
+                .get_untracked()
+                .iter()
+                .filter_map(|(object_id, _)| object_bounds(&editor, *object_id))
+                .collect::<Vec<_>>()
+        };
+
+        let translations = match arrangement {
+            Arrangement::Align(alignment) => align_translations(&objects, alignment),
+            Arrangement::Distribute(distribution) => {
+                distribute_translations(&objects, distribution)
+            }
+        };
+
+        let transforms = objects
+            .iter()
+            .zip(translations)
+            .filter(|(_, translation)| *translation != (0, 0))
+            .map(|(object, translation)| {
+                (
+                    object.id,
+                    object.object_type.clone(),
+                    translation,
+                    object.dimensions,
+                )
+            })
+            .collect::<Vec<_>>();
+
+        if transforms.is_empty() {
+            return;
+        }
+
+        let mut editor_state = editor_state.lock().unwrap();
+
+        if let Err(e) = editor_state.transform_objects(sequence_id.clone(), transforms) {
+            set_arrange_error.set(Some(e));
+            return;
+        }
+        set_arrange_error.set(None);
+
+        let saved_state = editor_state
+            .record_state
+            .saved_state
+            .as_ref()
+            .expect("Couldn't get saved state");
+
+        sequences.set(saved_state.sequences.clone());
+        selection.set(editor_state.selected_objects.clone());
+
+        drop(editor_state);
+
+        canvas_renderer.redraw.request();
+    });
+
+    let count = move || selection.with(|s| s.len());
+
+    view! {
+        <div class="flex flex-col w-full gap-2">
+            <h5>"Arrange"</h5>
+            <div class="grid grid-cols-3 gap-1">
+                {Alignment::ALL
+                    .into_iter()
+                    .map(|alignment| {
+                        view! {
+                            <button
+                                class="text-xs rounded-md border px-2 py-1 hover:bg-gray-200
+                                disabled:opacity-50 disabled:cursor-not-allowed"
+                                disabled=move || count() == 0
+                                on:click=move |_| arrange.run(Arrangement::Align(alignment))
+                            >
+                                {alignment.label()}
+                            </button>
+                        }
+                    })
+                    .collect_view()}
+            </div>
+            <div class="flex flex-row gap-1">
+                {[
+                    (Distribution::Horizontal, "Distribute horizontally"),
+                    (Distribution::Vertical, "Distribute vertically"),
+                ]
+                    .into_iter()
+                    .map(|(distribution, label)| {
+                        view! {
+                            <button
+                                class="text-xs rounded-md border px-2 py-1 hover:bg-gray-200
+                                disabled:opacity-50 disabled:cursor-not-allowed"
+                                disabled=move || count() < 3
+                                on:click=move |_| {
+                                    arrange.run(Arrangement::Distribute(distribution))
+                                }
+                            >
+                                {label}
+                            </button>
+                        }
+                    })
+                    .collect_view()}
+            </div>
+            <span class="text-xs">"Snapping (hold Alt while dragging to skip)"</span>
+            <div class="flex flex-row flex-wrap items-center gap-2">
+                <input
+                    type="checkbox"
+                    id="snap_objects"
+                    name="snap_objects"
+                    on:change=move |ev| {
+                        let objects = event_target_checked(&ev);
+                        snapping.update(|s| s.objects = objects);
+                    }
+                    prop:checked=move || snapping.get().objects
+                />
+                <label for="snap_objects" class="text-xs">
+                    "Objects"
+                </label>
+                <input
+                    type="checkbox"
+                    id="snap_canvas_center"
+                    name="snap_canvas_center"
+                    on:change=move |ev| {
+                        let canvas_center = event_target_checked(&ev);
+                        snapping.update(|s| s.canvas_center = canvas_center);
+                    }
+                    prop:checked=move || snapping.get().canvas_center
+                />
+                <label for="snap_canvas_center" class="text-xs">
+                    "Canvas center"
+                </label>
+                <input
+                    type="checkbox"
+                    id="snap_grid"
+                    name="snap_grid"
+                    on:change=move |ev| {
+                        let grid = event_target_checked(&ev);
+                        snapping.update(|s| s.grid = grid);
+                    }
+                    prop:checked=move || snapping.get().grid
+                />
+                <label for="snap_grid" class="text-xs">
+                    "Grid"
+                </label>
+                <input
+                    type="number"
+                    class="w-16 text-xs border rounded px-1"
+                    min="1"
+                    disabled=move || !snapping.get().grid
+                    on:change=move |ev| {
+                        let grid_size = event_target_value(&ev).parse::<u32>().unwrap_or(20).max(1);
+                        snapping.update(|s| s.grid_size = grid_size);
+                    }
+                    prop:value=move || snapping.get().grid_size.to_string()
+                />
+            </div>
+            {move || {
+                arrange_error.get().map(|e| view! { <span class="text-xs text-red-500">{e}</span> })
+            }}
+        </div>
+    }
+}
@@ -0,0 +1,250 @@
+use codee::string::JsonSerdeCodec;
+use leptos::prelude::*;
+use leptos_use::storage::use_local_storage;
+use reactive_stores::Store;
+use std::sync::{Arc, Mutex};
+use wasm_bindgen_futures::spawn_local;
+
+use crate::canvas_renderer::CanvasRenderer;
+use crate::editor_state::EditorState;
+use crate::fetchers::media::fetch_media_data;
+use crate::helpers::auto_zoom::{generate_zoom_keyframes, AutoZoomSettings};
+use crate::helpers::media::capture_file_path;
+use crate::helpers::users::AuthToken;
+use crate::helpers::utilities::{SavedState, SavedStateStoreFields};
+
+/// Regenerates the zoom track of screen captures in the sequence.
+/// The generated keyframes are regular keyframes and can be edited afterwards.
+#[component]
+pub fn AutoZoomPanel(
+    renderer: LocalResource<(Arc<Mutex<CanvasRenderer>>, Arc<Mutex<EditorState>>)>,
+    sequence_id: String,
+) -> impl IntoView {
+    let (auth_state, _, _) = use_local_storage::<AuthToken, JsonSerdeCodec>("auth-token");
+
+    let state = expect_context::<Store<SavedState>>();
+    let sequences = state.sequences();
+
+    let settings = RwSignal::new(AutoZoomSettings::default());
+    let (generating, set_generating) = signal(false);
+
+    // only videos recorded with pointer data can be auto-zoomed
+    let captures = Memo::new({
+        let sequence_id = sequence_id.clone();
+
+        move |_| {
+            sequences
+                .get()
+                .iter()
+                .find(|s| s.id == sequence_id)
+                .map(|s| {
+                    s.active_video_items
+                        .iter()
+                        .filter_map(|v| {
+                            v.mouse_path
+                                .clone()
+                                .map(|mouse_path| (v.id.clone(), v.name.clone(), mouse_path))
+                        })
+                        .collect::<Vec<_>>()
+                })
+                .unwrap_or_default()
+        }
+    });
+
+    let regenerate = Callback::new({
+        let sequence_id = sequence_id.clone();
+
+        move |(video_id, mouse_path): (String, String)| {
+            let renderer = renderer.get_untracked().expect("Couldn't get renderer");
+            let (canvas_renderer, editor_state) = renderer.take();
+
+            let token = auth_state.get_untracked().token;
+            let settings = settings.get_untracked();
+            let sequence_id = sequence_id.clone();
+
+            set_generating.set(true);
+
+            spawn_local(async move {
+                let clicks = fetch_media_data::<Vec<u128>>(
+                    token,
+                    capture_file_path(&mouse_path, "clicks.json"),
+                )
+                .await
+                .unwrap_or_default();
+
+                let canvas_renderer = canvas_renderer.lock().unwrap();
+                let editor_m = canvas_renderer.editor.clone();
+
+                let (mouse_positions, source_data) = {
+                    let editor = editor_m.lock().unwrap();
+
+                    editor
+                        .video_items
+                        .iter()
+                        .find(|v| v.id.to_string() == video_id)
+                        .map(|v| (v.mouse_positions.clone(), v.source_data.clone()))
+                        .unwrap_or_default()
+                };
+
+                let (Some(mouse_positions), Some(source_data)) = (mouse_positions, source_data)
+                else {
+                    log::warn!("No pointer data for video {}", video_id);
+                    set_generating.set(false);
+                    return;
+                };
+
+                if mouse_positions.is_empty() {
+                    log::warn!("No pointer data for video {}", video_id);
+                    set_generating.set(false);
+                    return;
+                }
+
+                let mut editor_state = editor_state.lock().unwrap();
+
+                // motion paths are scaled to the video when it's added
+                let duration_ms = editor_state
+                    .record_state
+                    .saved_state
+                    .as_ref()
+                    .and_then(|saved_state| {
+                        saved_state.sequences.iter().find(|s| s.id == sequence_id)
+                    })
+                    .and_then(|s| {
+                        s.polygon_motion_paths
+                            .iter()
+                            .find(|m| m.polygon_id == video_id)
+                    })
+                    .map(|m| m.duration.as_millis() as i64)
+                    .unwrap_or(0);
+
+                let keyframes = generate_zoom_keyframes(
+                    &mouse_positions,
+                    &clicks,
+                    duration_ms,
+                    (source_data.width, source_data.height),
+                    &settings,
+                );
+
+                editor_state.replace_property_keyframes(
+                    sequence_id.clone(),
+                    video_id.clone(),
+                    "zoom",
+                    "Zoom / Popout",
+                    keyframes.zoom,
+                );
+                editor_state.replace_property_keyframes(
+                    sequence_id.clone(),
+                    video_id.clone(),
+                    "zoom_focus",
+                    "Zoom Focus",
+                    keyframes.focus,
+                );
+
+                let saved_state = editor_state
+                    .record_state
+                    .saved_state
+                    .as_ref()
+                    .expect("Couldn't get saved state");
+                let updated_sequence = saved_state
+                    .sequences
+                    .iter()
+                    .find(|s| s.id == sequence_id)
+                    .expect("Couldn't get updated sequence")
+                    .clone();
+
+                sequences.set(saved_state.sequences.clone());
+
+                drop(editor_state);
+
+                let mut editor = editor_m.lock().unwrap();
+                editor.current_sequence_data = Some(updated_sequence.clone());
+                editor.update_motion_paths(&updated_sequence);
+                drop(editor);
+
+                canvas_renderer.redraw.request();
+                set_generating.set(false);
+            });
+        }
+    });
+
+    view! {
+        <Show when=move || !captures.get().is_empty()>
+            <div class="flex flex-col w-full gap-2">
+                <h5>"Auto-Zoom"</h5>
+                <div class="flex flex-row gap-2 items-center">
+                    <label for="zoom_sensitivity" class="text-xs">
+                        "Sensitivity"
+                    </label>
+                    <input
+                        type="range"
+                        id="zoom_sensitivity"
+                        name="zoom_sensitivity"
+                        min="0"
+                        max="100"
+                        on:change=move |ev| {
+                            let sensitivity = event_target_value(&ev).parse::<f32>().unwrap_or(50.0);
+                            settings.update(|s| s.sensitivity = sensitivity / 100.0);
+                        }
+                        prop:value=move || (settings.get().sensitivity * 100.0).round().to_string()
+                    />
+                </div>
+                <div class="flex flex-row gap-2 items-center">
+                    <label for="zoom_level" class="text-xs">
+                        "Zoom"
+                    </label>
+                    <select
+                        id="zoom_level"
+                        name="zoom_level"
+                        class="text-xs"
+                        on:change=move |ev| {
+                            let zoom_level = event_target_value(&ev).parse().unwrap_or(135);
+                            settings.update(|s| s.zoom_level = zoom_level);
+                        }
+                        prop:value=move || settings.get().zoom_level.to_string()
+                    >
+                        <option value="120">"120%"</option>
+                        <option value="135">"135%"</option>
+                        <option value="150">"150%"</option>
+                        <option value="200">"200%"</option>
+                    </select>
+                    <input
+                        type="checkbox"
+                        id="zoom_fast_out"
+                        name="zoom_fast_out"
+                        on:change=move |ev| {
+                            let zoom_out = event_target_checked(&ev);
+                            settings.update(|s| s.zoom_out_on_fast_movement = zoom_out);
+                        }
+                        prop:checked=move || settings.get().zoom_out_on_fast_movement
+                    />
+                    <label for="zoom_fast_out" class="text-xs">
+                        "Zoom out on fast moves"
+                    </label>
+                </div>
+                {move || {
+                    captures
+                        .get()
+                        .into_iter()
+                        .map(|(video_id, name, mouse_path)| {
+                            view! {
+                                <div class="flex flex-row gap-2 items-center justify-between">
+                                    <span class="text-xs">{name}</span>
+                                    <button
+                                        class="text-xs rounded-md border px-2 py-1 hover:bg-gray-200
+                                        disabled:opacity-50 disabled:cursor-not-allowed"
+                                        disabled=generating
+                                        on:click=move |_| {
+                                            regenerate.run((video_id.clone(), mouse_path.clone()))
+                                        }
+                                    >
+                                        "Regenerate"
+                                    </button>
+                                </div>
+                            }
+                        })
+                        .collect_view()
+                }}
+            </div>
+        </Show>
+    }
+}
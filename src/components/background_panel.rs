@@ -0,0 +1,443 @@
+use codee::string::JsonSerdeCodec;
+use leptos::prelude::*;
+use leptos_use::{storage::use_local_storage, use_debounce_fn};
+use reactive_stores::Store;
+use rfd::AsyncFileDialog;
+use std::sync::{Arc, Mutex};
+use stunts_engine::animations::{
+    BackgroundFill, BackgroundImage, GradientDefinition, GradientKind, GradientStop, ImageFit,
+};
+use wasm_bindgen_futures::spawn_local;
+
+use crate::canvas_renderer::CanvasRenderer;
+use crate::editor_state::EditorState;
+use crate::fetchers::projects::save_image;
+use crate::helpers::backgrounds::{apply_background_fill, hex_to_rgba, rgba_to_hex};
+use crate::helpers::users::AuthToken;
+use crate::helpers::utilities::{SavedState, SavedStateStoreFields};
+
+// quiet time after the last change before it goes on the undo record
+const RECORD_DELAY_MS: f64 = 500.0;
+
+fn default_gradient(kind: GradientKind) -> GradientDefinition {
+    GradientDefinition {
+        kind,
+        angle: 90,
+        stops: vec![
+            GradientStop {
+                offset: 0,
+                color: [255, 107, 107, 255],
+            },
+            GradientStop {
+                offset: 100,
+                color: [100, 181, 246, 255],
+            },
+        ],
+    }
+}
+
+fn fill_type(fill: &BackgroundFill) -> &'static str {
+    match fill {
+        BackgroundFill::Color(_) => "color",
+        BackgroundFill::Gradient(gradient) => match gradient.kind {
+            GradientKind::Linear => "linear",
+            GradientKind::Radial => "radial",
+        },
+        BackgroundFill::Image(_) => "image",
+    }
+}
+
+fn fit_value(fit: &ImageFit) -> &'static str {
+    match fit {
+        ImageFit::Cover => "cover",
+        ImageFit::Contain => "contain",
+        ImageFit::Tile => "tile",
+    }
+}
+
+// css version of the gradient for the swatch
+fn gradient_css(gradient: &GradientDefinition) -> String {
+    let mut stops = gradient.stops.clone();
+    stops.sort_by_key(|s| s.offset);
+
+    let stops = stops
+        .iter()
+        .map(|s| format!("{} {}%", rgba_to_hex(s.color), s.offset))
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    match gradient.kind {
+        GradientKind::Linear => format!("linear-gradient({}deg, {})", gradient.angle, stops),
+        GradientKind::Radial => format!("radial-gradient(farthest-corner, {})", stops),
+    }
+}
+
+#[component]
+pub fn BackgroundPanel(
+    renderer: LocalResource<(Arc<Mutex<CanvasRenderer>>, Arc<Mutex<EditorState>>)>,
+    sequence_id: String,
+) -> impl IntoView {
+    let (auth_state, _, _) = use_local_storage::<AuthToken, JsonSerdeCodec>("auth-token");
+
+    let state = expect_context::<Store<SavedState>>();
+    let sequences = state.sequences();
+
+    let initial_fill = sequences
+        .get_untracked()
+        .iter()
+        .find(|s| s.id == sequence_id)
+        .and_then(|s| s.background_fill.clone())
+        .unwrap_or(BackgroundFill::Color([200, 200, 200, 255]));
+
+    let (selected_type, set_selected_type) = signal(fill_type(&initial_fill).to_string());
+    let fill = RwSignal::new(initial_fill);
+    let (image_fit, set_image_fit) = signal(match fill.get_untracked() {
+        BackgroundFill::Image(image) => image.fit,
+        _ => ImageFit::Cover,
+    });
+    let (uploading, set_uploading) = signal(false);
+
+    // shown but not yet recorded, a burst of changes becomes one undo step and one save
+    let pending = StoredValue::new(None::<BackgroundFill>);
+
+    let record_pending = {
+        let sequence_id = sequence_id.clone();
+
+        move || {
+            let Some(new_fill) = pending.try_update_value(|p| p.take()).flatten() else {
+                return;
+            };
+            let Some(renderer) = renderer.try_get_untracked().flatten() else {
+                return;
+            };
+            let (_, editor_state) = renderer.take();
+
+            let mut editor_state = editor_state.lock().unwrap();
+            editor_state.update_background_fill(sequence_id.clone(), new_fill);
+
+            let saved_state = editor_state
+                .record_state
+                .saved_state
+                .as_ref()
+                .expect("Couldn't get saved state");
+            sequences.set(saved_state.sequences.clone());
+        }
+    };
+
+    let record_later = use_debounce_fn(record_pending.clone(), RECORD_DELAY_MS);
+
+    // closing the panel mid-burst still keeps the change
+    on_cleanup(record_pending);
+
+    // undo and redo change the fill from outside the panel
+    Effect::new({
+        let sequence_id = sequence_id.clone();
+
+        move |_| {
+            let saved = sequences.with(|sequences| {
+                sequences
+                    .iter()
+                    .find(|s| s.id == sequence_id)
+                    .and_then(|s| s.background_fill.clone())
+            });
+
+            if let (Some(saved), None) = (saved, pending.get_value()) {
+                set_selected_type.set(fill_type(&saved).to_string());
+                if let BackgroundFill::Image(image) = &saved {
+                    set_image_fit.set(image.fit.clone());
+                }
+                fill.set(saved);
+            }
+        }
+    });
+
+    // show a new fill now, record it once the changes settle
+    let apply = Callback::new({
+        let sequence_id = sequence_id.clone();
+
+        move |new_fill: BackgroundFill| {
+            fill.set(new_fill.clone());
+            pending.set_value(Some(new_fill.clone()));
+
+            let renderer = renderer.get_untracked().expect("Couldn't get renderer");
+            let (canvas_renderer, _) = renderer.take();
+            let canvas_renderer = canvas_renderer.lock().unwrap();
+
+            apply_background_fill(
+                &canvas_renderer,
+                sequence_id.clone(),
+                new_fill,
+                auth_state.get_untracked().token,
+            );
+
+            record_later();
+        }
+    });
+
+    let update_gradient = move |update: &dyn Fn(&mut GradientDefinition)| {
+        if let BackgroundFill::Gradient(mut gradient) = fill.get_untracked() {
+            update(&mut gradient);
+            apply.run(BackgroundFill::Gradient(gradient));
+        }
+    };
+
+    let on_type_change = move |ev| {
+        let new_type = event_target_value(&ev);
+        set_selected_type.set(new_type.clone());
+
+        let current = fill.get_untracked();
+
+        let new_fill = match new_type.as_str() {
+            "color" => {
+                let color = match &current {
+                    BackgroundFill::Gradient(gradient) => gradient
+                        .stops
+                        .first()
+                        .map(|s| s.color)
+                        .unwrap_or([200, 200, 200, 255]),
+                    _ => [200, 200, 200, 255],
+                };
+
+                Some(BackgroundFill::Color(color))
+            }
+            "linear" | "radial" => {
+                let kind = if new_type == "linear" {
+                    GradientKind::Linear
+                } else {
+                    GradientKind::Radial
+                };
+
+                let gradient = match current {
+                    BackgroundFill::Gradient(gradient) => GradientDefinition { kind, ..gradient },
+                    _ => default_gradient(kind),
+                };
+
+                Some(BackgroundFill::Gradient(gradient))
+            }
+            // images wait for an upload
+            _ => None,
+        };
+
+        if let Some(new_fill) = new_fill {
+            apply.run(new_fill);
+        }
+    };
+
+    let on_upload_image = move |ev: leptos::web_sys::MouseEvent| {
+        ev.prevent_default();
+
+        let token = auth_state.get_untracked().token;
+
+        spawn_local(async move {
+            if let Some(file) = AsyncFileDialog::new()
+                .add_filter("images", &["png", "jpg", "jpeg"])
+                .pick_file()
+                .await
+            {
+                set_uploading.set(true);
+
+                let file_data = file.read().await;
+
+                if let Some(response) = save_image(token, file.file_name(), file_data).await {
+                    apply.run(BackgroundFill::Image(BackgroundImage {
+                        url: response.url,
+                        fit: image_fit.get_untracked(),
+                    }));
+                }
+
+                set_uploading.set(false);
+            }
+        });
+    };
+
+    view! {
+        <div class="flex flex-col gap-2">
+            <label for="background_type" class="text-sm">
+                "Background"
+            </label>
+            <select
+                id="background_type"
+                name="background_type"
+                class="text-xs"
+                on:change=on_type_change
+                prop:value=selected_type
+            >
+                <option value="color">"Color"</option>
+                <option value="linear">"Linear Gradient"</option>
+                <option value="radial">"Radial Gradient"</option>
+                <option value="image">"Image"</option>
+            </select>
+            {move || {
+                match selected_type.get().as_str() {
+                    "color" => {
+                        let color = match fill.get() {
+                            BackgroundFill::Color(color) => color,
+                            _ => [200, 200, 200, 255],
+                        };
+
+                        view! {
+                            <input
+                                type="color"
+                                class="w-full h-8"
+                                prop:value=rgba_to_hex(color)
+                                on:change=move |ev| {
+                                    if let Some(color) = hex_to_rgba(&event_target_value(&ev)) {
+                                        apply.run(BackgroundFill::Color(color));
+                                    }
+                                }
+                            />
+                        }
+                            .into_any()
+                    }
+                    "linear" | "radial" => {
+                        let BackgroundFill::Gradient(gradient) = fill.get() else {
+                            return view! { <div></div> }.into_any();
+                        };
+                        let stop_count = gradient.stops.len();
+
+                        view! {
+                            <div class="flex flex-col gap-2">
+                                <div
+                                    class="w-full h-8 rounded border"
+                                    style:background=gradient_css(&gradient)
+                                ></div>
+                                {(gradient.kind == GradientKind::Linear)
+                                    .then(|| {
+                                        view! {
+                                            <div class="flex flex-row gap-2 items-center">
+                                                <label for="gradient_angle" class="text-xs">
+                                                    "Angle"
+                                                </label>
+                                                <input
+                                                    id="gradient_angle"
+                                                    type="number"
+                                                    min="0"
+                                                    max="360"
+                                                    class="border rounded px-2 py-1 w-20 text-xs"
+                                                    prop:value=gradient.angle.to_string()
+                                                    on:change=move |ev| {
+                                                        if let Ok(angle) = event_target_value(&ev)
+                                                            .parse::<i32>()
+                                                        {
+                                                            update_gradient(&|g| g.angle = angle.rem_euclid(360));
+                                                        }
+                                                    }
+                                                />
+                                            </div>
+                                        }
+                                    })}
+                                {gradient
+                                    .stops
+                                    .iter()
+                                    .enumerate()
+                                    .map(|(index, stop)| {
+                                        view! {
+                                            <div class="flex flex-row gap-2 items-center">
+                                                <input
+                                                    type="color"
+                                                    class="w-8 h-6"
+                                                    prop:value=rgba_to_hex(stop.color)
+                                                    on:change=move |ev| {
+                                                        if let Some(color) = hex_to_rgba(&event_target_value(&ev)) {
+                                                            update_gradient(&|g| g.stops[index].color = color);
+                                                        }
+                                                    }
+                                                />
+                                                <input
+                                                    type="number"
+                                                    min="0"
+                                                    max="100"
+                                                    class="border rounded px-2 py-1 w-16 text-xs"
+                                                    prop:value=stop.offset.to_string()
+                                                    on:change=move |ev| {
+                                                        if let Ok(offset) = event_target_value(&ev)
+                                                            .parse::<i32>()
+                                                        {
+                                                            update_gradient(&|g| g.stops[index].offset = offset.clamp(0, 100));
+                                                        }
+                                                    }
+                                                />
+                                                <span class="text-xs">"%"</span>
+                                                <button
+                                                    class="text-xs px-2 rounded hover:bg-gray-200 disabled:opacity-50"
+                                                    disabled=stop_count <= 2
+                                                    on:click=move |_| {
+                                                        update_gradient(&|g| {
+                                                            g.stops.remove(index);
+                                                        });
+                                                    }
+                                                >
+                                                    "Remove"
+                                                </button>
+                                            </div>
+                                        }
+                                    })
+                                    .collect_view()}
+                                <button
+                                    class="text-xs rounded border px-2 py-1 hover:bg-gray-200"
+                                    on:click=move |_| {
+                                        update_gradient(&|g| {
+                                            g.stops.push(GradientStop {
+                                                offset: 50,
+                                                color: [255, 255, 255, 255],
+                                            });
+                                        });
+                                    }
+                                >
+                                    "Add Stop"
+                                </button>
+                            </div>
+                        }
+                            .into_any()
+                    }
+                    _ => {
+                        view! {
+                            <div class="flex flex-col gap-2">
+                                <div class="flex flex-row gap-2 items-center">
+                                    <label for="background_fit" class="text-xs">
+                                        "Fit"
+                                    </label>
+                                    <select
+                                        id="background_fit"
+                                        name="background_fit"
+                                        class="text-xs"
+                                        prop:value=move || fit_value(&image_fit.get())
+                                        on:change=move |ev| {
+                                            let fit = match event_target_value(&ev).as_str() {
+                                                "contain" => ImageFit::Contain,
+                                                "tile" => ImageFit::Tile,
+                                                _ => ImageFit::Cover,
+                                            };
+                                            set_image_fit.set(fit.clone());
+
+                                            if let BackgroundFill::Image(image) = fill.get_untracked() {
+                                                apply.run(BackgroundFill::Image(BackgroundImage {
+                                                    fit,
+                                                    ..image
+                                                }));
+                                            }
+                                        }
+                                    >
+                                        <option value="cover">"Cover"</option>
+                                        <option value="contain">"Contain"</option>
+                                        <option value="tile">"Tile"</option>
+                                    </select>
+                                </div>
+                                <button
+                                    class="text-xs rounded border px-2 py-1 hover:bg-gray-200 disabled:opacity-50"
+                                    disabled=uploading
+                                    on:click=on_upload_image
+                                >
+                                    {move || {
+                                        if uploading.get() { "Uploading..." } else { "Choose Image" }
+                                    }}
+                                </button>
+                            </div>
+                        }
+                            .into_any()
+                    }
+                }
+            }}
+        </div>
+    }
+}
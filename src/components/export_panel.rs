@@ -0,0 +1,431 @@
+use leptos::prelude::*;
+use reactive_stores::Store;
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc, Mutex,
+};
+use stunts_engine::animations::Sequence;
+use wasm_bindgen_futures::spawn_local;
+
+use crate::canvas_renderer::{CanvasRenderer, FrameCapture};
+use crate::editor_state::EditorState;
+use crate::helpers::gif_export::{export_sequence_gif, GifExportSettings, PaletteMode};
+use crate::helpers::utilities::{download_bytes, SavedState, SavedStateStoreFields};
+use crate::helpers::video_export::{export_sequence_video, VideoContainer, VideoExportSettings};
+
+// points the editor at the sequence and hands back a capture that doesn't hold the renderer lock,
+// `size` maps the canvas size to the size frames are rendered at
+fn prepare_capture(
+    renderer: LocalResource<(Arc<Mutex<CanvasRenderer>>, Arc<Mutex<EditorState>>)>,
+    sequence: &Sequence,
+    size: impl FnOnce(u32, u32) -> (u32, u32),
+) -> FrameCapture {
+    let renderer = renderer.get_untracked().expect("Couldn't get renderer");
+    let (canvas_renderer, _) = renderer.take();
+
+    let capture = {
+        let mut canvas_renderer = canvas_renderer.lock().unwrap();
+        let (width, height) = size(canvas_renderer.window_width, canvas_renderer.window_height);
+
+        canvas_renderer.frame_capture(width, height)
+    };
+
+    {
+        let mut editor = capture.editor.lock().unwrap();
+        editor.current_sequence_data = Some(sequence.clone());
+    }
+
+    capture
+}
+
+#[component]
+pub fn ExportPanel(
+    renderer: LocalResource<(Arc<Mutex<CanvasRenderer>>, Arc<Mutex<EditorState>>)>,
+    sequence_id: String,
+) -> impl IntoView {
+    let state = expect_context::<Store<SavedState>>();
+    let sequences = state.sequences();
+
+    let gif_settings = RwSignal::new(GifExportSettings::default());
+    let video_settings = RwSignal::new(VideoExportSettings::default());
+
+    let (exporting, set_exporting) = signal(false);
+    let (progress, set_progress) = signal(0.0f32);
+    let (error, set_error) = signal(Option::<String>::None);
+    let (notice, set_notice) = signal(Option::<String>::None);
+    let (cancel, set_cancel) = signal(Option::<Arc<AtomicBool>>::None);
+
+    let duration_ms = {
+        let sequence_id = sequence_id.clone();
+
+        move || {
+            sequences
+                .get()
+                .iter()
+                .find(|s| s.id == sequence_id)
+                .map(|s| s.duration_ms)
+                .unwrap_or(0)
+        }
+    };
+
+    let canvas_size = move || {
+        renderer
+            .get()
+            .map(|renderer| {
+                let (canvas_renderer, _) = renderer.take();
+                let canvas_renderer = canvas_renderer.lock().unwrap();
+                (canvas_renderer.window_width, canvas_renderer.window_height)
+            })
+            .unwrap_or((900, 450))
+    };
+
+    let estimated_size = move || {
+        let (width, height) = canvas_size();
+        let bytes = gif_settings
+            .get()
+            .estimated_size_bytes(width, height, duration_ms());
+
+        format!("~{:.1} MB", bytes as f64 / 1_000_000.0)
+    };
+
+    let on_export_gif = {
+        let sequence_id = sequence_id.clone();
+
+        move |ev: leptos::web_sys::MouseEvent| {
+            ev.prevent_default();
+
+            let settings = gif_settings.get_untracked();
+            let sequence = sequences
+                .get_untracked()
+                .into_iter()
+                .find(|s| s.id == sequence_id)
+                .expect("Couldn't find sequence to export");
+
+            // downscaled after capture, the box filter smooths edges better than rendering small
+            let capture = prepare_capture(renderer, &sequence, |width, height| (width, height));
+
+            set_cancel.set(Some(capture.cancelled.clone()));
+
+            set_exporting.set(true);
+            set_progress.set(0.0);
+            set_error.set(None);
+            set_notice.set(None);
+
+            spawn_local(async move {
+                let result =
+                    export_sequence_gif(&capture, sequence.duration_ms, &settings, move |p| {
+                        set_progress.set(p)
+                    })
+                    .await;
+
+                match result {
+                    Ok(Some(bytes)) => {
+                        download_bytes(&bytes, &format!("{}.gif", sequence.name), "image/gif");
+                    }
+                    Ok(None) => set_notice.set(Some("Export cancelled".to_string())),
+                    Err(e) => {
+                        log::error!("GIF export failed: {}", e);
+                        set_error.set(Some(e));
+                    }
+                }
+
+                set_cancel.set(None);
+                set_exporting.set(false);
+            });
+        }
+    };
+
+    let on_export_video = {
+        let sequence_id = sequence_id.clone();
+
+        move |ev: leptos::web_sys::MouseEvent| {
+            ev.prevent_default();
+
+            let settings = video_settings.get_untracked();
+            let sequence = sequences
+                .get_untracked()
+                .into_iter()
+                .find(|s| s.id == sequence_id)
+                .expect("Couldn't find sequence to export");
+
+            // rendered at the output size, upscaling the canvas would blur it
+            let capture = prepare_capture(renderer, &sequence, |width, height| {
+                settings.output_size(width, height)
+            });
+
+            set_cancel.set(Some(capture.cancelled.clone()));
+
+            set_exporting.set(true);
+            set_progress.set(0.0);
+            set_error.set(None);
+            set_notice.set(None);
+
+            spawn_local(async move {
+                let result =
+                    export_sequence_video(&capture, sequence.duration_ms, &settings, move |p| {
+                        set_progress.set(p)
+                    })
+                    .await;
+
+                match result {
+                    Ok(Some(bytes)) => {
+                        download_bytes(
+                            &bytes,
+                            &format!("{}.{}", sequence.name, settings.container.extension()),
+                            settings.container.mime_type(),
+                        );
+                    }
+                    Ok(None) => set_notice.set(Some("Export cancelled".to_string())),
+                    Err(e) => {
+                        log::error!("Video export failed: {}", e);
+                        set_error.set(Some(e));
+                    }
+                }
+
+                set_cancel.set(None);
+                set_exporting.set(false);
+            });
+        }
+    };
+
+    let on_cancel = move |_| {
+        if let Some(cancelled) = cancel.get_untracked() {
+            cancelled.store(true, Ordering::SeqCst);
+        }
+    };
+
+    view! {
+        <div class="flex flex-col w-full gap-2">
+            <h5>"Export"</h5>
+            <div class="flex flex-row gap-2 items-center">
+                <label for="gif_fps" class="text-xs">
+                    "FPS"
+                </label>
+                <select
+                    id="gif_fps"
+                    name="gif_fps"
+                    class="text-xs"
+                    on:change=move |ev| {
+                        let fps = event_target_value(&ev).parse().unwrap_or(12);
+                        gif_settings.update(|s| s.fps = fps);
+                    }
+                    prop:value=move || gif_settings.get().fps.to_string()
+                >
+                    <option value="10">"10"</option>
+                    <option value="12">"12"</option>
+                    <option value="15">"15"</option>
+                    <option value="24">"24"</option>
+                </select>
+                <label for="gif_scale" class="text-xs">
+                    "Size"
+                </label>
+                <select
+                    id="gif_scale"
+                    name="gif_scale"
+                    class="text-xs"
+                    on:change=move |ev| {
+                        let scale = event_target_value(&ev).parse().unwrap_or(0.5);
+                        gif_settings.update(|s| s.scale = scale);
+                    }
+                    prop:value=move || gif_settings.get().scale.to_string()
+                >
+                    <option value="1">"100%"</option>
+                    <option value="0.75">"75%"</option>
+                    <option value="0.5">"50%"</option>
+                    <option value="0.25">"25%"</option>
+                </select>
+            </div>
+            <div class="flex flex-row gap-2 items-center">
+                <label for="gif_colors" class="text-xs">
+                    "Colors"
+                </label>
+                <select
+                    id="gif_colors"
+                    name="gif_colors"
+                    class="text-xs"
+                    on:change=move |ev| {
+                        let colors = event_target_value(&ev).parse().unwrap_or(256);
+                        gif_settings.update(|s| s.max_colors = colors);
+                    }
+                    prop:value=move || gif_settings.get().max_colors.to_string()
+                >
+                    <option value="256">"256"</option>
+                    <option value="128">"128"</option>
+                    <option value="64">"64"</option>
+                    <option value="32">"32"</option>
+                </select>
+                <label for="gif_palette" class="text-xs">
+                    "Palette"
+                </label>
+                <select
+                    id="gif_palette"
+                    name="gif_palette"
+                    class="text-xs"
+                    on:change=move |ev| {
+                        let mode = match event_target_value(&ev).as_str() {
+                            "frame" => PaletteMode::PerFrame,
+                            _ => PaletteMode::Global,
+                        };
+                        gif_settings.update(|s| s.palette_mode = mode);
+                    }
+                    prop:value=move || {
+                        match gif_settings.get().palette_mode {
+                            PaletteMode::Global => "global",
+                            PaletteMode::PerFrame => "frame",
+                        }
+                    }
+                >
+                    <option value="global">"Global"</option>
+                    <option value="frame">"Per Frame"</option>
+                </select>
+                <input
+                    type="checkbox"
+                    id="gif_dither"
+                    name="gif_dither"
+                    on:change=move |ev| {
+                        let dither = event_target_checked(&ev);
+                        gif_settings.update(|s| s.dither = dither);
+                    }
+                    prop:checked=move || gif_settings.get().dither
+                />
+                <label for="gif_dither" class="text-xs">
+                    "Dither"
+                </label>
+            </div>
+            <span class="text-xs">"Estimated size: " {estimated_size}</span>
+            <button
+                class="text-xs rounded-md text-white stunts-gradient px-2 py-1
+                disabled:opacity-50 disabled:cursor-not-allowed"
+                disabled=exporting
+                on:click=on_export_gif
+            >
+                {move || {
+                    if exporting.get() {
+                        format!("Exporting... {:.0}%", progress.get() * 100.0)
+                    } else {
+                        "Export GIF".to_string()
+                    }
+                }}
+            </button>
+            <div class="flex flex-row gap-2 items-center">
+                <label for="video_container" class="text-xs">
+                    "Format"
+                </label>
+                <select
+                    id="video_container"
+                    name="video_container"
+                    class="text-xs"
+                    on:change=move |ev| {
+                        let container = match event_target_value(&ev).as_str() {
+                            "webm" => VideoContainer::WebM,
+                            _ => VideoContainer::Mp4,
+                        };
+                        video_settings.update(|s| s.container = container);
+                    }
+                    prop:value=move || video_settings.get().container.extension()
+                >
+                    <option value="mp4">"MP4"</option>
+                    <option value="webm">"WebM"</option>
+                </select>
+                <label for="video_resolution" class="text-xs">
+                    "Resolution"
+                </label>
+                <select
+                    id="video_resolution"
+                    name="video_resolution"
+                    class="text-xs"
+                    on:change=move |ev| {
+                        let height = event_target_value(&ev).parse().unwrap_or(1080);
+                        video_settings.update(|s| s.height = height);
+                    }
+                    prop:value=move || video_settings.get().height.to_string()
+                >
+                    <option value="1080">"1080p"</option>
+                    <option value="720">"720p"</option>
+                    <option value="480">"480p"</option>
+                </select>
+            </div>
+            <div class="flex flex-row gap-2 items-center">
+                <label for="video_fps" class="text-xs">
+                    "FPS"
+                </label>
+                <select
+                    id="video_fps"
+                    name="video_fps"
+                    class="text-xs"
+                    on:change=move |ev| {
+                        let fps = event_target_value(&ev).parse().unwrap_or(30);
+                        video_settings.update(|s| s.fps = fps);
+                    }
+                    prop:value=move || video_settings.get().fps.to_string()
+                >
+                    <option value="24">"24"</option>
+                    <option value="30">"30"</option>
+                    <option value="60">"60"</option>
+                </select>
+                <label for="video_bitrate" class="text-xs">
+                    "Bitrate"
+                </label>
+                <select
+                    id="video_bitrate"
+                    name="video_bitrate"
+                    class="text-xs"
+                    on:change=move |ev| {
+                        let bitrate = event_target_value(&ev).parse().unwrap_or(8_000_000);
+                        video_settings.update(|s| s.bitrate = bitrate);
+                    }
+                    prop:value=move || video_settings.get().bitrate.to_string()
+                >
+                    <option value="2000000">"2 Mbps"</option>
+                    <option value="5000000">"5 Mbps"</option>
+                    <option value="8000000">"8 Mbps"</option>
+                    <option value="16000000">"16 Mbps"</option>
+                </select>
+            </div>
+            <div class="flex flex-row gap-2 items-center">
+                <button
+                    class="text-xs rounded-md text-white stunts-gradient px-2 py-1
+                    disabled:opacity-50 disabled:cursor-not-allowed"
+                    disabled=exporting
+                    on:click=on_export_video
+                >
+                    "Export Video"
+                </button>
+                {move || {
+                    cancel
+                        .get()
+                        .map(|_| {
+                            view! {
+                                <button
+                                    class="text-xs rounded-md border px-2 py-1 hover:bg-gray-200"
+                                    on:click=on_cancel
+                                >
+                                    "Cancel"
+                                </button>
+                            }
+                        })
+                }}
+            </div>
+            {move || {
+                cancel
+                    .get()
+                    .map(|_| {
+                        view! {
+                            <div class="w-full h-2 rounded bg-gray-200">
+                                <div
+                                    class="h-2 rounded stunts-gradient"
+                                    style:width=move || format!("{:.0}%", progress.get() * 100.0)
+                                ></div>
+                            </div>
+                        }
+                    })
+            }}
+            {move || {
+                notice.get().map(|notice| view! { <div class="text-gray-500 text-xs">{notice}</div> })
+            }}
+            {move || {
+                error.get().map(|err| view! { <div class="text-red-500 text-xs">{err}</div> })
+            }}
+        </div>
+    }
+}
@@ -0,0 +1,320 @@
+use leptos::prelude::*;
+use reactive_stores::Store;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use stunts_engine::animations::{ObjectType, SavedGroupConfig};
+use uuid::Uuid;
+
+use crate::canvas_renderer::CanvasRenderer;
+use crate::editor_state::EditorState;
+use crate::helpers::groups::{group_keyframes, set_group_keyframes, GroupPose};
+use crate::helpers::utilities::{SavedState, SavedStateStoreFields};
+
+// keyframe columns, with how each reads and writes its pose
+const KEYFRAME_FIELDS: [(&str, &str); 6] = [
+    ("time", "Time (s)"),
+    ("x", "X"),
+    ("y", "Y"),
+    ("rotation", "Rotate °"),
+    ("scale", "Scale %"),
+    ("opacity", "Opacity %"),
+];
+
+#[derive(Clone)]
+enum GroupChange {
+    Create(Vec<(Uuid, ObjectType)>, String),
+    Update(SavedGroupConfig),
+    Ungroup(String),
+}
+
+fn field_value(field: &str, time: Duration, pose: &GroupPose) -> f32 {
+    match field {
+        "time" => time.as_secs_f32(),
+        "x" => pose.position.0,
+        "y" => pose.position.1,
+        "rotation" => pose.rotation,
+        "scale" => pose.scale * 100.0,
+        "opacity" => pose.opacity * 100.0,
+        _ => 0.0,
+    }
+}
+
+fn set_field_value(field: &str, value: f32, time: &mut Duration, pose: &mut GroupPose) {
+    match field {
+        "time" => *time = Duration::from_secs_f32(value.max(0.0)),
+        "x" => pose.position.0 = value,
+        "y" => pose.position.1 = value,
+        "rotation" => pose.rotation = value,
+        "scale" => pose.scale = value / 100.0,
+        "opacity" => pose.opacity = (value / 100.0).clamp(0.0, 1.0),
+        _ => {}
+    }
+}
+
+/// Groups the selection, or renames, animates and ungroups the group it makes up.
+/// The group's animation plays on top of its objects' own.
+#[component]
+pub fn GroupPanel(
+    renderer: LocalResource<(Arc<Mutex<CanvasRenderer>>, Arc<Mutex<EditorState>>)>,
+    sequence_id: String,
+    selection: RwSignal<Vec<(Uuid, ObjectType)>>,
+) -> impl IntoView {
+    let state = expect_context::<Store<SavedState>>();
+    let sequences = state.sequences();
+
+    let (group_error, set_group_error) = signal(None::<String>);
+    let new_name = RwSignal::new(String::new());
+
+    let groups = Memo::new({
+        let sequence_id = sequence_id.clone();
+
+        move |_| {
+            sequences
+                .get()
+                .iter()
+                .find(|s| s.id == sequence_id)
+                .map(|s| s.groups.clone())
+                .unwrap_or_default()
+        }
+    });
+
+    // the group whose objects are exactly what's selected
+    let group = Memo::new(move |_| {
+        let selected = selection.get();
+
+        groups.get().into_iter().find(|g| {
+            !selected.is_empty()
+                && selected.len() == g.object_ids.len()
+                && selected
+                    .iter()
+                    .all(|(id, _)| g.object_ids.contains(&id.to_string()))
+        })
+    });
+
+    // makes the change as one undo step, then shows what it did
+    let change_groups = Callback::new(move |change: GroupChange| {
+        let renderer = renderer.get_untracked().expect("Couldn't get renderer");
+        let (canvas_renderer, editor_state) = renderer.take();
+        let canvas_renderer = canvas_renderer.lock().unwrap();
+
+        let mut editor_state = editor_state.lock().unwrap();
+
+        let result = match change {
+            GroupChange::Create(objects, name) => editor_state
+                .group_objects(sequence_id.clone(), &objects, name)
+                .map(|_| ()),
+            GroupChange::Update(group) => editor_state.update_group(sequence_id.clone(), group),
+            GroupChange::Ungroup(group_id) => editor_state.ungroup(sequence_id.clone(), group_id),
+        };
+
+        if let Err(e) = result {
+            set_group_error.set(Some(e));
+            return;
+        }
+        set_group_error.set(None);
+
+        let saved_state = editor_state
+            .record_state
+            .saved_state
+            .as_ref()
+            .expect("Couldn't get saved state");
+
+        sequences.set(saved_state.sequences.clone());
+
+        drop(editor_state);
+
+        canvas_renderer.redraw.request();
+    });
+
+    let on_group = move |_| {
+        let name = match new_name.get_untracked().trim() {
+            "" => format!("Group {}", groups.get_untracked().len() + 1),
+            name => name.to_string(),
+        };
+
+        change_groups.run(GroupChange::Create(selection.get_untracked(), name));
+        new_name.set(String::new());
+    };
+
+    let on_ungroup = move |_| {
+        let Some(group) = group.get_untracked() else {
+            return;
+        };
+
+        change_groups.run(GroupChange::Ungroup(group.id));
+    };
+
+    let edit_keyframe = move |index: usize, field: &'static str, value: f32| {
+        let Some(mut group) = group.get_untracked() else {
+            return;
+        };
+
+        let mut poses = group_keyframes(&group);
+        let Some((time, pose)) = poses.get_mut(index) else {
+            return;
+        };
+        set_field_value(field, value, time, pose);
+        poses.sort_by_key(|(time, _)| *time);
+
+        set_group_keyframes(&mut group, &poses);
+        change_groups.run(GroupChange::Update(group));
+    };
+
+    let add_keyframe = move |_| {
+        let Some(mut group) = group.get_untracked() else {
+            return;
+        };
+
+        // a second after the last one, holding its pose
+        let mut poses = group_keyframes(&group);
+        let (time, pose) = poses.last().copied().unwrap_or_default();
+        poses.push((time + Duration::from_secs(1), pose));
+
+        set_group_keyframes(&mut group, &poses);
+        change_groups.run(GroupChange::Update(group));
+    };
+
+    let remove_keyframe = move |index: usize| {
+        let Some(mut group) = group.get_untracked() else {
+            return;
+        };
+
+        let mut poses = group_keyframes(&group);
+        if poses.len() > 1 {
+            poses.remove(index);
+        }
+
+        set_group_keyframes(&mut group, &poses);
+        change_groups.run(GroupChange::Update(group));
+    };
+
+    view! {
+        <div class="flex flex-col w-full gap-2">
+            <h5>"Group"</h5>
+            {move || match group.get() {
+                Some(current) => {
+                    let poses = group_keyframes(&current);
+                    let can_remove = poses.len() > 1;
+
+                    view! {
+                        <div class="flex flex-row gap-1 items-center">
+                            <input
+                                class="text-xs border rounded px-1 py-1 w-full"
+                                prop:value=current.name.clone()
+                                on:change=move |ev| {
+                                    let name = event_target_value(&ev);
+                                    let Some(mut group) = group.get_untracked() else {
+                                        return;
+                                    };
+
+                                    if !name.trim().is_empty() {
+                                        group.name = name.trim().to_string();
+                                        change_groups.run(GroupChange::Update(group));
+                                    }
+                                }
+                            />
+                            <button
+                                class="text-xs rounded-md border px-2 py-1 hover:bg-gray-200"
+                                on:click=on_ungroup
+                            >
+                                "Ungroup"
+                            </button>
+                        </div>
+                        <table class="text-xs">
+                            <thead>
+                                <tr>
+                                    {KEYFRAME_FIELDS
+                                        .iter()
+                                        .map(|(_, label)| {
+                                            view! { <th class="font-normal text-left">{*label}</th> }
+                                        })
+                                        .collect_view()}
+                                    <th></th>
+                                </tr>
+                            </thead>
+                            <tbody>
+                                {poses
+                                    .into_iter()
+                                    .enumerate()
+                                    .map(|(index, (time, pose))| {
+                                        view! {
+                                            <tr>
+                                                {KEYFRAME_FIELDS
+                                                    .iter()
+                                                    .map(|(field, _)| {
+                                                        let value = field_value(field, time, &pose);
+
+                                                        view! {
+                                                            <td>
+                                                                <input
+                                                                    type="number"
+                                                                    step="any"
+                                                                    class="w-10 border rounded"
+                                                                    prop:value=format!("{}", (value * 10.0).round() / 10.0)
+                                                                    on:change=move |ev| {
+                                                                        if let Ok(value) = event_target_value(&ev)
+                                                                            .parse::<f32>()
+                                                                        {
+                                                                            edit_keyframe(index, field, value);
+                                                                        }
+                                                                    }
+                                                                />
+                                                            </td>
+                                                        }
+                                                    })
+                                                    .collect_view()}
+                                                <td>
+                                                    <button
+                                                        class="px-1 rounded hover:bg-gray-200 disabled:opacity-50"
+                                                        disabled=!can_remove
+                                                        on:click=move |_| remove_keyframe(index)
+                                                    >
+                                                        "×"
+                                                    </button>
+                                                </td>
+                                            </tr>
+                                        }
+                                    })
+                                    .collect_view()}
+                            </tbody>
+                        </table>
+                        <button
+                            class="text-xs rounded-md border px-2 py-1 hover:bg-gray-200"
+                            on:click=add_keyframe
+                        >
+                            "Add keyframe"
+                        </button>
+                    }
+                        .into_any()
+                }
+                None => {
+                    view! {
+                        <div class="flex flex-row gap-1 items-center">
+                            <input
+                                class="text-xs border rounded px-1 py-1 w-full"
+                                placeholder="Group name"
+                                prop:value=move || new_name.get()
+                                on:input=move |ev| new_name.set(event_target_value(&ev))
+                            />
+                            <button
+                                class="text-xs rounded-md border px-2 py-1 hover:bg-gray-200
+                                disabled:opacity-50 disabled:cursor-not-allowed"
+                                disabled=move || selection.with(|s| s.len() < 2)
+                                on:click=on_group
+                            >
+                                "Group"
+                            </button>
+                        </div>
+                        <span class="text-xs text-gray-500">
+                            "Select two or more objects to group them, or a group in the scene list to edit it."
+                        </span>
+                    }
+                        .into_any()
+                }
+            }}
+            {move || {
+                group_error.get().map(|e| view! { <span class="text-xs text-red-500">{e}</span> })
+            }}
+        </div>
+    }
+}
@@ -0,0 +1,141 @@
+use leptos::prelude::*;
+use reactive_stores::Store;
+use std::sync::{Arc, Mutex};
+
+use crate::canvas_renderer::CanvasRenderer;
+use crate::editor_state::EditorState;
+use crate::helpers::history::{save_session_history, MAX_STORED_EDITS};
+use crate::helpers::utilities::{SavedState, SavedStateStoreFields};
+
+/// Lists the undo record. Clicking an entry undoes or redoes up to it.
+#[component]
+pub fn HistoryPanel(
+    renderer: LocalResource<(Arc<Mutex<CanvasRenderer>>, Arc<Mutex<EditorState>>)>,
+    project_id: String,
+    sequence_id: String,
+) -> impl IntoView {
+    let state = expect_context::<Store<SavedState>>();
+    let sequences = state.sequences();
+
+    // every edit ends by setting the sequences, so they tell us when to re-read
+    let history = Memo::new(move |_| {
+        sequences.track();
+
+        let Some(renderer) = renderer.get() else {
+            return (Vec::new(), 0);
+        };
+        let (_, editor_state) = renderer.take();
+        let editor_state = editor_state.lock().unwrap();
+
+        editor_state.history()
+    });
+
+    Effect::new(move |_| {
+        history.track();
+
+        let Some(renderer) = renderer.get_untracked() else {
+            return;
+        };
+        let (_, editor_state) = renderer.take();
+        let stored = editor_state
+            .lock()
+            .unwrap()
+            .stored_history(MAX_STORED_EDITS);
+
+        save_session_history(&project_id, &stored);
+    });
+
+    let go_to = Callback::new(move |index: usize| {
+        let renderer = renderer.get_untracked().expect("Couldn't get renderer");
+        let (canvas_renderer, editor_state) = renderer.take();
+        let canvas_renderer = canvas_renderer.lock().unwrap();
+        let editor_m = canvas_renderer.editor.clone();
+
+        let mut editor_state = editor_state.lock().unwrap();
+        editor_state.go_to_history(index);
+
+        let saved_state = editor_state
+            .record_state
+            .saved_state
+            .as_ref()
+            .expect("Couldn't get saved state");
+        let updated_sequence = saved_state
+            .sequences
+            .iter()
+            .find(|s| s.id == sequence_id)
+            .cloned();
+
+        sequences.set(saved_state.sequences.clone());
+
+        drop(editor_state);
+
+        if let Some(updated_sequence) = updated_sequence {
+            let mut editor = editor_m.lock().unwrap();
+            editor.current_sequence_data = Some(updated_sequence.clone());
+            editor.update_motion_paths(&updated_sequence);
+        }
+
+        canvas_renderer.redraw.request();
+    });
+
+    view! {
+        <div class="flex flex-col w-full gap-2">
+            <div class="flex flex-row justify-between items-center">
+                <h5>"History"</h5>
+                <div class="flex flex-row gap-1">
+                    <button
+                        class="text-xs rounded-md border px-2 py-1 hover:bg-gray-200
+                        disabled:opacity-50 disabled:cursor-not-allowed"
+                        disabled=move || history.get().1 == 0
+                        on:click=move |_| go_to.run(history.get_untracked().1.saturating_sub(1))
+                    >
+                        "Undo"
+                    </button>
+                    <button
+                        class="text-xs rounded-md border px-2 py-1 hover:bg-gray-200
+                        disabled:opacity-50 disabled:cursor-not-allowed"
+                        disabled=move || {
+                            let (labels, head) = history.get();
+                            head >= labels.len()
+                        }
+                        on:click=move |_| go_to.run(history.get_untracked().1 + 1)
+                    >
+                        "Redo"
+                    </button>
+                </div>
+            </div>
+            <ul class="flex flex-col max-h-60 overflow-y-auto text-xs">
+                <li
+                    class="p-1 rounded cursor-pointer hover:bg-gray-100"
+                    class:font-semibold=move || history.get().1 == 0
+                    on:click=move |_| go_to.run(0)
+                >
+                    "Opened project"
+                </li>
+                {move || {
+                    let (labels, head) = history.get();
+
+                    labels
+                        .into_iter()
+                        .enumerate()
+                        .map(|(i, label)| {
+                            // entries after the head were undone and can still be redone
+                            let undone = i >= head;
+
+                            view! {
+                                <li
+                                    class="p-1 rounded cursor-pointer hover:bg-gray-100"
+                                    class:text-gray-400=undone
+                                    class:font-semibold=i + 1 == head
+                                    on:click=move |_| go_to.run(i + 1)
+                                >
+                                    {label}
+                                </li>
+                            }
+                        })
+                        .collect_view()
+                }}
+            </ul>
+        </div>
+    }
+}
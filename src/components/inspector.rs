@@ -0,0 +1,280 @@
+use leptos::prelude::*;
+use reactive_stores::Store;
+use std::sync::{Arc, Mutex};
+use stunts_engine::animations::ObjectType;
+use stunts_engine::editor::{wgpu_to_human, Editor};
+use uuid::Uuid;
+
+use crate::canvas_renderer::CanvasRenderer;
+use crate::editor_state::EditorState;
+use crate::helpers::utilities::{SavedState, SavedStateStoreFields};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum InspectorField {
+    Width,
+    Height,
+    Red,
+    Green,
+    Blue,
+    BorderRadius,
+    StrokeThickness,
+    StrokeRed,
+    StrokeGreen,
+    StrokeBlue,
+    BackgroundRed,
+    BackgroundGreen,
+    BackgroundBlue,
+}
+
+impl InspectorField {
+    fn for_object(object_type: &ObjectType) -> Vec<InspectorField> {
+        use InspectorField::*;
+
+        match object_type {
+            ObjectType::Polygon => vec![
+                Width,
+                Height,
+                Red,
+                Green,
+                Blue,
+                BorderRadius,
+                StrokeThickness,
+                StrokeRed,
+                StrokeGreen,
+                StrokeBlue,
+            ],
+            ObjectType::TextItem => vec![Width, Height, BackgroundRed, BackgroundGreen, BackgroundBlue],
+            ObjectType::ImageItem | ObjectType::VideoItem => vec![Width, Height],
+        }
+    }
+
+    fn label(&self) -> &'static str {
+        match self {
+            InspectorField::Width => "Width",
+            InspectorField::Height => "Height",
+            InspectorField::Red => "Red",
+            InspectorField::Green => "Green",
+            InspectorField::Blue => "Blue",
+            InspectorField::BorderRadius => "Border Radius",
+            InspectorField::StrokeThickness => "Stroke",
+            InspectorField::StrokeRed => "Stroke Red",
+            InspectorField::StrokeGreen => "Stroke Green",
+            InspectorField::StrokeBlue => "Stroke Blue",
+            InspectorField::BackgroundRed => "Background Red",
+            InspectorField::BackgroundGreen => "Background Green",
+            InspectorField::BackgroundBlue => "Background Blue",
+        }
+    }
+
+    /// current value as the update function expects it, colors in 0 - 255
+    fn current_value(&self, editor: &Editor, object_id: Uuid, object_type: &ObjectType) -> f32 {
+        match self {
+            InspectorField::Width => editor.get_object_width(object_id, object_type.clone()),
+            InspectorField::Height => editor.get_object_height(object_id, object_type.clone()),
+            InspectorField::Red => wgpu_to_human(editor.get_polygon_red(object_id)),
+            InspectorField::Green => wgpu_to_human(editor.get_polygon_green(object_id)),
+            InspectorField::Blue => wgpu_to_human(editor.get_polygon_blue(object_id)),
+            InspectorField::BorderRadius => editor.get_polygon_border_radius(object_id),
+            InspectorField::StrokeThickness => editor.get_polygon_stroke_thickness(object_id),
+            InspectorField::StrokeRed => wgpu_to_human(editor.get_polygon_stroke_red(object_id)),
+            InspectorField::StrokeGreen => {
+                wgpu_to_human(editor.get_polygon_stroke_green(object_id))
+            }
+            InspectorField::StrokeBlue => wgpu_to_human(editor.get_polygon_stroke_blue(object_id)),
+            InspectorField::BackgroundRed => wgpu_to_human(editor.get_fill_red(object_id)),
+            InspectorField::BackgroundGreen => wgpu_to_human(editor.get_fill_green(object_id)),
+            InspectorField::BackgroundBlue => wgpu_to_human(editor.get_fill_blue(object_id)),
+        }
+    }
+
+    fn apply(
+        &self,
+        editor_state: &mut EditorState,
+        object_id: Uuid,
+        value: &str,
+        object_type: ObjectType,
+        sequence_id: String,
+    ) -> Result<(), String> {
+        match self {
+            InspectorField::Width => editor_state.update_width(object_id, value, object_type),
+            InspectorField::Height => editor_state.update_height(object_id, value, object_type),
+            InspectorField::Red => editor_state.update_red(object_id, value),
+            InspectorField::Green => editor_state.update_green(object_id, value),
+            InspectorField::Blue => editor_state.update_blue(object_id, value),
+            InspectorField::BorderRadius => editor_state.update_border_radius(object_id, value),
+            InspectorField::StrokeThickness => {
+                editor_state.update_stroke_thickness(object_id, value)
+            }
+            InspectorField::StrokeRed => editor_state.update_stroke_red(object_id, value),
+            InspectorField::StrokeGreen => editor_state.update_stroke_green(object_id, value),
+            InspectorField::StrokeBlue => editor_state.update_stroke_blue(object_id, value),
+            InspectorField::BackgroundRed => {
+                editor_state.update_fill_red(object_id, value, sequence_id)
+            }
+            InspectorField::BackgroundGreen => {
+                editor_state.update_fill_green(object_id, value, sequence_id)
+            }
+            InspectorField::BackgroundBlue => {
+                editor_state.update_fill_blue(object_id, value, sequence_id)
+            }
+        }
+    }
+}
+
+// properties are only shown when one object is selected
+fn single_object(selection: &[(Uuid, ObjectType)]) -> Option<(Uuid, ObjectType)> {
+    match selection {
+        [only] => Some(only.clone()),
+        _ => None,
+    }
+}
+
+/// Properties of the selected object. Changes go through the EditorState update
+/// functions so they can be undone.
+#[component]
+pub fn PropertyInspector(
+    renderer: LocalResource<(Arc<Mutex<CanvasRenderer>>, Arc<Mutex<EditorState>>)>,
+    sequence_id: String,
+    selection: RwSignal<Vec<(Uuid, ObjectType)>>,
+) -> impl IntoView {
+    let state = expect_context::<Store<SavedState>>();
+    let sequences = state.sequences();
+
+    let field_error = RwSignal::new(None::<(InspectorField, String)>);
+
+    // selecting something else drops a stale error
+    Effect::new(move |_| {
+        selection.track();
+        field_error.set(None);
+    });
+
+    // re-read whenever the saved sequences change, which every edit does
+    let fields = move || {
+        sequences.track();
+
+        let (object_id, object_type) = selection.with(|selection| single_object(selection))?;
+        let renderer = renderer.get()?;
+        let (canvas_renderer, _) = renderer.take();
+        let editor_m = canvas_renderer.lock().unwrap().editor.clone();
+        let editor = editor_m.lock().unwrap();
+
+        Some(
+            InspectorField::for_object(&object_type)
+                .into_iter()
+                .map(|field| (field, field.current_value(&editor, object_id, &object_type)))
+                .collect::<Vec<_>>(),
+        )
+    };
+
+    let commit = Callback::new({
+        let sequence_id = sequence_id.clone();
+
+        move |(field, value): (InspectorField, String)| {
+            let Some((object_id, object_type)) =
+                selection.with_untracked(|selection| single_object(selection))
+            else {
+                return;
+            };
+
+            let renderer = renderer.get_untracked().expect("Couldn't get renderer");
+            let (canvas_renderer, editor_state) = renderer.take();
+            let canvas_renderer = canvas_renderer.lock().unwrap();
+            let editor_m = canvas_renderer.editor.clone();
+
+            let mut editor_state = editor_state.lock().unwrap();
+
+            if let Err(e) = field.apply(
+                &mut editor_state,
+                object_id,
+                &value,
+                object_type,
+                sequence_id.clone(),
+            ) {
+                field_error.set(Some((field, e)));
+                return;
+            }
+
+            field_error.set(None);
+
+            let saved_state = editor_state
+                .record_state
+                .saved_state
+                .as_ref()
+                .expect("Couldn't get saved state");
+            let updated_sequence = saved_state
+                .sequences
+                .iter()
+                .find(|s| s.id == sequence_id)
+                .expect("Couldn't get updated sequence")
+                .clone();
+
+            sequences.set(saved_state.sequences.clone());
+
+            drop(editor_state);
+
+            let mut editor = editor_m.lock().unwrap();
+            editor.current_sequence_data = Some(updated_sequence);
+        }
+    });
+
+    view! {
+        <div class="flex flex-col w-full gap-2">
+            <h5>"Properties"</h5>
+            {move || match fields() {
+                None if selection.with(|selection| selection.len() > 1) => {
+                    view! {
+                        <span class="text-xs text-gray-500">
+                            {move || format!("{} objects selected", selection.with(|s| s.len()))}
+                        </span>
+                    }
+                        .into_any()
+                }
+                None => {
+                    view! {
+                        <span class="text-xs text-gray-500">
+                            "Select an object on the canvas or in the scene list"
+                        </span>
+                    }
+                        .into_any()
+                }
+                Some(fields) => {
+                    fields
+                        .into_iter()
+                        .map(|(field, value)| {
+                            let input_id = format!("inspector_{:?}", field).to_lowercase();
+                            let error = move || {
+                                field_error
+                                    .get()
+                                    .filter(|(errored, _)| *errored == field)
+                                    .map(|(_, e)| e)
+                            };
+
+                            view! {
+                                <div class="flex flex-col">
+                                    <div class="flex flex-row gap-2 items-center">
+                                        <label for=input_id.clone() class="text-xs w-28">
+                                            {field.label()}
+                                        </label>
+                                        <input
+                                            type="text"
+                                            id=input_id.clone()
+                                            name=input_id
+                                            class="border rounded px-2 py-1 w-full min-w-2 text-xs"
+                                            class:border-red-500=move || error().is_some()
+                                            prop:value=format!("{}", (value * 100.0).round() / 100.0)
+                                            on:change=move |ev| commit.run((field, event_target_value(&ev)))
+                                        />
+                                    </div>
+                                    {move || {
+                                        error().map(|e| view! { <span class="text-xs text-red-500">{e}</span> })
+                                    }}
+                                </div>
+                            }
+                        })
+                        .collect_view()
+                        .into_any()
+                }
+            }}
+        </div>
+    }
+}
@@ -1,7 +1,9 @@
 use leptos::prelude::*;
+use reactive_stores::Store;
 use std::str::FromStr;
 use std::sync::{Arc, Mutex};
 use stunts_engine::animations::ObjectType;
+use stunts_engine::editor::Editor;
 use stunts_engine::polygon::PolygonConfig;
 use stunts_engine::st_image::StImageConfig;
 use stunts_engine::st_video::StVideoConfig;
@@ -11,6 +13,7 @@ use uuid::Uuid;
 use crate::canvas_renderer::CanvasRenderer;
 use crate::components::icon::CreateIcon;
 use crate::editor_state::EditorState;
+use crate::helpers::utilities::{SavedState, SavedStateStoreFields};
 
 #[derive(Clone, PartialEq, Eq)]
 pub struct Layer {
@@ -55,8 +58,47 @@ impl Layer {
     }
 }
 
+/// Layers for the objects showing in the editor, topmost first
+pub fn layers_from_editor(editor: &Editor) -> Vec<Layer> {
+    let mut new_layers = Vec::new();
+    editor.polygons.iter().for_each(|polygon| {
+        if !polygon.hidden {
+            let polygon_config: PolygonConfig = polygon.to_config();
+            let new_layer: Layer = Layer::from_polygon_config(&polygon_config);
+            new_layers.push(new_layer);
+        }
+    });
+    editor.text_items.iter().for_each(|text| {
+        if !text.hidden {
+            let text_config: TextRendererConfig = text.to_config();
+            let new_layer: Layer = Layer::from_text_config(&text_config);
+            new_layers.push(new_layer);
+        }
+    });
+    editor.image_items.iter().for_each(|image| {
+        if !image.hidden {
+            let image_config: StImageConfig = image.to_config();
+            let new_layer: Layer = Layer::from_image_config(&image_config);
+            new_layers.push(new_layer);
+        }
+    });
+    editor.video_items.iter().for_each(|video| {
+        if !video.hidden {
+            let video_config: StVideoConfig = video.to_config();
+            let new_layer: Layer = Layer::from_video_config(&video_config);
+            new_layers.push(new_layer);
+        }
+    });
+
+    // sort layers by layer_index property, lower values should come first in the list
+    // but reverse the order because the UI outputs the first one first, thus it displays last
+    new_layers.sort_by(|a, b| b.initial_layer_index.cmp(&a.initial_layer_index));
+
+    new_layers
+}
+
 #[component]
-pub fn SortableItem<F, FB, FC>(
+pub fn SortableItem<F, FB, FC, FD>(
     // editor: RwSignal<Vec<Layer>>,
     // renderer: LocalResource<(Arc<Mutex<CanvasRenderer>>, Arc<Mutex<EditorState>>)>,
     sortable_items: RwSignal<Vec<Layer>>,
@@ -68,15 +110,22 @@ pub fn SortableItem<F, FB, FC>(
     on_items_updated: F,
     on_item_duplicated: FB,
     on_item_deleted: FC,
+    /// called with whether shift was held, to add to the selection
+    on_item_selected: FD,
+    selection: RwSignal<Vec<(Uuid, ObjectType)>>,
 ) -> impl IntoView
 where
     F: Fn() + Clone + 'static,
     FB: Fn(Uuid, ObjectType) + Clone + 'static,
     FC: Fn(Uuid, ObjectType) + Clone + 'static,
+    FD: Fn(Uuid, ObjectType, bool) + Clone + 'static,
 {
+    let is_selected = move || selection.get().iter().any(|(id, _)| *id == item_id);
+
     view! {
         <div
             class="flex flex-row w-full justify-between items-center p-1 rounded-lg cursor-row-resize"
+            class:bg-gray-200=is_selected
             draggable="true"
             on:dragstart=move |_| dragger_id.set(item_id)
             on:dragover=move |_| {
@@ -102,7 +151,16 @@ where
             }
             on:dragend=move |_| on_items_updated()
         >
-            <div class="flex items-center gap-2">
+            <div
+                class="flex items-center gap-2 cursor-pointer"
+                on:click={
+                    let kind = kind.clone();
+
+                    move |ev: leptos::ev::MouseEvent| {
+                        on_item_selected(item_id, kind.clone(), ev.shift_key())
+                    }
+                }
+            >
                 <CreateIcon
                     icon=icon_name.to_string()
                     size="24px".to_string()
@@ -140,12 +198,58 @@ where
 #[component]
 pub fn LayerPanel(
     // renderer: LocalResource<(Arc<Mutex<CanvasRenderer>>, Arc<Mutex<EditorState>>)>,
+    sequence_id: String,
     layers: RwSignal<Vec<Layer>>,
     dragger_id: RwSignal<Uuid>,
     on_items_updated: impl Fn() + Clone + Send + Sync + 'static,
     on_item_duplicated: impl Fn(Uuid, ObjectType) + Clone + Send + Sync + 'static,
     on_item_deleted: impl Fn(Uuid, ObjectType) + Clone + Send + Sync + 'static,
+    on_item_selected: impl Fn(Uuid, ObjectType, bool) + Clone + Send + Sync + 'static,
+    /// called with every object of a group when its node is clicked
+    on_group_selected: impl Fn(Vec<(Uuid, ObjectType)>) + Clone + Send + Sync + 'static,
+    selection: RwSignal<Vec<(Uuid, ObjectType)>>,
 ) -> impl IntoView {
+    let state = expect_context::<Store<SavedState>>();
+    let sequences = state.sequences();
+
+    let groups = Memo::new(move |_| {
+        sequences
+            .get()
+            .iter()
+            .find(|s| s.id == sequence_id)
+            .map(|s| s.groups.clone())
+            .unwrap_or_default()
+    });
+
+    // ids of groups folded up in the list
+    let collapsed = RwSignal::new(Vec::<String>::new());
+
+    let item = move |layer: Layer| {
+        let icon_name = match layer.instance_kind {
+            ObjectType::Polygon => "square",
+            ObjectType::TextItem => "text",
+            ObjectType::ImageItem => "image",
+            ObjectType::VideoItem => "video",
+        };
+
+        view! {
+            <SortableItem
+                // renderer=renderer
+                sortable_items=layers
+                dragger_id=dragger_id
+                item_id=layer.instance_id
+                kind=layer.instance_kind
+                layer_name=layer.instance_name.clone()
+                icon_name=icon_name
+                on_items_updated=on_items_updated.clone()
+                on_item_duplicated=on_item_duplicated.clone()
+                on_item_deleted=on_item_deleted.clone()
+                on_item_selected=on_item_selected.clone()
+                selection=selection
+            />
+        }
+    };
+
     view! {
         <div class="flex flex-col w-full">
             <h3 class="text-lg font-semibold mb-3">"Scene"</h3>
@@ -154,26 +258,96 @@ pub fn LayerPanel(
                     each=move || layers.get()
                     key=|layer| layer.instance_id.clone()
                     children=move |layer: Layer| {
-                        let icon_name = match layer.instance_kind {
-                            ObjectType::Polygon => "square",
-                            ObjectType::TextItem => "text",
-                            ObjectType::ImageItem => "image",
-                            ObjectType::VideoItem => "video",
-                        };
-
-                        view! {
-                            <SortableItem
-                                // renderer=renderer
-                                sortable_items=layers
-                                dragger_id=dragger_id
-                                item_id=layer.instance_id
-                                kind=layer.instance_kind
-                                layer_name=layer.instance_name.clone()
-                                icon_name=icon_name
-                                on_items_updated=on_items_updated.clone()
-                                on_item_duplicated=on_item_duplicated.clone()
-                                on_item_deleted=on_item_deleted.clone()
-                            />
+                        let item = item.clone();
+                        let on_group_selected = on_group_selected.clone();
+
+                        move || {
+                            let layer_id = layer.instance_id.to_string();
+                            let Some(group) = groups
+                                .get()
+                                .into_iter()
+                                .find(|g| g.object_ids.contains(&layer_id))
+                            else {
+                                return item(layer.clone()).into_any();
+                            };
+
+                            let children = layers
+                                .get()
+                                .into_iter()
+                                .filter(|l| group.object_ids.contains(&l.instance_id.to_string()))
+                                .collect::<Vec<_>>();
+
+                            // the group's node sits where its topmost object would
+                            if children.first().map(|l| l.instance_id) != Some(layer.instance_id) {
+                                return ().into_any();
+                            }
+
+                            let members = children
+                                .iter()
+                                .map(|l| (l.instance_id, l.instance_kind.clone()))
+                                .collect::<Vec<_>>();
+                            let is_selected = {
+                                let members = members.clone();
+
+                                move || {
+                                    selection.with(|s| {
+                                        members.iter().all(|(id, _)| s.iter().any(|(s_id, _)| s_id == id))
+                                    })
+                                }
+                            };
+                            let expanded = collapsed.with(|c| !c.contains(&group.id));
+                            let toggle = {
+                                let group_id = group.id.clone();
+
+                                move |_: leptos::ev::MouseEvent| {
+                                    collapsed
+                                        .update(|c| {
+                                            if c.contains(&group_id) {
+                                                c.retain(|id| *id != group_id);
+                                            } else {
+                                                c.push(group_id.clone());
+                                            }
+                                        })
+                                }
+                            };
+                            let caret = if expanded { "caret-down" } else { "caret-right" };
+                            let on_group_selected = on_group_selected.clone();
+                            let item = item.clone();
+
+                            view! {
+                                <div class="flex flex-col w-full space-y-2">
+                                    <div
+                                        class="flex flex-row w-full items-center gap-2 p-1 rounded-lg"
+                                        class:bg-gray-200=is_selected
+                                    >
+                                        <button
+                                            class="text-black rounded hover:bg-gray-300"
+                                            on:click=toggle
+                                        >
+                                            <CreateIcon icon=caret.to_string() size="16px".to_string() />
+                                        </button>
+                                        <div
+                                            class="flex items-center gap-2 cursor-pointer"
+                                            on:click=move |_| on_group_selected(members.clone())
+                                        >
+                                            <CreateIcon
+                                                icon="folder-plus".to_string()
+                                                size="24px".to_string()
+                                            />
+                                            <span class="text-gray-800 text-xs">{group.name.clone()}</span>
+                                        </div>
+                                    </div>
+                                    {expanded
+                                        .then(|| {
+                                            view! {
+                                                <div class="flex flex-col pl-4 space-y-2">
+                                                    {children.into_iter().map(item).collect_view()}
+                                                </div>
+                                            }
+                                        })}
+                                </div>
+                            }
+                                .into_any()
                         }
                     }
                 />
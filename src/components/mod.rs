@@ -1,6 +1,18 @@
+pub mod arrange_panel;
+pub mod auto_zoom_panel;
+pub mod background_panel;
+pub mod export_panel;
+pub mod group_panel;
+pub mod history_panel;
 pub mod icon;
+pub mod inspector;
 pub mod items;
 pub mod layers;
 pub mod login_form;
+pub mod pen_tool;
 pub mod project_form;
 pub mod project_list;
+pub mod selection_overlay;
+pub mod shape_panel;
+pub mod shortcuts;
+pub mod text_editor;
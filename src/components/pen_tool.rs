@@ -0,0 +1,543 @@
+use leptos::ev;
+use leptos::prelude::*;
+use reactive_stores::Store;
+use std::sync::{Arc, Mutex};
+use stunts_engine::camera::Camera;
+use stunts_engine::editor::Point;
+use stunts_engine::polygon::{PolygonConfig, SavedShape, Stroke};
+use uuid::Uuid;
+use wasm_bindgen::JsCast;
+use web_sys::{Element, PointerEvent};
+
+use crate::canvas_renderer::CanvasRenderer;
+use crate::editor_state::EditorState;
+use crate::helpers::canvas_coords::{
+    client_to_pixel, pixel_to_scene, scene_to_pixel, world_to_scene,
+};
+use crate::helpers::keymap::typing_in_field;
+use crate::helpers::pen_path::{PenAnchor, PenPath};
+use crate::helpers::polygons::add_polygon_to_sequence;
+use crate::helpers::shapes::shape_points;
+use crate::helpers::utilities::{SavedState, SavedStateStoreFields};
+
+// how close in pixels the pointer has to be to grab a point
+const HIT_RADIUS: f32 = 8.0;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PenMode {
+    Off,
+    Draw,
+    Edit(Uuid),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum PenDrag {
+    NewHandle(usize),
+    Anchor(usize),
+    HandleIn(usize),
+    HandleOut(usize),
+}
+
+fn svg_path(path: &PenPath, camera: &Camera) -> String {
+    let pixel = |p: Point| scene_to_pixel(p, camera);
+
+    let Some(first) = path.anchors.first() else {
+        return String::new();
+    };
+
+    let start = pixel(first.point);
+    let mut d = format!("M {} {}", start.x, start.y);
+
+    for (a, b) in path.segments() {
+        let out = pixel(path.anchors[a].handle_out);
+        let handle_in = pixel(path.anchors[b].handle_in);
+        let end = pixel(path.anchors[b].point);
+
+        d.push_str(&format!(
+            " C {} {} {} {} {} {}",
+            out.x, out.y, handle_in.x, handle_in.y, end.x, end.y
+        ));
+    }
+
+    if path.closed {
+        d.push_str(" Z");
+    }
+
+    d
+}
+
+/// Draws and edits pen paths on top of the canvas. Drawing makes a new polygon,
+/// editing rewrites the points of an existing path as one undoable edit.
+#[component]
+pub fn PenOverlay(
+    renderer: LocalResource<(Arc<Mutex<CanvasRenderer>>, Arc<Mutex<EditorState>>)>,
+    sequence_id: String,
+    pen_mode: RwSignal<PenMode>,
+) -> impl IntoView {
+    let state = expect_context::<Store<SavedState>>();
+    let sequences = state.sequences();
+
+    let path = RwSignal::new(PenPath::default());
+    let camera = RwSignal::new(None::<Camera>);
+    let selected = RwSignal::new(None::<usize>);
+    let (pen_error, set_pen_error) = signal(None::<String>);
+
+    let drag = StoredValue::new(None::<PenDrag>);
+    // box of the polygon being edited, points stay relative to it
+    let editing_box = StoredValue::new(None::<(Point, (f32, f32))>);
+
+    Effect::new(move |_| {
+        let mode = pen_mode.get();
+
+        path.set(PenPath::default());
+        selected.set(None);
+        set_pen_error.set(None);
+        editing_box.set_value(None);
+
+        if mode == PenMode::Off {
+            return;
+        }
+
+        let Some(renderer) = renderer.get_untracked() else {
+            return;
+        };
+        let (canvas_renderer, editor_state) = renderer.take();
+        let editor_m = canvas_renderer.lock().unwrap().editor.clone();
+        let editor = editor_m.lock().unwrap();
+
+        camera.set(editor.camera);
+
+        let PenMode::Edit(polygon_id) = mode else {
+            return;
+        };
+
+        let Some(polygon) = editor.polygons.iter().find(|p| p.id == polygon_id) else {
+            set_pen_error.set(Some("Couldn't find the path on the canvas".to_string()));
+            return;
+        };
+
+        let center = world_to_scene(Point {
+            x: polygon.transform.position.x,
+            y: polygon.transform.position.y,
+        });
+        let dimensions = polygon.dimensions;
+
+        drop(editor);
+
+        let editor_state = editor_state.lock().unwrap();
+        let saved_shape = editor_state
+            .record_state
+            .saved_state
+            .as_ref()
+            .and_then(|saved_state| {
+                saved_state
+                    .sequences
+                    .iter()
+                    .flat_map(|s| s.active_polygons.iter())
+                    .find(|p| p.id == polygon_id.to_string())
+            })
+            .and_then(|p| p.shape.clone());
+
+        match saved_shape {
+            Some(SavedShape::Path { anchors, closed }) => {
+                editing_box.set_value(Some((center, dimensions)));
+                path.set(PenPath::from_saved(&anchors, closed, center, dimensions));
+            }
+            _ => set_pen_error.set(Some("Only pen paths can be edited point by point".to_string())),
+        }
+    });
+
+    on_cleanup(move || pen_mode.set(PenMode::Off));
+
+    let scene_point = move |ev: &PointerEvent| -> Option<Point> {
+        let camera = camera.get_untracked()?;
+        let target = ev.current_target()?.dyn_into::<Element>().ok()?;
+        let pixel = client_to_pixel(&target, ev.client_x(), ev.client_y(), &camera);
+
+        Some(pixel_to_scene(pixel, &camera))
+    };
+
+    let hit_radius = move || {
+        camera
+            .get_untracked()
+            .map(|camera| HIT_RADIUS / camera.zoom.max(0.01))
+            .unwrap_or(HIT_RADIUS)
+    };
+
+    let finish = Callback::new({
+        let sequence_id = sequence_id.clone();
+
+        move |_: ()| {
+            let current = path.get_untracked();
+
+            if current.anchors.len() < 2 {
+                set_pen_error.set(Some("A path needs at least two points".to_string()));
+                return;
+            }
+
+            let renderer = renderer.get_untracked().expect("Couldn't get renderer");
+            let (canvas_renderer, editor_state) = renderer.take();
+            let canvas_renderer = canvas_renderer.lock().unwrap();
+            let editor_m = canvas_renderer.editor.clone();
+
+            match pen_mode.get_untracked() {
+                PenMode::Draw => {
+                    let (center, dimensions) = current.bounds();
+                    let shape = current.to_saved(center, dimensions);
+
+                    let polygon_config = PolygonConfig {
+                        id: Uuid::new_v4(),
+                        name: "Path".to_string(),
+                        points: shape_points(&shape),
+                        dimensions,
+                        position: center,
+                        border_radius: 0.0,
+                        fill: [1.0, 1.0, 1.0, 1.0],
+                        stroke: Stroke {
+                            fill: [1.0, 1.0, 1.0, 1.0],
+                            thickness: 2.0,
+                        },
+                        layer: -2,
+                    };
+
+                    let updated_sequences = add_polygon_to_sequence(
+                        editor_m,
+                        editor_state,
+                        sequence_id.clone(),
+                        polygon_config,
+                        Some(shape),
+                    );
+
+                    sequences.set(updated_sequences);
+                }
+                PenMode::Edit(polygon_id) => {
+                    let Some((center, dimensions)) = editing_box.get_value() else {
+                        return;
+                    };
+
+                    let mut editor_state = editor_state.lock().unwrap();
+
+                    if let Err(e) = editor_state
+                        .update_polygon_shape(polygon_id, current.to_saved(center, dimensions))
+                    {
+                        set_pen_error.set(Some(e));
+                        return;
+                    }
+
+                    let saved_state = editor_state
+                        .record_state
+                        .saved_state
+                        .as_ref()
+                        .expect("Couldn't get saved state");
+                    let updated_sequence = saved_state
+                        .sequences
+                        .iter()
+                        .find(|s| s.id == sequence_id)
+                        .expect("Couldn't get updated sequence")
+                        .clone();
+
+                    sequences.set(saved_state.sequences.clone());
+
+                    drop(editor_state);
+
+                    let mut editor = editor_m.lock().unwrap();
+                    editor.current_sequence_data = Some(updated_sequence);
+                }
+                PenMode::Off => return,
+            }
+
+            canvas_renderer.redraw.request();
+            pen_mode.set(PenMode::Off);
+        }
+    });
+
+    let delete_selected = move || {
+        if let Some(index) = selected.get_untracked() {
+            path.update(|path| path.remove_anchor(index));
+            selected.set(None);
+        }
+    };
+
+    let handle = window_event_listener(ev::keydown, move |ev| {
+        if pen_mode.get_untracked() == PenMode::Off {
+            return;
+        }
+
+        // leave typing in the panels alone
+        if typing_in_field(&ev) {
+            return;
+        }
+
+        match ev.key().as_str() {
+            "Escape" => pen_mode.set(PenMode::Off),
+            "Enter" => finish.run(()),
+            "Delete" | "Backspace" => {
+                if matches!(pen_mode.get_untracked(), PenMode::Edit(_)) {
+                    ev.prevent_default();
+                    delete_selected();
+                }
+            }
+            _ => {}
+        }
+    });
+    on_cleanup(move || handle.remove());
+
+    let on_pointer_down = move |ev: PointerEvent| {
+        let Some(point) = scene_point(&ev) else {
+            return;
+        };
+        let radius = hit_radius();
+        let current = path.get_untracked();
+
+        match pen_mode.get_untracked() {
+            PenMode::Draw => {
+                // clicking the first point closes the path
+                if current.anchors.len() > 2 && current.nearest_anchor(point, radius) == Some(0) {
+                    path.update(|path| path.closed = true);
+                    finish.run(());
+                    return;
+                }
+
+                path.update(|path| path.anchors.push(PenAnchor::corner(point)));
+                drag.set_value(Some(PenDrag::NewHandle(current.anchors.len())));
+            }
+            PenMode::Edit(_) => {
+                let near = |p: Point| ((p.x - point.x).powi(2) + (p.y - point.y).powi(2)).sqrt() <= radius;
+
+                let handle_drag = selected.get_untracked().and_then(|i| {
+                    let anchor = current.anchors.get(i)?;
+
+                    if anchor.is_corner() {
+                        None
+                    } else if near(anchor.handle_out) {
+                        Some(PenDrag::HandleOut(i))
+                    } else if near(anchor.handle_in) {
+                        Some(PenDrag::HandleIn(i))
+                    } else {
+                        None
+                    }
+                });
+
+                if let Some(handle_drag) = handle_drag {
+                    drag.set_value(Some(handle_drag));
+                } else if let Some(i) = current.nearest_anchor(point, radius) {
+                    selected.set(Some(i));
+                    drag.set_value(Some(PenDrag::Anchor(i)));
+                } else {
+                    selected.set(None);
+                }
+            }
+            PenMode::Off => {}
+        }
+    };
+
+    let on_pointer_move = move |ev: PointerEvent| {
+        let Some(current_drag) = drag.get_value() else {
+            return;
+        };
+        let Some(point) = scene_point(&ev) else {
+            return;
+        };
+
+        path.update(|path| match current_drag {
+            PenDrag::NewHandle(i) => {
+                if let Some(anchor) = path.anchors.get_mut(i) {
+                    anchor.set_smooth_handle(point);
+                }
+            }
+            PenDrag::Anchor(i) => {
+                if let Some(anchor) = path.anchors.get_mut(i) {
+                    anchor.move_to(point);
+                }
+            }
+            PenDrag::HandleIn(i) => {
+                if let Some(anchor) = path.anchors.get_mut(i) {
+                    anchor.handle_in = point;
+                }
+            }
+            PenDrag::HandleOut(i) => {
+                if let Some(anchor) = path.anchors.get_mut(i) {
+                    anchor.handle_out = point;
+                }
+            }
+        });
+    };
+
+    let on_double_click = move |ev: web_sys::MouseEvent| {
+        let Some(camera) = camera.get_untracked() else {
+            return;
+        };
+        let Some(target) = ev
+            .current_target()
+            .and_then(|t| t.dyn_into::<Element>().ok())
+        else {
+            return;
+        };
+        let point = pixel_to_scene(
+            client_to_pixel(&target, ev.client_x(), ev.client_y(), &camera),
+            &camera,
+        );
+
+        match pen_mode.get_untracked() {
+            // the second click of a double click already added a point
+            PenMode::Draw => {
+                path.update(|path| {
+                    path.anchors.pop();
+                });
+                finish.run(());
+            }
+            PenMode::Edit(_) => {
+                let current = path.get_untracked();
+
+                if let Some((segment, t)) = current.nearest_segment(point, hit_radius()) {
+                    let mut index = 0;
+                    path.update(|path| index = path.insert_anchor(segment, t));
+                    selected.set(Some(index));
+                }
+            }
+            PenMode::Off => {}
+        }
+    };
+
+    let view_box = move || {
+        camera
+            .get()
+            .map(|camera| {
+                format!(
+                    "0 0 {} {}",
+                    camera.window_size.width, camera.window_size.height
+                )
+            })
+            .unwrap_or_else(|| "0 0 900 450".to_string())
+    };
+
+    view! {
+        <Show when=move || pen_mode.get() != PenMode::Off>
+            <div class="absolute top-0 left-0 flex flex-row gap-2 items-center p-1 bg-white/90 rounded-br-md z-10">
+                <span class="text-xs">
+                    {move || match pen_mode.get() {
+                        PenMode::Draw => "Click to add points, drag for curves, click the first point to close",
+                        _ => "Drag points and handles, double-click a segment to add a point",
+                    }}
+                </span>
+                <button
+                    class="text-xs rounded-md border px-2 py-1 hover:bg-gray-200
+                    disabled:opacity-50 disabled:cursor-not-allowed"
+                    disabled=move || path.get().anchors.len() < 3
+                    on:click=move |_| path.update(|path| path.closed = !path.closed)
+                >
+                    {move || if path.get().closed { "Open Path" } else { "Close Path" }}
+                </button>
+                <Show when=move || matches!(pen_mode.get(), PenMode::Edit(_))>
+                    <button
+                        class="text-xs rounded-md border px-2 py-1 hover:bg-gray-200
+                        disabled:opacity-50 disabled:cursor-not-allowed"
+                        disabled=move || selected.get().is_none()
+                        on:click=move |_| delete_selected()
+                    >
+                        "Delete Point"
+                    </button>
+                </Show>
+                <button
+                    class="text-xs rounded-md border px-2 py-1 hover:bg-gray-200"
+                    on:click=move |_| finish.run(())
+                >
+                    "Done"
+                </button>
+                <button
+                    class="text-xs rounded-md border px-2 py-1 hover:bg-gray-200"
+                    on:click=move |_| pen_mode.set(PenMode::Off)
+                >
+                    "Cancel"
+                </button>
+                {move || {
+                    pen_error.get().map(|e| view! { <span class="text-xs text-red-500">{e}</span> })
+                }}
+            </div>
+            <svg
+                class="absolute top-0 left-0 w-full h-full cursor-crosshair"
+                viewBox=view_box
+                on:pointerdown=on_pointer_down
+                on:pointermove=on_pointer_move
+                on:pointerup=move |_| drag.set_value(None)
+                on:pointerleave=move |_| drag.set_value(None)
+                on:dblclick=on_double_click
+            >
+                {move || {
+                    let Some(camera) = camera.get() else {
+                        return ().into_any();
+                    };
+                    let current = path.get();
+                    let selected = selected.get();
+                    let d = svg_path(&current, &camera);
+
+                    // handles of the point being worked on
+                    let active = match pen_mode.get() {
+                        PenMode::Draw => current.anchors.len().checked_sub(1),
+                        _ => selected,
+                    };
+
+                    let handles = active
+                        .and_then(|i| current.anchors.get(i))
+                        .filter(|anchor| !anchor.is_corner())
+                        .map(|anchor| {
+                            let point = scene_to_pixel(anchor.point, &camera);
+
+                            [anchor.handle_in, anchor.handle_out]
+                                .into_iter()
+                                .map(|handle| {
+                                    let handle = scene_to_pixel(handle, &camera);
+
+                                    view! {
+                                        <line
+                                            x1=point.x.to_string()
+                                            y1=point.y.to_string()
+                                            x2=handle.x.to_string()
+                                            y2=handle.y.to_string()
+                                            stroke="#6366f1"
+                                            stroke-width="1"
+                                        />
+                                        <circle
+                                            cx=handle.x.to_string()
+                                            cy=handle.y.to_string()
+                                            r="4"
+                                            fill="white"
+                                            stroke="#6366f1"
+                                        />
+                                    }
+                                })
+                                .collect_view()
+                        });
+
+                    let anchors = current
+                        .anchors
+                        .iter()
+                        .enumerate()
+                        .map(|(i, anchor)| {
+                            let point = scene_to_pixel(anchor.point, &camera);
+                            let fill = if Some(i) == selected { "#6366f1" } else { "white" };
+
+                            view! {
+                                <circle
+                                    cx=point.x.to_string()
+                                    cy=point.y.to_string()
+                                    r="5"
+                                    fill=fill
+                                    stroke="#6366f1"
+                                    stroke-width="2"
+                                />
+                            }
+                        })
+                        .collect_view();
+
+                    view! {
+                        <path d=d fill="none" stroke="#6366f1" stroke-width="2" />
+                        {handles}
+                        {anchors}
+                    }
+                        .into_any()
+                }}
+            </svg>
+        </Show>
+    }
+}
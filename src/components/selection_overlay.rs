@@ -0,0 +1,781 @@
+use leptos::prelude::*;
+use reactive_stores::Store;
+use std::sync::{Arc, Mutex};
+use stunts_engine::animations::ObjectType;
+use stunts_engine::camera::Camera;
+use stunts_engine::editor::Point;
+use uuid::Uuid;
+use wasm_bindgen::JsCast;
+use web_sys::{Element, MouseEvent, PointerEvent};
+
+use crate::canvas_renderer::CanvasRenderer;
+use crate::components::pen_tool::PenMode;
+use crate::editor_state::EditorState;
+use crate::helpers::alignment::{snap_frame, Guide, SnapSettings, SNAP_DISTANCE};
+use crate::helpers::canvas_coords::{client_to_pixel, pixel_to_world, world_to_pixel};
+use crate::helpers::object_transform::{hit_test, object_bounds, visible_objects, ObjectBounds};
+use crate::helpers::utilities::{SavedState, SavedStateStoreFields};
+
+// how close in pixels the pointer has to be to grab a handle
+const HANDLE_RADIUS: f32 = 6.0;
+// distance in pixels of the rotate handle above the top edge
+const ROTATE_OFFSET: f32 = 24.0;
+const MIN_SIZE: f32 = 4.0;
+const ROTATE_SNAP: f32 = 15.0;
+
+// resize handles as the side of the box they sit on, clockwise from the top left
+const RESIZE_HANDLES: [(f32, f32); 8] = [
+    (-1.0, -1.0),
+    (0.0, -1.0),
+    (1.0, -1.0),
+    (1.0, 0.0),
+    (1.0, 1.0),
+    (0.0, 1.0),
+    (-1.0, 1.0),
+    (-1.0, 0.0),
+];
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum DragKind {
+    Move,
+    Resize(f32, f32),
+    Rotate,
+}
+
+#[derive(Clone, Debug)]
+struct SelectionDrag {
+    kind: DragKind,
+    start: Point,
+    frame: ObjectBounds,
+    objects: Vec<ObjectBounds>,
+    // visible objects that aren't moving, for snapping
+    others: Vec<ObjectBounds>,
+}
+
+fn distance(a: Point, b: Point) -> f32 {
+    ((a.x - b.x).powi(2) + (a.y - b.y).powi(2)).sqrt()
+}
+
+/// Box the handles are drawn on. A single object keeps its own rotated box,
+/// several get an upright box around all of them.
+fn selection_frame(objects: &[ObjectBounds]) -> Option<ObjectBounds> {
+    if objects.len() == 1 {
+        return objects.first().cloned();
+    }
+
+    let corners = objects.iter().flat_map(|o| o.corners()).collect::<Vec<_>>();
+    let (min, max) = corners_extent(&corners)?;
+
+    Some(ObjectBounds {
+        id: Uuid::nil(),
+        object_type: ObjectType::Polygon,
+        center: Point {
+            x: (min.x + max.x) / 2.0,
+            y: (min.y + max.y) / 2.0,
+        },
+        dimensions: (max.x - min.x, max.y - min.y),
+        rotation: 0.0,
+        layer: 0,
+    })
+}
+
+fn corners_extent(corners: &[Point]) -> Option<(Point, Point)> {
+    if corners.is_empty() {
+        return None;
+    }
+
+    let mut min = Point {
+        x: f32::MAX,
+        y: f32::MAX,
+    };
+    let mut max = Point {
+        x: f32::MIN,
+        y: f32::MIN,
+    };
+
+    for corner in corners {
+        min.x = min.x.min(corner.x);
+        min.y = min.y.min(corner.y);
+        max.x = max.x.max(corner.x);
+        max.y = max.y.max(corner.y);
+    }
+
+    Some((min, max))
+}
+
+/// rotate handle position, in world space
+fn rotate_handle(bounds: &ObjectBounds, camera: &Camera) -> Point {
+    bounds.to_world(Point {
+        x: 0.0,
+        y: -bounds.dimensions.1 / 2.0 - ROTATE_OFFSET / camera.zoom.max(0.01),
+    })
+}
+
+fn resize_handle(bounds: &ObjectBounds, (sx, sy): (f32, f32)) -> Point {
+    bounds.to_world(Point {
+        x: sx * bounds.dimensions.0 / 2.0,
+        y: sy * bounds.dimensions.1 / 2.0,
+    })
+}
+
+/// the handle under a pixel, if any. Groups can't be rotated.
+fn handle_at(
+    frame: &ObjectBounds,
+    pixel: Point,
+    camera: &Camera,
+    rotatable: bool,
+) -> Option<DragKind> {
+    let near = |world: Point| distance(world_to_pixel(world, camera), pixel) <= HANDLE_RADIUS;
+
+    if rotatable && near(rotate_handle(frame, camera)) {
+        return Some(DragKind::Rotate);
+    }
+
+    RESIZE_HANDLES
+        .iter()
+        .find(|side| near(resize_handle(frame, **side)))
+        .map(|(sx, sy)| DragKind::Resize(*sx, *sy))
+}
+
+/// Carries each object along from the frame it started in to the dragged one,
+/// keeping its place inside the frame
+fn moved_objects(
+    start: &ObjectBounds,
+    frame: &ObjectBounds,
+    objects: &[ObjectBounds],
+) -> Vec<ObjectBounds> {
+    let scale_x = frame.dimensions.0 / start.dimensions.0.max(0.01);
+    let scale_y = frame.dimensions.1 / start.dimensions.1.max(0.01);
+    let rotation = frame.rotation - start.rotation;
+
+    objects
+        .iter()
+        .map(|object| {
+            let local = start.to_local(object.center);
+
+            ObjectBounds {
+                center: frame.to_world(Point {
+                    x: local.x * scale_x,
+                    y: local.y * scale_y,
+                }),
+                dimensions: (
+                    (object.dimensions.0 * scale_x).max(MIN_SIZE),
+                    (object.dimensions.1 * scale_y).max(MIN_SIZE),
+                ),
+                rotation: object.rotation + rotation,
+                ..object.clone()
+            }
+        })
+        .collect()
+}
+
+/// Where the frame would be if the drag ended at `point`
+fn dragged_frame(drag: &SelectionDrag, point: Point, snap: bool) -> ObjectBounds {
+    let start = &drag.frame;
+    let mut bounds = start.clone();
+
+    match drag.kind {
+        DragKind::Move => {
+            bounds.center = Point {
+                x: start.center.x + point.x - drag.start.x,
+                y: start.center.y + point.y - drag.start.y,
+            };
+        }
+        DragKind::Resize(sx, sy) => {
+            // the opposite side stays put, so work in the object's own frame
+            let local = start.to_local(point);
+            let (width, height) = start.dimensions;
+
+            let new_width = if sx == 0.0 {
+                width
+            } else {
+                (sx * local.x + width / 2.0).max(MIN_SIZE)
+            };
+            let new_height = if sy == 0.0 {
+                height
+            } else {
+                (sy * local.y + height / 2.0).max(MIN_SIZE)
+            };
+
+            bounds.dimensions = (new_width, new_height);
+            bounds.center = start.to_world(Point {
+                x: sx * (new_width - width) / 2.0,
+                y: sy * (new_height - height) / 2.0,
+            });
+        }
+        DragKind::Rotate => {
+            let angle = |p: Point| {
+                (p.y - start.center.y)
+                    .atan2(p.x - start.center.x)
+                    .to_degrees()
+            };
+
+            let mut rotation = start.rotation + angle(point) - angle(drag.start);
+
+            if snap {
+                rotation = (rotation / ROTATE_SNAP).round() * ROTATE_SNAP;
+            }
+
+            bounds.rotation = rotation;
+        }
+    }
+
+    bounds
+}
+
+fn outline_points(bounds: &ObjectBounds, camera: &Camera) -> String {
+    bounds
+        .corners()
+        .iter()
+        .map(|corner| {
+            let pixel = world_to_pixel(*corner, camera);
+            format!("{},{}", pixel.x, pixel.y)
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Selects objects by clicking the canvas, shift-clicking or dragging a marquee, and moves,
+/// resizes and rotates them with handles. The result is written back as one undoable edit
+/// when the pointer is released. Double-clicking a text item opens it in the text editor.
+#[component]
+pub fn SelectionOverlay(
+    renderer: LocalResource<(Arc<Mutex<CanvasRenderer>>, Arc<Mutex<EditorState>>)>,
+    sequence_id: String,
+    pen_mode: RwSignal<PenMode>,
+    selection: RwSignal<Vec<(Uuid, ObjectType)>>,
+    snapping: RwSignal<SnapSettings>,
+    editing_text: RwSignal<Option<Uuid>>,
+) -> impl IntoView {
+    let state = expect_context::<Store<SavedState>>();
+    let sequences = state.sequences();
+
+    let camera = RwSignal::new(None::<Camera>);
+    let objects = RwSignal::new(Vec::<ObjectBounds>::new());
+    // frame and objects as they'd be if the drag ended now
+    let preview = RwSignal::new(None::<(ObjectBounds, Vec<ObjectBounds>)>);
+    // corners of the marquee, in world space
+    let marquee = RwSignal::new(None::<(Point, Point)>);
+    let guides = RwSignal::new(Vec::<Guide>::new());
+    let (selection_error, set_selection_error) = signal(None::<String>);
+
+    let drag = StoredValue::new(None::<SelectionDrag>);
+
+    let refresh_bounds = move || {
+        let Some(renderer) = renderer.get_untracked() else {
+            return;
+        };
+        let (canvas_renderer, _) = renderer.take();
+        let editor_m = canvas_renderer.lock().unwrap().editor.clone();
+        let editor = editor_m.lock().unwrap();
+
+        camera.set(editor.camera);
+        objects.set(
+            selection
+                .get_untracked()
+                .iter()
+                .filter_map(|(object_id, _)| object_bounds(&editor, *object_id))
+                .collect(),
+        );
+    };
+
+    // selections from the layer panel and edits elsewhere move the handles too
+    Effect::new(move |_| {
+        selection.track();
+        sequences.track();
+        renderer.track();
+
+        set_selection_error.set(None);
+        refresh_bounds();
+    });
+
+    let pointer_pixel = move |ev: &MouseEvent| -> Option<(Point, Camera)> {
+        let camera = camera.get_untracked()?;
+        let target = ev.current_target()?.dyn_into::<Element>().ok()?;
+
+        Some((
+            client_to_pixel(&target, ev.client_x(), ev.client_y(), &camera),
+            camera,
+        ))
+    };
+
+    let sync_sequences = move |editor_state: &EditorState| {
+        let saved_state = editor_state
+            .record_state
+            .saved_state
+            .as_ref()
+            .expect("Couldn't get saved state");
+
+        sequences.set(saved_state.sequences.clone());
+        selection.set(editor_state.selected_objects.clone());
+    };
+
+    let on_pointer_down = move |ev: PointerEvent| {
+        // the camera may have moved since the handles were drawn
+        refresh_bounds();
+
+        let Some((pixel, camera)) = pointer_pixel(&ev) else {
+            return;
+        };
+        let point = pixel_to_world(pixel, &camera);
+
+        // keep getting moves when the pointer leaves the canvas mid-drag
+        if let Some(target) = ev
+            .current_target()
+            .and_then(|t| t.dyn_into::<Element>().ok())
+        {
+            let _ = target.set_pointer_capture(ev.pointer_id());
+        }
+
+        let selected = objects.get_untracked();
+        let frame = selection_frame(&selected);
+
+        let handle = frame
+            .as_ref()
+            .and_then(|frame| handle_at(frame, pixel, &camera, selected.len() == 1));
+
+        if let (Some(kind), Some(frame)) = (handle, frame.clone()) {
+            drag.set_value(Some(SelectionDrag {
+                kind,
+                start: point,
+                frame,
+                objects: selected,
+                others: Vec::new(),
+            }));
+            return;
+        }
+
+        let renderer = renderer.get_untracked().expect("Couldn't get renderer");
+        let (canvas_renderer, editor_state) = renderer.take();
+        let editor_m = canvas_renderer.lock().unwrap().editor.clone();
+        let (hit, visible) = {
+            let editor = editor_m.lock().unwrap();
+            (hit_test(&editor, point), visible_objects(&editor))
+        };
+        let others = |moving: &[ObjectBounds]| {
+            visible
+                .iter()
+                .filter(|o| !moving.iter().any(|m| m.id == o.id))
+                .cloned()
+                .collect::<Vec<_>>()
+        };
+
+        let mut editor_state = editor_state.lock().unwrap();
+
+        match hit {
+            Some(hit) if ev.shift_key() => {
+                editor_state.toggle_selected(hit.id, hit.object_type.clone());
+                selection.set(editor_state.selected_objects.clone());
+            }
+            // grabbing any selected object moves the whole selection
+            Some(hit) if selected.iter().any(|o| o.id == hit.id) => {
+                drag.set_value(Some(SelectionDrag {
+                    kind: DragKind::Move,
+                    start: point,
+                    frame: frame.unwrap_or_else(|| hit.clone()),
+                    others: others(&selected),
+                    objects: selected,
+                }));
+            }
+            Some(hit) => {
+                editor_state.select_object(hit.id, hit.object_type.clone());
+                selection.set(editor_state.selected_objects.clone());
+
+                drag.set_value(Some(SelectionDrag {
+                    kind: DragKind::Move,
+                    start: point,
+                    frame: hit.clone(),
+                    others: others(std::slice::from_ref(&hit)),
+                    objects: vec![hit],
+                }));
+            }
+            None => {
+                if !ev.shift_key() {
+                    editor_state.clear_selection();
+                    selection.set(Vec::new());
+                }
+
+                marquee.set(Some((point, point)));
+            }
+        }
+    };
+
+    let on_pointer_move = move |ev: PointerEvent| {
+        let Some((pixel, camera)) = pointer_pixel(&ev) else {
+            return;
+        };
+        let point = pixel_to_world(pixel, &camera);
+
+        if let Some((start, _)) = marquee.get_untracked() {
+            marquee.set(Some((start, point)));
+            return;
+        }
+
+        let Some(current_drag) = drag.get_value() else {
+            return;
+        };
+
+        let mut frame = dragged_frame(&current_drag, point, ev.shift_key());
+
+        // alt turns snapping off for the moment
+        if current_drag.kind == DragKind::Move && !ev.alt_key() {
+            let (offset, snapped_to) = snap_frame(
+                &frame,
+                &current_drag.others,
+                &snapping.get_untracked(),
+                SNAP_DISTANCE / camera.zoom.max(0.01),
+            );
+
+            frame.center.x += offset.x;
+            frame.center.y += offset.y;
+            guides.set(snapped_to);
+        } else {
+            guides.set(Vec::new());
+        }
+
+        let moved = moved_objects(&current_drag.frame, &frame, &current_drag.objects);
+
+        preview.set(Some((frame, moved)));
+    };
+
+    let select_in_marquee = move |additive: bool| {
+        let Some((a, b)) = marquee.get_untracked() else {
+            return;
+        };
+        marquee.set(None);
+
+        let (min, max) = corners_extent(&[a, b]).expect("Couldn't get marquee extent");
+
+        let renderer = renderer.get_untracked().expect("Couldn't get renderer");
+        let (canvas_renderer, editor_state) = renderer.take();
+        let editor_m = canvas_renderer.lock().unwrap().editor.clone();
+
+        // anything the marquee touches is picked up
+        let touched = visible_objects(&editor_m.lock().unwrap())
+            .into_iter()
+            .filter(|o| {
+                corners_extent(&o.corners())
+                    .map(|(o_min, o_max)| {
+                        o_min.x <= max.x && o_max.x >= min.x && o_min.y <= max.y && o_max.y >= min.y
+                    })
+                    .unwrap_or(false)
+            })
+            .map(|o| (o.id, o.object_type))
+            .collect::<Vec<_>>();
+
+        let mut editor_state = editor_state.lock().unwrap();
+
+        let mut picked = if additive {
+            editor_state.selected_objects.clone()
+        } else {
+            Vec::new()
+        };
+        for (object_id, object_type) in touched {
+            if !picked.iter().any(|(id, _)| *id == object_id) {
+                picked.push((object_id, object_type));
+            }
+        }
+
+        editor_state.set_selection(picked);
+        selection.set(editor_state.selected_objects.clone());
+    };
+
+    let on_pointer_up = {
+        let sequence_id = sequence_id.clone();
+
+        move |ev: PointerEvent| {
+            if marquee.get_untracked().is_some() {
+                select_in_marquee(ev.shift_key());
+                return;
+            }
+
+            let current_drag = drag.get_value();
+            drag.set_value(None);
+            guides.set(Vec::new());
+
+            let (Some(current_drag), Some((frame, moved))) =
+                (current_drag, preview.get_untracked())
+            else {
+                return;
+            };
+            preview.set(None);
+
+            let renderer = renderer.get_untracked().expect("Couldn't get renderer");
+            let (canvas_renderer, editor_state) = renderer.take();
+            let canvas_renderer = canvas_renderer.lock().unwrap();
+
+            let mut editor_state = editor_state.lock().unwrap();
+
+            let translation = |start: &ObjectBounds, end: &ObjectBounds| {
+                (
+                    (end.center.x - start.center.x).round() as i32,
+                    (end.center.y - start.center.y).round() as i32,
+                )
+            };
+
+            let result = match (current_drag.objects.as_slice(), moved.as_slice()) {
+                ([start], [end]) => editor_state.transform_object(
+                    sequence_id.clone(),
+                    start.id,
+                    start.object_type.clone(),
+                    translation(start, end),
+                    (frame.rotation - current_drag.frame.rotation).round() as i32,
+                    end.dimensions,
+                ),
+                (starts, ends) => editor_state.transform_objects(
+                    sequence_id.clone(),
+                    starts
+                        .iter()
+                        .zip(ends.iter())
+                        .map(|(start, end)| {
+                            (
+                                start.id,
+                                start.object_type.clone(),
+                                translation(start, end),
+                                end.dimensions,
+                            )
+                        })
+                        .collect(),
+                ),
+            };
+
+            if let Err(e) = result {
+                set_selection_error.set(Some(e));
+                return;
+            }
+
+            sync_sequences(&editor_state);
+
+            drop(editor_state);
+
+            canvas_renderer.redraw.request();
+        }
+    };
+
+    let on_double_click = move |ev: MouseEvent| {
+        let Some((pixel, camera)) = pointer_pixel(&ev) else {
+            return;
+        };
+        let point = pixel_to_world(pixel, &camera);
+
+        let renderer = renderer.get_untracked().expect("Couldn't get renderer");
+        let (canvas_renderer, editor_state) = renderer.take();
+        let editor_m = canvas_renderer.lock().unwrap().editor.clone();
+        let hit = hit_test(&editor_m.lock().unwrap(), point);
+
+        let Some(hit) = hit.filter(|hit| hit.object_type == ObjectType::TextItem) else {
+            return;
+        };
+
+        let mut editor_state = editor_state.lock().unwrap();
+        editor_state.select_object(hit.id, hit.object_type);
+        selection.set(editor_state.selected_objects.clone());
+
+        editing_text.set(Some(hit.id));
+    };
+
+    let on_pointer_cancel = move |_: PointerEvent| {
+        drag.set_value(None);
+        preview.set(None);
+        marquee.set(None);
+        guides.set(Vec::new());
+    };
+
+    let view_box = move || {
+        camera
+            .get()
+            .map(|camera| {
+                format!(
+                    "0 0 {} {}",
+                    camera.window_size.width, camera.window_size.height
+                )
+            })
+            .unwrap_or_else(|| "0 0 900 450".to_string())
+    };
+
+    view! {
+        <Show when=move || pen_mode.get() == PenMode::Off>
+            <svg
+                class="absolute top-0 left-0 w-full h-full"
+                style="touch-action: none;"
+                viewBox=view_box
+                on:pointerdown=on_pointer_down
+                on:pointermove=on_pointer_move
+                on:pointerup=on_pointer_up
+                on:pointercancel=on_pointer_cancel
+                on:dblclick=on_double_click
+            >
+                {move || {
+                    let Some(camera) = camera.get() else {
+                        return ().into_any();
+                    };
+
+                    let dragging = preview.get();
+                    let (frame, shown) = match dragging.clone() {
+                        Some((frame, moved)) => (Some(frame), moved),
+                        None => {
+                            let shown = objects.get();
+                            (selection_frame(&shown), shown)
+                        }
+                    };
+                    let Some(frame) = frame else {
+                        return ().into_any();
+                    };
+
+                    let dash = if dragging.is_some() { "4 2" } else { "none" };
+
+                    // each object gets its own outline when several are selected
+                    let outlines = if shown.len() > 1 {
+                        shown
+                            .iter()
+                            .map(|object| {
+                                view! {
+                                    <polygon
+                                        points=outline_points(object, &camera)
+                                        fill="none"
+                                        stroke="#a5b4fc"
+                                        stroke-width="1"
+                                        stroke-dasharray=dash
+                                    />
+                                }
+                            })
+                            .collect_view()
+                            .into_any()
+                    } else {
+                        ().into_any()
+                    };
+
+                    let rotate = (shown.len() == 1)
+                        .then(|| {
+                            let top = world_to_pixel(resize_handle(&frame, (0.0, -1.0)), &camera);
+                            let rotate = world_to_pixel(rotate_handle(&frame, &camera), &camera);
+
+                            view! {
+                                <line
+                                    x1=top.x.to_string()
+                                    y1=top.y.to_string()
+                                    x2=rotate.x.to_string()
+                                    y2=rotate.y.to_string()
+                                    stroke="#6366f1"
+                                    stroke-width="1"
+                                />
+                                <circle
+                                    cx=rotate.x.to_string()
+                                    cy=rotate.y.to_string()
+                                    r="5"
+                                    fill="white"
+                                    stroke="#6366f1"
+                                    class="cursor-grab"
+                                />
+                            }
+                        });
+
+                    let handles = RESIZE_HANDLES
+                        .iter()
+                        .map(|side| {
+                            let handle = world_to_pixel(resize_handle(&frame, *side), &camera);
+
+                            view! {
+                                <rect
+                                    x=(handle.x - 4.0).to_string()
+                                    y=(handle.y - 4.0).to_string()
+                                    width="8"
+                                    height="8"
+                                    fill="white"
+                                    stroke="#6366f1"
+                                    class="cursor-pointer"
+                                />
+                            }
+                        })
+                        .collect_view();
+
+                    view! {
+                        {outlines}
+                        <polygon
+                            points=outline_points(&frame, &camera)
+                            fill="none"
+                            stroke="#6366f1"
+                            stroke-width="1"
+                            stroke-dasharray=dash
+                            class="cursor-move"
+                        />
+                        {rotate}
+                        {handles}
+                    }
+                        .into_any()
+                }}
+                {move || {
+                    let camera = camera.get()?;
+
+                    Some(
+                        guides
+                            .get()
+                            .into_iter()
+                            .map(|guide| {
+                                let (from, to) = if guide.vertical {
+                                    (
+                                        Point { x: guide.at, y: guide.from },
+                                        Point { x: guide.at, y: guide.to },
+                                    )
+                                } else {
+                                    (
+                                        Point { x: guide.from, y: guide.at },
+                                        Point { x: guide.to, y: guide.at },
+                                    )
+                                };
+                                let (from, to) = (
+                                    world_to_pixel(from, &camera),
+                                    world_to_pixel(to, &camera),
+                                );
+
+                                view! {
+                                    <line
+                                        x1=from.x.to_string()
+                                        y1=from.y.to_string()
+                                        x2=to.x.to_string()
+                                        y2=to.y.to_string()
+                                        stroke="#ec4899"
+                                        stroke-width="1"
+                                    />
+                                }
+                            })
+                            .collect_view(),
+                    )
+                }}
+                {move || {
+                    let camera = camera.get()?;
+                    let (a, b) = marquee.get()?;
+                    let (min, max) = corners_extent(
+                        &[world_to_pixel(a, &camera), world_to_pixel(b, &camera)],
+                    )?;
+
+                    Some(
+                        view! {
+                            <rect
+                                x=min.x.to_string()
+                                y=min.y.to_string()
+                                width=(max.x - min.x).to_string()
+                                height=(max.y - min.y).to_string()
+                                fill="#6366f1"
+                                fill-opacity="0.1"
+                                stroke="#6366f1"
+                                stroke-width="1"
+                            />
+                        },
+                    )
+                }}
+            </svg>
+            {move || {
+                selection_error
+                    .get()
+                    .map(|e| {
+                        view! {
+                            <span class="absolute bottom-0 left-0 text-xs text-red-500 bg-white/90 p-1">
+                                {e}
+                            </span>
+                        }
+                    })
+            }}
+        </Show>
+    }
+}
@@ -0,0 +1,185 @@
+use leptos::prelude::*;
+use reactive_stores::Store;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use stunts_engine::polygon::SavedShape;
+use uuid::Uuid;
+
+use crate::canvas_renderer::CanvasRenderer;
+use crate::components::pen_tool::PenMode;
+use crate::editor_state::EditorState;
+use crate::helpers::shapes::{shape_label, shape_params, with_shape_param};
+use crate::helpers::utilities::{SavedState, SavedStateStoreFields};
+
+/// Parameters of the library shapes in the sequence, editable after they're created.
+/// Pen paths get a button to edit their points on the canvas instead.
+#[component]
+pub fn ShapePanel(
+    renderer: LocalResource<(Arc<Mutex<CanvasRenderer>>, Arc<Mutex<EditorState>>)>,
+    sequence_id: String,
+    pen_mode: RwSignal<PenMode>,
+) -> impl IntoView {
+    let state = expect_context::<Store<SavedState>>();
+    let sequences = state.sequences();
+
+    let (shape_error, set_shape_error) = signal(None::<String>);
+
+    // only shapes with parameters or points to edit are worth listing
+    let shapes = Memo::new({
+        let sequence_id = sequence_id.clone();
+
+        move |_| {
+            sequences
+                .get()
+                .iter()
+                .find(|s| s.id == sequence_id)
+                .map(|s| {
+                    s.active_polygons
+                        .iter()
+                        .filter_map(|p| {
+                            p.shape
+                                .clone()
+                                .filter(|shape| {
+                                    matches!(shape, SavedShape::Path { .. })
+                                        || !shape_params(shape).is_empty()
+                                })
+                                .map(|shape| (p.id.clone(), p.name.clone(), shape))
+                        })
+                        .collect::<Vec<_>>()
+                })
+                .unwrap_or_default()
+        }
+    });
+
+    let update_param = Callback::new({
+        let sequence_id = sequence_id.clone();
+
+        move |(polygon_id, shape, param, value): (
+            String,
+            SavedShape,
+            &'static str,
+            String,
+        )| {
+            let Ok(value) = value.parse::<i32>() else {
+                set_shape_error.set(Some(format!("{} must be a whole number", param)));
+                return;
+            };
+
+            let renderer = renderer.get_untracked().expect("Couldn't get renderer");
+            let (canvas_renderer, editor_state) = renderer.take();
+            let canvas_renderer = canvas_renderer.lock().unwrap();
+            let editor_m = canvas_renderer.editor.clone();
+
+            let polygon_id = Uuid::from_str(&polygon_id).expect("Couldn't convert string to uuid");
+            let new_shape = with_shape_param(&shape, param, value);
+
+            let mut editor_state = editor_state.lock().unwrap();
+
+            if let Err(e) = editor_state.update_polygon_shape(polygon_id, new_shape) {
+                set_shape_error.set(Some(e));
+                return;
+            }
+
+            set_shape_error.set(None);
+
+            let saved_state = editor_state
+                .record_state
+                .saved_state
+                .as_ref()
+                .expect("Couldn't get saved state");
+            let updated_sequence = saved_state
+                .sequences
+                .iter()
+                .find(|s| s.id == sequence_id)
+                .expect("Couldn't get updated sequence")
+                .clone();
+
+            sequences.set(saved_state.sequences.clone());
+
+            drop(editor_state);
+
+            let mut editor = editor_m.lock().unwrap();
+            editor.current_sequence_data = Some(updated_sequence);
+        }
+    });
+
+    view! {
+        <Show when=move || !shapes.get().is_empty()>
+            <div class="flex flex-col w-full gap-2">
+                <h5>"Shapes"</h5>
+                {move || {
+                    shapes
+                        .get()
+                        .into_iter()
+                        .map(|(polygon_id, name, shape)| {
+                            let label = format!("{} ({})", name, shape_label(&shape));
+                            let params = shape_params(&shape)
+                                .into_iter()
+                                .enumerate()
+                                .map(|(i, (param, value, min, max))| {
+                                    let polygon_id = polygon_id.clone();
+                                    let shape = shape.clone();
+                                    let input_id = format!("shape_{}_{}", polygon_id, i);
+
+                                    view! {
+                                        <div class="flex flex-row gap-2 items-center">
+                                            <label for=input_id.clone() class="text-xs w-24">
+                                                {param}
+                                            </label>
+                                            <input
+                                                type="number"
+                                                id=input_id.clone()
+                                                name=input_id
+                                                class="border rounded px-2 py-1 w-full min-w-2 text-xs"
+                                                min=min.to_string()
+                                                max=max.to_string()
+                                                prop:value=value.to_string()
+                                                on:change=move |ev| {
+                                                    update_param
+                                                        .run((
+                                                            polygon_id.clone(),
+                                                            shape.clone(),
+                                                            param,
+                                                            event_target_value(&ev),
+                                                        ))
+                                                }
+                                            />
+                                        </div>
+                                    }
+                                })
+                                .collect_view();
+
+                            let edit_points = matches!(shape, SavedShape::Path { .. })
+                                .then(|| {
+                                    let polygon_id = Uuid::from_str(&polygon_id)
+                                        .expect("Couldn't convert string to uuid");
+
+                                    view! {
+                                        <button
+                                            class="text-xs rounded-md border px-2 py-1 hover:bg-gray-200"
+                                            on:click=move |_| pen_mode.set(PenMode::Edit(polygon_id))
+                                        >
+                                            "Edit Points"
+                                        </button>
+                                    }
+                                });
+
+                            view! {
+                                <div class="flex flex-col gap-1">
+                                    <span class="text-xs">{label}</span>
+                                    {params}
+                                    {edit_points}
+                                </div>
+                            }
+                        })
+                        .collect_view()
+                }}
+                {move || {
+                    shape_error
+                        .get()
+                        .map(|e| view! { <span class="text-xs text-red-500">{e}</span> })
+                }}
+            </div>
+        </Show>
+    }
+}
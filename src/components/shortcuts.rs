@@ -0,0 +1,565 @@
+use codee::string::JsonSerdeCodec;
+use leptos::ev;
+use leptos::prelude::*;
+use leptos_use::storage::use_local_storage;
+use reactive_stores::Store;
+use std::sync::{Arc, Mutex};
+use stunts_engine::animations::ObjectType;
+use uuid::Uuid;
+use wasm_bindgen::JsCast;
+use wasm_bindgen_futures::spawn_local;
+use web_sys::{ClipboardEvent, HtmlDocument};
+
+use crate::canvas_renderer::CanvasRenderer;
+use crate::components::pen_tool::PenMode;
+use crate::editor_state::EditorState;
+use crate::helpers::clipboard::{
+    read_clipboard, read_clipboard_text, write_clipboard, ClipboardContents,
+};
+use crate::helpers::keymap::{typing_in_field, EditorAction, KeyBinding, Keymap};
+use crate::helpers::object_copy::{copy_objects, paste_objects, CopiedObject, COPY_OFFSET};
+use crate::helpers::object_transform::{object_bounds, visible_objects};
+use crate::helpers::users::AuthToken;
+use crate::helpers::utilities::{SavedState, SavedStateStoreFields};
+
+/// Keyboard shortcuts of the sequence view, and the overlay listing them.
+/// Bindings can be changed from the overlay and are kept in local storage.
+#[component]
+pub fn EditorShortcuts(
+    renderer: LocalResource<(Arc<Mutex<CanvasRenderer>>, Arc<Mutex<EditorState>>)>,
+    sequence_id: String,
+    pen_mode: RwSignal<PenMode>,
+    selection: RwSignal<Vec<(Uuid, ObjectType)>>,
+) -> impl IntoView {
+    let (auth_state, _, _) = use_local_storage::<AuthToken, JsonSerdeCodec>("auth-token");
+    let (keymap, set_keymap, _) = use_local_storage::<Keymap, JsonSerdeCodec>("editor-keymap");
+
+    let state = expect_context::<Store<SavedState>>();
+    let sequences = state.sequences();
+
+    let show_help = RwSignal::new(false);
+    // the action waiting for its new key
+    let capturing = RwSignal::new(None::<EditorAction>);
+    let (rebind_error, set_rebind_error) = signal(None::<String>);
+    let (shortcut_error, set_shortcut_error) = signal(None::<String>);
+
+    // pasting the same objects again lands a step further along each time
+    let pastes = StoredValue::new((String::new(), 0));
+
+    // undo and redo can hide objects, those shouldn't stay selected
+    let sync_sequences = move |editor_state: &mut EditorState, canvas_renderer: &CanvasRenderer| {
+        {
+            let editor = canvas_renderer.editor.lock().unwrap();
+            let visible = visible_objects(&editor);
+            let remaining = editor_state
+                .selected_objects
+                .iter()
+                .filter(|(id, _)| visible.iter().any(|o| o.id == *id))
+                .cloned()
+                .collect();
+            editor_state.set_selection(remaining);
+        }
+
+        let saved_state = editor_state
+            .record_state
+            .saved_state
+            .as_ref()
+            .expect("Couldn't get saved state");
+
+        sequences.set(saved_state.sequences.clone());
+        selection.set(editor_state.selected_objects.clone());
+
+        canvas_renderer.redraw.request();
+    };
+
+    let selected_copies = {
+        let sequence_id = sequence_id.clone();
+
+        move |editor_state: &EditorState| {
+            let saved_state = editor_state
+                .record_state
+                .saved_state
+                .as_ref()
+                .expect("Couldn't get saved state");
+
+            saved_state
+                .sequences
+                .iter()
+                .find(|s| s.id == sequence_id)
+                .map(|sequence| copy_objects(sequence, &selection.get_untracked()))
+                .unwrap_or_default()
+        }
+    };
+
+    let paste = {
+        let sequence_id = sequence_id.clone();
+
+        move |copied: Vec<CopiedObject>, offset: (i32, i32)| {
+            if copied.is_empty() {
+                return;
+            }
+
+            let renderer = renderer.get_untracked().expect("Couldn't get renderer");
+            let (canvas_renderer, editor_state) = renderer.take();
+            let editor_m = canvas_renderer.lock().unwrap().editor.clone();
+            let token = auth_state.get_untracked().token;
+            let sequence_id = sequence_id.clone();
+
+            spawn_local(async move {
+                let result = paste_objects(
+                    editor_m,
+                    editor_state.clone(),
+                    sequence_id,
+                    copied,
+                    offset,
+                    token,
+                )
+                .await;
+
+                match result {
+                    Ok(_) => {
+                        let mut editor_state = editor_state.lock().unwrap();
+                        sync_sequences(&mut editor_state, &canvas_renderer.lock().unwrap());
+                    }
+                    Err(e) => set_shortcut_error.set(Some(e)),
+                }
+            });
+        }
+    };
+
+    let paste_contents = {
+        let sequence_id = sequence_id.clone();
+        let paste = paste.clone();
+
+        move |contents: ClipboardContents| {
+            let key = contents
+                .objects
+                .iter()
+                .map(|c| c.object.id().to_string())
+                .collect::<Vec<_>>()
+                .join(",");
+
+            let count = pastes
+                .try_update_value(|(last, count)| {
+                    if *last == key {
+                        *count += 1;
+                    } else {
+                        *last = key;
+                        *count = 1;
+                    }
+
+                    *count
+                })
+                .unwrap_or(1);
+
+            // in another sequence or project the first paste keeps the original position
+            let steps = if contents.sequence_id == sequence_id {
+                count
+            } else {
+                count - 1
+            };
+
+            paste(
+                contents.objects,
+                (COPY_OFFSET.0 * steps, COPY_OFFSET.1 * steps),
+            );
+        }
+    };
+
+    // a held nudge key is one undo step, its transaction opens on the first repeat
+    let nudging = StoredValue::new(false);
+
+    let end_nudge = move |editor_state: &mut EditorState| {
+        if nudging.try_update_value(std::mem::take).unwrap_or(false) {
+            editor_state.commit_transaction();
+        }
+    };
+
+    let release_nudge = move || {
+        if !nudging.try_get_value().unwrap_or(false) {
+            return;
+        }
+
+        let Some(renderer) = renderer.try_get_untracked().flatten() else {
+            return;
+        };
+        let (canvas_renderer, editor_state) = renderer.take();
+        let canvas_renderer = canvas_renderer.lock().unwrap();
+        let mut editor_state = editor_state.lock().unwrap();
+
+        end_nudge(&mut editor_state);
+        sync_sequences(&mut editor_state, &canvas_renderer);
+    };
+
+    let run_action = Callback::new({
+        let paste_contents = paste_contents.clone();
+        let selected_copies = selected_copies.clone();
+        let sequence_id = sequence_id.clone();
+
+        move |(action, repeat): (EditorAction, bool)| {
+            set_shortcut_error.set(None);
+
+            if action == EditorAction::ShowShortcuts {
+                show_help.update(|show| *show = !*show);
+                return;
+            }
+
+            let renderer = renderer.get_untracked().expect("Couldn't get renderer");
+            let (canvas_renderer, editor_state) = renderer.take();
+            let canvas_renderer = canvas_renderer.lock().unwrap();
+            let mut editor_state = editor_state.lock().unwrap();
+
+            // undo and the rest refuse to run inside a transaction
+            if action.nudge().is_none() {
+                end_nudge(&mut editor_state);
+            }
+
+            let selected = selection.get_untracked();
+
+            let result = match action {
+                EditorAction::Undo | EditorAction::Redo => {
+                    if action == EditorAction::Undo {
+                        editor_state.undo();
+                    } else {
+                        editor_state.redo();
+                    }
+
+                    // plain property edits leave the editor's copy of the sequence alone
+                    let updated_sequence = editor_state
+                        .record_state
+                        .saved_state
+                        .as_ref()
+                        .and_then(|s| s.sequences.iter().find(|s| s.id == sequence_id))
+                        .cloned();
+
+                    if let Some(updated_sequence) = updated_sequence {
+                        let mut editor = canvas_renderer.editor.lock().unwrap();
+                        editor.current_sequence_data = Some(updated_sequence.clone());
+                        editor.update_motion_paths(&updated_sequence);
+                    }
+
+                    Ok(())
+                }
+                EditorAction::Delete => editor_state.delete_objects(sequence_id.clone(), selected),
+                EditorAction::Copy | EditorAction::Cut => {
+                    // the clipboard can only be written from its own events, the listeners
+                    // below handle those and need the locks
+                    drop(editor_state);
+                    drop(canvas_renderer);
+
+                    let command = if action == EditorAction::Copy { "copy" } else { "cut" };
+                    let written = document()
+                        .unchecked_into::<HtmlDocument>()
+                        .exec_command(command)
+                        .unwrap_or(false);
+
+                    if !written {
+                        set_shortcut_error.set(Some("Couldn't use the clipboard".to_string()));
+                    }
+
+                    return;
+                }
+                EditorAction::Duplicate => {
+                    let copied = selected_copies(&editor_state);
+
+                    drop(editor_state);
+                    drop(canvas_renderer);
+
+                    paste(copied, COPY_OFFSET);
+                    return;
+                }
+                EditorAction::Paste => {
+                    drop(editor_state);
+                    drop(canvas_renderer);
+
+                    let paste_contents = paste_contents.clone();
+
+                    spawn_local(async move {
+                        match read_clipboard_text().await {
+                            Some(contents) => paste_contents(contents),
+                            None => set_shortcut_error
+                                .set(Some("There are no copied objects to paste".to_string())),
+                        }
+                    });
+
+                    return;
+                }
+                EditorAction::SelectAll => {
+                    let all = {
+                        let editor = canvas_renderer.editor.lock().unwrap();
+
+                        visible_objects(&editor)
+                            .into_iter()
+                            .map(|o| (o.id, o.object_type))
+                            .collect()
+                    };
+
+                    editor_state.set_selection(all);
+                    Ok(())
+                }
+                EditorAction::PlayPause => {
+                    canvas_renderer.toggle_playback();
+                    Ok(())
+                }
+                EditorAction::ShowShortcuts => Ok(()),
+                nudge => {
+                    let (dx, dy) = nudge.nudge().unwrap_or_default();
+
+                    if repeat && !nudging.get_value() {
+                        editor_state.begin_transaction();
+                        nudging.set_value(true);
+                    }
+
+                    let transforms = {
+                        let editor = canvas_renderer.editor.lock().unwrap();
+
+                        selected
+                            .iter()
+                            .filter_map(|(object_id, object_type)| {
+                                let bounds = object_bounds(&editor, *object_id)?;
+
+                                Some((*object_id, object_type.clone(), (dx, dy), bounds.dimensions))
+                            })
+                            .collect::<Vec<_>>()
+                    };
+
+                    if transforms.is_empty() {
+                        return;
+                    }
+
+                    editor_state.transform_objects(sequence_id.clone(), transforms)
+                }
+            };
+
+            if let Err(e) = result {
+                set_shortcut_error.set(Some(e));
+            }
+
+            sync_sequences(&mut editor_state, &canvas_renderer);
+        }
+    });
+
+    let write_selection = move |ev: ClipboardEvent, cut: bool| {
+        // leave copying page text alone
+        if pen_mode.get_untracked() != PenMode::Off || typing_in_field(&ev) {
+            return;
+        }
+
+        let selected = selection.get_untracked();
+        if selected.is_empty() {
+            return;
+        }
+
+        let Some(data) = ev.clipboard_data() else {
+            return;
+        };
+
+        let renderer = renderer.get_untracked().expect("Couldn't get renderer");
+        let (canvas_renderer, editor_state) = renderer.take();
+        let canvas_renderer = canvas_renderer.lock().unwrap();
+        let mut editor_state = editor_state.lock().unwrap();
+
+        let contents = ClipboardContents::new(sequence_id.clone(), selected_copies(&editor_state));
+        if !write_clipboard(&data, &contents) {
+            set_shortcut_error.set(Some("Couldn't write to the clipboard".to_string()));
+            return;
+        }
+
+        ev.prevent_default();
+
+        if cut {
+            if let Err(e) = editor_state.delete_objects(sequence_id.clone(), selected) {
+                set_shortcut_error.set(Some(e));
+            }
+
+            sync_sequences(&mut editor_state, &canvas_renderer);
+        }
+    };
+
+    let copy_handle = window_event_listener(ev::copy, {
+        let write_selection = write_selection.clone();
+        move |ev| write_selection(ev, false)
+    });
+    let cut_handle = window_event_listener(ev::cut, move |ev| write_selection(ev, true));
+    let paste_handle = window_event_listener(ev::paste, move |ev| {
+        if pen_mode.get_untracked() != PenMode::Off || typing_in_field(&ev) {
+            return;
+        }
+
+        // anything else on the clipboard is left to the page
+        let Some(contents) = ev.clipboard_data().and_then(|data| read_clipboard(&data)) else {
+            return;
+        };
+
+        ev.prevent_default();
+        paste_contents(contents);
+    });
+    on_cleanup(move || {
+        copy_handle.remove();
+        cut_handle.remove();
+        paste_handle.remove();
+    });
+
+    let handle = window_event_listener(ev::keydown, move |ev| {
+        if let Some(action) = capturing.get_untracked() {
+            ev.prevent_default();
+
+            if ev.key() == "Escape" {
+                capturing.set(None);
+                return;
+            }
+
+            // wait for the key that goes with the modifiers
+            let Some(binding) = KeyBinding::from_event(&ev) else {
+                return;
+            };
+
+            let mut updated = keymap.get_untracked();
+            match updated.rebind(action, binding) {
+                Ok(()) => {
+                    set_keymap.set(updated);
+                    set_rebind_error.set(None);
+                }
+                Err(e) => set_rebind_error.set(Some(e)),
+            }
+
+            capturing.set(None);
+            return;
+        }
+
+        // the pen tool has keys of its own
+        if pen_mode.get_untracked() != PenMode::Off || typing_in_field(&ev) {
+            return;
+        }
+
+        if ev.key() == "Escape" && show_help.get_untracked() {
+            show_help.set(false);
+            return;
+        }
+
+        let Some(action) = keymap.get_untracked().action_for(&ev) else {
+            return;
+        };
+
+        // let the browser turn these into clipboard events, the listeners above take it from there
+        let native_clipboard = KeyBinding::from_event(&ev)
+            .map(|binding| binding.is_native_clipboard())
+            .unwrap_or(false);
+        if native_clipboard
+            && matches!(action, EditorAction::Copy | EditorAction::Cut | EditorAction::Paste)
+        {
+            return;
+        }
+
+        ev.prevent_default();
+
+        // holding a nudge key keeps moving, everything else happens once per press
+        if ev.repeat() && action.nudge().is_none() {
+            return;
+        }
+
+        run_action.run((action, ev.repeat()));
+    });
+    // the window losing focus means the keyup goes elsewhere
+    let keyup_handle = window_event_listener(ev::keyup, move |_| release_nudge());
+    let blur_handle = window_event_listener(ev::blur, move |_| release_nudge());
+    on_cleanup(move || {
+        release_nudge();
+        handle.remove();
+        keyup_handle.remove();
+        blur_handle.remove();
+    });
+
+    view! {
+        <Show when=move || shortcut_error.get().is_some()>
+            <div class="fixed bottom-4 left-4 z-50 flex flex-row gap-2 items-center p-2 rounded border border-red-400 bg-red-50 text-xs">
+                <span>{move || shortcut_error.get().unwrap_or_default()}</span>
+                <button
+                    class="px-2 rounded hover:bg-gray-200"
+                    on:click=move |_| set_shortcut_error.set(None)
+                >
+                    "Dismiss"
+                </button>
+            </div>
+        </Show>
+        <Show when=move || show_help.get()>
+            <div
+                class="fixed inset-0 z-50 flex items-center justify-center bg-black/40"
+                on:click=move |_| {
+                    capturing.set(None);
+                    show_help.set(false);
+                }
+            >
+                <div
+                    class="flex flex-col gap-2 w-[420px] max-h-[80vh] p-4 rounded-[15px] bg-white shadow-[0_0_15px_4px_rgba(0,0,0,0.16)]"
+                    on:click=move |ev| ev.stop_propagation()
+                >
+                    <div class="flex flex-row justify-between items-center">
+                        <h5>"Keyboard shortcuts"</h5>
+                        <button
+                            class="text-xs rounded-md border px-2 py-1 hover:bg-gray-200"
+                            on:click=move |_| show_help.set(false)
+                        >
+                            "Close"
+                        </button>
+                    </div>
+                    <span class="text-xs text-gray-500">
+                        "Ctrl is Cmd on macOS. Click a shortcut to change it, Esc cancels."
+                    </span>
+                    <ul class="flex flex-col overflow-y-auto text-xs">
+                        {move || {
+                            let keymap = keymap.get();
+
+                            EditorAction::ALL
+                                .into_iter()
+                                .map(|action| {
+                                    let bindings = keymap
+                                        .bindings(action)
+                                        .iter()
+                                        .map(|b| b.label())
+                                        .collect::<Vec<_>>()
+                                        .join(" or ");
+
+                                    view! {
+                                        <li class="flex flex-row justify-between items-center p-1 rounded hover:bg-gray-100">
+                                            <span>{action.label()}</span>
+                                            <button
+                                                class="min-w-[100px] rounded border px-2 py-1 font-mono hover:bg-gray-200"
+                                                on:click=move |_| {
+                                                    set_rebind_error.set(None);
+                                                    capturing.set(Some(action));
+                                                }
+                                            >
+                                                {move || {
+                                                    if capturing.get() == Some(action) {
+                                                        "Press a key...".to_string()
+                                                    } else {
+                                                        bindings.clone()
+                                                    }
+                                                }}
+                                            </button>
+                                        </li>
+                                    }
+                                })
+                                .collect_view()
+                        }}
+                    </ul>
+                    <Show when=move || rebind_error.get().is_some()>
+                        <span class="text-xs text-red-500">
+                            {move || rebind_error.get().unwrap_or_default()}
+                        </span>
+                    </Show>
+                    <button
+                        class="self-start text-xs rounded-md border px-2 py-1 hover:bg-gray-200"
+                        on:click=move |_| {
+                            set_keymap.set(Keymap::default());
+                            set_rebind_error.set(None);
+                        }
+                    >
+                        "Reset to defaults"
+                    </button>
+                </div>
+            </div>
+        </Show>
+    }
+}
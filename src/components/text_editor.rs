@@ -0,0 +1,383 @@
+use leptos::prelude::*;
+use reactive_stores::Store;
+use std::sync::{Arc, Mutex};
+use stunts_engine::animations::{ObjectType, Sequence};
+use stunts_engine::text_due::TextAlignment;
+use uuid::Uuid;
+use web_sys::KeyboardEvent;
+
+use crate::canvas_renderer::CanvasRenderer;
+use crate::editor_state::EditorState;
+use crate::helpers::backgrounds::{hex_to_rgba, rgba_to_hex};
+use crate::helpers::canvas_coords::world_to_pixel;
+use crate::helpers::object_transform::object_bounds;
+use crate::helpers::text_style::{font_names, TextStyle};
+use crate::helpers::utilities::{SavedState, SavedStateStoreFields};
+
+const ALIGNMENTS: [(TextAlignment, &str); 3] = [
+    (TextAlignment::Left, "Left"),
+    (TextAlignment::Center, "Center"),
+    (TextAlignment::Right, "Right"),
+];
+
+#[derive(Clone)]
+enum TextChange {
+    Text(String),
+    Font(String),
+    Size(i32),
+    LineHeight(i32),
+    LetterSpacing(i32),
+    Alignment(TextAlignment),
+    Color([i32; 4]),
+}
+
+impl TextChange {
+    fn apply(self, style: &mut TextStyle) {
+        match self {
+            TextChange::Text(text) => style.text = text,
+            TextChange::Font(font_family) => style.font_family = font_family,
+            TextChange::Size(font_size) => style.font_size = font_size,
+            TextChange::LineHeight(line_height) => style.line_height = line_height,
+            TextChange::LetterSpacing(letter_spacing) => style.letter_spacing = letter_spacing,
+            TextChange::Alignment(alignment) => style.alignment = alignment,
+            // the text keeps its own opacity
+            TextChange::Color(color) => {
+                style.color = [color[0], color[1], color[2], style.color[3]]
+            }
+        }
+    }
+}
+
+fn css_alignment(alignment: TextAlignment) -> &'static str {
+    match alignment {
+        TextAlignment::Left => "left",
+        TextAlignment::Center => "center",
+        TextAlignment::Right => "right",
+    }
+}
+
+fn saved_style(sequences: &[Sequence], sequence_id: &str, text_id: Uuid) -> Option<TextStyle> {
+    sequences
+        .iter()
+        .find(|s| s.id == sequence_id)?
+        .active_text_items
+        .iter()
+        .find(|t| t.id == text_id.to_string())
+        .map(TextStyle::from_saved)
+}
+
+/// Where the text sits over the canvas, as percentages of it, and the camera zoom
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct TextFrame {
+    left: f32,
+    top: f32,
+    width: f32,
+    height: f32,
+    zoom: f32,
+}
+
+/// Edits a text item in place, opened by double-clicking it on the canvas.
+/// Every change is one undo step and lays the text out again.
+#[component]
+pub fn TextEditor(
+    renderer: LocalResource<(Arc<Mutex<CanvasRenderer>>, Arc<Mutex<EditorState>>)>,
+    sequence_id: String,
+    selection: RwSignal<Vec<(Uuid, ObjectType)>>,
+    editing_text: RwSignal<Option<Uuid>>,
+) -> impl IntoView {
+    let state = expect_context::<Store<SavedState>>();
+    let sequences = state.sequences();
+
+    let (text_error, set_text_error) = signal(None::<String>);
+    let text_area = NodeRef::<leptos::html::Textarea>::new();
+    // typed but not yet committed, so it isn't lost if the editor closes first
+    let draft = StoredValue::new(None::<(Uuid, String)>);
+
+    // selecting something else closes the editor
+    Effect::new(move |_| {
+        let Some(text_id) = editing_text.get() else {
+            return;
+        };
+
+        if !selection.with(|s| s.iter().any(|(id, _)| *id == text_id)) {
+            editing_text.set(None);
+        }
+    });
+
+    Effect::new(move |_| {
+        editing_text.track();
+        set_text_error.set(None);
+    });
+
+    // ready to type as soon as it opens
+    Effect::new(move |_| {
+        editing_text.track();
+
+        if let Some(textarea) = text_area.get() {
+            let _ = textarea.focus();
+        }
+    });
+
+    let style = Memo::new({
+        let sequence_id = sequence_id.clone();
+
+        move |_| {
+            let text_id = editing_text.get()?;
+
+            sequences.with(|sequences| saved_style(sequences, &sequence_id, text_id))
+        }
+    });
+
+    let fonts = move || {
+        let Some(renderer) = renderer.get_untracked() else {
+            return Vec::new();
+        };
+        let (canvas_renderer, _) = renderer.take();
+        let editor_m = canvas_renderer.lock().unwrap().editor.clone();
+        let editor = editor_m.lock().unwrap();
+
+        font_names(&editor)
+    };
+
+    // re-read whenever the saved sequences change, moving the text moves the editor
+    let frame = Memo::new(move |_| {
+        sequences.track();
+
+        let text_id = editing_text.get()?;
+        let renderer = renderer.get()?;
+        let (canvas_renderer, _) = renderer.take();
+        let editor_m = canvas_renderer.lock().unwrap().editor.clone();
+        let editor = editor_m.lock().unwrap();
+
+        let camera = editor.camera.as_ref()?;
+        let bounds = object_bounds(&editor, text_id)?;
+
+        let pixels = bounds
+            .corners()
+            .iter()
+            .map(|corner| world_to_pixel(*corner, camera))
+            .collect::<Vec<_>>();
+        let min_x = pixels.iter().map(|p| p.x).fold(f32::MAX, f32::min);
+        let min_y = pixels.iter().map(|p| p.y).fold(f32::MAX, f32::min);
+        let max_x = pixels.iter().map(|p| p.x).fold(f32::MIN, f32::max);
+        let max_y = pixels.iter().map(|p| p.y).fold(f32::MIN, f32::max);
+
+        let (width, height) = (
+            camera.window_size.width as f32,
+            camera.window_size.height as f32,
+        );
+
+        Some(TextFrame {
+            left: min_x / width * 100.0,
+            top: min_y / height * 100.0,
+            width: (max_x - min_x) / width * 100.0,
+            height: (max_y - min_y) / height * 100.0,
+            zoom: camera.zoom,
+        })
+    });
+
+    let restyle = Callback::new(move |(text_id, change): (Uuid, TextChange)| {
+        let Some(mut new_style) =
+            sequences.with_untracked(|sequences| saved_style(sequences, &sequence_id, text_id))
+        else {
+            return;
+        };
+
+        change.apply(&mut new_style);
+
+        let renderer = renderer.get_untracked().expect("Couldn't get renderer");
+        let (canvas_renderer, editor_state) = renderer.take();
+        let canvas_renderer = canvas_renderer.lock().unwrap();
+
+        let mut editor_state = editor_state.lock().unwrap();
+
+        if let Err(e) = editor_state.update_text_style(sequence_id.clone(), text_id, new_style) {
+            set_text_error.set(Some(e));
+            return;
+        }
+        set_text_error.set(None);
+
+        let saved_state = editor_state
+            .record_state
+            .saved_state
+            .as_ref()
+            .expect("Couldn't get saved state");
+
+        sequences.set(saved_state.sequences.clone());
+
+        drop(editor_state);
+
+        canvas_renderer.redraw.request();
+    });
+
+    // clicking away can close the editor before the text area commits
+    Effect::new(move |_| {
+        let current = editing_text.get();
+
+        let Some((text_id, text)) = draft.get_value() else {
+            return;
+        };
+
+        if current != Some(text_id) {
+            draft.set_value(None);
+            restyle.run((text_id, TextChange::Text(text)));
+        }
+    });
+
+    let run_change = move |change: TextChange| {
+        if let Some(text_id) = editing_text.get_untracked() {
+            restyle.run((text_id, change));
+        }
+    };
+
+    let commit_text = move |text: String| {
+        draft.set_value(None);
+        run_change(TextChange::Text(text));
+    };
+
+    // escape or ctrl+enter keeps what was typed and closes the editor
+    let on_text_keydown = move |ev: KeyboardEvent| {
+        let closing =
+            ev.key() == "Escape" || (ev.key() == "Enter" && (ev.ctrl_key() || ev.meta_key()));
+
+        if !closing {
+            return;
+        }
+        ev.prevent_default();
+
+        commit_text(event_target_value(&ev));
+        editing_text.set(None);
+    };
+
+    // a field of the open text's style, kept up to date
+    let read = move |field: fn(&TextStyle) -> String| {
+        move || style.with(|s| s.as_ref().map(field).unwrap_or_default())
+    };
+    let place = move |field: fn(&TextFrame) -> String| {
+        move || frame.with(|f| f.as_ref().map(field).unwrap_or_default())
+    };
+    // sizes in pixels, following the camera zoom
+    let zoomed = move |field: fn(&TextStyle) -> i32| {
+        move || {
+            let zoom = frame.with(|f| f.map(|f| f.zoom).unwrap_or(1.0));
+
+            style.with(|s| {
+                s.as_ref()
+                    .map(|s| format!("{}px", field(s) as f32 * zoom))
+                    .unwrap_or_default()
+            })
+        }
+    };
+
+    let number_input = move |label: &'static str,
+                             value: fn(&TextStyle) -> String,
+                             change: fn(i32) -> TextChange| {
+        view! {
+            <label class="flex flex-row gap-1 items-center text-xs">
+                {label}
+                <input
+                    type="number"
+                    class="w-12 border rounded px-1"
+                    prop:value=read(value)
+                    on:change=move |ev| {
+                        if let Ok(value) = event_target_value(&ev).parse::<i32>() {
+                            run_change(change(value));
+                        }
+                    }
+                />
+            </label>
+        }
+    };
+
+    view! {
+        <Show when=move || style.with(Option::is_some) && frame.with(Option::is_some)>
+            <div class="absolute top-0 left-0 right-0 flex flex-row flex-wrap gap-2 items-center p-1 bg-white/90 border-b">
+                <select
+                    class="text-xs"
+                    on:change=move |ev| run_change(TextChange::Font(event_target_value(&ev)))
+                >
+                    {fonts()
+                        .into_iter()
+                        .map(|name| {
+                            let value = name.clone();
+                            let selected = move || {
+                                style.with(|s| s.as_ref().is_some_and(|s| s.font_family == name))
+                            };
+
+                            view! {
+                                <option value=value.clone() prop:selected=selected>
+                                    {value}
+                                </option>
+                            }
+                        })
+                        .collect_view()}
+                </select>
+                {number_input("Size", |s| s.font_size.to_string(), TextChange::Size)}
+                {number_input("Line %", |s| s.line_height.to_string(), TextChange::LineHeight)}
+                {number_input(
+                    "Spacing",
+                    |s| s.letter_spacing.to_string(),
+                    TextChange::LetterSpacing,
+                )}
+                <div class="flex flex-row">
+                    {ALIGNMENTS
+                        .into_iter()
+                        .map(|(alignment, label)| {
+                            view! {
+                                <button
+                                    class="text-xs border px-2 hover:bg-gray-200"
+                                    class:bg-gray-300=move || {
+                                        style.with(|s| s.as_ref().is_some_and(|s| s.alignment == alignment))
+                                    }
+                                    on:click=move |_| run_change(TextChange::Alignment(alignment))
+                                >
+                                    {label}
+                                </button>
+                            }
+                        })
+                        .collect_view()}
+                </div>
+                <input
+                    type="color"
+                    prop:value=read(|s| rgba_to_hex(s.color))
+                    on:change=move |ev| {
+                        if let Some(color) = hex_to_rgba(&event_target_value(&ev)) {
+                            run_change(TextChange::Color(color));
+                        }
+                    }
+                />
+                <button
+                    class="text-xs rounded-md border px-2 py-1 hover:bg-gray-200"
+                    on:click=move |_| editing_text.set(None)
+                >
+                    "Done"
+                </button>
+                {move || {
+                    text_error.get().map(|e| view! { <span class="text-xs text-red-500">{e}</span> })
+                }}
+            </div>
+            <textarea
+                class="absolute p-0 m-0 border border-indigo-500 bg-white/80 resize-none outline-none"
+                style:left=place(|f| format!("{}%", f.left))
+                style:top=place(|f| format!("{}%", f.top))
+                style:width=place(|f| format!("{}%", f.width))
+                style:height=place(|f| format!("{}%", f.height))
+                style:font-family=read(|s| s.font_family.clone())
+                style:text-align=read(|s| css_alignment(s.alignment).to_string())
+                style:color=read(|s| rgba_to_hex(s.color))
+                style:line-height=read(|s| format!("{}%", s.line_height))
+                style:font-size=zoomed(|s| s.font_size)
+                style:letter-spacing=zoomed(|s| s.letter_spacing)
+                prop:value=read(|s| s.text.clone())
+                node_ref=text_area
+                on:input=move |ev| {
+                    if let Some(text_id) = editing_text.get_untracked() {
+                        draft.set_value(Some((text_id, event_target_value(&ev))));
+                    }
+                }
+                on:change=move |ev| commit_text(event_target_value(&ev))
+                on:keydown=on_text_keydown
+            />
+        </Show>
+    }
+}
@@ -5,21 +5,30 @@ use std::time::Duration;
 
 use stunts_engine::animations::{
     AnimationData, AnimationProperty, BackgroundFill, EasingType, KeyType, KeyframeValue,
-    ObjectType, UIKeyframe,
+    ObjectType, SavedGroupConfig, Sequence, UIKeyframe,
 };
 use stunts_engine::editor::{
     color_to_wgpu, string_to_f32, wgpu_to_human, Editor, InputValue, ObjectProperty, PathType,
+    Point,
 };
-use stunts_engine::polygon::{SavedPoint, SavedPolygonConfig};
+use stunts_engine::polygon::{SavedPoint, SavedPolygonConfig, SavedShape};
 use stunts_engine::st_image::SavedStImageConfig;
 use stunts_engine::st_video::SavedStVideoConfig;
 use stunts_engine::text_due::SavedTextRendererConfig;
+use serde::{Deserialize, Serialize};
 use undo::Edit;
 use undo::Record;
 use uuid::Uuid;
 use wasm_bindgen_futures::spawn_local;
 
+use crate::canvas_renderer::RedrawFlag;
 use crate::fetchers::projects::save_sequences_data;
+use crate::helpers::groups::{group_of, new_group};
+use crate::helpers::media::restore_saved_object;
+use crate::helpers::object_transform::{object_bounds, set_object_hidden, set_object_transform};
+use crate::helpers::shapes::{shape_border_radius, shape_points};
+use crate::helpers::text_style::{apply_text_style, TextStyle};
+use crate::helpers::users::stored_token;
 // use crate::helpers::utilities::save_saved_state_raw;
 use crate::helpers::utilities::SavedState;
 
@@ -40,6 +49,8 @@ impl Edit for ObjectEdit {
     type Output = ();
 
     fn edit(&mut self, record_state: &mut RecordState) {
+        record_state.redraw.request();
+
         let mut editor = record_state.editor.lock().unwrap();
         let mut saved_state = record_state
             .saved_state
@@ -67,11 +78,7 @@ impl Edit for ObjectEdit {
 
                         let sequences = saved_state.sequences.clone();
 
-                        spawn_local({
-                            async move {
-                                save_sequences_data(sequences).await;
-                            }
-                        });
+                        record_state.saves.save(sequences);
                         
                     }
                     ObjectType::TextItem => {
@@ -92,11 +99,7 @@ impl Edit for ObjectEdit {
 
                         let sequences = saved_state.sequences.clone();
 
-                        spawn_local({
-                            async move {
-                                save_sequences_data(sequences).await;
-                            }
-                        });
+                        record_state.saves.save(sequences);
                     }
                     ObjectType::ImageItem => {
                         editor.update_image(self.object_id, "width", InputValue::Number(*w));
@@ -116,11 +119,7 @@ impl Edit for ObjectEdit {
 
                         let sequences = saved_state.sequences.clone();
 
-                        spawn_local({
-                            async move {
-                                save_sequences_data(sequences).await;
-                            }
-                        });
+                        record_state.saves.save(sequences);
                     }
                     ObjectType::VideoItem => {
                         editor.update_video(self.object_id, "width", InputValue::Number(*w));
@@ -140,11 +139,7 @@ impl Edit for ObjectEdit {
 
                         let sequences = saved_state.sequences.clone();
 
-                        spawn_local({
-                            async move {
-                                save_sequences_data(sequences).await;
-                            }
-                        });
+                        record_state.saves.save(sequences);
                     }
                 }
             }
@@ -168,11 +163,7 @@ impl Edit for ObjectEdit {
 
                         let sequences = saved_state.sequences.clone();
 
-                        spawn_local({
-                            async move {
-                                save_sequences_data(sequences).await;
-                            }
-                        });
+                        record_state.saves.save(sequences);
                     }
                     ObjectType::TextItem => {
                         editor.update_text(self.object_id, "height", InputValue::Number(*h));
@@ -192,11 +183,7 @@ impl Edit for ObjectEdit {
 
                         let sequences = saved_state.sequences.clone();
 
-                        spawn_local({
-                            async move {
-                                save_sequences_data(sequences).await;
-                            }
-                        });
+                        record_state.saves.save(sequences);
                     }
                     ObjectType::ImageItem => {
                         editor.update_image(self.object_id, "height", InputValue::Number(*h));
@@ -216,11 +203,7 @@ impl Edit for ObjectEdit {
 
                         let sequences = saved_state.sequences.clone();
 
-                        spawn_local({
-                            async move {
-                                save_sequences_data(sequences).await;
-                            }
-                        });
+                        record_state.saves.save(sequences);
                     }
                     ObjectType::VideoItem => {
                         editor.update_video(self.object_id, "height", InputValue::Number(*h));
@@ -240,11 +223,7 @@ impl Edit for ObjectEdit {
 
                         let sequences = saved_state.sequences.clone();
 
-                        spawn_local({
-                            async move {
-                                save_sequences_data(sequences).await;
-                            }
-                        });
+                        record_state.saves.save(sequences);
                     }
                 }
             }
@@ -285,11 +264,7 @@ impl Edit for ObjectEdit {
 
                         let sequences = saved_state.sequences.clone();
 
-                        spawn_local({
-                            async move {
-                                save_sequences_data(sequences).await;
-                            }
-                        });
+                        record_state.saves.save(sequences);
                     }
                 } else {
                     editor.update_polygon(self.object_id, "red", InputValue::Number(*h));
@@ -309,11 +284,7 @@ impl Edit for ObjectEdit {
 
                     let sequences = saved_state.sequences.clone();
 
-                        spawn_local({
-                            async move {
-                                save_sequences_data(sequences).await;
-                            }
-                        });
+                        record_state.saves.save(sequences);
                 }
             }
             ObjectProperty::Green(h) => {
@@ -353,11 +324,7 @@ impl Edit for ObjectEdit {
 
                         let sequences = saved_state.sequences.clone();
 
-                        spawn_local({
-                            async move {
-                                save_sequences_data(sequences).await;
-                            }
-                        });
+                        record_state.saves.save(sequences);
                     }
                 } else {
                     editor.update_polygon(self.object_id, "green", InputValue::Number(*h));
@@ -377,11 +344,7 @@ impl Edit for ObjectEdit {
 
                     let sequences = saved_state.sequences.clone();
 
-                        spawn_local({
-                            async move {
-                                save_sequences_data(sequences).await;
-                            }
-                        });
+                        record_state.saves.save(sequences);
                 }
             }
             ObjectProperty::Blue(h) => {
@@ -421,11 +384,7 @@ impl Edit for ObjectEdit {
 
                         let sequences = saved_state.sequences.clone();
 
-                        spawn_local({
-                            async move {
-                                save_sequences_data(sequences).await;
-                            }
-                        });
+                        record_state.saves.save(sequences);
                     }
                 } else {
                     editor.update_polygon(self.object_id, "blue", InputValue::Number(*h));
@@ -445,11 +404,7 @@ impl Edit for ObjectEdit {
 
                     let sequences = saved_state.sequences.clone();
 
-                        spawn_local({
-                            async move {
-                                save_sequences_data(sequences).await;
-                            }
-                        });
+                        record_state.saves.save(sequences);
                 }
             }
 
@@ -477,11 +432,7 @@ impl Edit for ObjectEdit {
 
                 let sequences = saved_state.sequences.clone();
 
-                        spawn_local({
-                            async move {
-                                save_sequences_data(sequences).await;
-                            }
-                        });
+                        record_state.saves.save(sequences);
             }
             ObjectProperty::FillGreen(h) => {
                 // let mut stroke_green = h.to_string();
@@ -507,11 +458,7 @@ impl Edit for ObjectEdit {
 
                 let sequences = saved_state.sequences.clone();
 
-                        spawn_local({
-                            async move {
-                                save_sequences_data(sequences).await;
-                            }
-                        });
+                        record_state.saves.save(sequences);
             }
             ObjectProperty::FillBlue(h) => {
                 // let mut stroke_green = h.to_string();
@@ -537,11 +484,7 @@ impl Edit for ObjectEdit {
 
                 let sequences = saved_state.sequences.clone();
 
-                        spawn_local({
-                            async move {
-                                save_sequences_data(sequences).await;
-                            }
-                        });
+                        record_state.saves.save(sequences);
             }
 
             ObjectProperty::BorderRadius(h) => {
@@ -562,11 +505,7 @@ impl Edit for ObjectEdit {
 
                 let sequences = saved_state.sequences.clone();
 
-                        spawn_local({
-                            async move {
-                                save_sequences_data(sequences).await;
-                            }
-                        });
+                        record_state.saves.save(sequences);
             }
             ObjectProperty::StrokeThickness(h) => {
                 editor.update_polygon(self.object_id, "stroke_thickness", InputValue::Number(*h));
@@ -586,11 +525,7 @@ impl Edit for ObjectEdit {
 
                 let sequences = saved_state.sequences.clone();
 
-                        spawn_local({
-                            async move {
-                                save_sequences_data(sequences).await;
-                            }
-                        });
+                        record_state.saves.save(sequences);
             }
             ObjectProperty::StrokeRed(h) => {
                 editor.update_polygon(self.object_id, "stroke_red", InputValue::Number(*h));
@@ -610,11 +545,7 @@ impl Edit for ObjectEdit {
 
                 let sequences = saved_state.sequences.clone();
 
-                        spawn_local({
-                            async move {
-                                save_sequences_data(sequences).await;
-                            }
-                        });
+                        record_state.saves.save(sequences);
             }
             ObjectProperty::StrokeGreen(h) => {
                 editor.update_polygon(self.object_id, "stroke_green", InputValue::Number(*h));
@@ -634,11 +565,7 @@ impl Edit for ObjectEdit {
 
                 let sequences = saved_state.sequences.clone();
 
-                        spawn_local({
-                            async move {
-                                save_sequences_data(sequences).await;
-                            }
-                        });
+                        record_state.saves.save(sequences);
             }
             ObjectProperty::StrokeBlue(h) => {
                 editor.update_polygon(self.object_id, "stroke_blue", InputValue::Number(*h));
@@ -658,11 +585,7 @@ impl Edit for ObjectEdit {
 
                 let sequences = saved_state.sequences.clone();
 
-                        spawn_local({
-                            async move {
-                                save_sequences_data(sequences).await;
-                            }
-                        });
+                        record_state.saves.save(sequences);
             } // ObjectProperty::Points(w) => {
               //     editor.update_polygon(self.object_id, "points", InputValue::Points(w.clone()));
               // }
@@ -670,6 +593,8 @@ impl Edit for ObjectEdit {
     }
 
     fn undo(&mut self, record_state: &mut RecordState) {
+        record_state.redraw.request();
+
         let mut editor = record_state.editor.lock().unwrap();
         let mut saved_state = record_state
             .saved_state
@@ -698,11 +623,7 @@ impl Edit for ObjectEdit {
 
                         let sequences = saved_state.sequences.clone();
 
-                        spawn_local({
-                            async move {
-                                save_sequences_data(sequences).await;
-                            }
-                        });
+                        record_state.saves.save(sequences);
                     }
                     ObjectType::TextItem => {
                         editor.update_text(self.object_id, "width", InputValue::Number(*w));
@@ -722,11 +643,7 @@ impl Edit for ObjectEdit {
 
                         let sequences = saved_state.sequences.clone();
 
-                        spawn_local({
-                            async move {
-                                save_sequences_data(sequences).await;
-                            }
-                        });
+                        record_state.saves.save(sequences);
                     }
                     ObjectType::ImageItem => {
                         editor.update_image(self.object_id, "width", InputValue::Number(*w));
@@ -746,11 +663,7 @@ impl Edit for ObjectEdit {
 
                         let sequences = saved_state.sequences.clone();
 
-                        spawn_local({
-                            async move {
-                                save_sequences_data(sequences).await;
-                            }
-                        });
+                        record_state.saves.save(sequences);
                     }
                     ObjectType::VideoItem => {
                         editor.update_video(self.object_id, "width", InputValue::Number(*w));
@@ -770,11 +683,7 @@ impl Edit for ObjectEdit {
 
                         let sequences = saved_state.sequences.clone();
 
-                        spawn_local({
-                            async move {
-                                save_sequences_data(sequences).await;
-                            }
-                        });
+                        record_state.saves.save(sequences);
                     }
                 }
             }
@@ -798,11 +707,7 @@ impl Edit for ObjectEdit {
 
                         let sequences = saved_state.sequences.clone();
 
-                        spawn_local({
-                            async move {
-                                save_sequences_data(sequences).await;
-                            }
-                        });
+                        record_state.saves.save(sequences);
                     }
                     ObjectType::TextItem => {
                         editor.update_text(self.object_id, "height", InputValue::Number(*h));
@@ -822,11 +727,7 @@ impl Edit for ObjectEdit {
 
                         let sequences = saved_state.sequences.clone();
 
-                        spawn_local({
-                            async move {
-                                save_sequences_data(sequences).await;
-                            }
-                        });
+                        record_state.saves.save(sequences);
                     }
                     ObjectType::ImageItem => {
                         editor.update_image(self.object_id, "height", InputValue::Number(*h));
@@ -846,11 +747,7 @@ impl Edit for ObjectEdit {
 
                         let sequences = saved_state.sequences.clone();
 
-                        spawn_local({
-                            async move {
-                                save_sequences_data(sequences).await;
-                            }
-                        });
+                        record_state.saves.save(sequences);
                     }
                     ObjectType::VideoItem => {
                         editor.update_video(self.object_id, "height", InputValue::Number(*h));
@@ -870,11 +767,7 @@ impl Edit for ObjectEdit {
 
                         let sequences = saved_state.sequences.clone();
 
-                        spawn_local({
-                            async move {
-                                save_sequences_data(sequences).await;
-                            }
-                        });
+                        record_state.saves.save(sequences);
                     }
                 }
             }
@@ -900,11 +793,7 @@ impl Edit for ObjectEdit {
 
                 let sequences = saved_state.sequences.clone();
 
-                        spawn_local({
-                            async move {
-                                save_sequences_data(sequences).await;
-                            }
-                        });
+                        record_state.saves.save(sequences);
             }
             ObjectProperty::Green(h) => {
                 // let mut stroke_green = h.to_string();
@@ -928,11 +817,7 @@ impl Edit for ObjectEdit {
 
                 let sequences = saved_state.sequences.clone();
 
-                        spawn_local({
-                            async move {
-                                save_sequences_data(sequences).await;
-                            }
-                        });
+                        record_state.saves.save(sequences);
             }
             ObjectProperty::Blue(h) => {
                 // let mut stroke_green = h.to_string();
@@ -956,11 +841,7 @@ impl Edit for ObjectEdit {
 
                 let sequences = saved_state.sequences.clone();
 
-                        spawn_local({
-                            async move {
-                                save_sequences_data(sequences).await;
-                            }
-                        });
+                        record_state.saves.save(sequences);
             }
 
             ObjectProperty::FillRed(h) => {
@@ -985,11 +866,7 @@ impl Edit for ObjectEdit {
 
                 let sequences = saved_state.sequences.clone();
 
-                        spawn_local({
-                            async move {
-                                save_sequences_data(sequences).await;
-                            }
-                        });
+                        record_state.saves.save(sequences);
             }
             ObjectProperty::FillGreen(h) => {
                 // let mut stroke_green = h.to_string();
@@ -1017,11 +894,7 @@ impl Edit for ObjectEdit {
 
                 let sequences = saved_state.sequences.clone();
 
-                        spawn_local({
-                            async move {
-                                save_sequences_data(sequences).await;
-                            }
-                        });
+                        record_state.saves.save(sequences);
             }
             ObjectProperty::FillBlue(h) => {
                 // let mut stroke_green = h.to_string();
@@ -1045,11 +918,7 @@ impl Edit for ObjectEdit {
 
                 let sequences = saved_state.sequences.clone();
 
-                        spawn_local({
-                            async move {
-                                save_sequences_data(sequences).await;
-                            }
-                        });
+                        record_state.saves.save(sequences);
             }
 
             ObjectProperty::BorderRadius(h) => {
@@ -1070,11 +939,7 @@ impl Edit for ObjectEdit {
 
                 let sequences = saved_state.sequences.clone();
 
-                        spawn_local({
-                            async move {
-                                save_sequences_data(sequences).await;
-                            }
-                        });
+                        record_state.saves.save(sequences);
             }
             ObjectProperty::StrokeThickness(h) => {
                 editor.update_polygon(self.object_id, "stroke_thickness", InputValue::Number(*h));
@@ -1094,11 +959,7 @@ impl Edit for ObjectEdit {
 
                 let sequences = saved_state.sequences.clone();
 
-                        spawn_local({
-                            async move {
-                                save_sequences_data(sequences).await;
-                            }
-                        });
+                        record_state.saves.save(sequences);
             }
             ObjectProperty::StrokeRed(h) => {
                 // let mut stroke_red = h.to_string();
@@ -1122,11 +983,7 @@ impl Edit for ObjectEdit {
 
                 let sequences = saved_state.sequences.clone();
 
-                        spawn_local({
-                            async move {
-                                save_sequences_data(sequences).await;
-                            }
-                        });
+                        record_state.saves.save(sequences);
             }
             ObjectProperty::StrokeGreen(h) => {
                 // let mut stroke_green = h.to_string();
@@ -1154,11 +1011,7 @@ impl Edit for ObjectEdit {
 
                 let sequences = saved_state.sequences.clone();
 
-                        spawn_local({
-                            async move {
-                                save_sequences_data(sequences).await;
-                            }
-                        });
+                        record_state.saves.save(sequences);
             }
             ObjectProperty::StrokeBlue(h) => {
                 // let mut stroke_blue = h.to_string();
@@ -1186,11 +1039,7 @@ impl Edit for ObjectEdit {
 
                 let sequences = saved_state.sequences.clone();
 
-                        spawn_local({
-                            async move {
-                                save_sequences_data(sequences).await;
-                            }
-                        });
+                        record_state.saves.save(sequences);
             } // ObjectProperty::Points(w) => {
               //     editor.update_polygon(self.object_id, "points", InputValue::Points(w.clone()));
               // }
@@ -1198,189 +1047,1580 @@ impl Edit for ObjectEdit {
     }
 }
 
-pub struct EditorState {
-    pub editor: Arc<Mutex<Editor>>,
-    pub record: Arc<Mutex<Record<ObjectEdit>>>,
-    pub record_state: RecordState,
-    pub polygon_selected: bool,
-    pub selected_polygon_id: Uuid,
-    pub text_selected: bool,
-    pub selected_text_id: Uuid,
-    pub image_selected: bool,
-    pub selected_image_id: Uuid,
-    pub video_selected: bool,
-    pub selected_video_id: Uuid,
-    // pub value_signals: Arc<Mutex<HashMap<String, RwSignal<String>>>>,
-    // pub current_modifiers: ModifiersState,
-    // pub saved_state: Option<SavedState>,
-    // pub project_selected_signal: Option<RwSignal<Uuid>>,
-    // pub active_sequence_mode: RwSignal<String>,
-    // pub sequence_timeline_state: TimelineState,
+/// Changes the parameters of a library shape, regenerating its points
+#[derive(Debug)]
+pub struct ShapeEdit {
+    pub polygon_id: Uuid,
+    pub old_shape: SavedShape,
+    pub new_shape: SavedShape,
 }
 
-pub struct RecordState {
-    pub editor: Arc<Mutex<Editor>>,
-    // pub record: Arc<Mutex<Record<ObjectEdit>>>,
-    // pub editor_state: EditorState,
-    pub saved_state: Option<SavedState>,
-}
+impl ShapeEdit {
+    fn apply(&self, record_state: &mut RecordState, shape: &SavedShape) {
+        record_state.redraw.request();
 
-impl EditorState {
-    pub fn new(editor: Arc<Mutex<Editor>>, record: Arc<Mutex<Record<ObjectEdit>>>) -> Self {
-        // let sequence_timeline_state = TimelineState::new();
+        let points = shape_points(shape);
+        let border_radius = shape_border_radius(shape);
+        let rounded = matches!(shape, SavedShape::RoundedRect { .. });
 
-        Self {
-            editor: Arc::clone(&editor),
-            record: Arc::clone(&record),
-            record_state: RecordState {
-                editor: Arc::clone(&editor),
-                saved_state: None,
-                // record: Arc::clone(&record),
-            },
-            polygon_selected: false,
-            selected_polygon_id: Uuid::nil(),
-            text_selected: false,
-            selected_text_id: Uuid::nil(),
-            image_selected: false,
-            selected_image_id: Uuid::nil(),
-            video_selected: false,
-            selected_video_id: Uuid::nil(),
-            // value_signals: Arc::new(Mutex::new(HashMap::new())),
-            // current_modifiers: ModifiersState::empty(),
-            // saved_state: None,
-            // project_selected_signal: None,
-            // sequence_timeline_state,
-            // active_sequence_mode: RwSignal::new("layout".to_string()),
+        let mut editor = record_state.editor.lock().unwrap();
+        editor.update_polygon(self.polygon_id, "points", InputValue::Points(points.clone()));
+        if rounded {
+            editor.update_polygon(
+                self.polygon_id,
+                "border_radius",
+                InputValue::Number(border_radius),
+            );
         }
-    }
-
-    pub fn save_default_keyframes(
-        &mut self,
-        savable_item_id: String,
-        object_type: ObjectType,
-        object_position: SavedPoint,
-    ) -> AnimationData {
-        let mut properties = Vec::new();
+        drop(editor);
 
-        let mut position_keyframes = Vec::new();
+        let saved_state = record_state
+            .saved_state
+            .as_mut()
+            .expect("Couldn't get saved state");
 
-        position_keyframes.push(UIKeyframe {
-            id: Uuid::new_v4().to_string(),
-            time: Duration::from_secs(0),
-            value: KeyframeValue::Position([object_position.x, object_position.y - 100]),
-            easing: EasingType::EaseInOut,
-            path_type: PathType::Linear,
-            key_type: KeyType::Frame,
-        });
-        position_keyframes.push(UIKeyframe {
-            id: Uuid::new_v4().to_string(),
-            time: Duration::from_millis(2500),
-            value: KeyframeValue::Position([object_position.x, object_position.y - 50]),
-            easing: EasingType::EaseInOut,
-            path_type: PathType::Linear,
-            key_type: KeyType::Frame,
-        });
-        position_keyframes.push(UIKeyframe {
-            id: Uuid::new_v4().to_string(),
-            time: Duration::from_secs(5),
-            value: KeyframeValue::Position([object_position.x, object_position.y]),
-            easing: EasingType::EaseInOut,
-            path_type: PathType::Linear,
-            key_type: KeyType::Frame,
-        });
-        position_keyframes.push(UIKeyframe {
-            id: Uuid::new_v4().to_string(),
-            time: Duration::from_secs(15),
-            value: KeyframeValue::Position([object_position.x, object_position.y + 50]),
-            easing: EasingType::EaseInOut,
-            path_type: PathType::Linear,
-            key_type: KeyType::Frame,
-        });
-        position_keyframes.push(UIKeyframe {
-            id: Uuid::new_v4().to_string(),
-            time: Duration::from_millis(17500),
-            value: KeyframeValue::Position([object_position.x, object_position.y + 100]),
-            easing: EasingType::EaseInOut,
-            path_type: PathType::Linear,
-            key_type: KeyType::Frame,
-        });
-        position_keyframes.push(UIKeyframe {
-            id: Uuid::new_v4().to_string(),
-            time: Duration::from_secs(20),
-            value: KeyframeValue::Position([object_position.x, object_position.y + 150]),
-            easing: EasingType::EaseInOut,
-            path_type: PathType::Linear,
-            key_type: KeyType::Frame,
+        saved_state.sequences.iter_mut().for_each(|s| {
+            s.active_polygons.iter_mut().for_each(|p| {
+                if p.id == self.polygon_id.to_string() {
+                    p.points = points.clone();
+                    p.shape = Some(shape.clone());
+                    if rounded {
+                        p.border_radius = border_radius as i32;
+                    }
+                }
+            });
         });
 
-        let mut position_prop = AnimationProperty {
-            name: "Position".to_string(),
-            property_path: "position".to_string(),
-            children: Vec::new(),
-            keyframes: position_keyframes,
-            depth: 0,
-        };
+        let sequences = saved_state.sequences.clone();
 
-        let mut rotation_keyframes = Vec::new();
+        record_state.saves.save(sequences);
+    }
+}
 
-        rotation_keyframes.push(UIKeyframe {
-            id: Uuid::new_v4().to_string(),
-            time: Duration::from_secs(0),
-            value: KeyframeValue::Rotation(0),
-            easing: EasingType::EaseInOut,
-            path_type: PathType::Linear,
-            key_type: KeyType::Frame,
-        });
-        rotation_keyframes.push(UIKeyframe {
-            id: Uuid::new_v4().to_string(),
-            time: Duration::from_millis(2500),
-            value: KeyframeValue::Rotation(0),
-            easing: EasingType::EaseInOut,
-            path_type: PathType::Linear,
-            key_type: KeyType::Frame,
-        });
-        rotation_keyframes.push(UIKeyframe {
-            id: Uuid::new_v4().to_string(),
-            time: Duration::from_secs(5),
-            value: KeyframeValue::Rotation(0),
-            easing: EasingType::EaseInOut,
-            path_type: PathType::Linear,
-            key_type: KeyType::Frame,
-        });
-        rotation_keyframes.push(UIKeyframe {
-            id: Uuid::new_v4().to_string(),
-            time: Duration::from_secs(15),
-            value: KeyframeValue::Rotation(0),
-            easing: EasingType::EaseInOut,
-            path_type: PathType::Linear,
-            key_type: KeyType::Frame,
-        });
-        rotation_keyframes.push(UIKeyframe {
-            id: Uuid::new_v4().to_string(),
-            time: Duration::from_millis(17500),
-            value: KeyframeValue::Rotation(0),
-            easing: EasingType::EaseInOut,
-            path_type: PathType::Linear,
-            key_type: KeyType::Frame,
-        });
-        rotation_keyframes.push(UIKeyframe {
-            id: Uuid::new_v4().to_string(),
-            time: Duration::from_secs(20),
-            value: KeyframeValue::Rotation(0),
-            easing: EasingType::EaseInOut,
-            path_type: PathType::Linear,
-            key_type: KeyType::Frame,
-        });
+impl Edit for ShapeEdit {
+    type Target = RecordState;
+    type Output = ();
 
-        let mut rotation_prop = AnimationProperty {
-            name: "Rotation".to_string(),
-            property_path: "rotation".to_string(),
-            children: Vec::new(),
-            keyframes: rotation_keyframes,
-            depth: 0,
-        };
+    fn edit(&mut self, record_state: &mut RecordState) {
+        let shape = self.new_shape.clone();
+        self.apply(record_state, &shape);
+    }
 
-        let mut scale_keyframes = Vec::new();
+    fn undo(&mut self, record_state: &mut RecordState) {
+        let shape = self.old_shape.clone();
+        self.apply(record_state, &shape);
+    }
+}
+
+/// Moves, resizes and rotates an object as one edit. Its whole motion path
+/// is shifted along so the animation keeps its shape.
+#[derive(Debug)]
+pub struct TransformEdit {
+    pub object_id: Uuid,
+    pub object_type: ObjectType,
+    pub sequence_id: String,
+    /// canvas pixels
+    pub translation: (i32, i32),
+    /// degrees
+    pub rotation: i32,
+    pub old_dimensions: (f32, f32),
+    pub new_dimensions: (f32, f32),
+}
+
+impl TransformEdit {
+    /// `direction` is 1 to apply the edit, -1 to take it back
+    fn apply(&self, record_state: &mut RecordState, direction: i32, dimensions: (f32, f32)) {
+        record_state.redraw.request();
+
+        let (dx, dy) = (self.translation.0 * direction, self.translation.1 * direction);
+        let rotation = self.rotation * direction;
+        let object_id = self.object_id.to_string();
+
+        let mut editor = record_state.editor.lock().unwrap();
+
+        if let Some(bounds) = object_bounds(&editor, self.object_id) {
+            set_object_transform(
+                &mut editor,
+                self.object_id,
+                &self.object_type,
+                Point {
+                    x: bounds.center.x + dx as f32,
+                    y: bounds.center.y + dy as f32,
+                },
+                bounds.rotation + rotation as f32,
+            );
+        }
+
+        if self.old_dimensions != self.new_dimensions {
+            let (width, height) = dimensions;
+
+            match self.object_type {
+                ObjectType::Polygon => {
+                    editor.update_polygon(self.object_id, "width", InputValue::Number(width));
+                    editor.update_polygon(self.object_id, "height", InputValue::Number(height));
+                }
+                ObjectType::TextItem => {
+                    editor.update_text(self.object_id, "width", InputValue::Number(width));
+                    editor.update_text(self.object_id, "height", InputValue::Number(height));
+                }
+                ObjectType::ImageItem => {
+                    editor.update_image(self.object_id, "width", InputValue::Number(width));
+                    editor.update_image(self.object_id, "height", InputValue::Number(height));
+                }
+                ObjectType::VideoItem => {
+                    editor.update_video(self.object_id, "width", InputValue::Number(width));
+                    editor.update_video(self.object_id, "height", InputValue::Number(height));
+                }
+            }
+        }
+
+        let saved_state = record_state
+            .saved_state
+            .as_mut()
+            .expect("Couldn't get saved state");
+
+        let Some(sequence) = saved_state
+            .sequences
+            .iter_mut()
+            .find(|s| s.id == self.sequence_id)
+        else {
+            return;
+        };
+
+        sequence
+            .polygon_motion_paths
+            .iter_mut()
+            .filter(|m| m.polygon_id == object_id)
+            .flat_map(|m| m.properties.iter_mut())
+            .flat_map(|p| p.keyframes.iter_mut())
+            .for_each(|k| match &mut k.value {
+                KeyframeValue::Position(position) => {
+                    position[0] += dx;
+                    position[1] += dy;
+                }
+                KeyframeValue::Rotation(r) => *r += rotation,
+                _ => {}
+            });
+
+        let (width, height) = dimensions;
+
+        match self.object_type {
+            ObjectType::Polygon => {
+                if let Some(p) = sequence.active_polygons.iter_mut().find(|p| p.id == object_id) {
+                    p.position.x += dx;
+                    p.position.y += dy;
+                    p.rotation += rotation;
+                    p.dimensions = (width as i32, height as i32);
+                }
+            }
+            ObjectType::TextItem => {
+                if let Some(t) = sequence.active_text_items.iter_mut().find(|t| t.id == object_id) {
+                    t.position.x += dx;
+                    t.position.y += dy;
+                    t.dimensions = (width as i32, height as i32);
+                }
+            }
+            ObjectType::ImageItem => {
+                if let Some(i) = sequence.active_image_items.iter_mut().find(|i| i.id == object_id) {
+                    i.position.x += dx;
+                    i.position.y += dy;
+                    i.dimensions = (width as u32, height as u32);
+                }
+            }
+            ObjectType::VideoItem => {
+                if let Some(v) = sequence.active_video_items.iter_mut().find(|v| v.id == object_id) {
+                    v.position.x += dx;
+                    v.position.y += dy;
+                    v.dimensions = (width as u32, height as u32);
+                }
+            }
+        }
+
+        let updated_sequence = sequence.clone();
+
+        editor.current_sequence_data = Some(updated_sequence.clone());
+        editor.update_motion_paths(&updated_sequence);
+
+        let sequences = saved_state.sequences.clone();
+
+        record_state.saves.save(sequences);
+    }
+}
+
+impl Edit for TransformEdit {
+    type Target = RecordState;
+    type Output = ();
+
+    fn edit(&mut self, record_state: &mut RecordState) {
+        self.apply(record_state, 1, self.new_dimensions);
+    }
+
+    fn undo(&mut self, record_state: &mut RecordState) {
+        self.apply(record_state, -1, self.old_dimensions);
+    }
+}
+
+/// An object's saved config, whatever its type
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum SavedObject {
+    Polygon(SavedPolygonConfig),
+    Text(SavedTextRendererConfig),
+    Image(SavedStImageConfig),
+    Video(SavedStVideoConfig),
+}
+
+impl SavedObject {
+    pub fn id(&self) -> &str {
+        match self {
+            SavedObject::Polygon(p) => &p.id,
+            SavedObject::Text(t) => &t.id,
+            SavedObject::Image(i) => &i.id,
+            SavedObject::Video(v) => &v.id,
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        match self {
+            SavedObject::Polygon(p) => &p.name,
+            SavedObject::Text(t) => &t.name,
+            SavedObject::Image(i) => &i.name,
+            SavedObject::Video(v) => &v.name,
+        }
+    }
+
+    pub fn object_type(&self) -> ObjectType {
+        match self {
+            SavedObject::Polygon(_) => ObjectType::Polygon,
+            SavedObject::Text(_) => ObjectType::TextItem,
+            SavedObject::Image(_) => ObjectType::ImageItem,
+            SavedObject::Video(_) => ObjectType::VideoItem,
+        }
+    }
+}
+
+/// Takes an object out of its sequence. The editor only hides it, so undo
+/// brings it back without loading its media again.
+#[derive(Debug)]
+pub struct DeleteEdit {
+    pub object_id: Uuid,
+    pub object_type: ObjectType,
+    pub sequence_id: String,
+    // the saved object and its motion path, with where they sat in their lists
+    removed: Option<(usize, SavedObject, Option<(usize, AnimationData)>)>,
+}
+
+/// Points the editor at the updated sequence and saves it
+fn sync_sequence(
+    editor: &mut Editor,
+    saved_state: &SavedState,
+    saves: &mut SaveQueue,
+    sequence_id: &str,
+) {
+    let Some(sequence) = saved_state.sequences.iter().find(|s| s.id == sequence_id) else {
+        return;
+    };
+
+    editor.current_sequence_data = Some(sequence.clone());
+    editor.update_motion_paths(sequence);
+
+    saves.save(saved_state.sequences.clone());
+}
+
+/// Takes an object and its motion path out of a sequence, with where they sat in their lists
+fn take_saved_object(
+    sequence: &mut Sequence,
+    object_id: &str,
+    object_type: &ObjectType,
+) -> Option<(usize, SavedObject, Option<(usize, AnimationData)>)> {
+    let (index, object) = match object_type {
+        ObjectType::Polygon => sequence
+            .active_polygons
+            .iter()
+            .position(|p| p.id == object_id)
+            .map(|i| (i, SavedObject::Polygon(sequence.active_polygons.remove(i)))),
+        ObjectType::TextItem => sequence
+            .active_text_items
+            .iter()
+            .position(|t| t.id == object_id)
+            .map(|i| (i, SavedObject::Text(sequence.active_text_items.remove(i)))),
+        ObjectType::ImageItem => sequence
+            .active_image_items
+            .iter()
+            .position(|i| i.id == object_id)
+            .map(|i| (i, SavedObject::Image(sequence.active_image_items.remove(i)))),
+        ObjectType::VideoItem => sequence
+            .active_video_items
+            .iter()
+            .position(|v| v.id == object_id)
+            .map(|i| (i, SavedObject::Video(sequence.active_video_items.remove(i)))),
+    }?;
+
+    let motion_path = sequence
+        .polygon_motion_paths
+        .iter()
+        .position(|m| m.polygon_id == object_id)
+        .map(|i| (i, sequence.polygon_motion_paths.remove(i)));
+
+    Some((index, object, motion_path))
+}
+
+/// Puts an object and its motion path back into a sequence, indexes past the end append
+fn put_saved_object(
+    sequence: &mut Sequence,
+    index: usize,
+    object: SavedObject,
+    motion_path: Option<(usize, AnimationData)>,
+) {
+    match object {
+        SavedObject::Polygon(p) => {
+            let index = index.min(sequence.active_polygons.len());
+            sequence.active_polygons.insert(index, p);
+        }
+        SavedObject::Text(t) => {
+            let index = index.min(sequence.active_text_items.len());
+            sequence.active_text_items.insert(index, t);
+        }
+        SavedObject::Image(i) => {
+            let index = index.min(sequence.active_image_items.len());
+            sequence.active_image_items.insert(index, i);
+        }
+        SavedObject::Video(v) => {
+            let index = index.min(sequence.active_video_items.len());
+            sequence.active_video_items.insert(index, v);
+        }
+    }
+
+    if let Some((index, motion_path)) = motion_path {
+        let index = index.min(sequence.polygon_motion_paths.len());
+        sequence.polygon_motion_paths.insert(index, motion_path);
+    }
+}
+
+/// Shows an object that's back in its sequence. Objects deleted before a reload
+/// were never loaded, those are fetched and shown once they're in.
+fn show_saved_object(
+    record_state: &RecordState,
+    editor: &mut Editor,
+    sequence_id: &str,
+    object: &SavedObject,
+) {
+    let Ok(object_id) = Uuid::from_str(object.id()) else {
+        return;
+    };
+
+    if set_object_hidden(editor, object_id, &object.object_type(), false) {
+        return;
+    }
+
+    // other sequences load it from the saved state when they're opened
+    if record_state.open_sequence.as_deref() != Some(sequence_id) {
+        return;
+    }
+
+    let (Ok(sequence_uuid), Some(token)) = (Uuid::from_str(sequence_id), stored_token()) else {
+        log::warn!("Couldn't load {}", object.name());
+        return;
+    };
+
+    let editor_m = record_state.editor.clone();
+    let redraw = record_state.redraw.clone();
+    let object = object.clone();
+
+    spawn_local(async move {
+        match restore_saved_object(editor_m, &object, sequence_uuid, false, token).await {
+            Ok(()) => redraw.request(),
+            Err(e) => log::warn!("Couldn't load {}: {}", object.name(), e),
+        }
+    });
+}
+
+impl Edit for DeleteEdit {
+    type Target = RecordState;
+    type Output = ();
+
+    fn edit(&mut self, record_state: &mut RecordState) {
+        record_state.redraw.request();
+
+        let mut editor = record_state.editor.lock().unwrap();
+        let saved_state = record_state
+            .saved_state
+            .as_mut()
+            .expect("Couldn't get saved state");
+
+        let Some(sequence) = saved_state
+            .sequences
+            .iter_mut()
+            .find(|s| s.id == self.sequence_id)
+        else {
+            return;
+        };
+
+        let Some(removed) =
+            take_saved_object(sequence, &self.object_id.to_string(), &self.object_type)
+        else {
+            return;
+        };
+
+        self.removed = Some(removed);
+
+        set_object_hidden(&mut editor, self.object_id, &self.object_type, true);
+        sync_sequence(
+            &mut editor,
+            saved_state,
+            &mut record_state.saves,
+            &self.sequence_id,
+        );
+    }
+
+    fn undo(&mut self, record_state: &mut RecordState) {
+        record_state.redraw.request();
+
+        let Some((index, object, motion_path)) = self.removed.take() else {
+            return;
+        };
+
+        let mut editor = record_state.editor.lock().unwrap();
+        let saved_state = record_state
+            .saved_state
+            .as_mut()
+            .expect("Couldn't get saved state");
+
+        let Some(sequence) = saved_state
+            .sequences
+            .iter_mut()
+            .find(|s| s.id == self.sequence_id)
+        else {
+            return;
+        };
+
+        put_saved_object(sequence, index, object.clone(), motion_path);
+
+        sync_sequence(
+            &mut editor,
+            saved_state,
+            &mut record_state.saves,
+            &self.sequence_id,
+        );
+        show_saved_object(record_state, &mut editor, &self.sequence_id, &object);
+    }
+}
+
+/// Adds an object to a sequence. It has to be in the editor already (hidden),
+/// undo hides it again like a delete would.
+#[derive(Debug)]
+pub struct InsertEdit {
+    pub object_id: Uuid,
+    pub object_type: ObjectType,
+    pub sequence_id: String,
+    pub object: SavedObject,
+    pub motion_path: Option<AnimationData>,
+}
+
+impl Edit for InsertEdit {
+    type Target = RecordState;
+    type Output = ();
+
+    fn edit(&mut self, record_state: &mut RecordState) {
+        record_state.redraw.request();
+
+        let mut editor = record_state.editor.lock().unwrap();
+        let saved_state = record_state
+            .saved_state
+            .as_mut()
+            .expect("Couldn't get saved state");
+
+        let Some(sequence) = saved_state
+            .sequences
+            .iter_mut()
+            .find(|s| s.id == self.sequence_id)
+        else {
+            return;
+        };
+
+        put_saved_object(
+            sequence,
+            usize::MAX,
+            self.object.clone(),
+            self.motion_path.clone().map(|m| (usize::MAX, m)),
+        );
+
+        sync_sequence(
+            &mut editor,
+            saved_state,
+            &mut record_state.saves,
+            &self.sequence_id,
+        );
+        show_saved_object(record_state, &mut editor, &self.sequence_id, &self.object);
+    }
+
+    fn undo(&mut self, record_state: &mut RecordState) {
+        record_state.redraw.request();
+
+        let mut editor = record_state.editor.lock().unwrap();
+        let saved_state = record_state
+            .saved_state
+            .as_mut()
+            .expect("Couldn't get saved state");
+
+        let Some(sequence) = saved_state
+            .sequences
+            .iter_mut()
+            .find(|s| s.id == self.sequence_id)
+        else {
+            return;
+        };
+
+        // the object may have been edited since, keep what it looks like now for redo
+        if let Some((_, object, motion_path)) =
+            take_saved_object(sequence, &self.object_id.to_string(), &self.object_type)
+        {
+            self.object = object;
+            self.motion_path = motion_path.map(|(_, m)| m);
+        }
+
+        set_object_hidden(&mut editor, self.object_id, &self.object_type, true);
+        sync_sequence(
+            &mut editor,
+            saved_state,
+            &mut record_state.saves,
+            &self.sequence_id,
+        );
+    }
+}
+
+/// Creates, changes or removes a group of objects. None on either side means
+/// the group doesn't exist before or after the edit.
+#[derive(Debug)]
+pub struct ObjectGroupEdit {
+    pub sequence_id: String,
+    pub group_id: String,
+    pub old_group: Option<SavedGroupConfig>,
+    pub new_group: Option<SavedGroupConfig>,
+}
+
+fn put_group(
+    record_state: &mut RecordState,
+    sequence_id: &str,
+    group_id: &str,
+    group: Option<SavedGroupConfig>,
+) {
+    record_state.redraw.request();
+
+    let mut editor = record_state.editor.lock().unwrap();
+    let saved_state = record_state
+        .saved_state
+        .as_mut()
+        .expect("Couldn't get saved state");
+
+    let Some(sequence) = saved_state
+        .sequences
+        .iter_mut()
+        .find(|s| s.id == sequence_id)
+    else {
+        return;
+    };
+
+    let index = sequence.groups.iter().position(|g| g.id == group_id);
+
+    match (index, group) {
+        (Some(i), Some(group)) => sequence.groups[i] = group,
+        (Some(i), None) => {
+            sequence.groups.remove(i);
+        }
+        (None, Some(group)) => sequence.groups.push(group),
+        (None, None) => {}
+    }
+
+    sync_sequence(&mut editor, saved_state, &mut record_state.saves, sequence_id);
+}
+
+impl Edit for ObjectGroupEdit {
+    type Target = RecordState;
+    type Output = ();
+
+    fn edit(&mut self, record_state: &mut RecordState) {
+        put_group(
+            record_state,
+            &self.sequence_id,
+            &self.group_id,
+            self.new_group.clone(),
+        );
+    }
+
+    fn undo(&mut self, record_state: &mut RecordState) {
+        put_group(
+            record_state,
+            &self.sequence_id,
+            &self.group_id,
+            self.old_group.clone(),
+        );
+    }
+}
+
+/// Changes a text item's content, font, spacing, alignment or color,
+/// laying its glyphs out again
+#[derive(Debug)]
+pub struct TextEdit {
+    pub text_id: Uuid,
+    pub sequence_id: String,
+    pub old_style: TextStyle,
+    pub new_style: TextStyle,
+}
+
+impl TextEdit {
+    fn apply(&self, record_state: &mut RecordState, style: &TextStyle) {
+        record_state.redraw.request();
+
+        let mut editor = record_state.editor.lock().unwrap();
+
+        if let Err(e) = apply_text_style(&mut editor, self.text_id, style) {
+            log::warn!("Couldn't restyle text {}: {}", self.text_id, e);
+        }
+
+        let saved_state = record_state
+            .saved_state
+            .as_mut()
+            .expect("Couldn't get saved state");
+
+        saved_state.sequences.iter_mut().for_each(|s| {
+            s.active_text_items.iter_mut().for_each(|t| {
+                if t.id == self.text_id.to_string() {
+                    style.write_saved(t);
+                }
+            });
+        });
+
+        sync_sequence(
+            &mut editor,
+            saved_state,
+            &mut record_state.saves,
+            &self.sequence_id,
+        );
+    }
+}
+
+impl Edit for TextEdit {
+    type Target = RecordState;
+    type Output = ();
+
+    fn edit(&mut self, record_state: &mut RecordState) {
+        let style = self.new_style.clone();
+        self.apply(record_state, &style);
+    }
+
+    fn undo(&mut self, record_state: &mut RecordState) {
+        let style = self.old_style.clone();
+        self.apply(record_state, &style);
+    }
+}
+
+/// Changes a sequence's background fill. The renderer shows it once the
+/// page picks up `background_changed`, gradients and images take a while.
+#[derive(Debug)]
+pub struct BackgroundEdit {
+    pub sequence_id: String,
+    pub old_fill: Option<BackgroundFill>,
+    pub new_fill: Option<BackgroundFill>,
+}
+
+impl BackgroundEdit {
+    fn apply(&self, record_state: &mut RecordState, fill: Option<BackgroundFill>) {
+        record_state.background_changed = Some(self.sequence_id.clone());
+
+        let mut editor = record_state.editor.lock().unwrap();
+        let saved_state = record_state
+            .saved_state
+            .as_mut()
+            .expect("Couldn't get saved state");
+
+        saved_state.sequences.iter_mut().for_each(|s| {
+            if s.id == self.sequence_id {
+                s.background_fill = fill.clone();
+            }
+        });
+
+        sync_sequence(
+            &mut editor,
+            saved_state,
+            &mut record_state.saves,
+            &self.sequence_id,
+        );
+    }
+}
+
+impl Edit for BackgroundEdit {
+    type Target = RecordState;
+    type Output = ();
+
+    fn edit(&mut self, record_state: &mut RecordState) {
+        let fill = self.new_fill.clone();
+        self.apply(record_state, fill);
+    }
+
+    fn undo(&mut self, record_state: &mut RecordState) {
+        let fill = self.old_fill.clone();
+        self.apply(record_state, fill);
+    }
+}
+
+/// Edits made in one transaction, undone and redone as a single step
+#[derive(Debug)]
+pub struct GroupEdit {
+    pub edits: Vec<EditorEdit>,
+    // the edits already ran while the transaction was open
+    applied: bool,
+}
+
+impl Edit for GroupEdit {
+    type Target = RecordState;
+    type Output = ();
+
+    fn edit(&mut self, record_state: &mut RecordState) {
+        if std::mem::take(&mut self.applied) {
+            return;
+        }
+
+        record_state.deferring_saves(|record_state| {
+            self.edits
+                .iter_mut()
+                .for_each(|edit| edit.edit(record_state));
+        });
+    }
+
+    fn undo(&mut self, record_state: &mut RecordState) {
+        record_state.deferring_saves(|record_state| {
+            self.edits
+                .iter_mut()
+                .rev()
+                .for_each(|edit| edit.undo(record_state));
+        });
+    }
+}
+
+/// Everything that can go on the undo record
+#[derive(Debug)]
+pub enum EditorEdit {
+    Object(ObjectEdit),
+    Shape(ShapeEdit),
+    Transform(TransformEdit),
+    Delete(DeleteEdit),
+    Insert(InsertEdit),
+    ObjectGroup(ObjectGroupEdit),
+    Text(TextEdit),
+    Background(BackgroundEdit),
+    Group(GroupEdit),
+}
+
+impl From<ObjectEdit> for EditorEdit {
+    fn from(edit: ObjectEdit) -> Self {
+        EditorEdit::Object(edit)
+    }
+}
+
+impl From<ShapeEdit> for EditorEdit {
+    fn from(edit: ShapeEdit) -> Self {
+        EditorEdit::Shape(edit)
+    }
+}
+
+impl From<TransformEdit> for EditorEdit {
+    fn from(edit: TransformEdit) -> Self {
+        EditorEdit::Transform(edit)
+    }
+}
+
+impl From<DeleteEdit> for EditorEdit {
+    fn from(edit: DeleteEdit) -> Self {
+        EditorEdit::Delete(edit)
+    }
+}
+
+impl From<InsertEdit> for EditorEdit {
+    fn from(edit: InsertEdit) -> Self {
+        EditorEdit::Insert(edit)
+    }
+}
+
+impl From<ObjectGroupEdit> for EditorEdit {
+    fn from(edit: ObjectGroupEdit) -> Self {
+        EditorEdit::ObjectGroup(edit)
+    }
+}
+
+impl From<TextEdit> for EditorEdit {
+    fn from(edit: TextEdit) -> Self {
+        EditorEdit::Text(edit)
+    }
+}
+
+impl From<BackgroundEdit> for EditorEdit {
+    fn from(edit: BackgroundEdit) -> Self {
+        EditorEdit::Background(edit)
+    }
+}
+
+impl From<GroupEdit> for EditorEdit {
+    fn from(edit: GroupEdit) -> Self {
+        EditorEdit::Group(edit)
+    }
+}
+
+impl Edit for EditorEdit {
+    type Target = RecordState;
+    type Output = ();
+
+    fn edit(&mut self, record_state: &mut RecordState) {
+        match self {
+            EditorEdit::Object(edit) => edit.edit(record_state),
+            EditorEdit::Shape(edit) => edit.edit(record_state),
+            EditorEdit::Transform(edit) => edit.edit(record_state),
+            EditorEdit::Delete(edit) => edit.edit(record_state),
+            EditorEdit::Insert(edit) => edit.edit(record_state),
+            EditorEdit::ObjectGroup(edit) => edit.edit(record_state),
+            EditorEdit::Text(edit) => edit.edit(record_state),
+            EditorEdit::Background(edit) => edit.edit(record_state),
+            EditorEdit::Group(edit) => edit.edit(record_state),
+        }
+    }
+
+    fn undo(&mut self, record_state: &mut RecordState) {
+        match self {
+            EditorEdit::Object(edit) => edit.undo(record_state),
+            EditorEdit::Shape(edit) => edit.undo(record_state),
+            EditorEdit::Transform(edit) => edit.undo(record_state),
+            EditorEdit::Delete(edit) => edit.undo(record_state),
+            EditorEdit::Insert(edit) => edit.undo(record_state),
+            EditorEdit::ObjectGroup(edit) => edit.undo(record_state),
+            EditorEdit::Text(edit) => edit.undo(record_state),
+            EditorEdit::Background(edit) => edit.undo(record_state),
+            EditorEdit::Group(edit) => edit.undo(record_state),
+        }
+    }
+}
+
+/// Name of an object in any sequence
+fn object_name(saved_state: &SavedState, object_id: &str) -> Option<String> {
+    saved_state.sequences.iter().find_map(|s| {
+        s.active_polygons
+            .iter()
+            .find(|p| p.id == object_id)
+            .map(|p| p.name.clone())
+            .or_else(|| {
+                s.active_text_items
+                    .iter()
+                    .find(|t| t.id == object_id)
+                    .map(|t| t.name.clone())
+            })
+            .or_else(|| {
+                s.active_image_items
+                    .iter()
+                    .find(|i| i.id == object_id)
+                    .map(|i| i.name.clone())
+            })
+            .or_else(|| {
+                s.active_video_items
+                    .iter()
+                    .find(|v| v.id == object_id)
+                    .map(|v| v.name.clone())
+            })
+    })
+}
+
+fn format_value(value: f32) -> String {
+    format!("{}", (value * 100.0).round() / 100.0)
+}
+
+/// ObjectProperty as a name and a number, so it can be labelled and stored
+fn property_parts(property: &ObjectProperty) -> Option<(&'static str, f32)> {
+    match property {
+        ObjectProperty::Width(v) => Some(("width", *v)),
+        ObjectProperty::Height(v) => Some(("height", *v)),
+        ObjectProperty::Red(v) => Some(("red", *v)),
+        ObjectProperty::Green(v) => Some(("green", *v)),
+        ObjectProperty::Blue(v) => Some(("blue", *v)),
+        ObjectProperty::FillRed(v) => Some(("fill_red", *v)),
+        ObjectProperty::FillGreen(v) => Some(("fill_green", *v)),
+        ObjectProperty::FillBlue(v) => Some(("fill_blue", *v)),
+        ObjectProperty::BorderRadius(v) => Some(("border_radius", *v)),
+        ObjectProperty::StrokeThickness(v) => Some(("stroke_thickness", *v)),
+        ObjectProperty::StrokeRed(v) => Some(("stroke_red", *v)),
+        ObjectProperty::StrokeGreen(v) => Some(("stroke_green", *v)),
+        ObjectProperty::StrokeBlue(v) => Some(("stroke_blue", *v)),
+        _ => None,
+    }
+}
+
+fn property_from_parts(name: &str, value: f32) -> Option<ObjectProperty> {
+    match name {
+        "width" => Some(ObjectProperty::Width(value)),
+        "height" => Some(ObjectProperty::Height(value)),
+        "red" => Some(ObjectProperty::Red(value)),
+        "green" => Some(ObjectProperty::Green(value)),
+        "blue" => Some(ObjectProperty::Blue(value)),
+        "fill_red" => Some(ObjectProperty::FillRed(value)),
+        "fill_green" => Some(ObjectProperty::FillGreen(value)),
+        "fill_blue" => Some(ObjectProperty::FillBlue(value)),
+        "border_radius" => Some(ObjectProperty::BorderRadius(value)),
+        "stroke_thickness" => Some(ObjectProperty::StrokeThickness(value)),
+        "stroke_red" => Some(ObjectProperty::StrokeRed(value)),
+        "stroke_green" => Some(ObjectProperty::StrokeGreen(value)),
+        "stroke_blue" => Some(ObjectProperty::StrokeBlue(value)),
+        _ => None,
+    }
+}
+
+impl ObjectEdit {
+    fn label(&self, saved_state: &SavedState) -> String {
+        let name = object_name(saved_state, &self.object_id.to_string())
+            .unwrap_or_else(|| "object".to_string());
+        let field = match self.field_name.as_str() {
+            "red_fill" => "background red".to_string(),
+            "green_fill" => "background green".to_string(),
+            "blue_fill" => "background blue".to_string(),
+            field => field.replace('_', " "),
+        };
+
+        match (property_parts(&self.old_value), property_parts(&self.new_value)) {
+            (Some((property, old)), Some((_, new))) => {
+                // colors are read back from the editor in 0.0 - 1.0 but mostly entered
+                // in 0 - 255. Background colors are converted before the edit is made.
+                let (old, new) = match property {
+                    "width" | "height" | "border_radius" | "stroke_thickness" => (old, new),
+                    "fill_red" | "fill_green" | "fill_blue" => {
+                        (wgpu_to_human(old), wgpu_to_human(new))
+                    }
+                    _ => (wgpu_to_human(old), new),
+                };
+
+                format!(
+                    "Change {} of {} {} → {}",
+                    field,
+                    name,
+                    format_value(old),
+                    format_value(new)
+                )
+            }
+            _ => format!("Change {} of {}", field, name),
+        }
+    }
+}
+
+impl EditorEdit {
+    /// What the edit did, for the history panel
+    pub fn label(&self, saved_state: &SavedState) -> String {
+        let name_of = |object_id: &Uuid| {
+            object_name(saved_state, &object_id.to_string()).unwrap_or_else(|| "object".to_string())
+        };
+
+        match self {
+            EditorEdit::Object(edit) => edit.label(saved_state),
+            EditorEdit::Shape(edit) => format!("Edit shape of {}", name_of(&edit.polygon_id)),
+            EditorEdit::Transform(edit) => {
+                let name = name_of(&edit.object_id);
+
+                if edit.rotation != 0 {
+                    format!("Rotate {} {}°", name, edit.rotation)
+                } else if edit.old_dimensions != edit.new_dimensions {
+                    format!(
+                        "Resize {} {}×{} → {}×{}",
+                        name,
+                        format_value(edit.old_dimensions.0),
+                        format_value(edit.old_dimensions.1),
+                        format_value(edit.new_dimensions.0),
+                        format_value(edit.new_dimensions.1)
+                    )
+                } else {
+                    format!("Move {}", name)
+                }
+            }
+            EditorEdit::Delete(edit) => {
+                // once deleted, the name only lives on in the edit
+                let name = match &edit.removed {
+                    Some((_, object, _)) => object.name().to_string(),
+                    None => name_of(&edit.object_id),
+                };
+
+                format!("Delete {}", name)
+            }
+            EditorEdit::Insert(edit) => format!("Add {}", edit.object.name()),
+            EditorEdit::ObjectGroup(edit) => match (&edit.old_group, &edit.new_group) {
+                (None, Some(group)) => format!("Group {}", group.name),
+                (Some(group), None) => format!("Ungroup {}", group.name),
+                (Some(old), Some(new)) if old.name != new.name => {
+                    format!("Rename group {} → {}", old.name, new.name)
+                }
+                (_, Some(group)) => format!("Animate {}", group.name),
+                (None, None) => "Change group".to_string(),
+            },
+            EditorEdit::Text(edit) => {
+                let (old, new) = (&edit.old_style, &edit.new_style);
+                let name = name_of(&edit.text_id);
+
+                if old.text != new.text {
+                    format!("Edit text of {}", name)
+                } else if old.font_family != new.font_family {
+                    format!("Change font of {} to {}", name, new.font_family)
+                } else if old.font_size != new.font_size {
+                    format!(
+                        "Change font size of {} {} → {}",
+                        name, old.font_size, new.font_size
+                    )
+                } else {
+                    format!("Restyle text of {}", name)
+                }
+            }
+            EditorEdit::Background(edit) => {
+                let name = saved_state
+                    .sequences
+                    .iter()
+                    .find(|s| s.id == edit.sequence_id)
+                    .map(|s| s.name.clone())
+                    .unwrap_or_else(|| "sequence".to_string());
+
+                format!("Change background of {}", name)
+            }
+            EditorEdit::Group(group) => match group.edits.as_slice() {
+                [only] => only.label(saved_state),
+                edits if edits.iter().all(|e| matches!(e, EditorEdit::Delete(_))) => {
+                    format!("Delete {} objects", edits.len())
+                }
+                edits if edits.iter().all(|e| matches!(e, EditorEdit::Insert(_))) => {
+                    format!("Add {} objects", edits.len())
+                }
+                edits if edits.iter().all(|e| matches!(e, EditorEdit::Transform(_))) => {
+                    format!("Transform {} objects", edits.len())
+                }
+                edits => format!("{} changes", edits.len()),
+            },
+        }
+    }
+
+    /// Serializable copy, None for edits that can't be stored
+    pub fn to_stored(&self) -> Option<StoredEdit> {
+        Some(match self {
+            EditorEdit::Object(edit) => {
+                let (old_property, old_value) = property_parts(&edit.old_value)?;
+                let (new_property, new_value) = property_parts(&edit.new_value)?;
+
+                StoredEdit::Object {
+                    object_id: edit.object_id.to_string(),
+                    object_type: (&edit.object_type).into(),
+                    background_flag: edit.background_flag,
+                    selected_sequence_id: edit.selected_sequence_id.clone(),
+                    field_name: edit.field_name.clone(),
+                    old_value: (old_property.to_string(), old_value),
+                    new_value: (new_property.to_string(), new_value),
+                }
+            }
+            EditorEdit::Shape(edit) => StoredEdit::Shape {
+                polygon_id: edit.polygon_id.to_string(),
+                old_shape: edit.old_shape.clone(),
+                new_shape: edit.new_shape.clone(),
+            },
+            EditorEdit::Transform(edit) => StoredEdit::Transform {
+                object_id: edit.object_id.to_string(),
+                object_type: (&edit.object_type).into(),
+                sequence_id: edit.sequence_id.clone(),
+                translation: edit.translation,
+                rotation: edit.rotation,
+                old_dimensions: edit.old_dimensions,
+                new_dimensions: edit.new_dimensions,
+            },
+            EditorEdit::Delete(edit) => StoredEdit::Delete {
+                object_id: edit.object_id.to_string(),
+                object_type: (&edit.object_type).into(),
+                sequence_id: edit.sequence_id.clone(),
+                removed: edit.removed.clone(),
+            },
+            EditorEdit::Insert(edit) => StoredEdit::Insert {
+                object_id: edit.object_id.to_string(),
+                object_type: (&edit.object_type).into(),
+                sequence_id: edit.sequence_id.clone(),
+                object: edit.object.clone(),
+                motion_path: edit.motion_path.clone(),
+            },
+            EditorEdit::ObjectGroup(edit) => StoredEdit::ObjectGroup {
+                sequence_id: edit.sequence_id.clone(),
+                group_id: edit.group_id.clone(),
+                old_group: edit.old_group.clone(),
+                new_group: edit.new_group.clone(),
+            },
+            EditorEdit::Text(edit) => StoredEdit::Text {
+                text_id: edit.text_id.to_string(),
+                sequence_id: edit.sequence_id.clone(),
+                old_style: edit.old_style.clone(),
+                new_style: edit.new_style.clone(),
+            },
+            EditorEdit::Background(edit) => StoredEdit::Background {
+                sequence_id: edit.sequence_id.clone(),
+                old_fill: edit.old_fill.clone(),
+                new_fill: edit.new_fill.clone(),
+            },
+            EditorEdit::Group(group) => StoredEdit::Group(
+                group
+                    .edits
+                    .iter()
+                    .map(|edit| edit.to_stored())
+                    .collect::<Option<Vec<_>>>()?,
+            ),
+        })
+    }
+}
+
+/// ObjectType as it's kept in session storage, so stored history doesn't
+/// depend on how the engine serializes its types
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum StoredObjectType {
+    Polygon,
+    TextItem,
+    ImageItem,
+    VideoItem,
+}
+
+impl From<&ObjectType> for StoredObjectType {
+    fn from(object_type: &ObjectType) -> Self {
+        match object_type {
+            ObjectType::Polygon => StoredObjectType::Polygon,
+            ObjectType::TextItem => StoredObjectType::TextItem,
+            ObjectType::ImageItem => StoredObjectType::ImageItem,
+            ObjectType::VideoItem => StoredObjectType::VideoItem,
+        }
+    }
+}
+
+impl From<StoredObjectType> for ObjectType {
+    fn from(object_type: StoredObjectType) -> Self {
+        match object_type {
+            StoredObjectType::Polygon => ObjectType::Polygon,
+            StoredObjectType::TextItem => ObjectType::TextItem,
+            StoredObjectType::ImageItem => ObjectType::ImageItem,
+            StoredObjectType::VideoItem => ObjectType::VideoItem,
+        }
+    }
+}
+
+/// An edit in a form that can be kept in session storage, so the
+/// history outlives a page reload
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum StoredEdit {
+    Object {
+        object_id: String,
+        object_type: StoredObjectType,
+        background_flag: bool,
+        selected_sequence_id: Option<String>,
+        field_name: String,
+        old_value: (String, f32),
+        new_value: (String, f32),
+    },
+    Shape {
+        polygon_id: String,
+        old_shape: SavedShape,
+        new_shape: SavedShape,
+    },
+    Transform {
+        object_id: String,
+        object_type: StoredObjectType,
+        sequence_id: String,
+        translation: (i32, i32),
+        rotation: i32,
+        old_dimensions: (f32, f32),
+        new_dimensions: (f32, f32),
+    },
+    Delete {
+        object_id: String,
+        object_type: StoredObjectType,
+        sequence_id: String,
+        removed: Option<(usize, SavedObject, Option<(usize, AnimationData)>)>,
+    },
+    Insert {
+        object_id: String,
+        object_type: StoredObjectType,
+        sequence_id: String,
+        object: SavedObject,
+        motion_path: Option<AnimationData>,
+    },
+    ObjectGroup {
+        sequence_id: String,
+        group_id: String,
+        old_group: Option<SavedGroupConfig>,
+        new_group: Option<SavedGroupConfig>,
+    },
+    Text {
+        text_id: String,
+        sequence_id: String,
+        old_style: TextStyle,
+        new_style: TextStyle,
+    },
+    Background {
+        sequence_id: String,
+        old_fill: Option<BackgroundFill>,
+        new_fill: Option<BackgroundFill>,
+    },
+    Group(Vec<StoredEdit>),
+}
+
+impl StoredEdit {
+    pub fn into_edit(self) -> Option<EditorEdit> {
+        let parse = |id: &str| Uuid::from_str(id).ok();
+
+        Some(match self {
+            StoredEdit::Object {
+                object_id,
+                object_type,
+                background_flag,
+                selected_sequence_id,
+                field_name,
+                old_value,
+                new_value,
+            } => ObjectEdit {
+                object_id: parse(&object_id)?,
+                object_type: object_type.into(),
+                background_flag,
+                selected_sequence_id,
+                field_name,
+                old_value: property_from_parts(&old_value.0, old_value.1)?,
+                new_value: property_from_parts(&new_value.0, new_value.1)?,
+            }
+            .into(),
+            StoredEdit::Shape {
+                polygon_id,
+                old_shape,
+                new_shape,
+            } => ShapeEdit {
+                polygon_id: parse(&polygon_id)?,
+                old_shape,
+                new_shape,
+            }
+            .into(),
+            StoredEdit::Transform {
+                object_id,
+                object_type,
+                sequence_id,
+                translation,
+                rotation,
+                old_dimensions,
+                new_dimensions,
+            } => TransformEdit {
+                object_id: parse(&object_id)?,
+                object_type: object_type.into(),
+                sequence_id,
+                translation,
+                rotation,
+                old_dimensions,
+                new_dimensions,
+            }
+            .into(),
+            StoredEdit::Delete {
+                object_id,
+                object_type,
+                sequence_id,
+                removed,
+            } => DeleteEdit {
+                object_id: parse(&object_id)?,
+                object_type: object_type.into(),
+                sequence_id,
+                removed,
+            }
+            .into(),
+            StoredEdit::Insert {
+                object_id,
+                object_type,
+                sequence_id,
+                object,
+                motion_path,
+            } => InsertEdit {
+                object_id: parse(&object_id)?,
+                object_type: object_type.into(),
+                sequence_id,
+                object,
+                motion_path,
+            }
+            .into(),
+            StoredEdit::ObjectGroup {
+                sequence_id,
+                group_id,
+                old_group,
+                new_group,
+            } => ObjectGroupEdit {
+                sequence_id,
+                group_id,
+                old_group,
+                new_group,
+            }
+            .into(),
+            StoredEdit::Text {
+                text_id,
+                sequence_id,
+                old_style,
+                new_style,
+            } => TextEdit {
+                text_id: parse(&text_id)?,
+                sequence_id,
+                old_style,
+                new_style,
+            }
+            .into(),
+            StoredEdit::Background {
+                sequence_id,
+                old_fill,
+                new_fill,
+            } => BackgroundEdit {
+                sequence_id,
+                old_fill,
+                new_fill,
+            }
+            .into(),
+            StoredEdit::Group(edits) => GroupEdit {
+                edits: edits
+                    .into_iter()
+                    .map(|edit| edit.into_edit())
+                    .collect::<Option<Vec<_>>>()?,
+                applied: false,
+            }
+            .into(),
+        })
+    }
+}
+
+pub struct EditorState {
+    pub editor: Arc<Mutex<Editor>>,
+    pub record: Arc<Mutex<Record<EditorEdit>>>,
+    pub record_state: RecordState,
+    /// everything selected, in the order it was picked
+    pub selected_objects: Vec<(Uuid, ObjectType)>,
+    transaction: Option<Transaction>,
+    // pub value_signals: Arc<Mutex<HashMap<String, RwSignal<String>>>>,
+    // pub current_modifiers: ModifiersState,
+    // pub saved_state: Option<SavedState>,
+    // pub project_selected_signal: Option<RwSignal<Uuid>>,
+    // pub active_sequence_mode: RwSignal<String>,
+    // pub sequence_timeline_state: TimelineState,
+}
+
+pub struct RecordState {
+    pub editor: Arc<Mutex<Editor>>,
+    // pub record: Arc<Mutex<Record<ObjectEdit>>>,
+    // pub editor_state: EditorState,
+    pub saved_state: Option<SavedState>,
+    pub redraw: RedrawFlag,
+    pub saves: SaveQueue,
+    /// sequence whose background fill an edit changed, for the page to show again
+    pub background_changed: Option<String>,
+    /// sequence shown on the canvas, objects brought back into it have to be loaded
+    pub open_sequence: Option<String>,
+}
+
+impl RecordState {
+    /// Runs `f` with saves held back, then writes whatever it changed once
+    fn deferring_saves(&mut self, f: impl FnOnce(&mut RecordState)) {
+        let outer = self.saves.deferred;
+        self.saves.deferred = true;
+
+        f(self);
+
+        self.saves.deferred = outer;
+        if !outer {
+            self.saves.flush();
+        }
+    }
+}
+
+/// Edits held while transactions are open, committed to the record as one step
+struct Transaction {
+    edits: Vec<EditorEdit>,
+    // where each open transaction's edits start, outermost first
+    starts: Vec<usize>,
+    // whether saves were deferred before the outermost transaction began
+    saves_deferred: bool,
+}
+
+/// Writes sequences to the backend after edits. While deferred, only the latest
+/// sequences are kept and written on flush, so a group of edits saves once.
+#[derive(Default)]
+pub struct SaveQueue {
+    deferred: bool,
+    pending: Option<Vec<Sequence>>,
+}
+
+impl SaveQueue {
+    pub fn save(&mut self, sequences: Vec<Sequence>) {
+        if self.deferred {
+            self.pending = Some(sequences);
+            return;
+        }
+
+        spawn_local({
+            async move {
+                save_sequences_data(sequences).await;
+            }
+        });
+    }
+
+    fn flush(&mut self) {
+        if let Some(sequences) = self.pending.take() {
+            self.save(sequences);
+        }
+    }
+}
+
+impl EditorState {
+    pub fn new(
+        editor: Arc<Mutex<Editor>>,
+        record: Arc<Mutex<Record<EditorEdit>>>,
+        redraw: RedrawFlag,
+    ) -> Self {
+        // let sequence_timeline_state = TimelineState::new();
+
+        Self {
+            editor: Arc::clone(&editor),
+            record: Arc::clone(&record),
+            record_state: RecordState {
+                editor: Arc::clone(&editor),
+                saved_state: None,
+                redraw,
+                saves: SaveQueue::default(),
+                background_changed: None,
+                open_sequence: None,
+                // record: Arc::clone(&record),
+            },
+            selected_objects: Vec::new(),
+            transaction: None,
+            // value_signals: Arc::new(Mutex::new(HashMap::new())),
+            // current_modifiers: ModifiersState::empty(),
+            // saved_state: None,
+            // project_selected_signal: None,
+            // sequence_timeline_state,
+            // active_sequence_mode: RwSignal::new("layout".to_string()),
+        }
+    }
+
+    pub fn save_default_keyframes(
+        &mut self,
+        savable_item_id: String,
+        object_type: ObjectType,
+        object_position: SavedPoint,
+    ) -> AnimationData {
+        let mut properties = Vec::new();
+
+        let mut position_keyframes = Vec::new();
+
+        position_keyframes.push(UIKeyframe {
+            id: Uuid::new_v4().to_string(),
+            time: Duration::from_secs(0),
+            value: KeyframeValue::Position([object_position.x, object_position.y - 100]),
+            easing: EasingType::EaseInOut,
+            path_type: PathType::Linear,
+            key_type: KeyType::Frame,
+        });
+        position_keyframes.push(UIKeyframe {
+            id: Uuid::new_v4().to_string(),
+            time: Duration::from_millis(2500),
+            value: KeyframeValue::Position([object_position.x, object_position.y - 50]),
+            easing: EasingType::EaseInOut,
+            path_type: PathType::Linear,
+            key_type: KeyType::Frame,
+        });
+        position_keyframes.push(UIKeyframe {
+            id: Uuid::new_v4().to_string(),
+            time: Duration::from_secs(5),
+            value: KeyframeValue::Position([object_position.x, object_position.y]),
+            easing: EasingType::EaseInOut,
+            path_type: PathType::Linear,
+            key_type: KeyType::Frame,
+        });
+        position_keyframes.push(UIKeyframe {
+            id: Uuid::new_v4().to_string(),
+            time: Duration::from_secs(15),
+            value: KeyframeValue::Position([object_position.x, object_position.y + 50]),
+            easing: EasingType::EaseInOut,
+            path_type: PathType::Linear,
+            key_type: KeyType::Frame,
+        });
+        position_keyframes.push(UIKeyframe {
+            id: Uuid::new_v4().to_string(),
+            time: Duration::from_millis(17500),
+            value: KeyframeValue::Position([object_position.x, object_position.y + 100]),
+            easing: EasingType::EaseInOut,
+            path_type: PathType::Linear,
+            key_type: KeyType::Frame,
+        });
+        position_keyframes.push(UIKeyframe {
+            id: Uuid::new_v4().to_string(),
+            time: Duration::from_secs(20),
+            value: KeyframeValue::Position([object_position.x, object_position.y + 150]),
+            easing: EasingType::EaseInOut,
+            path_type: PathType::Linear,
+            key_type: KeyType::Frame,
+        });
+
+        let mut position_prop = AnimationProperty {
+            name: "Position".to_string(),
+            property_path: "position".to_string(),
+            children: Vec::new(),
+            keyframes: position_keyframes,
+            depth: 0,
+        };
+
+        let mut rotation_keyframes = Vec::new();
+
+        rotation_keyframes.push(UIKeyframe {
+            id: Uuid::new_v4().to_string(),
+            time: Duration::from_secs(0),
+            value: KeyframeValue::Rotation(0),
+            easing: EasingType::EaseInOut,
+            path_type: PathType::Linear,
+            key_type: KeyType::Frame,
+        });
+        rotation_keyframes.push(UIKeyframe {
+            id: Uuid::new_v4().to_string(),
+            time: Duration::from_millis(2500),
+            value: KeyframeValue::Rotation(0),
+            easing: EasingType::EaseInOut,
+            path_type: PathType::Linear,
+            key_type: KeyType::Frame,
+        });
+        rotation_keyframes.push(UIKeyframe {
+            id: Uuid::new_v4().to_string(),
+            time: Duration::from_secs(5),
+            value: KeyframeValue::Rotation(0),
+            easing: EasingType::EaseInOut,
+            path_type: PathType::Linear,
+            key_type: KeyType::Frame,
+        });
+        rotation_keyframes.push(UIKeyframe {
+            id: Uuid::new_v4().to_string(),
+            time: Duration::from_secs(15),
+            value: KeyframeValue::Rotation(0),
+            easing: EasingType::EaseInOut,
+            path_type: PathType::Linear,
+            key_type: KeyType::Frame,
+        });
+        rotation_keyframes.push(UIKeyframe {
+            id: Uuid::new_v4().to_string(),
+            time: Duration::from_millis(17500),
+            value: KeyframeValue::Rotation(0),
+            easing: EasingType::EaseInOut,
+            path_type: PathType::Linear,
+            key_type: KeyType::Frame,
+        });
+        rotation_keyframes.push(UIKeyframe {
+            id: Uuid::new_v4().to_string(),
+            time: Duration::from_secs(20),
+            value: KeyframeValue::Rotation(0),
+            easing: EasingType::EaseInOut,
+            path_type: PathType::Linear,
+            key_type: KeyType::Frame,
+        });
+
+        let mut rotation_prop = AnimationProperty {
+            name: "Rotation".to_string(),
+            property_path: "rotation".to_string(),
+            children: Vec::new(),
+            keyframes: rotation_keyframes,
+            depth: 0,
+        };
+
+        let mut scale_keyframes = Vec::new();
 
         scale_keyframes.push(UIKeyframe {
             id: Uuid::new_v4().to_string(),
@@ -1490,961 +2730,1886 @@ impl EditorState {
             key_type: KeyType::Frame,
         });
 
-        let mut opacity_prop = AnimationProperty {
-            name: "Opacity".to_string(),
-            property_path: "opacity".to_string(),
-            children: Vec::new(),
-            keyframes: opacity_keyframes,
-            depth: 0,
-        };
+        let mut opacity_prop = AnimationProperty {
+            name: "Opacity".to_string(),
+            property_path: "opacity".to_string(),
+            children: Vec::new(),
+            keyframes: opacity_keyframes,
+            depth: 0,
+        };
+
+        properties.push(position_prop);
+        properties.push(rotation_prop);
+        properties.push(scale_prop);
+        // properties.push(perspective_x_prop);
+        // properties.push(perspective_y_prop);
+        properties.push(opacity_prop);
+
+        if object_type == ObjectType::VideoItem {
+            let mut zoom_keyframes = Vec::new();
+
+            zoom_keyframes.push(UIKeyframe {
+                id: Uuid::new_v4().to_string(),
+                time: Duration::from_secs(0),
+                value: KeyframeValue::Zoom(100),
+                easing: EasingType::EaseInOut,
+                path_type: PathType::Linear,
+                key_type: KeyType::Frame,
+            });
+            // zoom_keyframes.push(UIKeyframe {
+            //     id: Uuid::new_v4().to_string(),
+            //     time: Duration::from_millis(2500),
+            //     value: KeyframeValue::Position([object_position.x, object_position.y - 50]),
+            //     easing: EasingType::EaseInOut,
+            //     path_type: PathType::Linear,
+            //     key_type: KeyType::Frame,
+            // });
+            zoom_keyframes.push(UIKeyframe {
+                id: Uuid::new_v4().to_string(),
+                time: Duration::from_secs(5),
+                value: KeyframeValue::Zoom(135),
+                easing: EasingType::EaseInOut,
+                path_type: PathType::Linear,
+                key_type: KeyType::Frame,
+            });
+            zoom_keyframes.push(UIKeyframe {
+                id: Uuid::new_v4().to_string(),
+                time: Duration::from_secs(15),
+                value: KeyframeValue::Zoom(135),
+                easing: EasingType::EaseInOut,
+                path_type: PathType::Linear,
+                key_type: KeyType::Frame,
+            });
+            // zoom_keyframes.push(UIKeyframe {
+            //     id: Uuid::new_v4().to_string(),
+            //     time: Duration::from_millis(17500),
+            //     value: KeyframeValue::Position([object_position.x, object_position.y + 100]),
+            //     easing: EasingType::EaseInOut,
+            //     path_type: PathType::Linear,
+            //     key_type: KeyType::Frame,
+            // });
+            zoom_keyframes.push(UIKeyframe {
+                id: Uuid::new_v4().to_string(),
+                time: Duration::from_secs(20),
+                value: KeyframeValue::Zoom(100),
+                easing: EasingType::EaseInOut,
+                path_type: PathType::Linear,
+                key_type: KeyType::Frame,
+            });
+
+            let mut zoom_prop = AnimationProperty {
+                name: "Zoom / Popout".to_string(),
+                property_path: "zoom".to_string(),
+                children: Vec::new(),
+                keyframes: zoom_keyframes,
+                depth: 0,
+            };
+
+            properties.push(zoom_prop);
+        }
+
+        let new_motion_path = AnimationData {
+            id: Uuid::new_v4().to_string(),
+            object_type,
+            polygon_id: savable_item_id.clone(),
+            duration: Duration::from_secs(20),
+            start_time_ms: 0,
+            position: [0, 0],
+            properties: properties,
+        };
+
+        new_motion_path
+    }
+
+    pub fn reverse_keyframes(&self, animation: AnimationData) -> AnimationData {
+        let mut new_animation = animation.clone();
+
+        // For each property in the animation
+        for property in &mut new_animation.properties {
+            if property.keyframes.len() <= 1 {
+                continue; // No need to reverse single keyframe or empty properties
+            }
+
+            // Sort keyframes by time first to ensure proper ordering
+            property
+                .keyframes
+                .sort_by(|a, b| a.time.partial_cmp(&b.time).unwrap());
+
+            // Create a vector of all times
+            let times: Vec<Duration> = property.keyframes.iter().map(|k| k.time).collect();
+
+            // Reverse the times vector
+            let reversed_times: Vec<Duration> = times.iter().rev().cloned().collect();
+
+            // Apply the reversed times to the keyframes
+            for (keyframe, new_time) in property.keyframes.iter_mut().zip(reversed_times.iter()) {
+                keyframe.time = *new_time;
+            }
+        }
+
+        new_animation
+    }
+
+    /// squish keyframes into target_duration, keeping proportional time between them
+    pub fn scale_keyframes(
+        &self,
+        selected_sequence_id: String,
+        target_duration_s: f32,
+    ) -> Vec<AnimationData> {
+        let target_duration = Duration::from_secs_f32(target_duration_s);
+        let mut animations = Vec::new();
+
+        // Find the animation with matching ID
+        let saved_state = self
+            .record_state
+            .saved_state
+            .as_ref()
+            .expect("Couldn't get saved state");
+        if let Some(sequence) = saved_state
+            .sequences
+            .iter()
+            .find(|a| a.id == selected_sequence_id)
+        {
+            for animation in sequence.polygon_motion_paths.clone() {
+                let new_animation = self.scale_animation(animation, target_duration);
+                animations.push(new_animation);
+            }
+        }
+
+        animations
+    }
+
+    pub fn scale_animation(
+        &self,
+        animation: AnimationData,
+        target_duration: Duration,
+    ) -> AnimationData {
+        let original_duration = animation.duration;
+        let scale_factor = target_duration.as_secs_f32() / original_duration.as_secs_f32();
+
+        // Create a new animation with scaled keyframes
+        let mut new_animation = animation.clone();
+        new_animation.duration = target_duration;
+
+        // Scale each property's keyframes
+        for property in &mut new_animation.properties {
+            for keyframe in &mut property.keyframes {
+                let original_time_secs = keyframe.time.as_secs_f32();
+                let new_time_secs = original_time_secs * scale_factor;
+                keyframe.time = Duration::from_secs_f32(new_time_secs);
+            }
+        }
+
+        // animations.push(new_animation);
+
+        new_animation
+    }
+
+    pub fn add_saved_polygon(
+        &mut self,
+        selected_sequence_id: String,
+        savable_polygon: SavedPolygonConfig,
+    ) {
+        let new_motion_path = self.save_default_keyframes(
+            savable_polygon.id.clone(),
+            ObjectType::Polygon,
+            savable_polygon.position.clone(),
+        );
+
+        let mut saved_state = self
+            .record_state
+            .saved_state
+            .as_mut()
+            .expect("Couldn't get Saved State");
+
+        saved_state.sequences.iter_mut().for_each(|s| {
+            if s.id == selected_sequence_id {
+                s.active_polygons.push(savable_polygon.clone());
+                s.polygon_motion_paths.push(new_motion_path.clone());
+            }
+        });
+
+        let sequences = saved_state.sequences.clone();
+
+        self.record_state.saves.save(sequences);
+
+        self.record_state.saved_state = Some(saved_state.clone());
+    }
+
+    pub fn add_saved_text_item(
+        &mut self,
+        selected_sequence_id: String,
+        savable_text_item: SavedTextRendererConfig,
+    ) {
+        let new_motion_path = self.save_default_keyframes(
+            savable_text_item.id.clone(),
+            ObjectType::TextItem,
+            savable_text_item.position.clone(),
+        );
+
+        let mut saved_state = self
+            .record_state
+            .saved_state
+            .as_mut()
+            .expect("Couldn't get Saved State");
+
+        saved_state.sequences.iter_mut().for_each(|s| {
+            if s.id == selected_sequence_id {
+                s.active_text_items.push(savable_text_item.clone());
+                s.polygon_motion_paths.push(new_motion_path.clone()); // storing alongside polygon motion paths for now
+            }
+        });
+
+        let sequences = saved_state.sequences.clone();
 
-        properties.push(position_prop);
-        properties.push(rotation_prop);
-        properties.push(scale_prop);
-        // properties.push(perspective_x_prop);
-        // properties.push(perspective_y_prop);
-        properties.push(opacity_prop);
+        self.record_state.saves.save(sequences);
 
-        if object_type == ObjectType::VideoItem {
-            let mut zoom_keyframes = Vec::new();
+        self.record_state.saved_state = Some(saved_state.clone());
+    }
 
-            zoom_keyframes.push(UIKeyframe {
-                id: Uuid::new_v4().to_string(),
-                time: Duration::from_secs(0),
-                value: KeyframeValue::Zoom(100),
-                easing: EasingType::EaseInOut,
-                path_type: PathType::Linear,
-                key_type: KeyType::Frame,
-            });
-            // zoom_keyframes.push(UIKeyframe {
-            //     id: Uuid::new_v4().to_string(),
-            //     time: Duration::from_millis(2500),
-            //     value: KeyframeValue::Position([object_position.x, object_position.y - 50]),
-            //     easing: EasingType::EaseInOut,
-            //     path_type: PathType::Linear,
-            //     key_type: KeyType::Frame,
-            // });
-            zoom_keyframes.push(UIKeyframe {
-                id: Uuid::new_v4().to_string(),
-                time: Duration::from_secs(5),
-                value: KeyframeValue::Zoom(135),
-                easing: EasingType::EaseInOut,
-                path_type: PathType::Linear,
-                key_type: KeyType::Frame,
-            });
-            zoom_keyframes.push(UIKeyframe {
-                id: Uuid::new_v4().to_string(),
-                time: Duration::from_secs(15),
-                value: KeyframeValue::Zoom(135),
-                easing: EasingType::EaseInOut,
-                path_type: PathType::Linear,
-                key_type: KeyType::Frame,
+    pub fn add_saved_image_item(
+        &mut self,
+        selected_sequence_id: String,
+        savable_image_item: SavedStImageConfig,
+    ) {
+        let new_motion_path = self.save_default_keyframes(
+            savable_image_item.id.clone(),
+            ObjectType::ImageItem,
+            savable_image_item.position.clone(),
+        );
+
+        let mut saved_state = self
+            .record_state
+            .saved_state
+            .as_mut()
+            .expect("Couldn't get Saved State");
+
+        saved_state.sequences.iter_mut().for_each(|s| {
+            if s.id == selected_sequence_id {
+                s.active_image_items.push(savable_image_item.clone());
+                s.polygon_motion_paths.push(new_motion_path.clone()); // storing alongside polygon motion paths for now
+            }
+        });
+
+        let sequences = saved_state.sequences.clone();
+
+        self.record_state.saves.save(sequences);
+
+        self.record_state.saved_state = Some(saved_state.clone());
+    }
+
+    pub fn add_saved_video_item(
+        &mut self,
+        selected_sequence_id: String,
+        savable_video_item: SavedStVideoConfig,
+        source_duration_ms: i64,
+    ) {
+        {
+            let new_motion_path = self.save_default_keyframes(
+                savable_video_item.id.clone(),
+                ObjectType::VideoItem,
+                savable_video_item.position.clone(),
+            );
+
+            let mut saved_state = self
+                .record_state
+                .saved_state
+                .as_mut()
+                .expect("Couldn't get Saved State");
+
+            saved_state.sequences.iter_mut().for_each(|s| {
+                if s.id == selected_sequence_id {
+                    if s.duration_ms < source_duration_ms as i32 {
+                        s.duration_ms = source_duration_ms as i32;
+                    }
+
+                    s.active_video_items.push(savable_video_item.clone());
+                    s.polygon_motion_paths.push(new_motion_path.clone()); // storing alongside polygon motion paths for now
+                }
             });
-            // zoom_keyframes.push(UIKeyframe {
-            //     id: Uuid::new_v4().to_string(),
-            //     time: Duration::from_millis(17500),
-            //     value: KeyframeValue::Position([object_position.x, object_position.y + 100]),
-            //     easing: EasingType::EaseInOut,
-            //     path_type: PathType::Linear,
-            //     key_type: KeyType::Frame,
-            // });
-            zoom_keyframes.push(UIKeyframe {
-                id: Uuid::new_v4().to_string(),
-                time: Duration::from_secs(20),
-                value: KeyframeValue::Zoom(100),
-                easing: EasingType::EaseInOut,
-                path_type: PathType::Linear,
-                key_type: KeyType::Frame,
+        }
+
+        // after updated with motion paths
+        let scaled_paths = self.scale_keyframes(
+            selected_sequence_id.clone(),
+            source_duration_ms as f32 / 1000.0,
+        );
+
+        {
+            let mut saved_state = self
+                .record_state
+                .saved_state
+                .as_mut()
+                .expect("Couldn't get Saved State");
+
+            saved_state.sequences.iter_mut().for_each(|s| {
+                if s.id == selected_sequence_id {
+                    s.polygon_motion_paths = scaled_paths.clone();
+                }
             });
 
-            let mut zoom_prop = AnimationProperty {
-                name: "Zoom / Popout".to_string(),
-                property_path: "zoom".to_string(),
-                children: Vec::new(),
-                keyframes: zoom_keyframes,
-                depth: 0,
-            };
+            let sequences = saved_state.sequences.clone();
 
-            properties.push(zoom_prop);
+            self.record_state.saves.save(sequences);
+
+            self.record_state.saved_state = Some(saved_state.clone());
         }
+    }
 
-        let new_motion_path = AnimationData {
-            id: Uuid::new_v4().to_string(),
-            object_type,
-            polygon_id: savable_item_id.clone(),
-            duration: Duration::from_secs(20),
-            start_time_ms: 0,
-            position: [0, 0],
-            properties: properties,
+    /// swaps out the keyframes of one property on an object's motion path
+    /// Replaces the keyframes of one property, adding the property as `name`
+    /// when the motion path doesn't have it yet
+    pub fn replace_property_keyframes(
+        &mut self,
+        selected_sequence_id: String,
+        object_id: String,
+        property_path: &str,
+        name: &str,
+        keyframes: Vec<UIKeyframe>,
+    ) {
+        let saved_state = self
+            .record_state
+            .saved_state
+            .as_mut()
+            .expect("Couldn't get Saved State");
+
+        saved_state
+            .sequences
+            .iter_mut()
+            .filter(|s| s.id == selected_sequence_id)
+            .flat_map(|s| s.polygon_motion_paths.iter_mut())
+            .filter(|m| m.polygon_id == object_id)
+            .for_each(|m| {
+                match m
+                    .properties
+                    .iter_mut()
+                    .find(|p| p.property_path == property_path)
+                {
+                    Some(property) => property.keyframes = keyframes.clone(),
+                    None => m.properties.push(AnimationProperty {
+                        name: name.to_string(),
+                        property_path: property_path.to_string(),
+                        children: Vec::new(),
+                        keyframes: keyframes.clone(),
+                        depth: 0,
+                    }),
+                }
+            });
+
+        let sequences = saved_state.sequences.clone();
+
+        self.record_state.saves.save(sequences);
+    }
+
+    /// Records a new background fill as one undo step. The caller is already showing it.
+    pub fn update_background_fill(&mut self, selected_sequence_id: String, fill: BackgroundFill) {
+        let old_fill = self
+            .record_state
+            .saved_state
+            .as_ref()
+            .expect("Couldn't get Saved State")
+            .sequences
+            .iter()
+            .find(|s| s.id == selected_sequence_id)
+            .and_then(|s| s.background_fill.clone());
+
+        let edit = BackgroundEdit {
+            sequence_id: selected_sequence_id,
+            old_fill,
+            new_fill: Some(fill),
         };
 
-        new_motion_path
+        self.record_edit(edit.into());
+
+        self.record_state.background_changed = None;
     }
 
-    pub fn reverse_keyframes(&self, animation: AnimationData) -> AnimationData {
-        let mut new_animation = animation.clone();
+    // Helper method to register a new signal
+    // pub fn register_signal(
+    //     &mut self,
+    //     name: String,
+    //     signal: RwSignal<String>,
+    //     signal_type: ObjectType,
+    // ) {
+    //     let mut signals = self.value_signals.lock().unwrap();
 
-        // For each property in the animation
-        for property in &mut new_animation.properties {
-            if property.keyframes.len() <= 1 {
-                continue; // No need to reverse single keyframe or empty properties
+    //     match signal_type {
+    //         ObjectType::Polygon => {
+    //             signals.insert(name + &self.selected_polygon_id.to_string(), signal);
+    //         }
+    //         ObjectType::TextItem => {
+    //             signals.insert(name + &self.selected_text_id.to_string(), signal);
+    //         }
+    //         ObjectType::ImageItem => {
+    //             signals.insert(name + &self.selected_image_id.to_string(), signal);
+    //         }
+    //         ObjectType::VideoItem => {
+    //             signals.insert(name + &self.selected_video_id.to_string(), signal);
+    //         }
+    //     }
+    // }
+
+    /// Makes an object the whole selection
+    pub fn select_object(&mut self, object_id: Uuid, object_type: ObjectType) {
+        self.set_selection(vec![(object_id, object_type)]);
+    }
+
+    /// Adds an object to the selection, or takes it out if it's already in
+    pub fn toggle_selected(&mut self, object_id: Uuid, object_type: ObjectType) {
+        match self
+            .selected_objects
+            .iter()
+            .position(|(id, _)| *id == object_id)
+        {
+            Some(index) => {
+                self.selected_objects.remove(index);
             }
+            None => self.selected_objects.push((object_id, object_type)),
+        }
+    }
 
-            // Sort keyframes by time first to ensure proper ordering
-            property
-                .keyframes
-                .sort_by(|a, b| a.time.partial_cmp(&b.time).unwrap());
+    pub fn set_selection(&mut self, objects: Vec<(Uuid, ObjectType)>) {
+        self.selected_objects = objects;
+    }
+
+    pub fn clear_selection(&mut self) {
+        self.set_selection(Vec::new());
+    }
+
+    pub fn update_width(
+        &mut self,
+        object_id: Uuid,
+        new_width_str: &str,
+        object_type: ObjectType,
+    ) -> Result<(), String> {
+        let new_width =
+            string_to_f32(new_width_str).map_err(|_| "Couldn't convert string to f32")?;
+
+        let old_width = {
+            let editor = self.record_state.editor.lock().unwrap();
+            editor.get_object_width(object_id, object_type.clone())
+        };
+
+        let edit = ObjectEdit {
+            object_id,
+            object_type,
+            background_flag: false,
+            selected_sequence_id: None,
+            old_value: ObjectProperty::Width(old_width),
+            new_value: ObjectProperty::Width(new_width),
+            field_name: "width".to_string(),
+            // signal: Some(
+            //     self.value_signals
+            //         .lock()
+            //         .unwrap()
+            //         .get(&format!("width{}", object_id))
+            //         .cloned()
+            //         .expect("Couldn't get width value signal"),
+            // ),
+        };
 
-            // Create a vector of all times
-            let times: Vec<Duration> = property.keyframes.iter().map(|k| k.time).collect();
+        self.record_edit(edit.into());
 
-            // Reverse the times vector
-            let reversed_times: Vec<Duration> = times.iter().rev().cloned().collect();
+        Ok(())
+    }
 
-            // Apply the reversed times to the keyframes
-            for (keyframe, new_time) in property.keyframes.iter_mut().zip(reversed_times.iter()) {
-                keyframe.time = *new_time;
-            }
-        }
+    pub fn update_height(
+        &mut self,
+        object_id: Uuid,
+        new_height_str: &str,
+        object_type: ObjectType,
+    ) -> Result<(), String> {
+        let new_height =
+            string_to_f32(new_height_str).map_err(|_| "Couldn't convert string to f32")?;
 
-        new_animation
-    }
+        let old_height = {
+            let editor = self.editor.lock().unwrap();
+            editor.get_object_height(object_id, object_type.clone())
+        };
 
-    /// squish keyframes into target_duration, keeping proportional time between them
-    pub fn scale_keyframes(
-        &self,
-        selected_sequence_id: String,
-        target_duration_s: f32,
-    ) -> Vec<AnimationData> {
-        let target_duration = Duration::from_secs_f32(target_duration_s);
-        let mut animations = Vec::new();
+        let edit = ObjectEdit {
+            object_id,
+            object_type,
+            background_flag: false,
+            selected_sequence_id: None,
+            old_value: ObjectProperty::Height(old_height),
+            new_value: ObjectProperty::Height(new_height),
+            field_name: "height".to_string(),
+            // signal: Some(
+            //     self.value_signals
+            //         .lock()
+            //         .unwrap()
+            //         .get(&format!("height{}", object_id))
+            //         .cloned()
+            //         .expect("Couldn't get height value signal"),
+            // ),
+        };
 
-        // Find the animation with matching ID
-        let saved_state = self
-            .record_state
-            .saved_state
-            .as_ref()
-            .expect("Couldn't get saved state");
-        if let Some(sequence) = saved_state
-            .sequences
-            .iter()
-            .find(|a| a.id == selected_sequence_id)
-        {
-            for animation in sequence.polygon_motion_paths.clone() {
-                let new_animation = self.scale_animation(animation, target_duration);
-                animations.push(new_animation);
-            }
-        }
+        self.record_edit(edit.into());
 
-        animations
+        Ok(())
     }
 
-    pub fn scale_animation(
-        &self,
-        animation: AnimationData,
-        target_duration: Duration,
-    ) -> AnimationData {
-        let original_duration = animation.duration;
-        let scale_factor = target_duration.as_secs_f32() / original_duration.as_secs_f32();
+    pub fn update_fill_red(
+        &mut self,
+        object_id: Uuid,
+        new_red_str: &str,
+        selected_sequence_id: String,
+    ) -> Result<(), String> {
+        let new_red = string_to_f32(new_red_str).map_err(|_| "Couldn't convert string to f32")?;
+        let new_red = color_to_wgpu(new_red);
 
-        // Create a new animation with scaled keyframes
-        let mut new_animation = animation.clone();
-        new_animation.duration = target_duration;
+        let old_red = {
+            let editor = self.editor.lock().unwrap();
+            editor.get_fill_red(object_id)
+        };
 
-        // Scale each property's keyframes
-        for property in &mut new_animation.properties {
-            for keyframe in &mut property.keyframes {
-                let original_time_secs = keyframe.time.as_secs_f32();
-                let new_time_secs = original_time_secs * scale_factor;
-                keyframe.time = Duration::from_secs_f32(new_time_secs);
-            }
-        }
+        let edit = ObjectEdit {
+            object_id,
+            object_type: ObjectType::Polygon,
+            background_flag: true,
+            selected_sequence_id: Some(selected_sequence_id),
+            old_value: ObjectProperty::FillRed(old_red),
+            new_value: ObjectProperty::FillRed(new_red),
+            field_name: "red_fill".to_string(),
+            // signal: Some(
+            //     self.value_signals
+            //         .lock()
+            //         .unwrap()
+            //         .get(&format!("red_fill{}", object_id))
+            //         .cloned()
+            //         .expect("Couldn't get red_fill value signal"),
+            // ),
+        };
 
-        // animations.push(new_animation);
+        self.record_edit(edit.into());
 
-        new_animation
+        Ok(())
     }
 
-    pub fn add_saved_polygon(
+    pub fn update_fill_green(
         &mut self,
+        object_id: Uuid,
+        new_green_str: &str,
         selected_sequence_id: String,
-        savable_polygon: SavedPolygonConfig,
-    ) {
-        let new_motion_path = self.save_default_keyframes(
-            savable_polygon.id.clone(),
-            ObjectType::Polygon,
-            savable_polygon.position.clone(),
-        );
-
-        let mut saved_state = self
-            .record_state
-            .saved_state
-            .as_mut()
-            .expect("Couldn't get Saved State");
+    ) -> Result<(), String> {
+        let new_green =
+            string_to_f32(new_green_str).map_err(|_| "Couldn't convert string to f32")?;
+        let new_green = color_to_wgpu(new_green);
 
-        saved_state.sequences.iter_mut().for_each(|s| {
-            if s.id == selected_sequence_id {
-                s.active_polygons.push(savable_polygon.clone());
-                s.polygon_motion_paths.push(new_motion_path.clone());
-            }
-        });
+        let old_green = {
+            let editor = self.editor.lock().unwrap();
+            editor.get_fill_green(object_id)
+        };
 
-        let sequences = saved_state.sequences.clone();
+        let edit = ObjectEdit {
+            object_id,
+            object_type: ObjectType::Polygon,
+            background_flag: true,
+            selected_sequence_id: Some(selected_sequence_id),
+            old_value: ObjectProperty::FillGreen(old_green),
+            new_value: ObjectProperty::FillGreen(new_green),
+            field_name: "green_fill".to_string(),
+            // signal: Some(
+            //     self.value_signals
+            //         .lock()
+            //         .unwrap()
+            //         .get(&format!("green_fill{}", object_id))
+            //         .cloned()
+            //         .expect("Couldn't get green_fill value signal"),
+            // ),
+        };
 
-        spawn_local({
-            async move {
-                save_sequences_data(sequences).await;
-            }
-        });
+        self.record_edit(edit.into());
 
-        self.record_state.saved_state = Some(saved_state.clone());
+        Ok(())
     }
 
-    pub fn add_saved_text_item(
+    pub fn update_fill_blue(
         &mut self,
+        object_id: Uuid,
+        new_blue_str: &str,
         selected_sequence_id: String,
-        savable_text_item: SavedTextRendererConfig,
-    ) {
-        let new_motion_path = self.save_default_keyframes(
-            savable_text_item.id.clone(),
-            ObjectType::TextItem,
-            savable_text_item.position.clone(),
-        );
-
-        let mut saved_state = self
-            .record_state
-            .saved_state
-            .as_mut()
-            .expect("Couldn't get Saved State");
+    ) -> Result<(), String> {
+        let new_blue = string_to_f32(new_blue_str).map_err(|_| "Couldn't convert string to f32")?;
+        let new_blue = color_to_wgpu(new_blue);
 
-        saved_state.sequences.iter_mut().for_each(|s| {
-            if s.id == selected_sequence_id {
-                s.active_text_items.push(savable_text_item.clone());
-                s.polygon_motion_paths.push(new_motion_path.clone()); // storing alongside polygon motion paths for now
-            }
-        });
+        let old_blue = {
+            let editor = self.editor.lock().unwrap();
+            editor.get_fill_blue(object_id)
+        };
 
-        let sequences = saved_state.sequences.clone();
+        let edit = ObjectEdit {
+            object_id,
+            object_type: ObjectType::Polygon,
+            background_flag: true,
+            selected_sequence_id: Some(selected_sequence_id),
+            old_value: ObjectProperty::FillBlue(old_blue),
+            new_value: ObjectProperty::FillBlue(new_blue),
+            field_name: "blue_fill".to_string(),
+            // signal: Some(
+            //     self.value_signals
+            //         .lock()
+            //         .unwrap()
+            //         .get(&format!("blue_fill{}", object_id))
+            //         .cloned()
+            //         .expect("Couldn't get blue_fill value signal"),
+            // ),
+        };
 
-        spawn_local({
-            async move {
-                save_sequences_data(sequences).await;
-            }
-        });
+        self.record_edit(edit.into());
 
-        self.record_state.saved_state = Some(saved_state.clone());
+        Ok(())
     }
 
-    pub fn add_saved_image_item(
+    pub fn update_background_red(
         &mut self,
+        object_id: Uuid,
+        new_red_str: &str,
         selected_sequence_id: String,
-        savable_image_item: SavedStImageConfig,
-    ) {
-        let new_motion_path = self.save_default_keyframes(
-            savable_image_item.id.clone(),
-            ObjectType::ImageItem,
-            savable_image_item.position.clone(),
-        );
+    ) -> Result<(), String> {
+        let new_red = string_to_f32(new_red_str).map_err(|_| "Couldn't convert string to f32")?;
+
+        let old_red = {
+            let editor = self.editor.lock().unwrap();
+            editor.get_background_red(object_id)
+        };
+
+        let edit = ObjectEdit {
+            object_id,
+            object_type: ObjectType::Polygon,
+            background_flag: true,
+            selected_sequence_id: Some(selected_sequence_id),
+            old_value: ObjectProperty::Red(old_red),
+            new_value: ObjectProperty::Red(new_red),
+            field_name: "red".to_string(),
+            // signal: Some(
+            //     self.value_signals
+            //         .lock()
+            //         .unwrap()
+            //         .get(&format!("red{}", object_id))
+            //         .cloned()
+            //         .expect("Couldn't get width value signal"),
+            // ),
+        };
 
-        let mut saved_state = self
-            .record_state
-            .saved_state
-            .as_mut()
-            .expect("Couldn't get Saved State");
+        self.record_edit(edit.into());
 
-        saved_state.sequences.iter_mut().for_each(|s| {
-            if s.id == selected_sequence_id {
-                s.active_image_items.push(savable_image_item.clone());
-                s.polygon_motion_paths.push(new_motion_path.clone()); // storing alongside polygon motion paths for now
-            }
-        });
+        Ok(())
+    }
 
-        let sequences = saved_state.sequences.clone();
+    pub fn update_background_green(
+        &mut self,
+        object_id: Uuid,
+        new_green_str: &str,
+        selected_sequence_id: String,
+    ) -> Result<(), String> {
+        let new_green =
+            string_to_f32(new_green_str).map_err(|_| "Couldn't convert string to f32")?;
 
-        spawn_local({
-            async move {
-                save_sequences_data(sequences).await;
-            }
-        });
+        let old_green = {
+            let editor = self.editor.lock().unwrap();
+            editor.get_background_green(object_id)
+        };
 
-        self.record_state.saved_state = Some(saved_state.clone());
+        let edit = ObjectEdit {
+            object_id,
+            object_type: ObjectType::Polygon,
+            background_flag: true,
+            selected_sequence_id: Some(selected_sequence_id),
+            old_value: ObjectProperty::Green(old_green),
+            new_value: ObjectProperty::Green(new_green),
+            field_name: "green".to_string(),
+            // signal: Some(
+            //     self.value_signals
+            //         .lock()
+            //         .unwrap()
+            //         .get(&format!("green{}", object_id))
+            //         .cloned()
+            //         .expect("Couldn't get green value signal"),
+            // ),
+        };
+
+        self.record_edit(edit.into());
+
+        Ok(())
     }
 
-    pub fn add_saved_video_item(
+    pub fn update_background_blue(
         &mut self,
+        object_id: Uuid,
+        new_blue_str: &str,
         selected_sequence_id: String,
-        savable_video_item: SavedStVideoConfig,
-        source_duration_ms: i64,
-    ) {
-        {
-            let new_motion_path = self.save_default_keyframes(
-                savable_video_item.id.clone(),
-                ObjectType::VideoItem,
-                savable_video_item.position.clone(),
-            );
+    ) -> Result<(), String> {
+        let new_blue = string_to_f32(new_blue_str).map_err(|_| "Couldn't convert string to f32")?;
 
-            let mut saved_state = self
-                .record_state
-                .saved_state
-                .as_mut()
-                .expect("Couldn't get Saved State");
+        let old_blue = {
+            let editor = self.editor.lock().unwrap();
+            editor.get_background_blue(object_id)
+        };
 
-            saved_state.sequences.iter_mut().for_each(|s| {
-                if s.id == selected_sequence_id {
-                    if s.duration_ms < source_duration_ms as i32 {
-                        s.duration_ms = source_duration_ms as i32;
-                    }
+        let edit = ObjectEdit {
+            object_id,
+            object_type: ObjectType::Polygon,
+            background_flag: true,
+            selected_sequence_id: Some(selected_sequence_id),
+            old_value: ObjectProperty::Blue(old_blue),
+            new_value: ObjectProperty::Blue(new_blue),
+            field_name: "blue".to_string(),
+            // signal: Some(
+            //     self.value_signals
+            //         .lock()
+            //         .unwrap()
+            //         .get(&format!("blue{}", object_id))
+            //         .cloned()
+            //         .expect("Couldn't get blue value signal"),
+            // ),
+        };
 
-                    s.active_video_items.push(savable_video_item.clone());
-                    s.polygon_motion_paths.push(new_motion_path.clone()); // storing alongside polygon motion paths for now
-                }
-            });
-        }
+        self.record_edit(edit.into());
 
-        // after updated with motion paths
-        let scaled_paths = self.scale_keyframes(
-            selected_sequence_id.clone(),
-            (source_duration_ms / 1000) as f32,
-        );
+        Ok(())
+    }
 
-        {
-            let mut saved_state = self
-                .record_state
-                .saved_state
-                .as_mut()
-                .expect("Couldn't get Saved State");
+    pub fn update_red(&mut self, object_id: Uuid, new_red_str: &str) -> Result<(), String> {
+        let new_red = string_to_f32(new_red_str).map_err(|_| "Couldn't convert string to f32")?;
 
-            saved_state.sequences.iter_mut().for_each(|s| {
-                if s.id == selected_sequence_id {
-                    s.polygon_motion_paths = scaled_paths.clone();
-                }
-            });
+        let old_red = {
+            let editor = self.editor.lock().unwrap();
+            editor.get_polygon_red(object_id)
+        };
 
-            let sequences = saved_state.sequences.clone();
+        let edit = ObjectEdit {
+            object_id,
+            object_type: ObjectType::Polygon,
+            background_flag: false,
+            selected_sequence_id: None,
+            old_value: ObjectProperty::Red(old_red),
+            new_value: ObjectProperty::Red(new_red),
+            field_name: "red".to_string(),
+            // signal: Some(
+            //     self.value_signals
+            //         .lock()
+            //         .unwrap()
+            //         .get(&format!("red{}", object_id))
+            //         .cloned()
+            //         .expect("Couldn't get width value signal"),
+            // ),
+        };
 
-            spawn_local({
-                async move {
-                    save_sequences_data(sequences).await;
-                }
-            });
+        self.record_edit(edit.into());
 
-            self.record_state.saved_state = Some(saved_state.clone());
-        }
+        Ok(())
     }
 
-    // Helper method to register a new signal
-    // pub fn register_signal(
-    //     &mut self,
-    //     name: String,
-    //     signal: RwSignal<String>,
-    //     signal_type: ObjectType,
-    // ) {
-    //     let mut signals = self.value_signals.lock().unwrap();
-
-    //     match signal_type {
-    //         ObjectType::Polygon => {
-    //             signals.insert(name + &self.selected_polygon_id.to_string(), signal);
-    //         }
-    //         ObjectType::TextItem => {
-    //             signals.insert(name + &self.selected_text_id.to_string(), signal);
-    //         }
-    //         ObjectType::ImageItem => {
-    //             signals.insert(name + &self.selected_image_id.to_string(), signal);
-    //         }
-    //         ObjectType::VideoItem => {
-    //             signals.insert(name + &self.selected_video_id.to_string(), signal);
-    //         }
-    //     }
-    // }
+    pub fn update_green(&mut self, object_id: Uuid, new_green_str: &str) -> Result<(), String> {
+        let new_green =
+            string_to_f32(new_green_str).map_err(|_| "Couldn't convert string to f32")?;
 
-    pub fn update_width(
-        &mut self,
-        new_width_str: &str,
-        object_type: ObjectType,
-    ) -> Result<(), String> {
-        let new_width =
-            string_to_f32(new_width_str).map_err(|_| "Couldn't convert string to f32")?;
+        let old_green = {
+            let editor = self.editor.lock().unwrap();
+            editor.get_polygon_green(object_id)
+        };
 
-        let object_id = match object_type {
-            ObjectType::Polygon => self.selected_polygon_id,
-            ObjectType::TextItem => self.selected_text_id,
-            ObjectType::ImageItem => self.selected_image_id,
-            ObjectType::VideoItem => self.selected_video_id,
+        let edit = ObjectEdit {
+            object_id,
+            object_type: ObjectType::Polygon,
+            background_flag: false,
+            selected_sequence_id: None,
+            old_value: ObjectProperty::Green(old_green),
+            new_value: ObjectProperty::Green(new_green),
+            field_name: "green".to_string(),
+            // signal: Some(
+            //     self.value_signals
+            //         .lock()
+            //         .unwrap()
+            //         .get(&format!("green{}", object_id))
+            //         .cloned()
+            //         .expect("Couldn't get green value signal"),
+            // ),
         };
 
-        let old_width = {
-            let editor = self.record_state.editor.lock().unwrap();
-            editor.get_object_width(object_id, object_type.clone())
+        self.record_edit(edit.into());
+
+        Ok(())
+    }
+
+    pub fn update_blue(&mut self, object_id: Uuid, new_blue_str: &str) -> Result<(), String> {
+        let new_blue = string_to_f32(new_blue_str).map_err(|_| "Couldn't convert string to f32")?;
+
+        let old_blue = {
+            let editor = self.editor.lock().unwrap();
+            editor.get_polygon_blue(object_id)
         };
 
         let edit = ObjectEdit {
             object_id,
-            object_type,
+            object_type: ObjectType::Polygon,
             background_flag: false,
             selected_sequence_id: None,
-            old_value: ObjectProperty::Width(old_width),
-            new_value: ObjectProperty::Width(new_width),
-            field_name: "width".to_string(),
+            old_value: ObjectProperty::Blue(old_blue),
+            new_value: ObjectProperty::Blue(new_blue),
+            field_name: "blue".to_string(),
             // signal: Some(
             //     self.value_signals
             //         .lock()
             //         .unwrap()
-            //         .get(&format!("width{}", object_id))
+            //         .get(&format!("blue{}", object_id))
             //         .cloned()
-            //         .expect("Couldn't get width value signal"),
+            //         .expect("Couldn't get blue value signal"),
             // ),
         };
 
-        let mut record = self.record.lock().unwrap();
-        record.edit(&mut self.record_state, edit);
+        self.record_edit(edit.into());
 
         Ok(())
     }
 
-    pub fn update_height(
+    pub fn update_border_radius(
         &mut self,
-        new_height_str: &str,
-        object_type: ObjectType,
+        object_id: Uuid,
+        new_border_radius_str: &str,
     ) -> Result<(), String> {
-        let new_height =
-            string_to_f32(new_height_str).map_err(|_| "Couldn't convert string to f32")?;
-
-        let object_id = match object_type {
-            ObjectType::Polygon => self.selected_polygon_id,
-            ObjectType::TextItem => self.selected_text_id,
-            ObjectType::ImageItem => self.selected_image_id,
-            ObjectType::VideoItem => self.selected_video_id,
-        };
+        let new_border_radius = string_to_f32(new_border_radius_str)
+            .map_err(|_| "Couldn't convert string to height")?;
 
-        let old_height = {
+        let old_border_radius = {
             let editor = self.editor.lock().unwrap();
-            editor.get_object_height(object_id, object_type.clone())
+            editor.get_polygon_border_radius(object_id)
         };
 
         let edit = ObjectEdit {
             object_id,
-            object_type,
+            object_type: ObjectType::Polygon,
             background_flag: false,
             selected_sequence_id: None,
-            old_value: ObjectProperty::Height(old_height),
-            new_value: ObjectProperty::Height(new_height),
-            field_name: "height".to_string(),
+            old_value: ObjectProperty::BorderRadius(old_border_radius),
+            new_value: ObjectProperty::BorderRadius(new_border_radius),
+            field_name: "border_radius".to_string(),
             // signal: Some(
             //     self.value_signals
             //         .lock()
             //         .unwrap()
-            //         .get(&format!("height{}", object_id))
+            //         .get(&format!("border_radius{}", object_id))
             //         .cloned()
-            //         .expect("Couldn't get height value signal"),
+            //         .expect("Couldn't get border_radius value signal"),
             // ),
         };
 
-        let mut record = self.record.lock().unwrap();
-        record.edit(&mut self.record_state, edit);
+        self.record_edit(edit.into());
 
         Ok(())
     }
 
-    pub fn update_fill_red(
+    pub fn update_stroke_thickness(
         &mut self,
-        new_red_str: &str,
-        selected_sequence_id: String,
+        object_id: Uuid,
+        new_stroke_thickness_str: &str,
     ) -> Result<(), String> {
-        let new_red = string_to_f32(new_red_str).map_err(|_| "Couldn't convert string to f32")?;
-        let new_red = color_to_wgpu(new_red);
+        let new_stroke_thickness = string_to_f32(new_stroke_thickness_str)
+            .map_err(|_| "Couldn't convert string to height")?;
 
-        let old_red = {
+        let old_stroke_thickness = {
             let editor = self.editor.lock().unwrap();
-            editor.get_fill_red(self.selected_text_id)
+            editor.get_polygon_stroke_thickness(object_id)
         };
 
         let edit = ObjectEdit {
-            object_id: self.selected_text_id,
+            object_id,
             object_type: ObjectType::Polygon,
-            background_flag: true,
-            selected_sequence_id: Some(selected_sequence_id),
-            old_value: ObjectProperty::FillRed(old_red),
-            new_value: ObjectProperty::FillRed(new_red),
-            field_name: "red_fill".to_string(),
+            background_flag: false,
+            selected_sequence_id: None,
+            old_value: ObjectProperty::StrokeThickness(old_stroke_thickness),
+            new_value: ObjectProperty::StrokeThickness(new_stroke_thickness),
+            field_name: "stroke_thickness".to_string(),
             // signal: Some(
             //     self.value_signals
             //         .lock()
             //         .unwrap()
-            //         .get(&format!("red_fill{}", self.selected_text_id))
+            //         .get(&format!("stroke_thickness{}", object_id))
             //         .cloned()
-            //         .expect("Couldn't get red_fill value signal"),
+            //         .expect("Couldn't get stroke_thickness value signal"),
             // ),
         };
 
-        let mut record = self.record.lock().unwrap();
-        record.edit(&mut self.record_state, edit);
+        self.record_edit(edit.into());
 
         Ok(())
     }
 
-    pub fn update_fill_green(
+    pub fn update_stroke_red(
         &mut self,
-        new_green_str: &str,
-        selected_sequence_id: String,
+        object_id: Uuid,
+        new_stroke_red_str: &str,
     ) -> Result<(), String> {
-        let new_green =
-            string_to_f32(new_green_str).map_err(|_| "Couldn't convert string to f32")?;
-        let new_green = color_to_wgpu(new_green);
+        let new_stroke_red =
+            string_to_f32(new_stroke_red_str).map_err(|_| "Couldn't convert string to height")?;
 
-        let old_green = {
+        let old_stroke_red = {
             let editor = self.editor.lock().unwrap();
-            editor.get_fill_green(self.selected_text_id)
+            editor.get_polygon_stroke_red(object_id)
         };
 
         let edit = ObjectEdit {
-            object_id: self.selected_text_id,
+            object_id,
             object_type: ObjectType::Polygon,
-            background_flag: true,
-            selected_sequence_id: Some(selected_sequence_id),
-            old_value: ObjectProperty::FillGreen(old_green),
-            new_value: ObjectProperty::FillGreen(new_green),
-            field_name: "green_fill".to_string(),
+            background_flag: false,
+            selected_sequence_id: None,
+            old_value: ObjectProperty::StrokeRed(old_stroke_red),
+            new_value: ObjectProperty::StrokeRed(new_stroke_red),
+            field_name: "stroke_red".to_string(),
             // signal: Some(
             //     self.value_signals
             //         .lock()
             //         .unwrap()
-            //         .get(&format!("green_fill{}", self.selected_text_id))
+            //         .get(&format!("stroke_red{}", object_id))
             //         .cloned()
-            //         .expect("Couldn't get green_fill value signal"),
+            //         .expect("Couldn't get stroke_red value signal"),
             // ),
         };
 
-        let mut record = self.record.lock().unwrap();
-        record.edit(&mut self.record_state, edit);
+        self.record_edit(edit.into());
 
         Ok(())
     }
 
-    pub fn update_fill_blue(
+    pub fn update_stroke_green(
         &mut self,
-        new_blue_str: &str,
-        selected_sequence_id: String,
+        object_id: Uuid,
+        new_stroke_green_str: &str,
     ) -> Result<(), String> {
-        let new_blue = string_to_f32(new_blue_str).map_err(|_| "Couldn't convert string to f32")?;
-        let new_blue = color_to_wgpu(new_blue);
+        let new_stroke_green =
+            string_to_f32(new_stroke_green_str).map_err(|_| "Couldn't convert string to height")?;
 
-        let old_blue = {
+        let old_stroke_green = {
             let editor = self.editor.lock().unwrap();
-            editor.get_fill_blue(self.selected_text_id)
+            editor.get_polygon_stroke_green(object_id)
         };
 
         let edit = ObjectEdit {
-            object_id: self.selected_text_id,
+            object_id,
             object_type: ObjectType::Polygon,
-            background_flag: true,
-            selected_sequence_id: Some(selected_sequence_id),
-            old_value: ObjectProperty::FillBlue(old_blue),
-            new_value: ObjectProperty::FillBlue(new_blue),
-            field_name: "blue_fill".to_string(),
+            background_flag: false,
+            selected_sequence_id: None,
+            old_value: ObjectProperty::StrokeGreen(old_stroke_green),
+            new_value: ObjectProperty::StrokeGreen(new_stroke_green),
+            field_name: "stroke_green".to_string(),
             // signal: Some(
             //     self.value_signals
             //         .lock()
             //         .unwrap()
-            //         .get(&format!("blue_fill{}", self.selected_text_id))
+            //         .get(&format!("stroke_green{}", object_id))
             //         .cloned()
-            //         .expect("Couldn't get blue_fill value signal"),
+            //         .expect("Couldn't get stroke_green value signal"),
             // ),
         };
 
-        let mut record = self.record.lock().unwrap();
-        record.edit(&mut self.record_state, edit);
+        self.record_edit(edit.into());
 
         Ok(())
     }
 
-    pub fn update_background_red(
+    pub fn update_stroke_blue(
         &mut self,
-        new_red_str: &str,
-        selected_sequence_id: String,
+        object_id: Uuid,
+        new_stroke_blue_str: &str,
     ) -> Result<(), String> {
-        let new_red = string_to_f32(new_red_str).map_err(|_| "Couldn't convert string to f32")?;
+        let new_stroke_blue =
+            string_to_f32(new_stroke_blue_str).map_err(|_| "Couldn't convert string to height")?;
 
-        let old_red = {
+        let old_stroke_blue = {
             let editor = self.editor.lock().unwrap();
-            editor.get_background_red(self.selected_polygon_id)
+            editor.get_polygon_stroke_blue(object_id)
         };
 
         let edit = ObjectEdit {
-            object_id: self.selected_polygon_id,
+            object_id,
             object_type: ObjectType::Polygon,
-            background_flag: true,
-            selected_sequence_id: Some(selected_sequence_id),
-            old_value: ObjectProperty::Red(old_red),
-            new_value: ObjectProperty::Red(new_red),
-            field_name: "red".to_string(),
+            background_flag: false,
+            selected_sequence_id: None,
+            old_value: ObjectProperty::StrokeBlue(old_stroke_blue),
+            new_value: ObjectProperty::StrokeBlue(new_stroke_blue),
+            field_name: "stroke_blue".to_string(),
             // signal: Some(
             //     self.value_signals
             //         .lock()
             //         .unwrap()
-            //         .get(&format!("red{}", self.selected_polygon_id))
+            //         .get(&format!("stroke_blue{}", object_id))
             //         .cloned()
-            //         .expect("Couldn't get width value signal"),
+            //         .expect("Couldn't get stroke_blue value signal"),
             // ),
         };
 
-        let mut record = self.record.lock().unwrap();
-        record.edit(&mut self.record_state, edit);
+        self.record_edit(edit.into());
 
         Ok(())
     }
 
-    pub fn update_background_green(
+    pub fn update_polygon_shape(
         &mut self,
-        new_green_str: &str,
-        selected_sequence_id: String,
+        polygon_id: Uuid,
+        new_shape: SavedShape,
     ) -> Result<(), String> {
-        let new_green =
-            string_to_f32(new_green_str).map_err(|_| "Couldn't convert string to f32")?;
+        let old_shape = self
+            .record_state
+            .saved_state
+            .as_ref()
+            .and_then(|saved_state| {
+                saved_state
+                    .sequences
+                    .iter()
+                    .flat_map(|s| s.active_polygons.iter())
+                    .find(|p| p.id == polygon_id.to_string())
+            })
+            .and_then(|p| p.shape.clone())
+            .ok_or("Polygon isn't a library shape")?;
+
+        if old_shape == new_shape {
+            return Ok(());
+        }
 
-        let old_green = {
-            let editor = self.editor.lock().unwrap();
-            editor.get_background_green(self.selected_polygon_id)
+        let edit = ShapeEdit {
+            polygon_id,
+            old_shape,
+            new_shape,
         };
 
-        let edit = ObjectEdit {
-            object_id: self.selected_polygon_id,
-            object_type: ObjectType::Polygon,
-            background_flag: true,
-            selected_sequence_id: Some(selected_sequence_id),
-            old_value: ObjectProperty::Green(old_green),
-            new_value: ObjectProperty::Green(new_green),
-            field_name: "green".to_string(),
-            // signal: Some(
-            //     self.value_signals
-            //         .lock()
-            //         .unwrap()
-            //         .get(&format!("green{}", self.selected_polygon_id))
-            //         .cloned()
-            //         .expect("Couldn't get green value signal"),
-            // ),
+        self.record_edit(edit.into());
+
+        Ok(())
+    }
+
+    /// Moves by `translation`, turns by `rotation` degrees and resizes an object in one undo step
+    pub fn transform_object(
+        &mut self,
+        sequence_id: String,
+        object_id: Uuid,
+        object_type: ObjectType,
+        translation: (i32, i32),
+        rotation: i32,
+        new_dimensions: (f32, f32),
+    ) -> Result<(), String> {
+        if new_dimensions.0 < 1.0 || new_dimensions.1 < 1.0 {
+            return Err("Objects must be at least 1px wide and tall".to_string());
+        }
+
+        let old_dimensions = {
+            let editor = self.record_state.editor.lock().unwrap();
+
+            (
+                editor.get_object_width(object_id, object_type.clone()),
+                editor.get_object_height(object_id, object_type.clone()),
+            )
         };
 
-        let mut record = self.record.lock().unwrap();
-        record.edit(&mut self.record_state, edit);
+        let new_dimensions = (new_dimensions.0.round(), new_dimensions.1.round());
+
+        if translation == (0, 0) && rotation == 0 && old_dimensions == new_dimensions {
+            return Ok(());
+        }
+
+        let edit = TransformEdit {
+            object_id,
+            object_type,
+            sequence_id,
+            translation,
+            rotation,
+            old_dimensions,
+            new_dimensions,
+        };
+
+        self.record_edit(edit.into());
 
         Ok(())
     }
 
-    pub fn update_background_blue(
+    /// Moves and resizes several objects as one undo step.
+    /// Each entry is (object id, type, translation, new dimensions).
+    pub fn transform_objects(
         &mut self,
-        new_blue_str: &str,
-        selected_sequence_id: String,
+        sequence_id: String,
+        transforms: Vec<(Uuid, ObjectType, (i32, i32), (f32, f32))>,
     ) -> Result<(), String> {
-        let new_blue = string_to_f32(new_blue_str).map_err(|_| "Couldn't convert string to f32")?;
+        self.transaction(|editor_state| {
+            for (object_id, object_type, translation, new_dimensions) in transforms {
+                editor_state.transform_object(
+                    sequence_id.clone(),
+                    object_id,
+                    object_type,
+                    translation,
+                    0,
+                    new_dimensions,
+                )?;
+            }
 
-        let old_blue = {
+            Ok(())
+        })
+    }
+
+    /// Deletes objects from a sequence as one undo step, and drops them from the selection
+    pub fn delete_objects(
+        &mut self,
+        sequence_id: String,
+        objects: Vec<(Uuid, ObjectType)>,
+    ) -> Result<(), String> {
+        if objects.is_empty() {
+            return Err("Nothing is selected".to_string());
+        }
+
+        self.transaction(|editor_state| {
+            for (object_id, object_type) in objects.iter() {
+                editor_state.record_edit(
+                    DeleteEdit {
+                        object_id: *object_id,
+                        object_type: object_type.clone(),
+                        sequence_id: sequence_id.clone(),
+                        removed: None,
+                    }
+                    .into(),
+                );
+            }
+
+            Ok(())
+        })?;
+
+        let remaining = self
+            .selected_objects
+            .iter()
+            .filter(|(id, _)| !objects.iter().any(|(deleted, _)| deleted == id))
+            .cloned()
+            .collect();
+        self.set_selection(remaining);
+
+        Ok(())
+    }
+
+    /// Adds objects to a sequence as one undo step and selects them. Each one has to be
+    /// in the editor already, hidden until the edit shows it.
+    pub fn insert_objects(
+        &mut self,
+        sequence_id: String,
+        objects: Vec<(SavedObject, Option<AnimationData>)>,
+    ) -> Result<(), String> {
+        if objects.is_empty() {
+            return Err("Nothing to add".to_string());
+        }
+
+        let inserted = self.transaction(|editor_state| {
+            let mut inserted = Vec::new();
+
+            for (object, motion_path) in objects {
+                let object_id = Uuid::from_str(object.id())
+                    .map_err(|_| format!("\"{}\" has an invalid id", object.name()))?;
+                let object_type = object.object_type();
+
+                editor_state.record_edit(
+                    InsertEdit {
+                        object_id,
+                        object_type: object_type.clone(),
+                        sequence_id: sequence_id.clone(),
+                        object,
+                        motion_path,
+                    }
+                    .into(),
+                );
+
+                inserted.push((object_id, object_type));
+            }
+
+            Ok(inserted)
+        })?;
+
+        self.set_selection(inserted);
+
+        Ok(())
+    }
+
+    fn saved_group(&self, sequence_id: &str, group_id: &str) -> Result<SavedGroupConfig, String> {
+        self.record_state
+            .saved_state
+            .as_ref()
+            .expect("Couldn't get saved state")
+            .sequences
+            .iter()
+            .find(|s| s.id == sequence_id)
+            .and_then(|s| s.groups.iter().find(|g| g.id == group_id))
+            .cloned()
+            .ok_or_else(|| "Couldn't find group".to_string())
+    }
+
+    /// Puts objects into a new group as one undo step, returns the group's id.
+    /// Objects can only be in one group.
+    pub fn group_objects(
+        &mut self,
+        sequence_id: String,
+        objects: &[(Uuid, ObjectType)],
+        name: String,
+    ) -> Result<String, String> {
+        if objects.len() < 2 {
+            return Err("Select at least two objects to group".to_string());
+        }
+
+        let saved_state = self
+            .record_state
+            .saved_state
+            .as_ref()
+            .expect("Couldn't get saved state");
+        let sequence = saved_state
+            .sequences
+            .iter()
+            .find(|s| s.id == sequence_id)
+            .ok_or_else(|| "Couldn't find sequence".to_string())?;
+
+        let object_ids = objects
+            .iter()
+            .map(|(object_id, _)| object_id.to_string())
+            .collect::<Vec<_>>();
+
+        for object_id in &object_ids {
+            if let Some(group) = group_of(sequence, object_id) {
+                let name = object_name(saved_state, object_id)
+                    .unwrap_or_else(|| "An object".to_string());
+
+                return Err(format!("{} is already in {}", name, group.name));
+            }
+        }
+
+        let group = new_group(name, object_ids);
+        let group_id = group.id.clone();
+
+        self.record_edit(
+            ObjectGroupEdit {
+                sequence_id,
+                group_id: group_id.clone(),
+                old_group: None,
+                new_group: Some(group),
+            }
+            .into(),
+        );
+
+        Ok(group_id)
+    }
+
+    /// Removes a group, its objects stay where they are
+    pub fn ungroup(&mut self, sequence_id: String, group_id: String) -> Result<(), String> {
+        let group = self.saved_group(&sequence_id, &group_id)?;
+
+        self.record_edit(
+            ObjectGroupEdit {
+                sequence_id,
+                group_id,
+                old_group: Some(group),
+                new_group: None,
+            }
+            .into(),
+        );
+
+        Ok(())
+    }
+
+    /// Replaces a group's name or animation
+    pub fn update_group(
+        &mut self,
+        sequence_id: String,
+        group: SavedGroupConfig,
+    ) -> Result<(), String> {
+        let old_group = self.saved_group(&sequence_id, &group.id)?;
+
+        if old_group == group {
+            return Ok(());
+        }
+
+        self.record_edit(
+            ObjectGroupEdit {
+                sequence_id,
+                group_id: group.id.clone(),
+                old_group: Some(old_group),
+                new_group: Some(group),
+            }
+            .into(),
+        );
+
+        Ok(())
+    }
+
+    /// Restyles a text item, checking the font is loaded first
+    pub fn update_text_style(
+        &mut self,
+        sequence_id: String,
+        text_id: Uuid,
+        new_style: TextStyle,
+    ) -> Result<(), String> {
+        let old_style = self
+            .record_state
+            .saved_state
+            .as_ref()
+            .and_then(|saved_state| {
+                saved_state
+                    .sequences
+                    .iter()
+                    .find(|s| s.id == sequence_id)?
+                    .active_text_items
+                    .iter()
+                    .find(|t| t.id == text_id.to_string())
+            })
+            .map(TextStyle::from_saved)
+            .ok_or("Couldn't find text item")?;
+
+        if old_style == new_style {
+            return Ok(());
+        }
+
+        if new_style.text.trim().is_empty() {
+            return Err("Text can't be empty".to_string());
+        }
+
+        if new_style.font_size < 1 {
+            return Err("Font size must be at least 1".to_string());
+        }
+
+        if new_style.line_height < 1 {
+            return Err("Line height must be at least 1%".to_string());
+        }
+
+        let font_loaded = {
             let editor = self.editor.lock().unwrap();
-            editor.get_background_blue(self.selected_polygon_id)
+            editor
+                .font_manager
+                .get_font_by_name(&new_style.font_family)
+                .is_some()
         };
 
-        let edit = ObjectEdit {
-            object_id: self.selected_polygon_id,
-            object_type: ObjectType::Polygon,
-            background_flag: true,
-            selected_sequence_id: Some(selected_sequence_id),
-            old_value: ObjectProperty::Blue(old_blue),
-            new_value: ObjectProperty::Blue(new_blue),
-            field_name: "blue".to_string(),
-            // signal: Some(
-            //     self.value_signals
-            //         .lock()
-            //         .unwrap()
-            //         .get(&format!("blue{}", self.selected_polygon_id))
-            //         .cloned()
-            //         .expect("Couldn't get blue value signal"),
-            // ),
+        if !font_loaded {
+            return Err(format!("Unknown font \"{}\"", new_style.font_family));
+        }
+
+        self.record_edit(
+            TextEdit {
+                text_id,
+                sequence_id,
+                old_style,
+                new_style,
+            }
+            .into(),
+        );
+
+        Ok(())
+    }
+
+    /// Applies an edit and puts it on the record, or holds it for the open transaction
+    fn record_edit(&mut self, mut edit: EditorEdit) {
+        match self.transaction.as_mut() {
+            Some(transaction) => {
+                edit.edit(&mut self.record_state);
+                transaction.edits.push(edit);
+            }
+            None => {
+                let mut record = self.record.lock().unwrap();
+                record.edit(&mut self.record_state, edit);
+            }
+        }
+    }
+
+    /// Starts grouping edits. They still apply right away, but go on the record as one
+    /// step and are saved once when the outermost transaction is committed.
+    pub fn begin_transaction(&mut self) {
+        match self.transaction.as_mut() {
+            Some(transaction) => transaction.starts.push(transaction.edits.len()),
+            None => {
+                self.transaction = Some(Transaction {
+                    edits: Vec::new(),
+                    starts: vec![0],
+                    saves_deferred: self.record_state.saves.deferred,
+                });
+                self.record_state.saves.deferred = true;
+            }
+        }
+    }
+
+    pub fn commit_transaction(&mut self) {
+        let Some(transaction) = self.transaction.as_mut() else {
+            log::warn!("No transaction to commit");
+            return;
+        };
+
+        transaction.starts.pop();
+        if !transaction.starts.is_empty() {
+            // the outer transaction takes over the edits
+            return;
+        }
+
+        let Some(transaction) = self.transaction.take() else {
+            return;
+        };
+        self.end_deferring(transaction.saves_deferred);
+
+        if transaction.edits.is_empty() {
+            return;
+        }
+
+        let group = GroupEdit {
+            edits: transaction.edits,
+            applied: true,
         };
 
         let mut record = self.record.lock().unwrap();
-        record.edit(&mut self.record_state, edit);
-
-        Ok(())
+        record.edit(&mut self.record_state, group.into());
     }
 
-    pub fn update_red(&mut self, new_red_str: &str) -> Result<(), String> {
-        let new_red = string_to_f32(new_red_str).map_err(|_| "Couldn't convert string to f32")?;
-
-        let old_red = {
-            let editor = self.editor.lock().unwrap();
-            editor.get_polygon_red(self.selected_polygon_id)
+    /// Takes back the edits of the innermost open transaction. Outer transactions
+    /// keep theirs and stay open.
+    pub fn rollback_transaction(&mut self) {
+        let Some(transaction) = self.transaction.as_mut() else {
+            log::warn!("No transaction to roll back");
+            return;
         };
 
-        let edit = ObjectEdit {
-            object_id: self.selected_polygon_id,
-            object_type: ObjectType::Polygon,
-            background_flag: false,
-            selected_sequence_id: None,
-            old_value: ObjectProperty::Red(old_red),
-            new_value: ObjectProperty::Red(new_red),
-            field_name: "red".to_string(),
-            // signal: Some(
-            //     self.value_signals
-            //         .lock()
-            //         .unwrap()
-            //         .get(&format!("red{}", self.selected_polygon_id))
-            //         .cloned()
-            //         .expect("Couldn't get width value signal"),
-            // ),
-        };
+        let start = transaction.starts.pop().unwrap_or(0);
+        let edits = transaction.edits.split_off(start);
+        let outermost = transaction.starts.is_empty();
 
-        let mut record = self.record.lock().unwrap();
-        record.edit(&mut self.record_state, edit);
+        GroupEdit {
+            edits,
+            applied: true,
+        }
+        .undo(&mut self.record_state);
 
-        Ok(())
+        if outermost {
+            if let Some(transaction) = self.transaction.take() {
+                self.end_deferring(transaction.saves_deferred);
+            }
+        }
     }
 
-    pub fn update_green(&mut self, new_green_str: &str) -> Result<(), String> {
-        let new_green =
-            string_to_f32(new_green_str).map_err(|_| "Couldn't convert string to f32")?;
+    fn end_deferring(&mut self, saves_deferred: bool) {
+        self.record_state.saves.deferred = saves_deferred;
+        if !saves_deferred {
+            self.record_state.saves.flush();
+        }
+    }
 
-        let old_green = {
-            let editor = self.editor.lock().unwrap();
-            editor.get_polygon_green(self.selected_polygon_id)
-        };
+    /// Runs `f` in a transaction, committed if it succeeds and rolled back if it fails
+    pub fn transaction<T>(
+        &mut self,
+        f: impl FnOnce(&mut EditorState) -> Result<T, String>,
+    ) -> Result<T, String> {
+        self.begin_transaction();
+
+        match f(self) {
+            Ok(value) => {
+                self.commit_transaction();
+                Ok(value)
+            }
+            Err(e) => {
+                self.rollback_transaction();
+                Err(e)
+            }
+        }
+    }
 
-        let edit = ObjectEdit {
-            object_id: self.selected_polygon_id,
-            object_type: ObjectType::Polygon,
-            background_flag: false,
-            selected_sequence_id: None,
-            old_value: ObjectProperty::Green(old_green),
-            new_value: ObjectProperty::Green(new_green),
-            field_name: "green".to_string(),
-            // signal: Some(
-            //     self.value_signals
-            //         .lock()
-            //         .unwrap()
-            //         .get(&format!("green{}", self.selected_polygon_id))
-            //         .cloned()
-            //         .expect("Couldn't get green value signal"),
-            // ),
+    /// Labels of the recorded edits, oldest first, and how many of them are applied
+    pub fn history(&self) -> (Vec<String>, usize) {
+        let Some(saved_state) = self.record_state.saved_state.as_ref() else {
+            return (Vec::new(), 0);
         };
 
-        let mut record = self.record.lock().unwrap();
-        record.edit(&mut self.record_state, edit);
+        let record = self.record.lock().unwrap();
+        let labels = record
+            .entries()
+            .map(|entry| entry.get().label(saved_state))
+            .collect();
 
-        Ok(())
+        (labels, record.head())
     }
 
-    pub fn update_blue(&mut self, new_blue_str: &str) -> Result<(), String> {
-        let new_blue = string_to_f32(new_blue_str).map_err(|_| "Couldn't convert string to f32")?;
+    /// Undoes or redoes until `index` edits are applied
+    pub fn go_to_history(&mut self, index: usize) {
+        if self.transaction.is_some() {
+            log::warn!("Can't move through history while a transaction is open");
+            return;
+        }
 
-        let old_blue = {
-            let editor = self.editor.lock().unwrap();
-            editor.get_polygon_blue(self.selected_polygon_id)
-        };
+        let mut record = self.record.lock().unwrap();
+        record.go_to(&mut self.record_state, index);
+    }
 
-        let edit = ObjectEdit {
-            object_id: self.selected_polygon_id,
-            object_type: ObjectType::Polygon,
-            background_flag: false,
-            selected_sequence_id: None,
-            old_value: ObjectProperty::Blue(old_blue),
-            new_value: ObjectProperty::Blue(new_blue),
-            field_name: "blue".to_string(),
-            // signal: Some(
-            //     self.value_signals
-            //         .lock()
-            //         .unwrap()
-            //         .get(&format!("blue{}", self.selected_polygon_id))
-            //         .cloned()
-            //         .expect("Couldn't get blue value signal"),
-            // ),
-        };
+    /// The applied edits in storable form. Older edits are dropped when one of
+    /// the edits after them can't be stored, or past `limit`.
+    pub fn stored_history(&self, limit: usize) -> Vec<StoredEdit> {
+        let record = self.record.lock().unwrap();
+
+        let mut stored = record
+            .entries()
+            .take(record.head())
+            .collect::<Vec<_>>()
+            .into_iter()
+            .rev()
+            .map_while(|entry| entry.get().to_stored())
+            .take(limit)
+            .collect::<Vec<_>>();
+        stored.reverse();
+
+        stored
+    }
 
+    /// Puts stored edits back on an empty record. They took effect before the
+    /// reload, so they're recorded without being applied again. Objects deleted
+    /// before the reload aren't in the editor, undoing the delete loads them.
+    pub fn restore_history(&mut self, stored: Vec<StoredEdit>) {
         let mut record = self.record.lock().unwrap();
-        record.edit(&mut self.record_state, edit);
 
-        Ok(())
+        if !record.is_empty() {
+            return;
+        }
+
+        // edits are relative, so nothing before one that can't be restored is usable
+        let edits = stored
+            .into_iter()
+            .rev()
+            .map_while(|edit| edit.into_edit())
+            .collect::<Vec<_>>();
+
+        for edit in edits.into_iter().rev() {
+            let applied = GroupEdit {
+                edits: vec![edit],
+                applied: true,
+            };
+
+            record.edit(&mut self.record_state, applied.into());
+        }
     }
 
-    pub fn update_border_radius(&mut self, new_border_radius_str: &str) -> Result<(), String> {
-        let new_border_radius = string_to_f32(new_border_radius_str)
-            .map_err(|_| "Couldn't convert string to height")?;
+    pub fn undo(&mut self) {
+        if self.transaction.is_some() {
+            log::warn!("Can't undo while a transaction is open");
+            return;
+        }
 
-        let old_border_radius = {
-            let editor = self.editor.lock().unwrap();
-            editor.get_polygon_border_radius(self.selected_polygon_id)
-        };
+        let mut record = self.record.lock().unwrap();
 
-        let edit = ObjectEdit {
-            object_id: self.selected_polygon_id,
-            object_type: ObjectType::Polygon,
-            background_flag: false,
-            selected_sequence_id: None,
-            old_value: ObjectProperty::BorderRadius(old_border_radius),
-            new_value: ObjectProperty::BorderRadius(new_border_radius),
-            field_name: "border_radius".to_string(),
-            // signal: Some(
-            //     self.value_signals
-            //         .lock()
-            //         .unwrap()
-            //         .get(&format!("border_radius{}", self.selected_polygon_id))
-            //         .cloned()
-            //         .expect("Couldn't get border_radius value signal"),
-            // ),
-        };
+        if record.undo(&mut self.record_state).is_some() {
+            println!("Undo successful");
+            // println!("record cannB... {:?}", self.record.head());
+        }
+    }
+
+    pub fn redo(&mut self) {
+        if self.transaction.is_some() {
+            log::warn!("Can't redo while a transaction is open");
+            return;
+        }
 
         let mut record = self.record.lock().unwrap();
-        record.edit(&mut self.record_state, edit);
 
-        Ok(())
+        if record.redo(&mut self.record_state).is_some() {
+            println!("Redo successful");
+        }
     }
+}
 
-    pub fn update_stroke_thickness(
-        &mut self,
-        new_stroke_thickness_str: &str,
-    ) -> Result<(), String> {
-        let new_stroke_thickness = string_to_f32(new_stroke_thickness_str)
-            .map_err(|_| "Couldn't convert string to height")?;
+#[cfg(test)]
+mod tests {
+    use stunts_engine::editor::{init_editor_with_model, Viewport};
+    use stunts_engine::polygon::SavedStroke;
+    use stunts_engine::timelines::SavedTimelineStateConfig;
+    use wasm_bindgen_test::*;
+
+    use super::*;
+    use crate::helpers::history::MAX_STORED_EDITS;
+
+    fn saved_polygon(id: &Uuid) -> SavedPolygonConfig {
+        SavedPolygonConfig {
+            id: id.to_string(),
+            name: "Square".to_string(),
+            points: Vec::new(),
+            rotation: 0,
+            shape: None,
+            dimensions: (100, 100),
+            fill: [255, 255, 255, 255],
+            border_radius: 0,
+            position: SavedPoint { x: 200, y: 100 },
+            stroke: SavedStroke {
+                thickness: 2,
+                fill: [0, 0, 0, 255],
+            },
+            layer: -2,
+        }
+    }
 
-        let old_stroke_thickness = {
-            let editor = self.editor.lock().unwrap();
-            editor.get_polygon_stroke_thickness(self.selected_polygon_id)
-        };
+    fn saved_state(sequence_id: &str, polygon_ids: &[Uuid]) -> SavedState {
+        SavedState {
+            sequences: vec![Sequence {
+                id: sequence_id.to_string(),
+                name: "Sequence".to_string(),
+                background_fill: None,
+                duration_ms: 20000,
+                active_polygons: polygon_ids.iter().map(saved_polygon).collect(),
+                polygon_motion_paths: polygon_ids
+                    .iter()
+                    .map(|id| AnimationData {
+                        id: Uuid::new_v4().to_string(),
+                        object_type: ObjectType::Polygon,
+                        polygon_id: id.to_string(),
+                        duration: Duration::from_secs(20),
+                        start_time_ms: 0,
+                        position: [0, 0],
+                        properties: Vec::new(),
+                    })
+                    .collect(),
+                active_text_items: Vec::new(),
+                active_image_items: Vec::new(),
+                active_video_items: Vec::new(),
+                groups: Vec::new(),
+            }],
+            timeline_state: SavedTimelineStateConfig {
+                timeline_sequences: Vec::new(),
+            },
+        }
+    }
 
-        let edit = ObjectEdit {
-            object_id: self.selected_polygon_id,
-            object_type: ObjectType::Polygon,
-            background_flag: false,
-            selected_sequence_id: None,
-            old_value: ObjectProperty::StrokeThickness(old_stroke_thickness),
-            new_value: ObjectProperty::StrokeThickness(new_stroke_thickness),
-            field_name: "stroke_thickness".to_string(),
-            // signal: Some(
-            //     self.value_signals
-            //         .lock()
-            //         .unwrap()
-            //         .get(&format!("stroke_thickness{}", self.selected_polygon_id))
-            //         .cloned()
-            //         .expect("Couldn't get stroke_thickness value signal"),
-            // ),
-        };
+    // no gpu, so nothing is ever loaded into the editor
+    fn editor_state(saved_state: SavedState) -> EditorState {
+        let viewport = Arc::new(Mutex::new(Viewport::new(900.0, 450.0)));
+        let editor = Arc::new(Mutex::new(init_editor_with_model(viewport)));
 
-        let mut record = self.record.lock().unwrap();
-        record.edit(&mut self.record_state, edit);
+        let mut editor_state = EditorState::new(
+            editor,
+            Arc::new(Mutex::new(Record::new())),
+            RedrawFlag::default(),
+        );
+        editor_state.record_state.saved_state = Some(saved_state);
+        // there's no project to save to
+        editor_state.record_state.saves.deferred = true;
 
-        Ok(())
+        editor_state
     }
 
-    pub fn update_stroke_red(&mut self, new_stroke_red_str: &str) -> Result<(), String> {
-        let new_stroke_red =
-            string_to_f32(new_stroke_red_str).map_err(|_| "Couldn't convert string to height")?;
+    fn sequence_ids(editor_state: &EditorState) -> (Vec<String>, Vec<String>) {
+        let sequence = &editor_state
+            .record_state
+            .saved_state
+            .as_ref()
+            .expect("Couldn't get saved state")
+            .sequences[0];
+
+        (
+            sequence
+                .active_polygons
+                .iter()
+                .map(|p| p.id.clone())
+                .collect(),
+            sequence
+                .polygon_motion_paths
+                .iter()
+                .map(|m| m.polygon_id.clone())
+                .collect(),
+        )
+    }
 
-        let old_stroke_red = {
-            let editor = self.editor.lock().unwrap();
-            editor.get_polygon_stroke_red(self.selected_polygon_id)
-        };
+    #[wasm_bindgen_test]
+    fn undoing_a_delete_after_a_reload_puts_the_object_back() {
+        let sequence_id = Uuid::new_v4().to_string();
+        let polygon_ids = [Uuid::new_v4(), Uuid::new_v4()];
+        let ids = polygon_ids.map(|id| id.to_string()).to_vec();
+
+        let mut before = editor_state(saved_state(&sequence_id, &polygon_ids));
+        before
+            .delete_objects(
+                sequence_id.clone(),
+                vec![(polygon_ids[0], ObjectType::Polygon)],
+            )
+            .expect("Couldn't delete polygon");
+
+        // what a reload reads back, the saved project and the session history
+        let project = serde_json::to_string(&before.record_state.saved_state)
+            .expect("Couldn't serialize project");
+        let history = serde_json::to_string(&before.stored_history(MAX_STORED_EDITS))
+            .expect("Couldn't serialize history");
+
+        let mut after = editor_state(
+            serde_json::from_str::<Option<SavedState>>(&project)
+                .expect("Couldn't read project")
+                .expect("Couldn't get saved state"),
+        );
+        assert_eq!(sequence_ids(&after), (ids[1..].to_vec(), ids[1..].to_vec()));
 
-        let edit = ObjectEdit {
-            object_id: self.selected_polygon_id,
-            object_type: ObjectType::Polygon,
-            background_flag: false,
-            selected_sequence_id: None,
-            old_value: ObjectProperty::StrokeRed(old_stroke_red),
-            new_value: ObjectProperty::StrokeRed(new_stroke_red),
-            field_name: "stroke_red".to_string(),
-            // signal: Some(
-            //     self.value_signals
-            //         .lock()
-            //         .unwrap()
-            //         .get(&format!("stroke_red{}", self.selected_polygon_id))
-            //         .cloned()
-            //         .expect("Couldn't get stroke_red value signal"),
-            // ),
-        };
+        after.restore_history(serde_json::from_str(&history).expect("Couldn't read history"));
+        after.undo();
 
-        let mut record = self.record.lock().unwrap();
-        record.edit(&mut self.record_state, edit);
+        assert_eq!(sequence_ids(&after), (ids.clone(), ids.clone()));
 
-        Ok(())
+        after.redo();
+
+        assert_eq!(sequence_ids(&after), (ids[1..].to_vec(), ids[1..].to_vec()));
     }
 
-    pub fn update_stroke_green(&mut self, new_stroke_green_str: &str) -> Result<(), String> {
-        let new_stroke_green =
-            string_to_f32(new_stroke_green_str).map_err(|_| "Couldn't convert string to height")?;
+    #[wasm_bindgen_test]
+    fn transaction_edits_undo_as_one_step() {
+        let sequence_id = Uuid::new_v4().to_string();
+        let polygon_ids = [Uuid::new_v4(), Uuid::new_v4()];
+        let ids = polygon_ids.map(|id| id.to_string()).to_vec();
+
+        let mut editor_state = editor_state(saved_state(&sequence_id, &polygon_ids));
+        editor_state
+            .transaction(|editor_state| {
+                for id in polygon_ids {
+                    editor_state
+                        .delete_objects(sequence_id.clone(), vec![(id, ObjectType::Polygon)])?;
+                }
 
-        let old_stroke_green = {
-            let editor = self.editor.lock().unwrap();
-            editor.get_polygon_stroke_green(self.selected_polygon_id)
-        };
+                Ok(())
+            })
+            .expect("Couldn't delete polygons");
 
-        let edit = ObjectEdit {
-            object_id: self.selected_polygon_id,
-            object_type: ObjectType::Polygon,
-            background_flag: false,
-            selected_sequence_id: None,
-            old_value: ObjectProperty::StrokeGreen(old_stroke_green),
-            new_value: ObjectProperty::StrokeGreen(new_stroke_green),
-            field_name: "stroke_green".to_string(),
-            // signal: Some(
-            //     self.value_signals
-            //         .lock()
-            //         .unwrap()
-            //         .get(&format!("stroke_green{}", self.selected_polygon_id))
-            //         .cloned()
-            //         .expect("Couldn't get stroke_green value signal"),
-            // ),
-        };
+        assert_eq!(sequence_ids(&editor_state), (Vec::new(), Vec::new()));
+        assert_eq!(editor_state.history().0.len(), 1);
+        // saves go back to how they were before the transaction
+        assert!(editor_state.record_state.saves.deferred);
 
-        let mut record = self.record.lock().unwrap();
-        record.edit(&mut self.record_state, edit);
+        editor_state.undo();
 
-        Ok(())
+        assert_eq!(sequence_ids(&editor_state), (ids.clone(), ids.clone()));
     }
 
-    pub fn update_stroke_blue(&mut self, new_stroke_blue_str: &str) -> Result<(), String> {
-        let new_stroke_blue =
-            string_to_f32(new_stroke_blue_str).map_err(|_| "Couldn't convert string to height")?;
+    #[wasm_bindgen_test]
+    fn failed_transactions_take_their_edits_back() {
+        let sequence_id = Uuid::new_v4().to_string();
+        let polygon_ids = [Uuid::new_v4(), Uuid::new_v4()];
+        let ids = polygon_ids.map(|id| id.to_string()).to_vec();
 
-        let old_stroke_blue = {
-            let editor = self.editor.lock().unwrap();
-            editor.get_polygon_stroke_blue(self.selected_polygon_id)
-        };
+        let mut editor_state = editor_state(saved_state(&sequence_id, &polygon_ids));
+        let result: Result<(), String> = editor_state.transaction(|editor_state| {
+            editor_state.delete_objects(
+                sequence_id.clone(),
+                vec![(polygon_ids[0], ObjectType::Polygon)],
+            )?;
 
-        let edit = ObjectEdit {
-            object_id: self.selected_polygon_id,
+            Err("Failed".to_string())
+        });
+
+        assert!(result.is_err());
+        assert_eq!(sequence_ids(&editor_state), (ids.clone(), ids.clone()));
+        assert!(editor_state.history().0.is_empty());
+        assert!(editor_state.transaction.is_none());
+    }
+
+    #[wasm_bindgen_test]
+    fn failed_nested_transactions_leave_the_outer_one_alone() {
+        let sequence_id = Uuid::new_v4().to_string();
+        let polygon_ids = [Uuid::new_v4(), Uuid::new_v4()];
+        let ids = polygon_ids.map(|id| id.to_string()).to_vec();
+
+        let mut editor_state = editor_state(saved_state(&sequence_id, &polygon_ids));
+        editor_state
+            .transaction(|editor_state| {
+                editor_state.delete_objects(
+                    sequence_id.clone(),
+                    vec![(polygon_ids[0], ObjectType::Polygon)],
+                )?;
+
+                let nested: Result<(), String> = editor_state.transaction(|editor_state| {
+                    editor_state.delete_objects(
+                        sequence_id.clone(),
+                        vec![(polygon_ids[1], ObjectType::Polygon)],
+                    )?;
+
+                    Err("Failed".to_string())
+                });
+                assert!(nested.is_err());
+
+                // the outer transaction is still open
+                assert!(editor_state.transaction.is_some());
+                Ok(())
+            })
+            .expect("Couldn't delete polygon");
+
+        assert_eq!(
+            sequence_ids(&editor_state),
+            (ids[1..].to_vec(), ids[1..].to_vec())
+        );
+        assert_eq!(editor_state.history().0.len(), 1);
+
+        editor_state.undo();
+
+        assert_eq!(sequence_ids(&editor_state), (ids.clone(), ids.clone()));
+    }
+
+    fn object_edit(
+        polygon_id: Uuid,
+        field: &str,
+        old: ObjectProperty,
+        new: ObjectProperty,
+    ) -> ObjectEdit {
+        ObjectEdit {
+            object_id: polygon_id,
             object_type: ObjectType::Polygon,
             background_flag: false,
             selected_sequence_id: None,
-            old_value: ObjectProperty::StrokeBlue(old_stroke_blue),
-            new_value: ObjectProperty::StrokeBlue(new_stroke_blue),
-            field_name: "stroke_blue".to_string(),
-            // signal: Some(
-            //     self.value_signals
-            //         .lock()
-            //         .unwrap()
-            //         .get(&format!("stroke_blue{}", self.selected_polygon_id))
-            //         .cloned()
-            //         .expect("Couldn't get stroke_blue value signal"),
-            // ),
-        };
+            field_name: field.to_string(),
+            old_value: old,
+            new_value: new,
+        }
+    }
 
-        let mut record = self.record.lock().unwrap();
-        record.edit(&mut self.record_state, edit);
+    #[test]
+    fn size_edits_are_labelled_in_pixels() {
+        let polygon_id = Uuid::new_v4();
+        let saved_state = saved_state("sequence", &[polygon_id]);
 
-        Ok(())
+        let edit = object_edit(
+            polygon_id,
+            "width",
+            ObjectProperty::Width(100.0),
+            ObjectProperty::Width(240.5),
+        );
+
+        assert_eq!(
+            edit.label(&saved_state),
+            "Change width of Square 100 → 240.5"
+        );
     }
 
-    pub fn undo(&mut self) {
-        let mut record = self.record.lock().unwrap();
+    #[test]
+    fn color_edits_are_labelled_in_0_to_255() {
+        let polygon_id = Uuid::new_v4();
+        let saved_state = saved_state("sequence", &[polygon_id]);
+
+        // entered as 0 - 255, read back from the editor as 0.0 - 1.0
+        let red = object_edit(
+            polygon_id,
+            "red",
+            ObjectProperty::Red(color_to_wgpu(51.0)),
+            ObjectProperty::Red(204.0),
+        );
+        // converted to 0.0 - 1.0 before the edit is made
+        let background_red = object_edit(
+            polygon_id,
+            "red_fill",
+            ObjectProperty::FillRed(color_to_wgpu(51.0)),
+            ObjectProperty::FillRed(color_to_wgpu(204.0)),
+        );
 
-        if record.undo(&mut self.record_state).is_some() {
-            println!("Undo successful");
-            // println!("record cannB... {:?}", self.record.head());
-        }
+        assert_eq!(red.label(&saved_state), "Change red of Square 51 → 204");
+        assert_eq!(
+            background_red.label(&saved_state),
+            "Change background red of Square 51 → 204"
+        );
     }
 
-    pub fn redo(&mut self) {
-        let mut record = self.record.lock().unwrap();
-
-        if record.redo(&mut self.record_state).is_some() {
-            println!("Redo successful");
+    #[test]
+    fn stored_object_types_round_trip() {
+        for object_type in [
+            ObjectType::Polygon,
+            ObjectType::TextItem,
+            ObjectType::ImageItem,
+            ObjectType::VideoItem,
+        ] {
+            let stored = StoredObjectType::from(&object_type);
+            let json = serde_json::to_string(&stored).expect("Couldn't serialize object type");
+            let read: StoredObjectType =
+                serde_json::from_str(&json).expect("Couldn't read object type");
+
+            assert!(ObjectType::from(read) == object_type);
         }
     }
 }
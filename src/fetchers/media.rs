@@ -1,5 +1,8 @@
 use gloo_net::http::Request;
-use serde::Deserialize;
+use serde::{de::DeserializeOwned, Deserialize};
+
+pub const IMAGE_ENDPOINT: &str = "http://localhost:3000/api/media/image";
+pub const VIDEO_ENDPOINT: &str = "http://localhost:3000/api/media/video";
 
 #[derive(Deserialize, Clone)]
 #[serde(rename_all = "camelCase")]
@@ -47,4 +50,106 @@ pub async fn fetch_image(
             None
         }
     }
-}
\ No newline at end of file
+}
+
+pub enum MediaFetch {
+    NotModified,
+    Fetched { data: Vec<u8>, etag: Option<String> },
+    Failed,
+}
+
+// asks the server to skip the body if our copy is still current
+async fn fetch_revalidated(
+    endpoint: &str,
+    token: String,
+    file_name: String,
+    etag: Option<String>,
+) -> MediaFetch {
+    let mut request = Request::get(endpoint)
+        .header("Authorization", &format!("Bearer {}", token))
+        .query([("filename", file_name)]);
+
+    if let Some(etag) = &etag {
+        request = request.header("If-None-Match", etag);
+    }
+
+    match request.send().await {
+        Ok(resp) => {
+            if resp.status() == 304 {
+                log::info!("Media not modified");
+
+                MediaFetch::NotModified
+            } else if resp.ok() {
+                let etag = resp.headers().get("etag");
+
+                match resp.binary().await {
+                    Ok(data) => MediaFetch::Fetched { data, etag },
+                    Err(err) => {
+                        log::error!("Media read error: {:?}", err);
+
+                        MediaFetch::Failed
+                    }
+                }
+            } else {
+                log::error!("fetched failed: {}", resp.status_text());
+
+                MediaFetch::Failed
+            }
+        }
+        Err(err) => {
+            log::error!("fetched error: {:?}", err);
+
+            MediaFetch::Failed
+        }
+    }
+}
+
+// same as fetch_image but revalidates our cached copy
+pub async fn fetch_image_revalidated(
+    token: String,
+    file_name: String,
+    etag: Option<String>,
+) -> MediaFetch {
+    fetch_revalidated(IMAGE_ENDPOINT, token, file_name, etag).await
+}
+
+pub async fn fetch_video_revalidated(
+    token: String,
+    file_name: String,
+    etag: Option<String>,
+) -> MediaFetch {
+    fetch_revalidated(VIDEO_ENDPOINT, token, file_name, etag).await
+}
+
+// stored JSON that travels with media, like capture mouse positions and source data
+pub async fn fetch_media_data<T: DeserializeOwned>(token: String, file_name: String) -> Option<T> {
+    let response = Request::get("http://localhost:3000/api/media/data")
+        .header("Authorization", &format!("Bearer {}", token))
+        .query([("filename", file_name)])
+        .send()
+        .await;
+
+    match response {
+        Ok(resp) => {
+            if resp.ok() {
+                match resp.json::<T>().await {
+                    Ok(data) => Some(data),
+                    Err(err) => {
+                        log::error!("Media data parse error: {:?}", err);
+
+                        None
+                    }
+                }
+            } else {
+                log::error!("Media data fetch failed: {}", resp.status_text());
+
+                None
+            }
+        }
+        Err(err) => {
+            log::error!("Media data fetch error: {:?}", err);
+
+            None
+        }
+    }
+}
@@ -219,3 +219,70 @@ pub async fn save_image(token: String, file_name: String, file_data: Vec<u8>) ->
 }
 
 
+
+pub async fn save_video(token: String, file_name: String, file_data: Vec<u8>) -> Option<UploadResponse> {
+    let response = Request::post("http://localhost:3000/api/upload/video")
+        .header("Authorization", &format!("Bearer {}", token))
+        .header("X-File-Name", &file_name)
+        .body(file_data)
+        .expect("Couldn't add request body")
+        .send()
+        .await;
+
+    match response {
+        Ok(resp) => {
+            if resp.ok() {
+                log::info!("Video uploaded successfully!");
+
+                let upload_response: UploadResponse = resp
+                    .json()
+                    .await
+                    .expect("Failed to parse upload response");
+
+                Some(upload_response)
+            } else {
+                log::error!("Video upload failed: {}", resp.status_text());
+
+                None
+            }
+        }
+        Err(err) => {
+            log::error!("Video upload error: {:?}", err);
+
+            None
+        }
+    }
+}
+
+// JSON that travels with media, like capture mouse positions and source data
+pub async fn save_media_data<T: Serialize>(token: String, file_name: String, data: &T) -> Option<UploadResponse> {
+    let response = Request::post("http://localhost:3000/api/upload/data")
+        .header("Authorization", &format!("Bearer {}", token))
+        .header("X-File-Name", &file_name)
+        .json(data)
+        .expect("Couldn't serialize media data")
+        .send()
+        .await;
+
+    match response {
+        Ok(resp) => {
+            if resp.ok() {
+                let upload_response: UploadResponse = resp
+                    .json()
+                    .await
+                    .expect("Failed to parse upload response");
+
+                Some(upload_response)
+            } else {
+                log::error!("Media data upload failed: {}", resp.status_text());
+
+                None
+            }
+        }
+        Err(err) => {
+            log::error!("Media data upload error: {:?}", err);
+
+            None
+        }
+    }
+}
@@ -0,0 +1,466 @@
+use serde::{Deserialize, Serialize};
+use stunts_engine::editor::Point;
+
+use crate::helpers::backgrounds::{BACKGROUND_HEIGHT, BACKGROUND_WIDTH};
+use crate::helpers::canvas_coords::scene_to_world;
+use crate::helpers::object_transform::ObjectBounds;
+
+// how close in pixels an edge has to come before it snaps
+pub const SNAP_DISTANCE: f32 = 6.0;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Alignment {
+    Left,
+    Center,
+    Right,
+    Top,
+    Middle,
+    Bottom,
+}
+
+impl Alignment {
+    pub const ALL: [Alignment; 6] = [
+        Alignment::Left,
+        Alignment::Center,
+        Alignment::Right,
+        Alignment::Top,
+        Alignment::Middle,
+        Alignment::Bottom,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Alignment::Left => "Left",
+            Alignment::Center => "Center",
+            Alignment::Right => "Right",
+            Alignment::Top => "Top",
+            Alignment::Middle => "Middle",
+            Alignment::Bottom => "Bottom",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Distribution {
+    Horizontal,
+    Vertical,
+}
+
+/// The visible canvas in world space, as its min and max corners
+pub fn canvas_extent() -> (Point, Point) {
+    (
+        scene_to_world(Point { x: 0.0, y: 0.0 }),
+        scene_to_world(Point {
+            x: BACKGROUND_WIDTH as f32,
+            y: BACKGROUND_HEIGHT as f32,
+        }),
+    )
+}
+
+/// Smallest upright box around all of `objects`, as its min and max corners
+pub fn union_extent(objects: &[ObjectBounds]) -> Option<(Point, Point)> {
+    objects
+        .iter()
+        .map(|o| o.extent())
+        .reduce(|(min, max), (o_min, o_max)| {
+            (
+                Point {
+                    x: min.x.min(o_min.x),
+                    y: min.y.min(o_min.y),
+                },
+                Point {
+                    x: max.x.max(o_max.x),
+                    y: max.y.max(o_max.y),
+                },
+            )
+        })
+}
+
+/// How far each object moves to line up with the others.
+/// A single object lines up with the canvas instead.
+pub fn align_translations(objects: &[ObjectBounds], alignment: Alignment) -> Vec<(i32, i32)> {
+    let reference = if objects.len() == 1 {
+        Some(canvas_extent())
+    } else {
+        union_extent(objects)
+    };
+    let Some((min, max)) = reference else {
+        return Vec::new();
+    };
+
+    objects
+        .iter()
+        .map(|object| {
+            let (o_min, o_max) = object.extent();
+
+            let (dx, dy) = match alignment {
+                Alignment::Left => (min.x - o_min.x, 0.0),
+                Alignment::Center => ((min.x + max.x) / 2.0 - object.center.x, 0.0),
+                Alignment::Right => (max.x - o_max.x, 0.0),
+                Alignment::Top => (0.0, min.y - o_min.y),
+                Alignment::Middle => (0.0, (min.y + max.y) / 2.0 - object.center.y),
+                Alignment::Bottom => (0.0, max.y - o_max.y),
+            };
+
+            (dx.round() as i32, dy.round() as i32)
+        })
+        .collect()
+}
+
+/// How far each object moves so the gaps between them are even. The outermost
+/// objects stay put, so at least three are needed.
+pub fn distribute_translations(
+    objects: &[ObjectBounds],
+    distribution: Distribution,
+) -> Vec<(i32, i32)> {
+    let mut translations = vec![(0, 0); objects.len()];
+
+    if objects.len() < 3 {
+        return translations;
+    }
+
+    // (index, start, size) along the axis
+    let mut spans = objects
+        .iter()
+        .enumerate()
+        .map(|(i, object)| {
+            let (min, max) = object.extent();
+
+            match distribution {
+                Distribution::Horizontal => (i, min.x, max.x - min.x),
+                Distribution::Vertical => (i, min.y, max.y - min.y),
+            }
+        })
+        .collect::<Vec<_>>();
+    spans.sort_by(|a, b| a.1.total_cmp(&b.1));
+
+    let first = spans[0];
+    let last = spans[spans.len() - 1];
+    let total_size = spans.iter().map(|(_, _, size)| size).sum::<f32>();
+    let gap = (last.1 + last.2 - first.1 - total_size) / (spans.len() - 1) as f32;
+
+    let mut position = first.1;
+    for (i, start, size) in spans {
+        let offset = (position - start).round() as i32;
+
+        translations[i] = match distribution {
+            Distribution::Horizontal => (offset, 0),
+            Distribution::Vertical => (0, offset),
+        };
+
+        position += size + gap;
+    }
+
+    translations
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SnapSettings {
+    pub grid: bool,
+    pub grid_size: u32,
+    pub objects: bool,
+    pub canvas_center: bool,
+}
+
+impl Default for SnapSettings {
+    fn default() -> Self {
+        SnapSettings {
+            grid: false,
+            grid_size: 20,
+            objects: true,
+            canvas_center: true,
+        }
+    }
+}
+
+/// A smart guide, a line in world space showing what a drag snapped to
+#[derive(Clone, Debug, PartialEq)]
+pub struct Guide {
+    pub vertical: bool,
+    /// x of a vertical guide, y of a horizontal one
+    pub at: f32,
+    pub from: f32,
+    pub to: f32,
+}
+
+// a line an edge or center can snap to, with how far it reaches along the other axis
+struct SnapTarget {
+    at: f32,
+    span: (f32, f32),
+}
+
+/// Smallest move along one axis that puts one of `lines` on a target,
+/// with the target it landed on
+fn closest_snap<'a>(
+    lines: [f32; 3],
+    targets: &'a [SnapTarget],
+    threshold: f32,
+) -> Option<(f32, &'a SnapTarget)> {
+    targets
+        .iter()
+        .flat_map(|target| lines.iter().map(move |line| (target.at - line, target)))
+        .filter(|(offset, _)| offset.abs() <= threshold)
+        .min_by(|a, b| a.0.abs().total_cmp(&b.0.abs()))
+}
+
+/// Where a moved frame should go with snapping applied, as the extra offset
+/// to add to it, and the guides to draw. `threshold` is in world units.
+pub fn snap_frame(
+    frame: &ObjectBounds,
+    others: &[ObjectBounds],
+    settings: &SnapSettings,
+    threshold: f32,
+) -> (Point, Vec<Guide>) {
+    let (min, max) = frame.extent();
+    let (canvas_min, canvas_max) = canvas_extent();
+
+    let mut x_targets = Vec::new();
+    let mut y_targets = Vec::new();
+
+    if settings.objects {
+        for other in others {
+            let (o_min, o_max) = other.extent();
+
+            for at in [o_min.x, other.center.x, o_max.x] {
+                x_targets.push(SnapTarget {
+                    at,
+                    span: (o_min.y, o_max.y),
+                });
+            }
+            for at in [o_min.y, other.center.y, o_max.y] {
+                y_targets.push(SnapTarget {
+                    at,
+                    span: (o_min.x, o_max.x),
+                });
+            }
+        }
+    }
+
+    if settings.canvas_center {
+        x_targets.push(SnapTarget {
+            at: (canvas_min.x + canvas_max.x) / 2.0,
+            span: (canvas_min.y, canvas_max.y),
+        });
+        y_targets.push(SnapTarget {
+            at: (canvas_min.y + canvas_max.y) / 2.0,
+            span: (canvas_min.x, canvas_max.x),
+        });
+    }
+
+    let x_snap = closest_snap([min.x, frame.center.x, max.x], &x_targets, threshold);
+    let y_snap = closest_snap([min.y, frame.center.y, max.y], &y_targets, threshold);
+
+    // the grid is the fallback, counted from the canvas corner
+    let grid_offset = |start: f32, origin: f32| {
+        let size = settings.grid_size.max(1) as f32;
+        ((start - origin) / size).round() * size + origin - start
+    };
+
+    let dx = match &x_snap {
+        Some((offset, _)) => *offset,
+        None if settings.grid => grid_offset(min.x, canvas_min.x),
+        None => 0.0,
+    };
+    let dy = match &y_snap {
+        Some((offset, _)) => *offset,
+        None if settings.grid => grid_offset(min.y, canvas_min.y),
+        None => 0.0,
+    };
+
+    let mut guides = Vec::new();
+
+    if let Some((_, target)) = x_snap {
+        guides.push(Guide {
+            vertical: true,
+            at: target.at,
+            from: target.span.0.min(min.y + dy),
+            to: target.span.1.max(max.y + dy),
+        });
+    }
+    if let Some((_, target)) = y_snap {
+        guides.push(Guide {
+            vertical: false,
+            at: target.at,
+            from: target.span.0.min(min.x + dx),
+            to: target.span.1.max(max.x + dx),
+        });
+    }
+
+    (Point { x: dx, y: dy }, guides)
+}
+
+#[cfg(test)]
+mod tests {
+    use stunts_engine::animations::ObjectType;
+    use uuid::Uuid;
+
+    use super::*;
+
+    fn bounds(center: (f32, f32), dimensions: (f32, f32)) -> ObjectBounds {
+        ObjectBounds {
+            id: Uuid::new_v4(),
+            object_type: ObjectType::Polygon,
+            center: Point {
+                x: center.0,
+                y: center.1,
+            },
+            dimensions,
+            rotation: 0.0,
+            layer: 0,
+        }
+    }
+
+    fn close(a: f32, b: f32) -> bool {
+        (a - b).abs() < 1e-3
+    }
+
+    const OBJECTS_ONLY: SnapSettings = SnapSettings {
+        grid: false,
+        grid_size: 20,
+        objects: true,
+        canvas_center: false,
+    };
+
+    #[test]
+    fn nothing_moves_without_a_selection() {
+        assert!(align_translations(&[], Alignment::Left).is_empty());
+        assert!(distribute_translations(&[], Distribution::Horizontal).is_empty());
+        assert!(union_extent(&[]).is_none());
+    }
+
+    #[test]
+    fn a_single_object_aligns_to_the_canvas() {
+        let (canvas_min, canvas_max) = canvas_extent();
+        let object = bounds((canvas_min.x + 200.0, canvas_min.y + 100.0), (100.0, 50.0));
+
+        assert_eq!(
+            align_translations(&[object.clone()], Alignment::Left),
+            vec![(-150, 0)]
+        );
+        assert_eq!(
+            align_translations(&[object], Alignment::Bottom),
+            vec![(0, (canvas_max.y - canvas_min.y - 125.0).round() as i32)]
+        );
+    }
+
+    #[test]
+    fn several_objects_align_to_their_union() {
+        let objects = [
+            bounds((100.0, 100.0), (50.0, 50.0)),
+            bounds((300.0, 200.0), (100.0, 90.0)),
+        ];
+
+        assert_eq!(
+            align_translations(&objects, Alignment::Left),
+            vec![(0, 0), (-175, 0)]
+        );
+        assert_eq!(
+            align_translations(&objects, Alignment::Right),
+            vec![(225, 0), (0, 0)]
+        );
+        assert_eq!(
+            align_translations(&objects, Alignment::Middle),
+            vec![(0, 60), (0, -40)]
+        );
+    }
+
+    #[test]
+    fn zero_size_objects_align_by_their_centers() {
+        let objects = [
+            bounds((10.0, 0.0), (0.0, 0.0)),
+            bounds((30.0, 0.0), (0.0, 0.0)),
+        ];
+
+        assert_eq!(
+            align_translations(&objects, Alignment::Center),
+            vec![(10, 0), (-10, 0)]
+        );
+    }
+
+    #[test]
+    fn distributing_evens_out_the_gaps() {
+        // given out of order, the outermost objects stay put
+        let objects = [
+            bounds((115.0, 0.0), (30.0, 10.0)),
+            bounds((5.0, 0.0), (10.0, 10.0)),
+            bounds((60.0, 0.0), (20.0, 10.0)),
+        ];
+
+        assert_eq!(
+            distribute_translations(&objects, Distribution::Horizontal),
+            vec![(0, 0), (0, 0), (-5, 0)]
+        );
+    }
+
+    #[test]
+    fn distributing_needs_three_objects() {
+        let objects = [
+            bounds((0.0, 0.0), (10.0, 10.0)),
+            bounds((0.0, 80.0), (10.0, 10.0)),
+        ];
+
+        assert_eq!(
+            distribute_translations(&objects, Distribution::Vertical),
+            vec![(0, 0), (0, 0)]
+        );
+    }
+
+    #[test]
+    fn zero_size_objects_distribute_by_position() {
+        let objects = [
+            bounds((0.0, 0.0), (0.0, 0.0)),
+            bounds((0.0, 10.0), (0.0, 0.0)),
+            bounds((0.0, 40.0), (0.0, 0.0)),
+        ];
+
+        assert_eq!(
+            distribute_translations(&objects, Distribution::Vertical),
+            vec![(0, 0), (0, 10), (0, 0)]
+        );
+    }
+
+    #[test]
+    fn frames_snap_to_nearby_edges() {
+        let other = bounds((100.0, 100.0), (50.0, 50.0));
+        // left edge 3px right of the other's right edge
+        let frame = bounds((138.0, 300.0), (20.0, 20.0));
+
+        let (offset, guides) = snap_frame(&frame, &[other], &OBJECTS_ONLY, SNAP_DISTANCE);
+
+        assert!(close(offset.x, -3.0) && close(offset.y, 0.0));
+        assert_eq!(guides.len(), 1);
+        assert!(guides[0].vertical);
+        assert!(close(guides[0].at, 125.0));
+        // from the top of the other object to the bottom of the frame
+        assert!(close(guides[0].from, 75.0) && close(guides[0].to, 310.0));
+    }
+
+    #[test]
+    fn frames_out_of_reach_stay_put() {
+        let other = bounds((100.0, 100.0), (50.0, 50.0));
+        let frame = bounds((400.0, 300.0), (0.0, 0.0));
+
+        let (offset, guides) = snap_frame(&frame, &[other], &OBJECTS_ONLY, SNAP_DISTANCE);
+
+        assert!(close(offset.x, 0.0) && close(offset.y, 0.0));
+        assert!(guides.is_empty());
+    }
+
+    #[test]
+    fn the_grid_counts_from_the_canvas_corner() {
+        let (canvas_min, _) = canvas_extent();
+        let settings = SnapSettings {
+            grid: true,
+            objects: false,
+            canvas_center: false,
+            ..SnapSettings::default()
+        };
+        // top left corner at (47, 12) on the canvas
+        let frame = bounds((canvas_min.x + 57.0, canvas_min.y + 22.0), (20.0, 20.0));
+
+        let (offset, guides) = snap_frame(&frame, &[], &settings, SNAP_DISTANCE);
+
+        assert!(close(offset.x, -7.0) && close(offset.y, 8.0));
+        assert!(guides.is_empty());
+    }
+}
@@ -0,0 +1,498 @@
+use std::time::Duration;
+
+use stunts_engine::animations::{EasingType, KeyType, KeyframeValue, UIKeyframe};
+use stunts_engine::editor::PathType;
+use stunts_engine::st_video::MousePosition;
+use uuid::Uuid;
+
+pub const ZOOM_OUT: i32 = 100;
+
+// focus is kept at least this long after a click
+const CLICK_HOLD_MS: u128 = 1200;
+// zoom starts a little before the click so it lands on time
+const CLICK_LEAD_MS: u128 = 300;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct AutoZoomSettings {
+    /// 0.0 - 1.0, higher picks up shorter dwells and looser clusters
+    pub sensitivity: f32,
+    pub zoom_level: i32,
+    pub zoom_out_on_fast_movement: bool,
+    /// pointer speed in frame pixels per ms that counts as fast
+    pub fast_speed: f32,
+    pub transition_ms: u128,
+}
+
+impl Default for AutoZoomSettings {
+    fn default() -> Self {
+        AutoZoomSettings {
+            sensitivity: 0.5,
+            zoom_level: 135,
+            zoom_out_on_fast_movement: true,
+            fast_speed: 1.5,
+            transition_ms: 600,
+        }
+    }
+}
+
+impl AutoZoomSettings {
+    /// how long the pointer has to stay put to count as a dwell
+    pub fn dwell_ms(&self) -> u128 {
+        let sensitivity = self.sensitivity.clamp(0.0, 1.0);
+        (1500.0 - 1100.0 * sensitivity) as u128
+    }
+
+    /// how far the pointer can wander and still be in the same cluster
+    pub fn cluster_radius(&self) -> f32 {
+        let sensitivity = self.sensitivity.clamp(0.0, 1.0);
+        30.0 + 60.0 * sensitivity
+    }
+}
+
+/// Keyframes for the "zoom" and "zoom_focus" properties of a captured video.
+/// Focus is in frame pixels and changes at the same times as the zoom.
+#[derive(Clone, Debug)]
+pub struct AutoZoomKeyframes {
+    pub zoom: Vec<UIKeyframe>,
+    pub focus: Vec<UIKeyframe>,
+}
+
+fn keyframe(time_ms: u128, value: KeyframeValue) -> UIKeyframe {
+    UIKeyframe {
+        id: Uuid::new_v4().to_string(),
+        time: Duration::from_millis(time_ms as u64),
+        value,
+        easing: EasingType::EaseInOut,
+        path_type: PathType::Linear,
+        key_type: KeyType::Frame,
+    }
+}
+
+fn distance(a: &MousePosition, b: &MousePosition) -> f32 {
+    ((b.x - a.x).powi(2) + (b.y - a.y).powi(2)).sqrt()
+}
+
+/// sorts and merges (start, end) spans that overlap or sit within `gap` of each other
+fn merge_spans(mut spans: Vec<(u128, u128)>, gap: u128) -> Vec<(u128, u128)> {
+    spans.sort_by_key(|s| s.0);
+
+    let mut merged: Vec<(u128, u128)> = Vec::new();
+
+    for (start, end) in spans {
+        match merged.last_mut() {
+            Some(last) if last.1 + gap >= start => last.1 = last.1.max(end),
+            _ => merged.push((start, end)),
+        }
+    }
+
+    merged
+}
+
+/// Spans where the pointer stayed within the cluster radius for at least the dwell time.
+/// Pointer events stop while the pointer is still, so a dwell runs until the next sample
+/// that leaves the cluster.
+pub fn detect_dwells(
+    positions: &[MousePosition],
+    settings: &AutoZoomSettings,
+    duration_ms: u128,
+) -> Vec<(u128, u128)> {
+    let radius = settings.cluster_radius();
+    let dwell_ms = settings.dwell_ms();
+
+    let mut dwells = Vec::new();
+    let mut anchor = 0;
+
+    while anchor < positions.len() {
+        let mut exit = anchor + 1;
+
+        while exit < positions.len() && distance(&positions[anchor], &positions[exit]) <= radius {
+            exit += 1;
+        }
+
+        let start = positions[anchor].timestamp;
+        let end = positions
+            .get(exit)
+            .map(|p| p.timestamp)
+            .unwrap_or(duration_ms);
+
+        if end.saturating_sub(start) >= dwell_ms {
+            dwells.push((start, end));
+        }
+
+        anchor = exit;
+    }
+
+    dwells
+}
+
+/// Spans where the pointer moves faster than `fast_speed`
+pub fn detect_fast_movement(
+    positions: &[MousePosition],
+    settings: &AutoZoomSettings,
+) -> Vec<(u128, u128)> {
+    let spans = positions
+        .windows(2)
+        .filter(|pair| {
+            let elapsed = pair[1].timestamp.saturating_sub(pair[0].timestamp).max(1);
+            distance(&pair[0], &pair[1]) / elapsed as f32 > settings.fast_speed
+        })
+        .map(|pair| (pair[0].timestamp, pair[1].timestamp))
+        .collect();
+
+    merge_spans(spans, 100)
+}
+
+/// removes `cuts` from `spans`, dropping anything left shorter than `min_length`
+fn subtract_spans(
+    spans: Vec<(u128, u128)>,
+    cuts: &[(u128, u128)],
+    min_length: u128,
+) -> Vec<(u128, u128)> {
+    let mut remaining = spans;
+
+    for (cut_start, cut_end) in cuts {
+        remaining = remaining
+            .into_iter()
+            .flat_map(|(start, end)| {
+                if *cut_end <= start || *cut_start >= end {
+                    return vec![(start, end)];
+                }
+
+                let mut pieces = Vec::new();
+                if *cut_start > start {
+                    pieces.push((start, *cut_start));
+                }
+                if *cut_end < end {
+                    pieces.push((*cut_end, end));
+                }
+                pieces
+            })
+            .collect();
+    }
+
+    remaining
+        .into_iter()
+        .filter(|(start, end)| end - start >= min_length)
+        .collect()
+}
+
+/// Spans of the recording worth zooming into: click and dwell clusters, minus fast movement
+pub fn focus_spans(
+    positions: &[MousePosition],
+    clicks: &[u128],
+    duration_ms: u128,
+    settings: &AutoZoomSettings,
+) -> Vec<(u128, u128)> {
+    let mut positions = positions.to_vec();
+    positions.sort_by_key(|p| p.timestamp);
+
+    let mut spans = detect_dwells(&positions, settings, duration_ms);
+
+    spans.extend(clicks.iter().map(|click| {
+        (
+            click.saturating_sub(CLICK_LEAD_MS),
+            (click + CLICK_HOLD_MS).min(duration_ms),
+        )
+    }));
+
+    // close gaps too short to zoom out and back in
+    let spans = merge_spans(spans, settings.transition_ms * 2);
+
+    if !settings.zoom_out_on_fast_movement {
+        return spans;
+    }
+
+    let fast = detect_fast_movement(&positions, settings);
+
+    subtract_spans(spans, &fast, settings.transition_ms)
+}
+
+/// Where the pointer was during a span: the mean of its samples, or the last
+/// sample before the span when the pointer didn't move inside it
+fn span_center(positions: &[MousePosition], (start, end): (u128, u128)) -> Option<(f32, f32)> {
+    let inside = positions
+        .iter()
+        .filter(|p| p.timestamp >= start && p.timestamp < end)
+        .collect::<Vec<_>>();
+
+    if inside.is_empty() {
+        return positions
+            .iter()
+            .filter(|p| p.timestamp < start)
+            .max_by_key(|p| p.timestamp)
+            .map(|p| (p.x, p.y));
+    }
+
+    let count = inside.len() as f32;
+    Some((
+        inside.iter().map(|p| p.x).sum::<f32>() / count,
+        inside.iter().map(|p| p.y).sum::<f32>() / count,
+    ))
+}
+
+/// Moves `center` just far enough that a frame zoomed to `zoom` percent around it
+/// stays inside the video
+pub fn clamp_focus(center: (f32, f32), frame_size: (u32, u32), zoom: i32) -> [i32; 2] {
+    let scale = (zoom.max(ZOOM_OUT) as f32 / ZOOM_OUT as f32).max(1.0);
+
+    let clamp = |value: f32, side: u32| {
+        let half_visible = side as f32 / scale / 2.0;
+        value
+            .clamp(half_visible, side as f32 - half_visible)
+            .round() as i32
+    };
+
+    [clamp(center.0, frame_size.0), clamp(center.1, frame_size.1)]
+}
+
+/// Zoom and focus keyframes for a screen recording. Zooms into click and dwell clusters
+/// and back out between them or when the pointer moves fast. Focus sits on the middle
+/// of each cluster while zoomed in and on the middle of the frame while zoomed out, so
+/// the zoomed frame never leaves the video. Timestamps are milliseconds from the start
+/// of the recording and `frame_size` is the captured frame the positions are in.
+pub fn generate_zoom_keyframes(
+    positions: &[MousePosition],
+    clicks: &[u128],
+    duration_ms: i64,
+    frame_size: (u32, u32),
+    settings: &AutoZoomSettings,
+) -> AutoZoomKeyframes {
+    let duration_ms = duration_ms.max(0) as u128;
+    let transition_ms = settings.transition_ms;
+
+    let frame_center = [frame_size.0 as i32 / 2, frame_size.1 as i32 / 2];
+
+    // (time, zoom, focus)
+    let mut stops = vec![(0, ZOOM_OUT, frame_center)];
+    let mut last_time = 0;
+
+    for span in focus_spans(positions, clicks, duration_ms, settings) {
+        let (start, end) = span;
+        let focus = span_center(positions, span)
+            .map(|center| clamp_focus(center, frame_size, settings.zoom_level))
+            .unwrap_or(frame_center);
+
+        // ease in so the zoom lands as the cluster starts
+        let zoom_from = start.saturating_sub(transition_ms).max(last_time);
+        let zoom_to = start.max(zoom_from + 1).min(duration_ms);
+        let hold_to = end.max(zoom_to).min(duration_ms);
+        let release_at = (hold_to + transition_ms).min(duration_ms);
+
+        if zoom_from > last_time {
+            stops.push((zoom_from, ZOOM_OUT, frame_center));
+        }
+        stops.push((zoom_to, settings.zoom_level, focus));
+        if hold_to > zoom_to {
+            stops.push((hold_to, settings.zoom_level, focus));
+        }
+        stops.push((release_at, ZOOM_OUT, frame_center));
+
+        last_time = release_at;
+    }
+
+    if duration_ms > last_time {
+        stops.push((duration_ms, ZOOM_OUT, frame_center));
+    }
+
+    // drop stops that landed on the same time, keeping the first
+    stops.dedup_by(|b, a| a.0 == b.0);
+
+    AutoZoomKeyframes {
+        zoom: stops
+            .iter()
+            .map(|(time, zoom, _)| keyframe(*time, KeyframeValue::Zoom(*zoom)))
+            .collect(),
+        focus: stops
+            .iter()
+            .map(|(time, _, focus)| keyframe(*time, KeyframeValue::Position(*focus)))
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use stunts_engine::st_video::PointType;
+
+    fn at(timestamp: u128, x: f32, y: f32) -> MousePosition {
+        MousePosition {
+            x,
+            y,
+            timestamp,
+            point_type: PointType::Point,
+        }
+    }
+
+    // jitters around (x, y) every 50ms from `from` to `to`
+    fn hover(from: u128, to: u128, x: f32, y: f32) -> Vec<MousePosition> {
+        (from..=to)
+            .step_by(50)
+            .map(|time| {
+                let jitter = (time / 50 % 3) as f32;
+                at(time, x + jitter, y - jitter)
+            })
+            .collect()
+    }
+
+    // zig-zags 70px every 100ms, too far for a dwell and too slow to count as fast
+    fn wander(from: u128, to: u128, x: f32, y: f32) -> Vec<MousePosition> {
+        (from..=to)
+            .step_by(100)
+            .map(|time| at(time, x + (time / 100 % 2) as f32 * 70.0, y))
+            .collect()
+    }
+
+    fn track(keyframes: &[UIKeyframe]) -> Vec<(u128, KeyframeValue)> {
+        keyframes
+            .iter()
+            .map(|k| (k.time.as_millis(), k.value.clone()))
+            .collect()
+    }
+
+    #[test]
+    fn dwell_runs_until_the_pointer_leaves_the_cluster() {
+        let mut positions = hover(0, 1500, 500.0, 300.0);
+        positions.push(at(1600, 900.0, 500.0));
+
+        assert_eq!(
+            detect_dwells(&positions, &AutoZoomSettings::default(), 1600),
+            vec![(0, 1600)]
+        );
+    }
+
+    #[test]
+    fn dwell_runs_to_the_end_when_the_pointer_stops() {
+        let positions = vec![at(0, 100.0, 100.0), at(2000, 500.0, 300.0)];
+
+        assert_eq!(
+            detect_dwells(&positions, &AutoZoomSettings::default(), 3500),
+            vec![(0, 2000), (2000, 3500)]
+        );
+    }
+
+    #[test]
+    fn short_pauses_are_not_dwells() {
+        let mut positions = hover(0, 500, 500.0, 300.0);
+        positions.push(at(600, 900.0, 500.0));
+        positions.extend(hover(650, 900, 900.0, 500.0));
+        positions.push(at(1000, 100.0, 100.0));
+
+        assert_eq!(
+            detect_dwells(&positions, &AutoZoomSettings::default(), 1000),
+            vec![]
+        );
+    }
+
+    #[test]
+    fn fast_movement_merges_nearby_jumps() {
+        let positions = vec![
+            at(0, 0.0, 0.0),
+            at(100, 400.0, 0.0),
+            at(150, 800.0, 0.0),
+            at(200, 800.0, 10.0),
+            at(1000, 820.0, 10.0),
+            at(1100, 1400.0, 10.0),
+        ];
+
+        assert_eq!(
+            detect_fast_movement(&positions, &AutoZoomSettings::default()),
+            vec![(0, 150), (1000, 1100)]
+        );
+    }
+
+    #[test]
+    fn clicks_lead_and_hold_and_merge_when_close() {
+        let positions = wander(0, 10_000, 0.0, 0.0);
+
+        assert_eq!(
+            focus_spans(
+                &positions,
+                &[1000, 2500],
+                10_000,
+                &AutoZoomSettings::default()
+            ),
+            vec![(700, 3700)]
+        );
+        assert_eq!(
+            focus_spans(&positions, &[9500], 10_000, &AutoZoomSettings::default()),
+            vec![(9200, 10_000)]
+        );
+    }
+
+    #[test]
+    fn fast_movement_cuts_focus_spans() {
+        let mut positions = wander(0, 2000, 0.0, 0.0);
+        positions.extend(wander(2100, 5000, 1000.0, 0.0));
+
+        // the piece before the jump is too short to zoom into
+        assert_eq!(
+            focus_spans(&positions, &[1800], 5000, &AutoZoomSettings::default()),
+            vec![(2100, 3000)]
+        );
+
+        let settings = AutoZoomSettings {
+            zoom_out_on_fast_movement: false,
+            ..AutoZoomSettings::default()
+        };
+        assert_eq!(
+            focus_spans(&positions, &[1800], 5000, &settings),
+            vec![(1500, 3000)]
+        );
+    }
+
+    #[test]
+    fn focus_is_clamped_inside_the_frame() {
+        assert_eq!(clamp_focus((10.0, 10.0), (1920, 1080), 135), [711, 400]);
+        assert_eq!(
+            clamp_focus((1910.0, 1070.0), (1920, 1080), 200),
+            [1440, 810]
+        );
+        assert_eq!(clamp_focus((960.0, 200.0), (1920, 1080), 200), [960, 270]);
+        assert_eq!(clamp_focus((10.0, 10.0), (1920, 1080), 100), [960, 540]);
+    }
+
+    #[test]
+    fn focus_follows_each_span_and_recenters_when_zoomed_out() {
+        let mut positions = hover(0, 1500, 500.0, 300.0);
+        positions.extend(wander(1600, 3500, 900.0, 500.0));
+        positions.extend(hover(3600, 5000, 1900.0, 1000.0));
+        positions.extend(wander(5100, 6000, 100.0, 100.0));
+
+        let keyframes = generate_zoom_keyframes(
+            &positions,
+            &[],
+            6000,
+            (1920, 1080),
+            &AutoZoomSettings::default(),
+        );
+
+        assert_eq!(
+            track(&keyframes.zoom),
+            vec![
+                (0, KeyframeValue::Zoom(100)),
+                (1, KeyframeValue::Zoom(135)),
+                (1500, KeyframeValue::Zoom(135)),
+                (2100, KeyframeValue::Zoom(100)),
+                (3000, KeyframeValue::Zoom(100)),
+                (3600, KeyframeValue::Zoom(135)),
+                (5000, KeyframeValue::Zoom(135)),
+                (5600, KeyframeValue::Zoom(100)),
+                (6000, KeyframeValue::Zoom(100)),
+            ]
+        );
+        assert_eq!(
+            track(&keyframes.focus),
+            vec![
+                (0, KeyframeValue::Position([960, 540])),
+                (1, KeyframeValue::Position([711, 400])),
+                (1500, KeyframeValue::Position([711, 400])),
+                (2100, KeyframeValue::Position([960, 540])),
+                (3000, KeyframeValue::Position([960, 540])),
+                (3600, KeyframeValue::Position([1209, 680])),
+                (5000, KeyframeValue::Position([1209, 680])),
+                (5600, KeyframeValue::Position([960, 540])),
+                (6000, KeyframeValue::Position([960, 540])),
+            ]
+        );
+    }
+}
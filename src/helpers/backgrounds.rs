@@ -0,0 +1,303 @@
+use std::io::Cursor;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use image::{imageops, imageops::FilterType, ImageFormat, Rgba, RgbaImage};
+use log::info;
+use stunts_engine::{
+    animations::{BackgroundFill, BackgroundImage, GradientDefinition, GradientKind, ImageFit},
+    camera::Camera,
+    editor::{rgb_to_wgpu, Point, CANVAS_HORIZ_OFFSET, CANVAS_VERT_OFFSET},
+    st_image::{StImage, StImageConfig},
+};
+use uuid::Uuid;
+use wasm_bindgen_futures::spawn_local;
+
+use crate::canvas_renderer::CanvasRenderer;
+use crate::helpers::media_cache::cached_image;
+
+// size of the sequence canvas in scene units
+pub const BACKGROUND_WIDTH: u32 = 800;
+pub const BACKGROUND_HEIGHT: u32 = 450;
+// keeps a zoomed in raster within common texture limits
+const MAX_RASTER_SIDE: u32 = 4096;
+
+// bumped on every apply so a slow raster/fetch can't overwrite a newer background
+static BACKGROUND_GENERATION: AtomicUsize = AtomicUsize::new(0);
+
+pub fn rgba_to_hex(color: [i32; 4]) -> String {
+    format!(
+        "#{:02x}{:02x}{:02x}",
+        color[0].clamp(0, 255),
+        color[1].clamp(0, 255),
+        color[2].clamp(0, 255)
+    )
+}
+
+pub fn hex_to_rgba(hex: &str) -> Option<[i32; 4]> {
+    let hex = hex.trim_start_matches('#');
+
+    if hex.len() != 6 {
+        return None;
+    }
+
+    let channel = |i: usize| i32::from_str_radix(&hex[i..i + 2], 16).ok();
+
+    Some([channel(0)?, channel(2)?, channel(4)?, 255])
+}
+
+fn to_rgba(color: [i32; 4]) -> Rgba<u8> {
+    Rgba(color.map(|c| c.clamp(0, 255) as u8))
+}
+
+fn lerp_color(a: [i32; 4], b: [i32; 4], t: f32) -> Rgba<u8> {
+    let mix = |i: usize| (a[i] as f32 + (b[i] - a[i]) as f32 * t).round().clamp(0.0, 255.0) as u8;
+
+    Rgba([mix(0), mix(1), mix(2), mix(3)])
+}
+
+/// Color at `t` (0.0 - 1.0) along the gradient, stop offsets are percentages
+pub fn sample_gradient(gradient: &GradientDefinition, t: f32) -> Rgba<u8> {
+    let mut stops = gradient.stops.clone();
+    stops.sort_by_key(|s| s.offset);
+
+    let (Some(first), Some(last)) = (stops.first(), stops.last()) else {
+        return Rgba([255, 255, 255, 255]);
+    };
+
+    let t = t.clamp(0.0, 1.0) * 100.0;
+
+    if t <= first.offset as f32 {
+        return to_rgba(first.color);
+    }
+    if t >= last.offset as f32 {
+        return to_rgba(last.color);
+    }
+
+    for pair in stops.windows(2) {
+        let (from, to) = (&pair[0], &pair[1]);
+
+        if t >= from.offset as f32 && t <= to.offset as f32 {
+            let span = (to.offset - from.offset).max(1) as f32;
+            return lerp_color(from.color, to.color, (t - from.offset as f32) / span);
+        }
+    }
+
+    to_rgba(last.color)
+}
+
+/// Linear angles follow CSS, 0 degrees runs bottom to top and 90 runs left to right.
+/// Radial gradients run from the center out to the farthest corner.
+pub fn rasterize_gradient(gradient: &GradientDefinition, width: u32, height: u32) -> RgbaImage {
+    let cx = width as f32 / 2.0;
+    let cy = height as f32 / 2.0;
+
+    let angle = (gradient.angle as f32).to_radians();
+    let (dx, dy) = (angle.sin(), -angle.cos());
+    let line_length = (width as f32 * dx.abs() + height as f32 * dy.abs()).max(1.0);
+    let corner_distance = (cx * cx + cy * cy).sqrt().max(1.0);
+
+    RgbaImage::from_fn(width, height, |x, y| {
+        let px = x as f32 + 0.5 - cx;
+        let py = y as f32 + 0.5 - cy;
+
+        let t = match gradient.kind {
+            GradientKind::Linear => (px * dx + py * dy) / line_length + 0.5,
+            GradientKind::Radial => (px * px + py * py).sqrt() / corner_distance,
+        };
+
+        sample_gradient(gradient, t)
+    })
+}
+
+/// Places `source` in a `width` x `height` frame. Contain leaves the uncovered area transparent.
+pub fn fit_image(source: &RgbaImage, width: u32, height: u32, fit: &ImageFit) -> RgbaImage {
+    let (source_width, source_height) = source.dimensions();
+
+    if source_width == 0 || source_height == 0 {
+        return RgbaImage::new(width, height);
+    }
+
+    let scale_x = width as f32 / source_width as f32;
+    let scale_y = height as f32 / source_height as f32;
+
+    match fit {
+        ImageFit::Cover | ImageFit::Contain => {
+            let scale = match fit {
+                ImageFit::Cover => scale_x.max(scale_y),
+                _ => scale_x.min(scale_y),
+            };
+
+            let scaled_width = ((source_width as f32 * scale).round() as u32).max(1);
+            let scaled_height = ((source_height as f32 * scale).round() as u32).max(1);
+            let scaled = imageops::resize(source, scaled_width, scaled_height, FilterType::Triangle);
+
+            let mut output = RgbaImage::new(width, height);
+            imageops::overlay(
+                &mut output,
+                &scaled,
+                (width as i64 - scaled_width as i64) / 2,
+                (height as i64 - scaled_height as i64) / 2,
+            );
+            output
+        }
+        ImageFit::Tile => RgbaImage::from_fn(width, height, |x, y| {
+            *source.get_pixel(x % source_width, y % source_height)
+        }),
+    }
+}
+
+pub fn encode_png(image: &RgbaImage) -> Option<Vec<u8>> {
+    let mut bytes = Vec::new();
+
+    image
+        .write_to(&mut Cursor::new(&mut bytes), ImageFormat::Png)
+        .map_err(|e| log::error!("Couldn't encode background: {}", e))
+        .ok()?;
+
+    Some(bytes)
+}
+
+/// Drawing buffer pixels the sequence covers at the camera's zoom. Exports capture
+/// the canvas at this same size, so a raster this big is sharp in both.
+pub fn background_raster_size(camera: &Camera, surface_size: (u32, u32)) -> (u32, u32) {
+    let zoom = camera.zoom.max(0.01);
+    let scale_x = surface_size.0 as f32 / camera.window_size.width.max(1) as f32;
+    let scale_y = surface_size.1 as f32 / camera.window_size.height.max(1) as f32;
+
+    let side = |units: u32, scale: f32| {
+        ((units as f32 * zoom * scale).round() as u32).clamp(1, MAX_RASTER_SIDE)
+    };
+
+    (
+        side(BACKGROUND_WIDTH, scale_x),
+        side(BACKGROUND_HEIGHT, scale_y),
+    )
+}
+
+async fn rasterize_background(
+    fill: &BackgroundFill,
+    token: String,
+    (width, height): (u32, u32),
+) -> Option<RgbaImage> {
+    match fill {
+        BackgroundFill::Color(_) => None,
+        BackgroundFill::Gradient(gradient) => Some(rasterize_gradient(gradient, width, height)),
+        BackgroundFill::Image(BackgroundImage { url, fit }) => {
+            let data = cached_image(token, url.clone()).await?;
+            let source = image::load_from_memory(&data)
+                .map_err(|e| log::error!("Couldn't decode background image: {}", e))
+                .ok()?
+                .to_rgba8();
+
+            Some(fit_image(&source, width, height, fit))
+        }
+    }
+}
+
+/// Shows `fill` behind the sequence. Plain colors go through the background polygon,
+/// gradients and images are rasterized off the main path and drawn as a textured quad
+/// in the renderer's background pass, with the polygon made transparent.
+pub fn apply_background_fill(
+    canvas_renderer: &CanvasRenderer,
+    sequence_id: String,
+    fill: BackgroundFill,
+    token: String,
+) {
+    let generation = BACKGROUND_GENERATION.fetch_add(1, Ordering::SeqCst) + 1;
+    let background_id = Uuid::from_str(&sequence_id).expect("Couldn't convert string to uuid");
+
+    let editor_m = canvas_renderer.editor.clone();
+    let background = canvas_renderer.background.clone();
+    let redraw = canvas_renderer.redraw.clone();
+
+    let raster_size = {
+        let mut editor = editor_m.lock().unwrap();
+
+        let color = match &fill {
+            BackgroundFill::Color(fill) => {
+                rgb_to_wgpu(fill[0] as u8, fill[1] as u8, fill[2] as u8, fill[3] as f32)
+            }
+            _ => rgb_to_wgpu(255, 255, 255, 0.0),
+        };
+
+        editor.replace_background(background_id, color);
+
+        let camera = editor.camera.as_ref().expect("Couldn't get camera");
+        background_raster_size(
+            camera,
+            (canvas_renderer.window_width, canvas_renderer.window_height),
+        )
+    };
+
+    if let BackgroundFill::Color(_) = fill {
+        background.lock().unwrap().take();
+        redraw.request();
+        return;
+    }
+
+    spawn_local(async move {
+        let Some(raster) = rasterize_background(&fill, token, raster_size).await else {
+            return;
+        };
+        let Some(png) = encode_png(&raster) else {
+            return;
+        };
+
+        if BACKGROUND_GENERATION.load(Ordering::SeqCst) != generation {
+            info!("Background superseded, skipping...");
+            return;
+        }
+
+        let editor = editor_m.lock().unwrap();
+
+        let camera = editor.camera.as_ref().expect("Couldn't get camera");
+        let window_size = camera.window_size.clone();
+        let gpu_resources = editor
+            .gpu_resources
+            .as_ref()
+            .expect("Couldn't get gpu resources");
+        let model_bind_group_layout = editor
+            .model_bind_group_layout
+            .as_ref()
+            .expect("Couldn't get model bind group layout");
+        let group_bind_group_layout = editor
+            .group_bind_group_layout
+            .as_ref()
+            .expect("Couldn't get group bind group layout");
+
+        let image_config = StImageConfig {
+            id: Uuid::new_v4().to_string(),
+            name: "Background".to_string(),
+            // the quad is in scene units, the texture keeps the raster's pixels
+            dimensions: (BACKGROUND_WIDTH, BACKGROUND_HEIGHT),
+            url: "background.png".to_string(),
+            position: Point {
+                x: CANVAS_HORIZ_OFFSET + BACKGROUND_WIDTH as f32 / 2.0,
+                y: CANVAS_VERT_OFFSET + BACKGROUND_HEIGHT as f32 / 2.0,
+            },
+            layer: 0,
+        };
+
+        let background_image = StImage::new(
+            &gpu_resources.device,
+            &gpu_resources.queue,
+            image_config.url.clone(),
+            &png,
+            image_config.clone(),
+            &window_size,
+            model_bind_group_layout,
+            group_bind_group_layout,
+            0.0,
+            image_config.id.clone(),
+            background_id,
+        );
+
+        drop(editor);
+
+        background.lock().unwrap().replace(background_image);
+        redraw.request();
+
+        info!("Background applied!");
+    });
+}
@@ -0,0 +1,147 @@
+use stunts_engine::camera::Camera;
+use stunts_engine::editor::{Point, CANVAS_HORIZ_OFFSET, CANVAS_VERT_OFFSET};
+use web_sys::Element;
+
+// Three spaces are involved when pointing at the canvas:
+// pixel - the canvas drawing buffer, as the element is css-scaled
+// world - after undoing the camera pan and zoom, what text, images and videos are positioned in
+// scene - world minus the canvas offsets, what polygons are positioned in
+
+/// Drawing buffer pixel under a pointer event's client position
+pub fn client_to_pixel(canvas: &Element, client_x: i32, client_y: i32, camera: &Camera) -> Point {
+    let rect = canvas.get_bounding_client_rect();
+    let width = rect.width().max(1.0) as f32;
+    let height = rect.height().max(1.0) as f32;
+
+    Point {
+        x: (client_x as f32 - rect.left() as f32) * camera.window_size.width as f32 / width,
+        y: (client_y as f32 - rect.top() as f32) * camera.window_size.height as f32 / height,
+    }
+}
+
+pub fn pixel_to_world(pixel: Point, camera: &Camera) -> Point {
+    let center_x = camera.window_size.width as f32 / 2.0;
+    let center_y = camera.window_size.height as f32 / 2.0;
+    let zoom = camera.zoom.max(0.01);
+
+    Point {
+        x: (pixel.x - center_x) / zoom + center_x + camera.position.x,
+        y: (pixel.y - center_y) / zoom + center_y + camera.position.y,
+    }
+}
+
+pub fn world_to_pixel(world: Point, camera: &Camera) -> Point {
+    let center_x = camera.window_size.width as f32 / 2.0;
+    let center_y = camera.window_size.height as f32 / 2.0;
+
+    Point {
+        x: (world.x - camera.position.x - center_x) * camera.zoom + center_x,
+        y: (world.y - camera.position.y - center_y) * camera.zoom + center_y,
+    }
+}
+
+pub fn world_to_scene(world: Point) -> Point {
+    Point {
+        x: world.x - CANVAS_HORIZ_OFFSET,
+        y: world.y - CANVAS_VERT_OFFSET,
+    }
+}
+
+pub fn scene_to_world(scene: Point) -> Point {
+    Point {
+        x: scene.x + CANVAS_HORIZ_OFFSET,
+        y: scene.y + CANVAS_VERT_OFFSET,
+    }
+}
+
+pub fn pixel_to_scene(pixel: Point, camera: &Camera) -> Point {
+    world_to_scene(pixel_to_world(pixel, camera))
+}
+
+pub fn scene_to_pixel(scene: Point, camera: &Camera) -> Point {
+    world_to_pixel(scene_to_world(scene), camera)
+}
+
+#[cfg(test)]
+mod tests {
+    use stunts_engine::editor::WindowSize;
+
+    use super::*;
+
+    fn camera(zoom: f32, pan: (f32, f32)) -> Camera {
+        let mut camera = Camera::new(WindowSize {
+            width: 900,
+            height: 550,
+        });
+        camera.zoom = zoom;
+        camera.position.x = pan.0;
+        camera.position.y = pan.1;
+        camera
+    }
+
+    fn close(a: Point, b: Point) -> bool {
+        (a.x - b.x).abs() < 1e-3 && (a.y - b.y).abs() < 1e-3
+    }
+
+    #[test]
+    fn canvas_center_stays_put_when_zooming() {
+        let camera = camera(2.5, (0.0, 0.0));
+        let center = Point { x: 450.0, y: 275.0 };
+
+        assert!(close(pixel_to_world(center, &camera), center));
+    }
+
+    #[test]
+    fn zoom_scales_distances_from_the_center() {
+        let camera = camera(2.0, (0.0, 0.0));
+
+        let world = pixel_to_world(Point { x: 650.0, y: 275.0 }, &camera);
+
+        assert!(close(world, Point { x: 550.0, y: 275.0 }));
+    }
+
+    #[test]
+    fn pan_shifts_the_world_under_the_pointer() {
+        let camera = camera(1.0, (120.0, -40.0));
+
+        let world = pixel_to_world(Point { x: 100.0, y: 100.0 }, &camera);
+
+        assert!(close(world, Point { x: 220.0, y: 60.0 }));
+    }
+
+    #[test]
+    fn scene_is_world_minus_the_canvas_offsets() {
+        let world = Point { x: 500.0, y: 300.0 };
+        let scene = world_to_scene(world);
+
+        assert!(close(
+            scene,
+            Point {
+                x: 500.0 - CANVAS_HORIZ_OFFSET,
+                y: 300.0 - CANVAS_VERT_OFFSET,
+            }
+        ));
+        assert!(close(scene_to_world(scene), world));
+    }
+
+    #[test]
+    fn screen_and_scene_round_trip_when_zoomed_and_panned() {
+        let pixels = [
+            Point { x: 0.0, y: 0.0 },
+            Point { x: 450.0, y: 275.0 },
+            Point { x: 873.5, y: 12.25 },
+        ];
+
+        for camera in [
+            camera(0.5, (0.0, 0.0)),
+            camera(1.0, (75.0, 30.0)),
+            camera(3.0, (-210.0, 145.5)),
+        ] {
+            for pixel in pixels {
+                let scene = pixel_to_scene(pixel, &camera);
+
+                assert!(close(scene_to_pixel(scene, &camera), pixel));
+            }
+        }
+    }
+}
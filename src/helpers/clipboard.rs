@@ -0,0 +1,63 @@
+use serde::{Deserialize, Serialize};
+use wasm_bindgen_futures::JsFuture;
+use web_sys::DataTransfer;
+
+use crate::helpers::object_copy::CopiedObject;
+
+/// Other apps ignore this type, so copied objects only paste back into Stunts
+pub const CLIPBOARD_MIME: &str = "application/x-stunts-objects+json";
+
+/// What goes on the system clipboard when objects are copied
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ClipboardContents {
+    /// marks plain text as ours, for browsers that drop custom types
+    pub stunts_objects: u32,
+    /// sequence the objects were copied from
+    pub sequence_id: String,
+    pub objects: Vec<CopiedObject>,
+}
+
+impl ClipboardContents {
+    pub fn new(sequence_id: String, objects: Vec<CopiedObject>) -> Self {
+        ClipboardContents {
+            stunts_objects: 1,
+            sequence_id,
+            objects,
+        }
+    }
+
+    pub fn to_json(&self) -> Option<String> {
+        serde_json::to_string(self)
+            .map_err(|e| log::warn!("Couldn't serialize copied objects: {}", e))
+            .ok()
+    }
+
+    pub fn from_json(json: &str) -> Option<Self> {
+        serde_json::from_str(json).ok()
+    }
+}
+
+/// Puts copied objects on the clipboard of a copy or cut event
+pub fn write_clipboard(data: &DataTransfer, contents: &ClipboardContents) -> bool {
+    let Some(json) = contents.to_json() else {
+        return false;
+    };
+
+    data.set_data(CLIPBOARD_MIME, &json).is_ok() && data.set_data("text/plain", &json).is_ok()
+}
+
+/// Copied objects from the clipboard of a paste event, None if it holds something else
+pub fn read_clipboard(data: &DataTransfer) -> Option<ClipboardContents> {
+    [CLIPBOARD_MIME, "text/plain"]
+        .into_iter()
+        .filter_map(|format| data.get_data(format).ok())
+        .find_map(|json| ClipboardContents::from_json(&json))
+}
+
+/// Copied objects read outside a paste event, where browsers only hand out plain text
+pub async fn read_clipboard_text() -> Option<ClipboardContents> {
+    let clipboard = web_sys::window()?.navigator().clipboard();
+    let text = JsFuture::from(clipboard.read_text()).await.ok()?.as_string()?;
+
+    ClipboardContents::from_json(&text)
+}
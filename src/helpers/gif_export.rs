@@ -0,0 +1,456 @@
+use std::{borrow::Cow, sync::atomic::Ordering};
+
+use color_quant::NeuQuant;
+use log::info;
+
+use crate::canvas_renderer::FrameCapture;
+
+// frames sampled when building a global palette
+const PALETTE_SAMPLE_FRAMES: u32 = 8;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum PaletteMode {
+    Global,
+    PerFrame,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct GifExportSettings {
+    pub fps: u32,
+    /// output size relative to the canvas, 0.0 - 1.0
+    pub scale: f32,
+    /// 2 - 256
+    pub max_colors: usize,
+    pub dither: bool,
+    pub palette_mode: PaletteMode,
+    /// NeuQuant sampling factor, 1 is best and slowest, 30 is fastest
+    pub quantizer_speed: i32,
+    /// None loops forever
+    pub loop_count: Option<u16>,
+}
+
+impl Default for GifExportSettings {
+    fn default() -> Self {
+        Self {
+            fps: 12,
+            scale: 0.5,
+            max_colors: 256,
+            dither: true,
+            palette_mode: PaletteMode::Global,
+            quantizer_speed: 10,
+            loop_count: None,
+        }
+    }
+}
+
+impl GifExportSettings {
+    pub fn output_size(&self, width: u32, height: u32) -> (u16, u16) {
+        let scale = self.scale.clamp(0.05, 1.0);
+
+        (
+            ((width as f32 * scale).round() as u16).max(1),
+            ((height as f32 * scale).round() as u16).max(1),
+        )
+    }
+
+    pub fn frame_count(&self, duration_ms: i32) -> u32 {
+        ((duration_ms.max(0) as f32 / 1000.0) * self.fps as f32).ceil() as u32
+    }
+
+    /// GIF delays are in hundredths of a second. Each frame gets the rounding left over
+    /// from the ones before it, so playback keeps the sequence's duration.
+    pub fn frame_delay(&self, frame_index: u32) -> u16 {
+        let fps = self.fps.max(1) as u64;
+        // hundredths elapsed when a frame starts, rounded to the nearest
+        let start = |index: u64| (index * 100 + fps / 2) / fps;
+
+        (start(frame_index as u64 + 1) - start(frame_index as u64)) as u16
+    }
+
+    /// rough guess for the size-vs-quality controls, LZW results vary a lot with content
+    pub fn estimated_size_bytes(&self, width: u32, height: u32, duration_ms: i32) -> u64 {
+        let (out_width, out_height) = self.output_size(width, height);
+        let bits_per_pixel = (self.max_colors.max(2) as f32).log2().ceil();
+        // dithering noise compresses poorly
+        let compression = if self.dither { 0.6 } else { 0.35 };
+
+        let frame_bytes =
+            out_width as f32 * out_height as f32 * bits_per_pixel / 8.0 * compression;
+        let palette_bytes = match self.palette_mode {
+            PaletteMode::Global => 0.0,
+            PaletteMode::PerFrame => (self.max_colors * 3) as f32,
+        };
+
+        ((frame_bytes + palette_bytes) * self.frame_count(duration_ms) as f32) as u64
+    }
+}
+
+/// Box-filter downscale of tightly packed RGBA pixels
+pub fn downscale_rgba(
+    pixels: &[u8],
+    width: u32,
+    height: u32,
+    out_width: u32,
+    out_height: u32,
+) -> Vec<u8> {
+    if width == out_width && height == out_height {
+        return pixels.to_vec();
+    }
+
+    let mut output = Vec::with_capacity((out_width * out_height * 4) as usize);
+
+    for oy in 0..out_height {
+        let y0 = oy * height / out_height;
+        let y1 = ((oy + 1) * height / out_height).max(y0 + 1).min(height);
+
+        for ox in 0..out_width {
+            let x0 = ox * width / out_width;
+            let x1 = ((ox + 1) * width / out_width).max(x0 + 1).min(width);
+
+            let mut sum = [0u32; 4];
+            let mut count = 0u32;
+
+            for y in y0..y1 {
+                for x in x0..x1 {
+                    let i = ((y * width + x) * 4) as usize;
+                    sum[0] += pixels[i] as u32;
+                    sum[1] += pixels[i + 1] as u32;
+                    sum[2] += pixels[i + 2] as u32;
+                    sum[3] += pixels[i + 3] as u32;
+                    count += 1;
+                }
+            }
+
+            output.extend_from_slice(&[
+                (sum[0] / count) as u8,
+                (sum[1] / count) as u8,
+                (sum[2] / count) as u8,
+                (sum[3] / count) as u8,
+            ]);
+        }
+    }
+
+    output
+}
+
+pub fn build_quantizer(samples: &[u8], settings: &GifExportSettings) -> NeuQuant {
+    NeuQuant::new(
+        settings.quantizer_speed.clamp(1, 30),
+        settings.max_colors.clamp(2, 256),
+        samples,
+    )
+}
+
+/// Maps RGBA pixels to palette indices, optionally with Floyd-Steinberg error diffusion
+pub fn quantize_frame(
+    pixels: &[u8],
+    width: u32,
+    height: u32,
+    quantizer: &NeuQuant,
+    dither: bool,
+) -> Vec<u8> {
+    let palette = quantizer.color_map_rgb();
+    let width = width as usize;
+    let height = height as usize;
+
+    let mut indices = Vec::with_capacity(width * height);
+
+    if !dither {
+        for pixel in pixels.chunks(4) {
+            indices.push(quantizer.index_of(&[pixel[0], pixel[1], pixel[2], 255]) as u8);
+        }
+
+        return indices;
+    }
+
+    // error carried into the current and next row
+    let mut current_errors = vec![[0.0f32; 3]; width + 2];
+    let mut next_errors = vec![[0.0f32; 3]; width + 2];
+
+    for y in 0..height {
+        for x in 0..width {
+            let i = (y * width + x) * 4;
+            let error = current_errors[x + 1];
+
+            let wanted = [
+                (pixels[i] as f32 + error[0]).clamp(0.0, 255.0),
+                (pixels[i + 1] as f32 + error[1]).clamp(0.0, 255.0),
+                (pixels[i + 2] as f32 + error[2]).clamp(0.0, 255.0),
+            ];
+
+            let index =
+                quantizer.index_of(&[wanted[0] as u8, wanted[1] as u8, wanted[2] as u8, 255]);
+            indices.push(index as u8);
+
+            let chosen = &palette[index * 3..index * 3 + 3];
+
+            for c in 0..3 {
+                let diff = wanted[c] - chosen[c] as f32;
+
+                current_errors[x + 2][c] += diff * 7.0 / 16.0;
+                next_errors[x][c] += diff * 3.0 / 16.0;
+                next_errors[x + 1][c] += diff * 5.0 / 16.0;
+                next_errors[x + 2][c] += diff * 1.0 / 16.0;
+            }
+        }
+
+        std::mem::swap(&mut current_errors, &mut next_errors);
+        next_errors.iter_mut().for_each(|e| *e = [0.0; 3]);
+    }
+
+    indices
+}
+
+fn frame_time_s(frame_index: u32, fps: u32) -> f32 {
+    frame_index as f32 / fps.max(1) as f32
+}
+
+/// Renders the open sequence frame by frame at the reduced GIF frame rate and encodes it.
+/// Setting `capture.cancelled` stops the export at the next frame and returns `Ok(None)`.
+/// `on_progress` receives 0.0 - 1.0.
+pub async fn export_sequence_gif(
+    capture: &FrameCapture,
+    duration_ms: i32,
+    settings: &GifExportSettings,
+    on_progress: impl Fn(f32),
+) -> Result<Option<Vec<u8>>, String> {
+    let (out_width, out_height) = settings.output_size(capture.width, capture.height);
+    let frame_count = settings.frame_count(duration_ms).max(1);
+
+    // the live canvas would step the same editor and buffers between our frames
+    let _session = capture.begin();
+
+    info!(
+        "Exporting GIF {}x{} with {} frames...",
+        out_width, out_height, frame_count
+    );
+
+    let global_quantizer = match settings.palette_mode {
+        PaletteMode::Global => {
+            // sample a spread of frames so late scene changes still get colors
+            let mut samples = Vec::new();
+            let sample_frames = PALETTE_SAMPLE_FRAMES.min(frame_count);
+
+            for s in 0..sample_frames {
+                if capture.cancelled.load(Ordering::SeqCst) {
+                    return Ok(None);
+                }
+
+                let frame_index = s * frame_count / sample_frames;
+                let pixels = capture
+                    .capture_frame(frame_time_s(frame_index, settings.fps))
+                    .await?;
+                let pixels = downscale_rgba(
+                    &pixels,
+                    capture.width,
+                    capture.height,
+                    out_width as u32,
+                    out_height as u32,
+                );
+                samples.extend_from_slice(&pixels);
+            }
+
+            Some(build_quantizer(&samples, settings))
+        }
+        PaletteMode::PerFrame => None,
+    };
+
+    let global_palette = global_quantizer
+        .as_ref()
+        .map(|q| q.color_map_rgb())
+        .unwrap_or_default();
+
+    let mut output = Vec::new();
+
+    {
+        let mut encoder = gif::Encoder::new(&mut output, out_width, out_height, &global_palette)
+            .map_err(|e| format!("Couldn't create GIF encoder: {}", e))?;
+
+        encoder
+            .set_repeat(match settings.loop_count {
+                Some(count) => gif::Repeat::Finite(count),
+                None => gif::Repeat::Infinite,
+            })
+            .map_err(|e| format!("Couldn't set GIF loop: {}", e))?;
+
+        for frame_index in 0..frame_count {
+            if capture.cancelled.load(Ordering::SeqCst) {
+                info!("GIF export cancelled at frame {}", frame_index);
+                return Ok(None);
+            }
+
+            let pixels = capture
+                .capture_frame(frame_time_s(frame_index, settings.fps))
+                .await?;
+            let pixels = downscale_rgba(
+                &pixels,
+                capture.width,
+                capture.height,
+                out_width as u32,
+                out_height as u32,
+            );
+
+            let frame_quantizer;
+            let quantizer = match &global_quantizer {
+                Some(quantizer) => quantizer,
+                None => {
+                    frame_quantizer = build_quantizer(&pixels, settings);
+                    &frame_quantizer
+                }
+            };
+
+            let indices = quantize_frame(
+                &pixels,
+                out_width as u32,
+                out_height as u32,
+                quantizer,
+                settings.dither,
+            );
+
+            let frame = gif::Frame {
+                width: out_width,
+                height: out_height,
+                delay: settings.frame_delay(frame_index),
+                buffer: Cow::Owned(indices),
+                palette: match settings.palette_mode {
+                    PaletteMode::Global => None,
+                    PaletteMode::PerFrame => Some(quantizer.color_map_rgb()),
+                },
+                ..gif::Frame::default()
+            };
+
+            encoder
+                .write_frame(&frame)
+                .map_err(|e| format!("Couldn't write GIF frame: {}", e))?;
+
+            on_progress((frame_index + 1) as f32 / frame_count as f32);
+        }
+    }
+
+    info!("GIF exported, {} bytes", output.len());
+
+    Ok(Some(output))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn settings(fps: u32) -> GifExportSettings {
+        GifExportSettings {
+            fps,
+            ..GifExportSettings::default()
+        }
+    }
+
+    #[test]
+    fn frame_count_rounds_partial_frames_up() {
+        assert_eq!(settings(12).frame_count(1000), 12);
+        assert_eq!(settings(12).frame_count(1001), 13);
+        assert_eq!(settings(10).frame_count(250), 3);
+        assert_eq!(settings(12).frame_count(0), 0);
+        assert_eq!(settings(12).frame_count(-500), 0);
+    }
+
+    fn total_delay(settings: &GifExportSettings, frame_count: u32) -> u32 {
+        (0..frame_count)
+            .map(|i| settings.frame_delay(i) as u32)
+            .sum()
+    }
+
+    #[test]
+    fn frame_delay_carries_rounding_between_frames() {
+        assert_eq!(settings(10).frame_delay(0), 10);
+        // 8.33 hundredths a frame
+        assert_eq!(
+            (0..6)
+                .map(|i| settings(12).frame_delay(i))
+                .collect::<Vec<_>>(),
+            vec![8, 9, 8, 8, 9, 8]
+        );
+        // a zero frame rate is treated as one frame a second
+        assert_eq!(settings(0).frame_delay(0), 100);
+    }
+
+    #[test]
+    fn frame_delays_add_up_to_the_duration() {
+        for fps in [10, 12, 15, 24, 30] {
+            let settings = settings(fps);
+
+            // one second, then ten
+            assert_eq!(total_delay(&settings, fps), 100, "{} fps", fps);
+            assert_eq!(total_delay(&settings, fps * 10), 1000, "{} fps", fps);
+        }
+    }
+
+    #[test]
+    fn downscale_averages_each_block() {
+        // 2x2 -> 1x1, every channel averaged
+        let pixels = [
+            0, 0, 0, 255, //
+            100, 0, 0, 255, //
+            0, 200, 0, 255, //
+            0, 0, 40, 255,
+        ];
+
+        assert_eq!(downscale_rgba(&pixels, 2, 2, 1, 1), vec![25, 50, 10, 255]);
+    }
+
+    #[test]
+    fn downscale_keeps_same_size_untouched() {
+        let pixels = (0..4 * 3 * 2).map(|i| i as u8).collect::<Vec<_>>();
+
+        assert_eq!(downscale_rgba(&pixels, 3, 2, 3, 2), pixels);
+    }
+
+    #[test]
+    fn downscale_output_has_requested_size() {
+        let pixels = vec![128; 10 * 6 * 4];
+
+        let output = downscale_rgba(&pixels, 10, 6, 3, 2);
+
+        assert_eq!(output.len(), 3 * 2 * 4);
+        assert!(output.iter().all(|c| *c == 128));
+    }
+
+    // a horizontal gradient with plenty of distinct colors
+    fn gradient(width: u32, height: u32) -> Vec<u8> {
+        (0..height)
+            .flat_map(|_| (0..width).flat_map(|x| [(x * 255 / width) as u8, 64, 200, 255]))
+            .collect()
+    }
+
+    #[test]
+    fn quantize_frame_stays_inside_palette() {
+        let (width, height) = (32, 8);
+        let pixels = gradient(width, height);
+
+        for (max_colors, dither) in [(16, false), (16, true), (4, true)] {
+            let settings = GifExportSettings {
+                max_colors,
+                quantizer_speed: 1,
+                ..GifExportSettings::default()
+            };
+            let quantizer = build_quantizer(&pixels, &settings);
+
+            assert_eq!(quantizer.color_map_rgb().len(), max_colors * 3);
+
+            let indices = quantize_frame(&pixels, width, height, &quantizer, dither);
+
+            assert_eq!(indices.len(), (width * height) as usize);
+            assert!(indices.iter().all(|i| (*i as usize) < max_colors));
+        }
+    }
+
+    #[test]
+    fn build_quantizer_clamps_palette_size() {
+        let pixels = gradient(16, 4);
+
+        let settings = GifExportSettings {
+            max_colors: 1000,
+            ..GifExportSettings::default()
+        };
+
+        assert_eq!(build_quantizer(&pixels, &settings).color_map_rgb().len(), 256 * 3);
+    }
+}
@@ -0,0 +1,512 @@
+use std::sync::Arc;
+use std::time::Duration;
+use stunts_engine::animations::{
+    AnimationProperty, EasingType, KeyType, KeyframeValue, PathType, SavedGroupConfig, Sequence,
+    UIKeyframe,
+};
+use stunts_engine::editor::{Editor, Point};
+use uuid::Uuid;
+use wgpu::util::DeviceExt;
+
+use crate::helpers::alignment::union_extent;
+use crate::helpers::object_transform::object_bounds;
+
+/// Where a group's animation has it, applied on top of its children's own animation
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct GroupPose {
+    /// pixels, away from where the children were placed
+    pub position: (f32, f32),
+    /// degrees, clockwise
+    pub rotation: f32,
+    /// 1.0 keeps the children's own size
+    pub scale: f32,
+    /// 0.0 - 1.0
+    pub opacity: f32,
+}
+
+impl Default for GroupPose {
+    fn default() -> Self {
+        GroupPose {
+            position: (0.0, 0.0),
+            rotation: 0.0,
+            scale: 1.0,
+            opacity: 1.0,
+        }
+    }
+}
+
+/// A group of objects that starts out at rest
+pub fn new_group(name: String, object_ids: Vec<String>) -> SavedGroupConfig {
+    let mut group = SavedGroupConfig {
+        id: Uuid::new_v4().to_string(),
+        name,
+        object_ids,
+        properties: Vec::new(),
+    };
+
+    set_group_keyframes(&mut group, &[(Duration::ZERO, GroupPose::default())]);
+
+    group
+}
+
+/// The group holding an object, objects are in one group at most
+pub fn group_of<'a>(sequence: &'a Sequence, object_id: &str) -> Option<&'a SavedGroupConfig> {
+    sequence
+        .groups
+        .iter()
+        .find(|g| g.object_ids.iter().any(|id| id == object_id))
+}
+
+fn keyframe(time: Duration, value: KeyframeValue) -> UIKeyframe {
+    UIKeyframe {
+        id: Uuid::new_v4().to_string(),
+        time,
+        value,
+        easing: EasingType::EaseInOut,
+        path_type: PathType::Linear,
+        key_type: KeyType::Frame,
+    }
+}
+
+/// Replaces a group's animation with one keyframe per pose, for every property
+pub fn set_group_keyframes(group: &mut SavedGroupConfig, poses: &[(Duration, GroupPose)]) {
+    let property = |name: &str, value: fn(&GroupPose) -> KeyframeValue| AnimationProperty {
+        name: name.to_string(),
+        property_path: name.to_lowercase(),
+        children: Vec::new(),
+        keyframes: poses
+            .iter()
+            .map(|(time, pose)| keyframe(*time, value(pose)))
+            .collect(),
+        depth: 0,
+    };
+
+    group.properties = vec![
+        property("Position", |p| {
+            KeyframeValue::Position([p.position.0.round() as i32, p.position.1.round() as i32])
+        }),
+        property("Rotation", |p| {
+            KeyframeValue::Rotation(p.rotation.round() as i32)
+        }),
+        property("Scale", |p| {
+            KeyframeValue::Scale((p.scale * 100.0).round() as i32)
+        }),
+        property("Opacity", |p| {
+            KeyframeValue::Opacity((p.opacity * 100.0).round() as i32)
+        }),
+    ];
+}
+
+/// A pose for every time any of the group's properties has a keyframe
+pub fn group_keyframes(group: &SavedGroupConfig) -> Vec<(Duration, GroupPose)> {
+    let mut times = group
+        .properties
+        .iter()
+        .flat_map(|p| p.keyframes.iter().map(|k| k.time))
+        .collect::<Vec<_>>();
+    times.sort();
+    times.dedup();
+
+    times
+        .into_iter()
+        .map(|time| (time, group_pose(group, time)))
+        .collect()
+}
+
+fn eased(easing: &EasingType, t: f32) -> f32 {
+    match easing {
+        EasingType::EaseInOut => t * t * (3.0 - 2.0 * t),
+        _ => t,
+    }
+}
+
+// keyframe values as plain numbers, the second one is only used by positions
+fn keyframe_numbers(value: &KeyframeValue) -> [f32; 2] {
+    match value {
+        KeyframeValue::Position(p) => [p[0] as f32, p[1] as f32],
+        KeyframeValue::Rotation(r) => [*r as f32, 0.0],
+        KeyframeValue::Scale(s) => [*s as f32, 0.0],
+        KeyframeValue::Opacity(o) => [*o as f32, 0.0],
+        _ => [0.0, 0.0],
+    }
+}
+
+/// A property's value at `time`, holding the first and last keyframes outside of them
+fn sample_property(property: &AnimationProperty, time: Duration) -> Option<[f32; 2]> {
+    let mut keyframes = property.keyframes.iter().collect::<Vec<_>>();
+    keyframes.sort_by_key(|k| k.time);
+
+    let next = keyframes.iter().position(|k| k.time > time);
+
+    let (from, to) = match next {
+        None => return keyframes.last().map(|k| keyframe_numbers(&k.value)),
+        Some(0) => return keyframes.first().map(|k| keyframe_numbers(&k.value)),
+        Some(i) => (keyframes[i - 1], keyframes[i]),
+    };
+
+    let span = (to.time - from.time).as_secs_f32();
+    let t = eased(&from.easing, (time - from.time).as_secs_f32() / span);
+    let (a, b) = (keyframe_numbers(&from.value), keyframe_numbers(&to.value));
+
+    Some([a[0] + (b[0] - a[0]) * t, a[1] + (b[1] - a[1]) * t])
+}
+
+pub fn group_pose(group: &SavedGroupConfig, time: Duration) -> GroupPose {
+    let mut pose = GroupPose::default();
+
+    for property in &group.properties {
+        let Some(value) = sample_property(property, time) else {
+            continue;
+        };
+
+        match property.property_path.as_str() {
+            "position" => pose.position = (value[0], value[1]),
+            "rotation" => pose.rotation = value[0],
+            "scale" => pose.scale = value[0] / 100.0,
+            "opacity" => pose.opacity = (value[0] / 100.0).clamp(0.0, 1.0),
+            _ => {}
+        }
+    }
+
+    pose
+}
+
+/// Column-major matrix that rotates and scales about `pivot`, then moves by the pose's position
+pub fn group_matrix(pose: &GroupPose, pivot: Point) -> [[f32; 4]; 4] {
+    let (sin, cos) = pose.rotation.to_radians().sin_cos();
+    let (a, b) = (cos * pose.scale, sin * pose.scale);
+
+    let x = pivot.x + pose.position.0 - (a * pivot.x - b * pivot.y);
+    let y = pivot.y + pose.position.1 - (b * pivot.x + a * pivot.y);
+
+    [
+        [a, b, 0.0, 0.0],
+        [-b, a, 0.0, 0.0],
+        [0.0, 0.0, 1.0, 0.0],
+        [x, y, 0.0, 1.0],
+    ]
+}
+
+const IDENTITY: [[f32; 4]; 4] = [
+    [1.0, 0.0, 0.0, 0.0],
+    [0.0, 1.0, 0.0, 0.0],
+    [0.0, 0.0, 1.0, 0.0],
+    [0.0, 0.0, 0.0, 1.0],
+];
+
+// opacity padded out to 16 bytes for the uniform
+fn style_contents(opacity: f32) -> [f32; 4] {
+    [opacity, 0.0, 0.0, 0.0]
+}
+
+/// A group on the GPU, its children are drawn with its transform and opacity
+struct SceneGroup {
+    group: SavedGroupConfig,
+    // center of the children as placed, what the group rotates and scales about
+    pivot: Point,
+    buffer: wgpu::Buffer,
+    bind_group: wgpu::BindGroup,
+    style_buffer: wgpu::Buffer,
+    style_bind_group: wgpu::BindGroup,
+}
+
+/// Groups of the open sequence, ready to bind while drawing
+pub struct SceneGroups {
+    style_layout: Arc<wgpu::BindGroupLayout>,
+    default_style: wgpu::BindGroup,
+    groups: Vec<SceneGroup>,
+}
+
+impl SceneGroups {
+    pub fn new(device: &wgpu::Device, style_layout: Arc<wgpu::BindGroupLayout>) -> Self {
+        let default_style_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Default Group Style Buffer"),
+            contents: bytemuck::cast_slice(&style_contents(1.0)),
+            usage: wgpu::BufferUsages::UNIFORM,
+        });
+
+        let default_style = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: &style_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: default_style_buffer.as_entire_binding(),
+            }],
+            label: Some("Default Group Style"),
+        });
+
+        SceneGroups {
+            style_layout,
+            default_style,
+            groups: Vec::new(),
+        }
+    }
+
+    /// Catches up with the groups of `sequence`, reusing buffers of groups that are still there
+    pub fn sync(&mut self, editor: &Editor, sequence: &Sequence) {
+        let (Some(gpu_resources), Some(group_layout)) = (
+            editor.gpu_resources.as_ref(),
+            editor.group_bind_group_layout.as_ref(),
+        ) else {
+            return;
+        };
+        let device = &gpu_resources.device;
+
+        let mut existing = std::mem::take(&mut self.groups);
+
+        for group in &sequence.groups {
+            let children = group
+                .object_ids
+                .iter()
+                .filter_map(|id| Uuid::parse_str(id).ok())
+                .filter_map(|id| object_bounds(editor, id))
+                .collect::<Vec<_>>();
+
+            let pivot = union_extent(&children)
+                .map(|(min, max)| Point {
+                    x: (min.x + max.x) / 2.0,
+                    y: (min.y + max.y) / 2.0,
+                })
+                .unwrap_or(Point { x: 0.0, y: 0.0 });
+
+            if let Some(i) = existing.iter().position(|g| g.group.id == group.id) {
+                let mut scene_group = existing.remove(i);
+                scene_group.group = group.clone();
+                scene_group.pivot = pivot;
+                self.groups.push(scene_group);
+                continue;
+            }
+
+            let buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("Group Buffer"),
+                contents: bytemuck::cast_slice(&IDENTITY),
+                usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            });
+            let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+                layout: group_layout,
+                entries: &[wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: buffer.as_entire_binding(),
+                }],
+                label: Some("Group Bind Group"),
+            });
+
+            let style_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("Group Style Buffer"),
+                contents: bytemuck::cast_slice(&style_contents(1.0)),
+                usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            });
+            let style_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+                layout: &self.style_layout,
+                entries: &[wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: style_buffer.as_entire_binding(),
+                }],
+                label: Some("Group Style Bind Group"),
+            });
+
+            self.groups.push(SceneGroup {
+                group: group.clone(),
+                pivot,
+                buffer,
+                bind_group,
+                style_buffer,
+                style_bind_group,
+            });
+        }
+    }
+
+    /// Writes where every group is at `time`. Without a time (not playing) groups only
+    /// keep their starting opacity, so objects stay where the selection handles put them.
+    pub fn update(&self, queue: &wgpu::Queue, time: Option<Duration>) {
+        for scene_group in &self.groups {
+            let pose = group_pose(&scene_group.group, time.unwrap_or(Duration::ZERO));
+            let matrix = match time {
+                Some(_) => group_matrix(&pose, scene_group.pivot),
+                None => IDENTITY,
+            };
+
+            queue.write_buffer(&scene_group.buffer, 0, bytemuck::cast_slice(&matrix));
+            queue.write_buffer(
+                &scene_group.style_buffer,
+                0,
+                bytemuck::cast_slice(&style_contents(pose.opacity)),
+            );
+        }
+    }
+
+    /// Style for anything outside a group
+    pub fn default_style(&self) -> &wgpu::BindGroup {
+        &self.default_style
+    }
+
+    /// Group transform and style to draw an object with, `own` being the object's
+    /// own (identity) group binding for when it isn't grouped
+    pub fn bindings_for<'a>(
+        &'a self,
+        object_id: &str,
+        own: &'a wgpu::BindGroup,
+    ) -> (&'a wgpu::BindGroup, &'a wgpu::BindGroup) {
+        self.groups
+            .iter()
+            .find(|g| g.group.object_ids.iter().any(|id| id == object_id))
+            .map(|g| (&g.bind_group, &g.style_bind_group))
+            .unwrap_or((own, &self.default_style))
+    }
+
+    pub fn clear(&mut self) {
+        self.groups.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(a: f32, b: f32) -> bool {
+        (a - b).abs() < 1e-3
+    }
+
+    // where a column-major matrix puts a point
+    fn apply(matrix: &[[f32; 4]; 4], point: Point) -> Point {
+        Point {
+            x: matrix[0][0] * point.x + matrix[1][0] * point.y + matrix[3][0],
+            y: matrix[0][1] * point.x + matrix[1][1] * point.y + matrix[3][1],
+        }
+    }
+
+    fn animated_group() -> SavedGroupConfig {
+        let mut group = new_group("Group".to_string(), vec!["a".to_string()]);
+
+        set_group_keyframes(
+            &mut group,
+            &[
+                (Duration::ZERO, GroupPose::default()),
+                (
+                    Duration::from_secs(2),
+                    GroupPose {
+                        position: (100.0, -40.0),
+                        rotation: 90.0,
+                        scale: 2.0,
+                        opacity: 0.0,
+                    },
+                ),
+            ],
+        );
+
+        group
+    }
+
+    #[test]
+    fn new_groups_start_at_rest() {
+        let group = new_group("Group".to_string(), vec!["a".to_string(), "b".to_string()]);
+
+        assert_eq!(
+            group_keyframes(&group),
+            vec![(Duration::ZERO, GroupPose::default())]
+        );
+    }
+
+    #[test]
+    fn keyframes_round_trip() {
+        let group = animated_group();
+
+        let keyframes = group_keyframes(&group);
+
+        assert_eq!(keyframes.len(), 2);
+        assert_eq!(keyframes[1].0, Duration::from_secs(2));
+        assert_eq!(keyframes[1].1.position, (100.0, -40.0));
+        assert_eq!(keyframes[1].1.rotation, 90.0);
+        assert_eq!(keyframes[1].1.scale, 2.0);
+        assert_eq!(keyframes[1].1.opacity, 0.0);
+    }
+
+    #[test]
+    fn poses_ease_between_keyframes() {
+        let group = animated_group();
+
+        // eased in and out, so halfway in time is halfway there
+        let pose = group_pose(&group, Duration::from_secs(1));
+
+        assert!(close(pose.position.0, 50.0) && close(pose.position.1, -20.0));
+        assert!(close(pose.rotation, 45.0));
+        assert!(close(pose.scale, 1.5));
+        assert!(close(pose.opacity, 0.5));
+
+        // slower at the start
+        let early = group_pose(&group, Duration::from_millis(500));
+        assert!(early.position.0 < 25.0);
+    }
+
+    #[test]
+    fn poses_hold_outside_the_keyframes() {
+        let group = animated_group();
+
+        assert_eq!(group_pose(&group, Duration::ZERO), GroupPose::default());
+        assert_eq!(
+            group_pose(&group, Duration::from_secs(5)),
+            group_pose(&group, Duration::from_secs(2))
+        );
+    }
+
+    #[test]
+    fn groups_without_keyframes_are_at_rest() {
+        let group = SavedGroupConfig {
+            id: Uuid::new_v4().to_string(),
+            name: "Group".to_string(),
+            object_ids: Vec::new(),
+            properties: Vec::new(),
+        };
+
+        assert_eq!(
+            group_pose(&group, Duration::from_secs(1)),
+            GroupPose::default()
+        );
+    }
+
+    #[test]
+    fn resting_groups_leave_their_children_alone() {
+        let matrix = group_matrix(&GroupPose::default(), Point { x: 300.0, y: 200.0 });
+
+        assert_eq!(matrix, IDENTITY);
+    }
+
+    #[test]
+    fn groups_rotate_and_scale_about_their_pivot() {
+        let pivot = Point { x: 100.0, y: 50.0 };
+        let pose = GroupPose {
+            position: (10.0, 0.0),
+            rotation: 90.0,
+            scale: 2.0,
+            opacity: 1.0,
+        };
+        let matrix = group_matrix(&pose, pivot);
+
+        // the pivot only moves by the position
+        let moved_pivot = apply(&matrix, pivot);
+        assert!(close(moved_pivot.x, 110.0) && close(moved_pivot.y, 50.0));
+
+        // a point right of the pivot ends up twice as far below it, rotating clockwise
+        let moved = apply(&matrix, Point { x: 110.0, y: 50.0 });
+        assert!(close(moved.x, 110.0) && close(moved.y, 70.0));
+    }
+
+    #[test]
+    fn objects_are_found_in_their_group() {
+        let group = new_group("Group".to_string(), vec!["a".to_string()]);
+        let sequence = Sequence {
+            id: Uuid::new_v4().to_string(),
+            name: "Sequence".to_string(),
+            background_fill: None,
+            duration_ms: 1000,
+            active_polygons: Vec::new(),
+            polygon_motion_paths: Vec::new(),
+            active_text_items: Vec::new(),
+            active_image_items: Vec::new(),
+            active_video_items: Vec::new(),
+            groups: vec![group.clone()],
+        };
+
+        assert_eq!(group_of(&sequence, "a").map(|g| &g.id), Some(&group.id));
+        assert!(group_of(&sequence, "b").is_none());
+    }
+}
@@ -0,0 +1,46 @@
+use crate::editor_state::StoredEdit;
+
+// session storage is only a few megabytes, motion paths of deleted objects add up
+pub const MAX_STORED_EDITS: usize = 100;
+
+fn history_key(project_id: &str) -> String {
+    format!("stunts-history-{}", project_id)
+}
+
+fn session_storage() -> Option<web_sys::Storage> {
+    web_sys::window()?.session_storage().ok().flatten()
+}
+
+/// Keeps the undo history of a project for the rest of the browser session
+pub fn save_session_history(project_id: &str, history: &[StoredEdit]) {
+    let Some(storage) = session_storage() else {
+        return;
+    };
+
+    let json = match serde_json::to_string(history) {
+        Ok(json) => json,
+        Err(e) => {
+            log::warn!("Couldn't serialize history: {}", e);
+            return;
+        }
+    };
+
+    if storage.set_item(&history_key(project_id), &json).is_err() {
+        log::warn!("Couldn't store history, session storage may be full");
+    }
+}
+
+pub fn load_session_history(project_id: &str) -> Option<Vec<StoredEdit>> {
+    let json = session_storage()?
+        .get_item(&history_key(project_id))
+        .ok()
+        .flatten()?;
+
+    match serde_json::from_str(&json) {
+        Ok(history) => Some(history),
+        Err(e) => {
+            log::warn!("Couldn't read stored history: {}", e);
+            None
+        }
+    }
+}
@@ -0,0 +1,373 @@
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::JsCast;
+use web_sys::{Event, HtmlElement, KeyboardEvent};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum EditorAction {
+    Undo,
+    Redo,
+    Delete,
+    Duplicate,
+    Copy,
+    Cut,
+    Paste,
+    SelectAll,
+    PlayPause,
+    NudgeLeft,
+    NudgeRight,
+    NudgeUp,
+    NudgeDown,
+    NudgeLeftFar,
+    NudgeRightFar,
+    NudgeUpFar,
+    NudgeDownFar,
+    ShowShortcuts,
+}
+
+impl EditorAction {
+    /// Every action, in the order the help overlay lists them
+    pub const ALL: [EditorAction; 18] = [
+        EditorAction::Undo,
+        EditorAction::Redo,
+        EditorAction::Delete,
+        EditorAction::Duplicate,
+        EditorAction::Copy,
+        EditorAction::Cut,
+        EditorAction::Paste,
+        EditorAction::SelectAll,
+        EditorAction::PlayPause,
+        EditorAction::NudgeLeft,
+        EditorAction::NudgeRight,
+        EditorAction::NudgeUp,
+        EditorAction::NudgeDown,
+        EditorAction::NudgeLeftFar,
+        EditorAction::NudgeRightFar,
+        EditorAction::NudgeUpFar,
+        EditorAction::NudgeDownFar,
+        EditorAction::ShowShortcuts,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            EditorAction::Undo => "Undo",
+            EditorAction::Redo => "Redo",
+            EditorAction::Delete => "Delete selection",
+            EditorAction::Duplicate => "Duplicate selection",
+            EditorAction::Copy => "Copy",
+            EditorAction::Cut => "Cut",
+            EditorAction::Paste => "Paste",
+            EditorAction::SelectAll => "Select all",
+            EditorAction::PlayPause => "Play / pause",
+            EditorAction::NudgeLeft => "Nudge left 1px",
+            EditorAction::NudgeRight => "Nudge right 1px",
+            EditorAction::NudgeUp => "Nudge up 1px",
+            EditorAction::NudgeDown => "Nudge down 1px",
+            EditorAction::NudgeLeftFar => "Nudge left 10px",
+            EditorAction::NudgeRightFar => "Nudge right 10px",
+            EditorAction::NudgeUpFar => "Nudge up 10px",
+            EditorAction::NudgeDownFar => "Nudge down 10px",
+            EditorAction::ShowShortcuts => "Show shortcuts",
+        }
+    }
+
+    /// How far a nudge moves the selection, None for other actions
+    pub fn nudge(&self) -> Option<(i32, i32)> {
+        match self {
+            EditorAction::NudgeLeft => Some((-1, 0)),
+            EditorAction::NudgeRight => Some((1, 0)),
+            EditorAction::NudgeUp => Some((0, -1)),
+            EditorAction::NudgeDown => Some((0, 1)),
+            EditorAction::NudgeLeftFar => Some((-10, 0)),
+            EditorAction::NudgeRightFar => Some((10, 0)),
+            EditorAction::NudgeUpFar => Some((0, -10)),
+            EditorAction::NudgeDownFar => Some((0, 10)),
+            _ => None,
+        }
+    }
+
+    fn default_bindings(&self) -> Vec<KeyBinding> {
+        match self {
+            EditorAction::Undo => vec![KeyBinding::primary("z")],
+            EditorAction::Redo => vec![
+                KeyBinding::primary("z").with_shift(),
+                KeyBinding::primary("y"),
+            ],
+            EditorAction::Delete => vec![KeyBinding::key("Delete"), KeyBinding::key("Backspace")],
+            EditorAction::Duplicate => vec![KeyBinding::primary("d")],
+            EditorAction::Copy => vec![KeyBinding::primary("c")],
+            EditorAction::Cut => vec![KeyBinding::primary("x")],
+            EditorAction::Paste => vec![KeyBinding::primary("v")],
+            EditorAction::SelectAll => vec![KeyBinding::primary("a")],
+            EditorAction::PlayPause => vec![KeyBinding::key(" ")],
+            EditorAction::NudgeLeft => vec![KeyBinding::key("ArrowLeft")],
+            EditorAction::NudgeRight => vec![KeyBinding::key("ArrowRight")],
+            EditorAction::NudgeUp => vec![KeyBinding::key("ArrowUp")],
+            EditorAction::NudgeDown => vec![KeyBinding::key("ArrowDown")],
+            EditorAction::NudgeLeftFar => vec![KeyBinding::key("ArrowLeft").with_shift()],
+            EditorAction::NudgeRightFar => vec![KeyBinding::key("ArrowRight").with_shift()],
+            EditorAction::NudgeUpFar => vec![KeyBinding::key("ArrowUp").with_shift()],
+            EditorAction::NudgeDownFar => vec![KeyBinding::key("ArrowDown").with_shift()],
+            EditorAction::ShowShortcuts => vec![KeyBinding::key("?").with_shift()],
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KeyBinding {
+    /// `KeyboardEvent.key`, lowercased when it's a single character
+    pub key: String,
+    /// Ctrl, or Cmd on macOS
+    pub primary: bool,
+    pub shift: bool,
+    pub alt: bool,
+}
+
+fn normalize_key(key: &str) -> String {
+    if key.chars().count() == 1 {
+        key.to_lowercase()
+    } else {
+        key.to_string()
+    }
+}
+
+impl KeyBinding {
+    pub fn key(key: &str) -> Self {
+        KeyBinding {
+            key: normalize_key(key),
+            primary: false,
+            shift: false,
+            alt: false,
+        }
+    }
+
+    pub fn primary(key: &str) -> Self {
+        KeyBinding {
+            primary: true,
+            ..KeyBinding::key(key)
+        }
+    }
+
+    pub fn with_shift(self) -> Self {
+        KeyBinding {
+            shift: true,
+            ..self
+        }
+    }
+
+    /// The binding a key press would trigger, None while only modifiers are held
+    pub fn from_event(ev: &KeyboardEvent) -> Option<Self> {
+        let key = ev.key();
+
+        if matches!(key.as_str(), "Control" | "Meta" | "Shift" | "Alt" | "CapsLock") {
+            return None;
+        }
+
+        Some(KeyBinding {
+            key: normalize_key(&key),
+            primary: ev.ctrl_key() || ev.meta_key(),
+            shift: ev.shift_key(),
+            alt: ev.alt_key(),
+        })
+    }
+
+    pub fn matches(&self, ev: &KeyboardEvent) -> bool {
+        KeyBinding::from_event(ev).as_ref() == Some(self)
+    }
+
+    /// Ctrl+C, Ctrl+X and Ctrl+V, which the browser turns into clipboard events by itself
+    pub fn is_native_clipboard(&self) -> bool {
+        self.primary && !self.shift && !self.alt && matches!(self.key.as_str(), "c" | "x" | "v")
+    }
+
+    /// e.g. "Ctrl+Shift+Z"
+    pub fn label(&self) -> String {
+        let mut parts = Vec::new();
+
+        if self.primary {
+            parts.push("Ctrl".to_string());
+        }
+        if self.alt {
+            parts.push("Alt".to_string());
+        }
+        // the character already says shift was held
+        if self.shift && self.key != "?" {
+            parts.push("Shift".to_string());
+        }
+
+        parts.push(match self.key.as_str() {
+            " " => "Space".to_string(),
+            "ArrowLeft" => "←".to_string(),
+            "ArrowRight" => "→".to_string(),
+            "ArrowUp" => "↑".to_string(),
+            "ArrowDown" => "↓".to_string(),
+            key => key.to_uppercase(),
+        });
+
+        parts.join("+")
+    }
+}
+
+/// Shortcuts of the project page. Only remapped actions are stored,
+/// everything else keeps its default bindings.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Keymap {
+    pub custom: Vec<(EditorAction, KeyBinding)>,
+}
+
+impl Keymap {
+    pub fn bindings(&self, action: EditorAction) -> Vec<KeyBinding> {
+        match self.custom.iter().find(|(a, _)| *a == action) {
+            Some((_, binding)) => vec![binding.clone()],
+            None => action.default_bindings(),
+        }
+    }
+
+    pub fn action_for(&self, ev: &KeyboardEvent) -> Option<EditorAction> {
+        EditorAction::ALL
+            .into_iter()
+            .find(|action| self.bindings(*action).iter().any(|b| b.matches(ev)))
+    }
+
+    /// Binds `action` to `binding` alone, unless another action already uses it
+    pub fn rebind(&mut self, action: EditorAction, binding: KeyBinding) -> Result<(), String> {
+        let taken_by = EditorAction::ALL
+            .into_iter()
+            .filter(|other| *other != action)
+            .find(|other| self.bindings(*other).contains(&binding));
+
+        if let Some(other) = taken_by {
+            return Err(format!("{} is already used for {}", binding.label(), other.label()));
+        }
+
+        self.custom.retain(|(a, _)| *a != action);
+        if action.default_bindings() != vec![binding.clone()] {
+            self.custom.push((action, binding));
+        }
+
+        Ok(())
+    }
+}
+
+/// Whether a key press (or clipboard event) is meant for a text field rather than the editor
+pub fn typing_in_field(ev: &Event) -> bool {
+    let Some(element) = ev.target().and_then(|t| t.dyn_into::<HtmlElement>().ok()) else {
+        return false;
+    };
+
+    matches!(element.tag_name().as_str(), "INPUT" | "TEXTAREA" | "SELECT")
+        || element.is_content_editable()
+}
+
+#[cfg(test)]
+mod tests {
+    use wasm_bindgen_test::*;
+    use web_sys::KeyboardEventInit;
+
+    use super::*;
+
+    wasm_bindgen_test_configure!(run_in_browser);
+
+    fn key_down(key: &str, ctrl: bool, shift: bool) -> KeyboardEvent {
+        let init = KeyboardEventInit::new();
+        init.set_key(key);
+        init.set_ctrl_key(ctrl);
+        init.set_shift_key(shift);
+
+        KeyboardEvent::new_with_keyboard_event_init_dict("keydown", &init)
+            .expect("Couldn't create keyboard event")
+    }
+
+    #[test]
+    fn single_characters_are_lowercased() {
+        assert_eq!(KeyBinding::primary("Z").key, "z");
+        assert_eq!(KeyBinding::key("ArrowLeft").key, "ArrowLeft");
+    }
+
+    #[test]
+    fn labels_name_the_modifiers() {
+        assert_eq!(
+            KeyBinding::primary("z").with_shift().label(),
+            "Ctrl+Shift+Z"
+        );
+        assert_eq!(KeyBinding::key(" ").label(), "Space");
+        assert_eq!(KeyBinding::key("ArrowUp").with_shift().label(), "Shift+↑");
+        assert_eq!(KeyBinding::key("?").with_shift().label(), "?");
+    }
+
+    #[test]
+    fn default_bindings_belong_to_one_action_each() {
+        let keymap = Keymap::default();
+
+        for (i, action) in EditorAction::ALL.iter().enumerate() {
+            for other in &EditorAction::ALL[i + 1..] {
+                for binding in keymap.bindings(*action) {
+                    assert!(
+                        !keymap.bindings(*other).contains(&binding),
+                        "{} is used for {} and {}",
+                        binding.label(),
+                        action.label(),
+                        other.label()
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn rebinding_replaces_the_defaults() {
+        let mut keymap = Keymap::default();
+
+        keymap
+            .rebind(EditorAction::Undo, KeyBinding::primary("u"))
+            .expect("Couldn't rebind undo");
+
+        assert_eq!(
+            keymap.bindings(EditorAction::Undo),
+            vec![KeyBinding::primary("u")]
+        );
+
+        // going back to the default isn't stored
+        keymap
+            .rebind(EditorAction::Undo, KeyBinding::primary("z"))
+            .expect("Couldn't rebind undo");
+
+        assert!(keymap.custom.is_empty());
+    }
+
+    #[test]
+    fn bindings_of_other_actions_are_refused() {
+        let mut keymap = Keymap::default();
+
+        let result = keymap.rebind(EditorAction::Undo, KeyBinding::primary("c"));
+
+        assert_eq!(result, Err("Ctrl+C is already used for Copy".to_string()));
+        assert!(keymap.custom.is_empty());
+    }
+
+    #[test]
+    fn only_plain_clipboard_shortcuts_are_native() {
+        assert!(KeyBinding::primary("v").is_native_clipboard());
+        assert!(!KeyBinding::primary("v").with_shift().is_native_clipboard());
+        assert!(!KeyBinding::key("c").is_native_clipboard());
+    }
+
+    #[wasm_bindgen_test]
+    fn key_presses_find_their_action() {
+        let keymap = Keymap::default();
+
+        assert_eq!(
+            keymap.action_for(&key_down("Z", true, true)),
+            Some(EditorAction::Redo)
+        );
+        assert_eq!(
+            keymap.action_for(&key_down("ArrowLeft", false, true)),
+            Some(EditorAction::NudgeLeftFar)
+        );
+        assert_eq!(keymap.action_for(&key_down("q", true, false)), None);
+        // modifiers alone don't trigger anything
+        assert_eq!(
+            KeyBinding::from_event(&key_down("Shift", false, true)),
+            None
+        );
+    }
+}
@@ -1,361 +1,631 @@
+use std::cell::RefCell;
 use std::str::FromStr;
 use std::sync::{Arc, Mutex};
 use futures::future::join_all;
 use log::info;
+use rand::Rng;
 use stunts_engine::st_video::StVideo;
 use stunts_engine::{
     animations::Sequence,
-    editor::{Editor, Point},
-    polygon::{Polygon, Stroke},
-    st_image::{StImage, StImageConfig},
-    st_video::{SourceData, StVideoConfig},
-    text_due::{TextRenderer, TextRendererConfig},
+    editor::{Editor, Point, CANVAS_HORIZ_OFFSET, CANVAS_VERT_OFFSET},
+    polygon::{Polygon, SavedPoint, SavedPolygonConfig, Stroke},
+    st_image::{SavedStImageConfig, StImage, StImageConfig},
+    st_video::{MousePosition, SavedStVideoConfig, SourceData, StVideoConfig},
+    text_due::{SavedTextRendererConfig, TextRenderer, TextRendererConfig},
 };
 use uuid::Uuid;
-use wasm_bindgen_futures::spawn_local;
 
-use crate::fetchers::media::fetch_image;
+use crate::editor_state::{EditorState, SavedObject};
+use crate::fetchers::media::fetch_media_data;
+use crate::helpers::media_cache::{cached_image, cached_video, shared_texture};
+use crate::helpers::text_style::{DEFAULT_LETTER_SPACING, DEFAULT_LINE_HEIGHT};
+use crate::helpers::video_probe::{fit_dimensions, VideoProbe};
+
+// captures keep sourceData.json and clicks.json in the same folder as their mouse positions
+pub fn capture_file_path(mouse_path: &str, file_name: &str) -> String {
+    match mouse_path.rsplit_once('/') {
+        Some((folder, _)) => format!("{}/{}", folder, file_name),
+        None => file_name.to_string(),
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct RestoreProgress {
+    pub loaded: usize,
+    pub total: usize,
+    /// name of the object restored (or skipped) last
+    pub current: String,
+}
+
+impl RestoreProgress {
+    pub fn fraction(&self) -> f32 {
+        if self.total == 0 {
+            return 1.0;
+        }
+
+        self.loaded as f32 / self.total as f32
+    }
+}
+
+// counts restored or skipped objects and reports after each one
+struct ProgressTracker<F: Fn(RestoreProgress)> {
+    progress: RefCell<RestoreProgress>,
+    on_progress: F,
+}
+
+impl<F: Fn(RestoreProgress)> ProgressTracker<F> {
+    fn advance(&self, name: &str) {
+        let progress = {
+            let mut progress = self.progress.borrow_mut();
+            progress.loaded += 1;
+            progress.current = name.to_string();
+            progress.clone()
+        };
+
+        (self.on_progress)(progress);
+    }
+}
+
+pub fn object_count(sequences: &[Sequence]) -> usize {
+    sequences
+        .iter()
+        .map(|s| {
+            s.active_polygons.len()
+                + s.active_text_items.len()
+                + s.active_image_items.len()
+                + s.active_video_items.len()
+        })
+        .sum()
+}
+
+fn parse_uuid(id: &str, what: &str) -> Result<Uuid, String> {
+    Uuid::from_str(id).map_err(|_| format!("{} has an invalid id \"{}\"", what, id))
+}
+
+fn restore_polygon(
+    editor: &mut Editor,
+    p: &SavedPolygonConfig,
+    sequence_id: Uuid,
+    hidden: bool,
+) -> Result<(), String> {
+    let polygon_id = parse_uuid(&p.id, &format!("Polygon \"{}\"", p.name))?;
+
+    let points = p.points.clone();
+    if points.len() < 2 {
+        return Err(format!("Polygon \"{}\" has too few points", p.name));
+    }
+
+    let camera = editor.camera.as_ref().ok_or("Couldn't get camera")?;
+    let gpu_resources = editor
+        .gpu_resources
+        .as_ref()
+        .ok_or("Couldn't get gpu resources")?;
+    let model_bind_group_layout = editor
+        .model_bind_group_layout
+        .as_ref()
+        .ok_or("Couldn't get model bind group layout")?;
+    let group_bind_group_layout = editor
+        .group_bind_group_layout
+        .as_ref()
+        .ok_or("Couldn't get group bind group layout")?;
+
+    let mut restored_polygon = Polygon::new(
+        &camera.window_size,
+        &gpu_resources.device,
+        &gpu_resources.queue,
+        model_bind_group_layout,
+        group_bind_group_layout,
+        camera,
+        points,
+        (p.dimensions.0 as f32, p.dimensions.1 as f32),
+        Point {
+            x: p.position.x as f32,
+            y: p.position.y as f32,
+        },
+        (p.rotation as f32).to_radians(),
+        p.border_radius as f32,
+        [
+            p.fill[0] as f32,
+            p.fill[1] as f32,
+            p.fill[2] as f32,
+            p.fill[3] as f32,
+        ],
+        Stroke {
+            thickness: p.stroke.thickness as f32,
+            fill: [
+                p.stroke.fill[0] as f32,
+                p.stroke.fill[1] as f32,
+                p.stroke.fill[2] as f32,
+                p.stroke.fill[3] as f32,
+            ],
+        },
+        -2.0,
+        p.layer.clone(),
+        p.name.clone(),
+        polygon_id,
+        sequence_id,
+    );
+
+    restored_polygon.hidden = hidden;
+
+    editor.polygons.push(restored_polygon);
+
+    Ok(())
+}
+
+fn restore_text(
+    editor: &mut Editor,
+    t: &SavedTextRendererConfig,
+    sequence_id: Uuid,
+    hidden: bool,
+) -> Result<(), String> {
+    let text_id = parse_uuid(&t.id, &format!("Text \"{}\"", t.name))?;
+
+    let font_data = editor
+        .font_manager
+        .get_font_by_name(&t.font_family)
+        .ok_or(format!("Text \"{}\" uses an unknown font \"{}\"", t.name, t.font_family))?;
+
+    let camera = editor.camera.as_ref().ok_or("Couldn't get camera")?;
+    let gpu_resources = editor
+        .gpu_resources
+        .as_ref()
+        .ok_or("Couldn't get gpu resources")?;
+    let model_bind_group_layout = editor
+        .model_bind_group_layout
+        .as_ref()
+        .ok_or("Couldn't get model bind group layout")?;
+    let group_bind_group_layout = editor
+        .group_bind_group_layout
+        .as_ref()
+        .ok_or("Couldn't get group bind group layout")?;
+
+    let position = Point {
+        x: 0.0 + t.position.x as f32,
+        y: 0.0 + t.position.y as f32,
+    };
+
+    let mut restored_text = TextRenderer::new(
+        &gpu_resources.device,
+        &gpu_resources.queue,
+        model_bind_group_layout,
+        group_bind_group_layout,
+        font_data,
+        &camera.window_size,
+        t.text.clone(),
+        TextRendererConfig {
+            id: text_id,
+            name: t.name.clone(),
+            text: t.text.clone(),
+            font_family: t.font_family.clone(),
+            dimensions: (t.dimensions.0 as f32, t.dimensions.1 as f32),
+            position,
+            layer: t.layer.clone(),
+            color: t.color.clone(),
+            font_size: t.font_size.clone(),
+            background_fill: t.background_fill.unwrap_or([200, 200, 200, 255]),
+            line_height: t.line_height.unwrap_or(DEFAULT_LINE_HEIGHT),
+            letter_spacing: t.letter_spacing.unwrap_or(DEFAULT_LETTER_SPACING),
+            text_alignment: t.text_alignment.unwrap_or_default(),
+        },
+        text_id,
+        sequence_id,
+        camera,
+    );
+
+    restored_text.hidden = hidden;
+
+    restored_text.render_text(&gpu_resources.device, &gpu_resources.queue);
+
+    editor.text_items.push(restored_text);
+
+    Ok(())
+}
+
+fn restore_image(
+    editor: &mut Editor,
+    image_data: &[u8],
+    i: &SavedStImageConfig,
+    sequence_id: Uuid,
+    hidden: bool,
+) -> Result<(), String> {
+    // decode fully before handing the bytes to the engine, truncated or corrupt
+    // files still have a valid header
+    image::load_from_memory(image_data)
+        .map_err(|e| format!("Image \"{}\" couldn't be decoded: {}", i.name, e))?;
+
+    let camera = editor.camera.as_ref().ok_or("Couldn't get camera")?;
+    let gpu_resources = editor
+        .gpu_resources
+        .as_ref()
+        .ok_or("Couldn't get gpu resources")?;
+    let model_bind_group_layout = editor
+        .model_bind_group_layout
+        .as_ref()
+        .ok_or("Couldn't get model bind group layout")?;
+    let group_bind_group_layout = editor
+        .group_bind_group_layout
+        .as_ref()
+        .ok_or("Couldn't get group bind group layout")?;
+
+    let image_config = StImageConfig {
+        id: i.id.clone(),
+        name: i.name.clone(),
+        dimensions: i.dimensions.clone(),
+        url: i.url.clone(),
+        position: Point {
+            x: 0.0 + i.position.x as f32,
+            y: 0.0 + i.position.y as f32,
+        },
+        layer: i.layer.clone(),
+    };
+
+    // images with the same source share one texture
+    let texture = shared_texture(
+        &gpu_resources.device,
+        &gpu_resources.queue,
+        &i.url,
+        image_data,
+    )
+    .map_err(|e| format!("Image \"{}\" couldn't be loaded: {}", i.name, e))?;
+
+    let mut restored_image = StImage::new_with_texture(
+        &gpu_resources.device,
+        &gpu_resources.queue,
+        i.url.clone(),
+        texture,
+        image_config,
+        &camera.window_size,
+        model_bind_group_layout,
+        group_bind_group_layout,
+        -2.0,
+        i.id.clone(),
+        sequence_id,
+    );
+
+    restored_image.hidden = hidden;
 
-pub fn restore_sequence_objects(
-    editor: Arc<Mutex<Editor>>,
+    editor.image_items.push(restored_image);
+
+    Ok(())
+}
+
+fn restore_video(
+    editor: &mut Editor,
+    video_data: &[u8],
+    v: &SavedStVideoConfig,
+    mouse_positions: Option<Vec<MousePosition>>,
+    source_data: Option<SourceData>,
+    sequence_id: Uuid,
+    hidden: bool,
+) -> Result<(), String> {
+    let camera = editor.camera.as_ref().ok_or("Couldn't get camera")?;
+    let gpu_resources = editor
+        .gpu_resources
+        .as_ref()
+        .ok_or("Couldn't get gpu resources")?;
+    let model_bind_group_layout = editor
+        .model_bind_group_layout
+        .as_ref()
+        .ok_or("Couldn't get model bind group layout")?;
+    let group_bind_group_layout = editor
+        .group_bind_group_layout
+        .as_ref()
+        .ok_or("Couldn't get group bind group layout")?;
+
+    let video_config = StVideoConfig {
+        id: v.id.clone(),
+        name: v.name.clone(),
+        dimensions: v.dimensions.clone(),
+        path: v.path.clone(),
+        position: Point {
+            x: 0.0 + v.position.x as f32,
+            y: 0.0 + v.position.y as f32,
+        },
+        layer: v.layer.clone(),
+        mouse_path: v.mouse_path.clone(),
+    };
+
+    let mut restored_video = StVideo::new(
+        &gpu_resources.device,
+        &gpu_resources.queue,
+        video_data,
+        video_config,
+        &camera.window_size,
+        model_bind_group_layout,
+        group_bind_group_layout,
+        -2.0,
+        v.id.clone(),
+        sequence_id,
+    )
+    .map_err(|e| format!("Video \"{}\" couldn't be decoded: {:?}", v.name, e))?;
+
+    restored_video.hidden = hidden;
+
+    // set window data from capture
+    restored_video.source_data = source_data;
+
+    // set mouse positions
+    restored_video.mouse_positions = mouse_positions;
+
+    // render 1 frame to provide preview image
+    if let Err(e) = restored_video.draw_video_frame(&gpu_resources.device, &gpu_resources.queue) {
+        log::error!("Couldn't draw video preview frame: {:?}", e);
+    }
+
+    editor.video_items.push(restored_video);
+
+    Ok(())
+}
+
+/// Restores the objects of `saved_sequences` into the editor, reporting progress after each
+/// object. Objects that can't be restored are skipped, the returned warnings say which and why.
+pub async fn restore_sequence_objects(
+    editor_m: Arc<Mutex<Editor>>,
     saved_sequences: Vec<Sequence>,
     hidden: bool,
-    token: String
-) {
-    let saved_sequences = saved_sequences.clone();
-        let editor_m = editor.clone();
-        
-    spawn_local(
-        async move {
-            info!("Spawned..."); 
-
-            // let mut editor = editor_m.lock().unwrap();
-
-            // saved_sequences.iter().enumerate().for_each(|(i, saved_sequence)| {
-            for (i, saved_sequence) in saved_sequences.iter().enumerate() {
-                
-                    // let saved_sequence = saved_sequence.clone();
-                    // let editor = editor_m.clone();
+    token: String,
+    on_progress: impl Fn(RestoreProgress),
+) -> Vec<String> {
+    let tracker = ProgressTracker {
+        progress: RefCell::new(RestoreProgress {
+            loaded: 0,
+            total: object_count(&saved_sequences),
+            current: String::new(),
+        }),
+        on_progress,
+    };
+    let mut warnings = Vec::new();
+
+    let record = |result: Result<(), String>, name: &str, warnings: &mut Vec<String>| {
+        if let Err(warning) = result {
+            log::warn!("Skipping object: {}", warning);
+            warnings.push(warning);
+        }
+        tracker.advance(name);
+    };
+
+    for saved_sequence in saved_sequences.iter() {
+        info!("Restoring sequence...");
+
+        let sequence_id = match parse_uuid(&saved_sequence.id, &format!("Sequence \"{}\"", saved_sequence.name)) {
+            Ok(id) => id,
+            Err(warning) => {
+                warnings.push(warning);
+                for _ in 0..object_count(std::slice::from_ref(saved_sequence)) {
+                    tracker.advance(&saved_sequence.name);
+                }
+                continue;
+            }
+        };
+
+        for p in saved_sequence.active_polygons.iter() {
+            let result = {
+                let mut editor = editor_m.lock().unwrap();
+                restore_polygon(&mut editor, p, sequence_id, hidden)
+            };
+            record(result, &p.name, &mut warnings);
+        }
+
+        for t in saved_sequence.active_text_items.iter() {
+            let result = {
+                let mut editor = editor_m.lock().unwrap();
+                restore_text(&mut editor, t, sequence_id, hidden)
+            };
+            record(result, &t.name, &mut warnings);
+        }
+
+        info!("Restoring images...");
+        let image_results = join_all(saved_sequence.active_image_items.iter().map(|i| {
+            let token = token.clone();
+            async move { (i, cached_image(token, i.url.clone()).await) }
+        }))
+        .await;
+
+        for (i, image_data) in image_results {
+            let result = match image_data {
+                Some(image_data) => {
+                    let mut editor = editor_m.lock().unwrap();
+                    restore_image(&mut editor, &image_data, i, sequence_id, hidden)
+                }
+                None => Err(format!("Image \"{}\" couldn't be downloaded", i.name)),
+            };
+            record(result, &i.name, &mut warnings);
+        }
+
+        info!("Restoring videos...");
+        let video_results = join_all(saved_sequence.active_video_items.iter().map(|v| {
+            let token = token.clone();
+
+            async move {
+                let video_data = cached_video(token.clone(), v.path.clone()).await;
+
+                // capture data is stored as JSON alongside the mouse path
+                let mut mouse_positions = None;
+                let mut source_data = None;
+                if let Some(mouse_path) = &v.mouse_path {
+                    mouse_positions =
+                        fetch_media_data::<Vec<MousePosition>>(token.clone(), mouse_path.clone())
+                            .await;
+                    source_data = fetch_media_data::<SourceData>(
+                        token.clone(),
+                        capture_file_path(mouse_path, "sourceData.json"),
+                    )
+                    .await;
+                }
+
+                (v, video_data, mouse_positions, source_data)
+            }
+        }))
+        .await;
+
+        for (v, video_data, mouse_positions, source_data) in video_results {
+            let result = match video_data {
+                Some(video_data) => {
                     let mut editor = editor_m.lock().unwrap();
+                    restore_video(
+                        &mut editor,
+                        &video_data,
+                        v,
+                        mouse_positions,
+                        source_data,
+                        sequence_id,
+                        hidden,
+                    )
+                }
+                None => Err(format!("Video \"{}\" couldn't be downloaded", v.name)),
+            };
+            record(result, &v.name, &mut warnings);
+        }
+    }
+
+    info!("Restore finished with {} warnings", warnings.len());
+
+    warnings
+}
+
+/// Loads one saved object into the editor, fetching its media first.
+/// Used for objects that aren't in a sequence yet, like pasted copies.
+pub async fn restore_saved_object(
+    editor_m: Arc<Mutex<Editor>>,
+    object: &SavedObject,
+    sequence_id: Uuid,
+    hidden: bool,
+    token: String,
+) -> Result<(), String> {
+    match object {
+        SavedObject::Polygon(p) => {
+            let mut editor = editor_m.lock().unwrap();
+            restore_polygon(&mut editor, p, sequence_id, hidden)
+        }
+        SavedObject::Text(t) => {
+            let mut editor = editor_m.lock().unwrap();
+            restore_text(&mut editor, t, sequence_id, hidden)
+        }
+        SavedObject::Image(i) => {
+            let image_data = cached_image(token, i.url.clone())
+                .await
+                .ok_or(format!("Image \"{}\" couldn't be downloaded", i.name))?;
 
-                    info!("Restoring sequence..."); 
-
-                    saved_sequence.active_polygons.iter().for_each(|p| {
-
-                        info!("Restoring polygon..."); 
-
-                        let camera = editor.camera.as_ref().expect("Couldn't get camera");
-                        let window_size = &camera.window_size;
-
-                        let gpu_resources = editor
-                            .gpu_resources
-                            .as_ref()
-                            .expect("Couldn't get gpu resources");
-
-                        let device = &gpu_resources.device;
-                        let queue = &gpu_resources.queue;
-
-                        let mut restored_polygon = Polygon::new(
-                            &window_size,
-                            &device,
-                            &queue,
-                            &editor
-                                .model_bind_group_layout
-                                .as_ref()
-                                .expect("Couldn't get model bind group layout"),
-                            &editor
-                                .group_bind_group_layout
-                                .as_ref()
-                                .expect("Couldn't get group bind group layout"),
-                            &camera,
-                            // TODO: restoring triangles or non rectangles?
-                            vec![
-                                Point { x: 0.0, y: 0.0 },
-                                Point { x: 1.0, y: 0.0 },
-                                Point { x: 1.0, y: 1.0 },
-                                Point { x: 0.0, y: 1.0 },
-                            ],
-                            (p.dimensions.0 as f32, p.dimensions.1 as f32),
-                            Point {
-                                // x: random_number_800 as f32,
-                                // y: random_number_450 as f32,
-                                x: p.position.x as f32,
-                                y: p.position.y as f32,
-                            },
-                            // TODO: restore rotation?
-                            0.0,
-                            p.border_radius as f32,
-                            [
-                                p.fill[0] as f32,
-                                p.fill[1] as f32,
-                                p.fill[2] as f32,
-                                p.fill[3] as f32,
-                            ],
-                            Stroke {
-                                thickness: p.stroke.thickness as f32,
-                                fill: [
-                                    p.stroke.fill[0] as f32,
-                                    p.stroke.fill[1] as f32,
-                                    p.stroke.fill[2] as f32,
-                                    p.stroke.fill[3] as f32,
-                                ],
-                            },
-                            -2.0,
-                            p.layer.clone(),
-                            p.name.clone(),
-                            Uuid::from_str(&p.id).expect("Couldn't convert string to uuid"),
-                            Uuid::from_str(&saved_sequence.id.clone())
-                                .expect("Couldn't convert string to uuid"),
-                        );
-
-                        restored_polygon.hidden = hidden;
-
-                        // editor.add_polygon(restored_polygon);
-                        editor.polygons.push(restored_polygon);
-
-                        println!("Polygon restored...");
-
-                        
-                    });
-                    // drop(editor);
-
-                
-                    // let saved_sequence = saved_sequence.clone();
-                    // let editor = editor.clone();
-                    // let mut editor = editor_m.lock().unwrap();
-
-                    saved_sequence.active_text_items.iter().for_each(|t| {
-
-                        info!("Restoring text..."); 
-
-                        let camera = editor.camera.as_ref().expect("Couldn't get camera");
-                        let window_size = &camera.window_size;
-
-                        let gpu_resources = editor
-                            .gpu_resources
-                            .as_ref()
-                            .expect("Couldn't get gpu resources");
-
-                        let device = &gpu_resources.device;
-                        let queue = &gpu_resources.queue;
-
-                        let position = Point {
-                            x: 0.0 + t.position.x as f32,
-                            y: 0.0 + t.position.y as f32,
-                        };
-
-                        let mut restored_text = TextRenderer::new(
-                            &device,
-                            &queue,
-                            editor
-                                .model_bind_group_layout
-                                .as_ref()
-                                .expect("Couldn't get model bind group layout"),
-                            &editor
-                                .group_bind_group_layout
-                                .as_ref()
-                                .expect("Couldn't get group bind group layout"),
-                            editor
-                                .font_manager
-                                .get_font_by_name(&t.font_family)
-                                .expect("Couldn't get font family"),
-                            &window_size,
-                            t.text.clone(),
-                            TextRendererConfig {
-                                id: Uuid::from_str(&t.id).expect("Couldn't convert uuid"),
-                                name: t.name.clone(),
-                                text: t.text.clone(),
-                                font_family: t.font_family.clone(),
-                                dimensions: (t.dimensions.0 as f32, t.dimensions.1 as f32),
-                                position,
-                                layer: t.layer.clone(),
-                                color: t.color.clone(),
-                                font_size: t.font_size.clone(),
-                                background_fill: t.background_fill.unwrap_or([200, 200, 200, 255]),
-                            },
-                            Uuid::from_str(&t.id).expect("Couldn't convert string to uuid"),
-                            Uuid::from_str(&saved_sequence.id.clone())
-                                .expect("Couldn't convert string to uuid"),
-                            camera,
-                        );
-
-                        restored_text.hidden = hidden;
-
-                        restored_text.render_text(&device, &queue);
-
-                        // editor.add_polygon(restored_polygon);
-                        editor.text_items.push(restored_text);
-
-                        println!("Text restored...");
-
-                        
-                    });
-                    drop(editor);
-
-                
-                    // let saved_sequence = saved_sequence.clone();
-                    // let editor = editor.clone();
-
-                    // let mut editor = editor_m.lock().unwrap();
-                    
-                    
-                        // let saved_sequence = saved_sequence.clone();
-                        // let editor = editor.clone();
-                    
-                        info!("Restoring images...");
-                        let image_futures: Vec<_> = saved_sequence.active_image_items
-                            .iter()
-                            .map(|i| {
-                                let token = token.clone();
-                                let image_config = StImageConfig { // Create config *outside* the async block
-                                    id: i.id.clone(),
-                                    name: i.name.clone(),
-                                    dimensions: i.dimensions.clone(),
-                                    url: i.url.clone(),
-                                    position: Point {
-                                        x: 0.0 + i.position.x as f32,
-                                        y: 0.0 + i.position.y as f32,
-                                    },
-                                    layer: i.layer.clone(),
-                                };
-
-                                async move {  // async block *after* config creation
-                                    info!("Fetching image...");
-                                    let image_data = fetch_image(token.clone(), i.url.clone()).await.expect("Couldn't fetch image data");
-                                    (image_data, image_config, i.id.clone(), i.url.clone())
-                                }
-                            })
-                            .collect();
-
-                        info!("Fetching all...");
-                        let image_results = join_all(image_futures).await;
-
-                        info!("Adding all...");
-                        for (image_data, image_config, id, url) in image_results {
-                            let mut editor = editor_m.lock().unwrap(); // Lock *once* *after* all fetches are done
-                            let camera = editor.camera.as_ref().expect("Couldn't get camera");
-                            let window_size = camera.window_size.clone(); // Clone these *outside* the loop
-                            let gpu_resources = editor.gpu_resources.as_ref().expect("Couldn't get gpu resources");
-                            let device = &gpu_resources.device;
-                            let queue = &gpu_resources.queue;
-                            let model_bind_group_layout = editor.model_bind_group_layout.as_ref().expect("Couldn't get model bind group layout").clone();
-                            let group_bind_group_layout = editor.group_bind_group_layout.as_ref().expect("Couldn't get group bind group layout").clone();
-
-
-                            let mut restored_image = StImage::new(
-                                &device,
-                                &queue,
-                                url.clone(),
-                                &image_data,
-                                image_config,
-                                &window_size,
-                                &model_bind_group_layout,
-                                &group_bind_group_layout,
-                                -2.0,
-                                id.clone(),
-                                Uuid::from_str(&saved_sequence.id.clone()).expect("Couldn't convert string to uuid"),
-                            );
-
-                            restored_image.hidden = hidden;
-                            editor.image_items.push(restored_image);
-                            info!("Image restored...");
-                            drop(editor); // Drop the lock after all images are created.
-
-                        }
-                    
-
-                    
-                
-            // });
+            let mut editor = editor_m.lock().unwrap();
+            restore_image(&mut editor, &image_data, i, sequence_id, hidden)
+        }
+        SavedObject::Video(v) => {
+            let video_data = cached_video(token.clone(), v.path.clone())
+                .await
+                .ok_or(format!("Video \"{}\" couldn't be downloaded", v.name))?;
+
+            let mut mouse_positions = None;
+            let mut source_data = None;
+            if let Some(mouse_path) = &v.mouse_path {
+                mouse_positions =
+                    fetch_media_data::<Vec<MousePosition>>(token.clone(), mouse_path.clone()).await;
+                source_data = fetch_media_data::<SourceData>(
+                    token.clone(),
+                    capture_file_path(mouse_path, "sourceData.json"),
+                )
+                .await;
             }
-            // drop(editor);
+
+            let mut editor = editor_m.lock().unwrap();
+            restore_video(
+                &mut editor,
+                &video_data,
+                v,
+                mouse_positions,
+                source_data,
+                sequence_id,
+                hidden,
+            )
         }
+    }
+}
+
+/// Adds an uploaded video to the scene and saves it to the sequence,
+/// sequence duration and motion paths are stretched to fit the video.
+/// Returns the new item id and the updated sequences.
+pub fn add_video_to_sequence(
+    editor_m: Arc<Mutex<Editor>>,
+    editor_state: Arc<Mutex<EditorState>>,
+    sequence_id: String,
+    url: String,
+    file_data: &[u8],
+    probe: &VideoProbe,
+    mouse_path: Option<String>,
+) -> Option<(Uuid, Vec<Sequence>)> {
+    let mut editor = editor_m.lock().unwrap();
+
+    let mut rng = rand::thread_rng();
+    let dimensions = fit_dimensions(probe.width, probe.height, 400, 225);
+    let random_number_800 = rng.gen_range(0..=(800 - dimensions.0));
+    let random_number_450 = rng.gen_range(0..=(450 - dimensions.1));
+
+    let new_id = Uuid::new_v4();
+
+    let position = Point {
+        x: random_number_800 as f32 + dimensions.0 as f32 / 2.0 + CANVAS_HORIZ_OFFSET,
+        y: random_number_450 as f32 + dimensions.1 as f32 / 2.0 + CANVAS_VERT_OFFSET,
+    };
+
+    let video_config = StVideoConfig {
+        id: new_id.to_string(),
+        name: "New Video Item".to_string(),
+        dimensions,
+        path: url.clone(),
+        position,
+        layer: -1,
+        mouse_path: mouse_path.clone(),
+    };
+
+    if let Err(e) = editor.add_video_item(
+        video_config.clone(),
+        file_data,
+        new_id,
+        sequence_id.clone(),
+    ) {
+        log::error!("Couldn't add video: {:?}", e);
+        return None;
+    }
+
+    info!("Adding video: {:?}", new_id);
+
+    drop(editor);
+
+    let mut editor_state = editor_state.lock().unwrap();
+    editor_state.add_saved_video_item(
+        sequence_id.clone(),
+        SavedStVideoConfig {
+            id: video_config.id.clone(),
+            name: video_config.name.clone(),
+            dimensions: video_config.dimensions,
+            path: url,
+            position: SavedPoint {
+                x: position.x as i32,
+                y: position.y as i32,
+            },
+            layer: video_config.layer,
+            mouse_path,
+        },
+        probe.duration_ms,
     );
 
-    // saved_sequence.active_video_items.iter().for_each(|i| {
-    //     // let mut saved_mouse_path = None;
-    //     let mut source_data_path = None;
-    //     let mut stored_mouse_positions = None;
-    //     if let Some(mouse_path) = &i.mouse_path {
-    //         let mut mouse_pathbuf = Path::new(&mouse_path).to_path_buf();
-    //         mouse_pathbuf.pop();
-    //         source_data_path = Some(mouse_pathbuf.join("sourceData.json"));
-
-    //         if let Ok(positions) = fs::read_to_string(mouse_path) {
-    //             if let Ok(mouse_positions) = serde_json::from_str::<Vec<MousePosition>>(&positions)
-    //             {
-    //                 // saved_mouse_path = Some(mouse_path);
-    //                 stored_mouse_positions = Some(mouse_positions);
-    //             }
-    //         }
-    //     }
-
-    //     let mut stored_source_data = None;
-    //     if let Some(source_path) = &source_data_path {
-    //         if let Ok(source_data) = fs::read_to_string(source_path) {
-    //             if let Ok(data) = serde_json::from_str::<SourceData>(&source_data) {
-    //                 stored_source_data = Some(data);
-    //             }
-    //         }
-    //     }
-
-    //     println!(
-    //         "Restoring video source data... {:?} {:?}",
-    //         source_data_path, stored_source_data
-    //     );
-
-    //     let position = Point {
-    //         x: 0.0 + i.position.x as f32,
-    //         y: 0.0 + i.position.y as f32,
-    //     };
-
-    //     let video_config = StVideoConfig {
-    //         id: i.id.clone(),
-    //         name: i.name.clone(),
-    //         dimensions: i.dimensions.clone(),
-    //         path: i.path.clone(),
-    //         position,
-    //         layer: i.layer.clone(),
-    //         mouse_path: i.mouse_path.clone(),
-    //     };
-
-    //     let mut restored_video = StVideo::new(
-    //         &device,
-    //         &queue,
-    //         // string to Path
-    //         Path::new(&i.path),
-    //         video_config,
-    //         &window_size,
-    //         editor
-    //             .model_bind_group_layout
-    //             .as_ref()
-    //             .expect("Couldn't get model bind group layout"),
-    //         &editor
-    //             .group_bind_group_layout
-    //             .as_ref()
-    //             .expect("Couldn't get group bind group layout"),
-    //         -2.0,
-    //         i.id.clone(),
-    //         Uuid::from_str(&saved_sequence.id.clone()).expect("Couldn't convert string to uuid"),
-    //     );
-    //     // .expect("Couldn't restore video");
-
-    //     restored_video.hidden = hidden;
-
-    //     // set window data from capture
-    //     restored_video.source_data = stored_source_data;
-
-    //     // set mouse positions
-    //     restored_video.mouse_positions = stored_mouse_positions;
-
-    //     // render 1 frame to provide preview image
-    //     restored_video
-    //         .draw_video_frame(device, queue)
-    //         .expect("Couldn't draw video frame");
-
-    //     // editor.add_polygon(restored_polygon);
-    //     editor.video_items.push(restored_video);
-
-    //     println!("Video restored...");
-    // });
+    let saved_state = editor_state
+        .record_state
+        .saved_state
+        .as_ref()
+        .expect("Couldn't get saved state");
+    let sequences = saved_state.sequences.clone();
+
+    drop(editor_state);
+
+    let updated_sequence = sequences
+        .iter()
+        .find(|s| s.id == sequence_id)
+        .expect("Couldn't get updated sequence");
+
+    let mut editor = editor_m.lock().unwrap();
+    editor.current_sequence_data = Some(updated_sequence.clone());
+    editor.update_motion_paths(updated_sequence);
+
+    Some((new_id, sequences))
 }
@@ -0,0 +1,334 @@
+use std::cell::RefCell;
+use std::collections::{HashMap, VecDeque};
+use std::rc::Rc;
+use std::sync::{Arc, Weak};
+
+use futures::future::{FutureExt, LocalBoxFuture, Shared};
+use js_sys::{encode_uri_component, Uint8Array};
+use log::info;
+use stunts_engine::st_image::SharedImageTexture;
+use wasm_bindgen::JsCast;
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{window, Cache, Headers, Response, ResponseInit};
+
+use crate::fetchers::media::{
+    fetch_image_revalidated, fetch_video_revalidated, MediaFetch, IMAGE_ENDPOINT, VIDEO_ENDPOINT,
+};
+
+const CACHE_NAME: &str = "stunts-media";
+
+// media kept in memory at once, older entries are read back from Cache Storage
+const MEMORY_BUDGET_BYTES: usize = 256 * 1024 * 1024;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum MediaKind {
+    Image,
+    Video,
+}
+
+#[derive(Clone)]
+struct CachedMedia {
+    data: Rc<Vec<u8>>,
+    etag: Option<String>,
+}
+
+/// Least recently used media up to a byte budget
+struct MemoryCache {
+    budget: usize,
+    bytes: usize,
+    entries: HashMap<String, CachedMedia>,
+    // most recently used first
+    recent: VecDeque<String>,
+}
+
+impl MemoryCache {
+    fn new(budget: usize) -> Self {
+        MemoryCache {
+            budget,
+            bytes: 0,
+            entries: HashMap::new(),
+            recent: VecDeque::new(),
+        }
+    }
+
+    fn get(&mut self, key: &str) -> Option<CachedMedia> {
+        let media = self.entries.get(key)?.clone();
+
+        self.recent.retain(|k| k != key);
+        self.recent.push_front(key.to_string());
+
+        Some(media)
+    }
+
+    fn insert(&mut self, key: String, media: CachedMedia) {
+        self.remove(&key);
+
+        // too big to keep around, Cache Storage still has it
+        if media.data.len() > self.budget {
+            return;
+        }
+
+        self.bytes += media.data.len();
+        self.entries.insert(key.clone(), media);
+        self.recent.push_front(key);
+
+        while self.bytes > self.budget {
+            match self.recent.back().cloned() {
+                Some(oldest) => self.remove(&oldest),
+                None => break,
+            }
+        }
+    }
+
+    fn remove(&mut self, key: &str) {
+        if let Some(media) = self.entries.remove(key) {
+            self.bytes -= media.data.len();
+            self.recent.retain(|k| k != key);
+        }
+    }
+}
+
+type PendingFetch = Shared<LocalBoxFuture<'static, Option<Rc<Vec<u8>>>>>;
+
+thread_local! {
+    // recently loaded media, keyed by storage key
+    static MEMORY: RefCell<MemoryCache> = RefCell::new(MemoryCache::new(MEMORY_BUDGET_BYTES));
+    // downloads in flight, so concurrent requests for a url share one
+    static PENDING: RefCell<HashMap<String, PendingFetch>> = RefCell::new(HashMap::new());
+    // weak so a texture is freed once the last image using it is dropped
+    static TEXTURES: RefCell<HashMap<String, Weak<SharedImageTexture>>> = RefCell::new(HashMap::new());
+}
+
+// Cache Storage wants an absolute request url
+fn storage_key(kind: MediaKind, url: &str) -> String {
+    let endpoint = match kind {
+        MediaKind::Image => IMAGE_ENDPOINT,
+        MediaKind::Video => VIDEO_ENDPOINT,
+    };
+
+    format!(
+        "{}?filename={}",
+        endpoint,
+        String::from(encode_uri_component(url))
+    )
+}
+
+async fn open_cache() -> Option<Cache> {
+    let caches = window()?.caches().ok()?;
+
+    JsFuture::from(caches.open(CACHE_NAME))
+        .await
+        .ok()?
+        .dyn_into::<Cache>()
+        .ok()
+}
+
+async fn read_stored(key: &str) -> Option<CachedMedia> {
+    let cache = open_cache().await?;
+
+    let response = JsFuture::from(cache.match_with_str(key))
+        .await
+        .ok()?
+        .dyn_into::<Response>()
+        .ok()?;
+
+    let etag = response.headers().get("ETag").ok().flatten();
+    let buffer = JsFuture::from(response.array_buffer().ok()?).await.ok()?;
+
+    Some(CachedMedia {
+        data: Rc::new(Uint8Array::new(&buffer).to_vec()),
+        etag,
+    })
+}
+
+async fn write_stored(key: &str, media: &CachedMedia) {
+    let Some(cache) = open_cache().await else {
+        return;
+    };
+
+    let headers = Headers::new().expect("Couldn't create headers");
+    if let Some(etag) = &media.etag {
+        let _ = headers.append("ETag", etag);
+    }
+
+    let init = ResponseInit::new();
+    init.set_headers(&headers);
+
+    let mut body = media.data.as_ref().clone();
+    let Ok(response) = Response::new_with_opt_u8_array_and_init(Some(&mut body), &init) else {
+        return;
+    };
+
+    if let Err(e) = JsFuture::from(cache.put_with_str(key, &response)).await {
+        log::warn!("Couldn't store {} in media cache: {:?}", key, e);
+    }
+}
+
+async fn load_media(kind: MediaKind, token: String, url: String) -> Option<Rc<Vec<u8>>> {
+    let key = storage_key(kind, &url);
+    let stored = read_stored(&key).await;
+    let etag = stored.as_ref().and_then(|s| s.etag.clone());
+
+    let fetched = match kind {
+        MediaKind::Image => fetch_image_revalidated(token, url.clone(), etag).await,
+        MediaKind::Video => fetch_video_revalidated(token, url.clone(), etag).await,
+    };
+
+    let media = match fetched {
+        MediaFetch::NotModified => {
+            info!("Using cached {:?} {}", kind, url);
+            stored?
+        }
+        MediaFetch::Fetched { data, etag } => {
+            let media = CachedMedia {
+                data: Rc::new(data),
+                etag,
+            };
+            write_stored(&key, &media).await;
+            media
+        }
+        // offline or server trouble, an old copy beats nothing
+        MediaFetch::Failed => stored?,
+    };
+
+    MEMORY.with(|memory| memory.borrow_mut().insert(key, media.clone()));
+
+    Some(media.data)
+}
+
+async fn cached_media(kind: MediaKind, token: String, url: String) -> Option<Rc<Vec<u8>>> {
+    let key = storage_key(kind, &url);
+
+    if let Some(hit) = MEMORY.with(|memory| memory.borrow_mut().get(&key)) {
+        return Some(hit.data);
+    }
+
+    let pending = PENDING.with(|pending| pending.borrow().get(&key).cloned());
+
+    let fetch = match pending {
+        Some(fetch) => fetch,
+        None => {
+            let fetch = load_media(kind, token, url).boxed_local().shared();
+            PENDING.with(|pending| pending.borrow_mut().insert(key.clone(), fetch.clone()));
+            fetch
+        }
+    };
+
+    let result = fetch.await;
+
+    PENDING.with(|pending| pending.borrow_mut().remove(&key));
+
+    result
+}
+
+/// Image bytes for `url`, from memory, Cache Storage (revalidated with its ETag) or the
+/// server. Concurrent calls for the same url share a single download.
+pub async fn cached_image(token: String, url: String) -> Option<Rc<Vec<u8>>> {
+    cached_media(MediaKind::Image, token, url).await
+}
+
+/// Same as `cached_image`, for video files
+pub async fn cached_video(token: String, path: String) -> Option<Rc<Vec<u8>>> {
+    cached_media(MediaKind::Video, token, path).await
+}
+
+/// Seeds the in-memory cache with bytes we already have, like a fresh upload
+pub fn remember_image(url: String, data: Vec<u8>) {
+    MEMORY.with(|memory| {
+        memory.borrow_mut().insert(
+            storage_key(MediaKind::Image, &url),
+            CachedMedia {
+                data: Rc::new(data),
+                etag: None,
+            },
+        )
+    });
+}
+
+/// GPU texture for `url`, shared by every `StImage` drawing the same source
+pub fn shared_texture(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    url: &str,
+    data: &[u8],
+) -> Result<Arc<SharedImageTexture>, String> {
+    if let Some(texture) = TEXTURES.with(|textures| textures.borrow().get(url).and_then(Weak::upgrade)) {
+        return Ok(texture);
+    }
+
+    let texture = Arc::new(
+        SharedImageTexture::new(device, queue, data)
+            .map_err(|e| format!("Couldn't create texture: {:?}", e))?,
+    );
+
+    TEXTURES.with(|textures| {
+        let mut textures = textures.borrow_mut();
+        // forget textures nobody uses anymore while we're here
+        textures.retain(|_, texture| texture.strong_count() > 0);
+        textures.insert(url.to_string(), Arc::downgrade(&texture));
+    });
+
+    Ok(texture)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn media(len: usize) -> CachedMedia {
+        CachedMedia {
+            data: Rc::new(vec![0; len]),
+            etag: None,
+        }
+    }
+
+    #[test]
+    fn memory_stays_within_budget() {
+        let mut memory = MemoryCache::new(10);
+
+        memory.insert("a".into(), media(4));
+        memory.insert("b".into(), media(4));
+        memory.insert("c".into(), media(4));
+
+        assert_eq!(memory.bytes, 8);
+        assert!(memory.get("a").is_none());
+        assert!(memory.get("b").is_some());
+        assert!(memory.get("c").is_some());
+    }
+
+    #[test]
+    fn memory_drops_the_least_recently_used() {
+        let mut memory = MemoryCache::new(10);
+
+        memory.insert("a".into(), media(4));
+        memory.insert("b".into(), media(4));
+        memory.get("a");
+        memory.insert("c".into(), media(4));
+
+        assert!(memory.get("a").is_some());
+        assert!(memory.get("b").is_none());
+    }
+
+    #[test]
+    fn memory_skips_media_over_budget() {
+        let mut memory = MemoryCache::new(10);
+
+        memory.insert("a".into(), media(4));
+        memory.insert("huge".into(), media(11));
+
+        assert!(memory.get("huge").is_none());
+        assert!(memory.get("a").is_some());
+        assert_eq!(memory.bytes, 4);
+    }
+
+    #[test]
+    fn replacing_an_entry_frees_its_old_bytes() {
+        let mut memory = MemoryCache::new(10);
+
+        memory.insert("a".into(), media(8));
+        memory.insert("a".into(), media(2));
+
+        assert_eq!(memory.bytes, 2);
+        assert_eq!(memory.recent.len(), 1);
+    }
+}
@@ -1,4 +1,25 @@
+pub mod alignment;
+pub mod auto_zoom;
+pub mod backgrounds;
+pub mod canvas_coords;
+pub mod clipboard;
+pub mod gif_export;
+pub mod groups;
+pub mod history;
+pub mod keymap;
 pub mod media;
+pub mod media_cache;
+pub mod object_copy;
+pub mod object_transform;
+pub mod pen_path;
+pub mod polygons;
 pub mod projects;
+pub mod screen_capture;
+pub mod sequence_cache;
+pub mod shapes;
+pub mod text_style;
 pub mod users;
 pub mod utilities;
+pub mod video_export;
+pub mod video_mux;
+pub mod video_probe;
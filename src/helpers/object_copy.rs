@@ -0,0 +1,328 @@
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use stunts_engine::animations::{AnimationData, KeyframeValue, ObjectType, Sequence};
+use stunts_engine::editor::Editor;
+use uuid::Uuid;
+
+use crate::editor_state::{EditorState, SavedObject};
+use crate::helpers::media::restore_saved_object;
+
+/// How far pasted and duplicated copies land from the original
+pub const COPY_OFFSET: (i32, i32) = (20, 20);
+
+/// A saved object together with its motion path
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CopiedObject {
+    pub object: SavedObject,
+    pub motion_path: Option<AnimationData>,
+}
+
+/// Snapshots objects of a sequence, skipping any that aren't in it
+pub fn copy_objects(sequence: &Sequence, objects: &[(Uuid, ObjectType)]) -> Vec<CopiedObject> {
+    objects
+        .iter()
+        .filter_map(|(object_id, object_type)| {
+            let object_id = object_id.to_string();
+
+            let object = match object_type {
+                ObjectType::Polygon => sequence
+                    .active_polygons
+                    .iter()
+                    .find(|p| p.id == object_id)
+                    .cloned()
+                    .map(SavedObject::Polygon),
+                ObjectType::TextItem => sequence
+                    .active_text_items
+                    .iter()
+                    .find(|t| t.id == object_id)
+                    .cloned()
+                    .map(SavedObject::Text),
+                ObjectType::ImageItem => sequence
+                    .active_image_items
+                    .iter()
+                    .find(|i| i.id == object_id)
+                    .cloned()
+                    .map(SavedObject::Image),
+                ObjectType::VideoItem => sequence
+                    .active_video_items
+                    .iter()
+                    .find(|v| v.id == object_id)
+                    .cloned()
+                    .map(SavedObject::Video),
+            }?;
+
+            let motion_path = sequence
+                .polygon_motion_paths
+                .iter()
+                .find(|m| m.polygon_id == object_id)
+                .cloned();
+
+            Some(CopiedObject {
+                object,
+                motion_path,
+            })
+        })
+        .collect()
+}
+
+/// A new object from a copy, with fresh ids and moved by `offset`
+pub fn fresh_copy(copied: &CopiedObject, offset: (i32, i32)) -> CopiedObject {
+    let new_id = Uuid::new_v4().to_string();
+    let (dx, dy) = offset;
+
+    let mut object = copied.object.clone();
+    match &mut object {
+        SavedObject::Polygon(p) => {
+            p.id = new_id.clone();
+            p.position.x += dx;
+            p.position.y += dy;
+        }
+        SavedObject::Text(t) => {
+            t.id = new_id.clone();
+            t.position.x += dx;
+            t.position.y += dy;
+        }
+        SavedObject::Image(i) => {
+            // the copy points at the same upload, media isn't duplicated
+            i.id = new_id.clone();
+            i.position.x += dx;
+            i.position.y += dy;
+        }
+        SavedObject::Video(v) => {
+            v.id = new_id.clone();
+            v.position.x += dx;
+            v.position.y += dy;
+        }
+    }
+
+    let motion_path = copied.motion_path.clone().map(|mut motion_path| {
+        motion_path.id = Uuid::new_v4().to_string();
+        motion_path.polygon_id = new_id.clone();
+
+        motion_path
+            .properties
+            .iter_mut()
+            .flat_map(|p| p.keyframes.iter_mut())
+            .for_each(|k| {
+                k.id = Uuid::new_v4().to_string();
+
+                if let KeyframeValue::Position(position) = &mut k.value {
+                    position[0] += dx;
+                    position[1] += dy;
+                }
+            });
+
+        motion_path
+    });
+
+    CopiedObject {
+        object,
+        motion_path,
+    }
+}
+
+/// Loads fresh copies into the editor and adds them to the sequence as one undo step,
+/// selected. Copies that can't be loaded are skipped. Returns the copies that were added.
+pub async fn paste_objects(
+    editor_m: Arc<Mutex<Editor>>,
+    editor_state: Arc<Mutex<EditorState>>,
+    sequence_id: String,
+    copied: Vec<CopiedObject>,
+    offset: (i32, i32),
+    token: String,
+) -> Result<Vec<CopiedObject>, String> {
+    let sequence_uuid =
+        Uuid::from_str(&sequence_id).map_err(|_| "Couldn't parse sequence id".to_string())?;
+
+    let mut pasted = Vec::new();
+
+    for copy in copied.iter().map(|c| fresh_copy(c, offset)) {
+        // hidden until the insert edit shows it
+        let result = restore_saved_object(
+            editor_m.clone(),
+            &copy.object,
+            sequence_uuid,
+            true,
+            token.clone(),
+        )
+        .await;
+
+        match result {
+            Ok(()) => pasted.push(copy),
+            Err(e) => log::warn!("Skipping pasted object: {}", e),
+        }
+    }
+
+    if pasted.is_empty() {
+        return Err("Nothing could be pasted".to_string());
+    }
+
+    editor_state.lock().unwrap().insert_objects(
+        sequence_id,
+        pasted
+            .iter()
+            .map(|c| (c.object.clone(), c.motion_path.clone()))
+            .collect(),
+    )?;
+
+    Ok(pasted)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use stunts_engine::animations::{AnimationProperty, EasingType, KeyType, UIKeyframe};
+    use stunts_engine::editor::PathType;
+    use stunts_engine::polygon::{SavedPoint, SavedPolygonConfig, SavedStroke};
+
+    use super::*;
+
+    fn position_keyframe(time_ms: u64, position: [i32; 2]) -> UIKeyframe {
+        UIKeyframe {
+            id: Uuid::new_v4().to_string(),
+            time: Duration::from_millis(time_ms),
+            value: KeyframeValue::Position(position),
+            easing: EasingType::EaseInOut,
+            path_type: PathType::Linear,
+            key_type: KeyType::Frame,
+        }
+    }
+
+    fn sequence(polygon_id: &str) -> Sequence {
+        Sequence {
+            id: Uuid::new_v4().to_string(),
+            name: "Sequence".to_string(),
+            background_fill: None,
+            duration_ms: 1000,
+            active_polygons: vec![SavedPolygonConfig {
+                id: polygon_id.to_string(),
+                name: "Square".to_string(),
+                points: Vec::new(),
+                rotation: 0,
+                shape: None,
+                dimensions: (100, 100),
+                fill: [0, 0, 0, 255],
+                border_radius: 0,
+                position: SavedPoint { x: 100, y: 100 },
+                stroke: SavedStroke {
+                    thickness: 2,
+                    fill: [0, 0, 0, 255],
+                },
+                layer: -2,
+            }],
+            polygon_motion_paths: vec![AnimationData {
+                id: Uuid::new_v4().to_string(),
+                object_type: ObjectType::Polygon,
+                polygon_id: polygon_id.to_string(),
+                duration: Duration::from_secs(1),
+                start_time_ms: 0,
+                position: [0, 0],
+                properties: vec![AnimationProperty {
+                    name: "Position".to_string(),
+                    property_path: "position".to_string(),
+                    children: Vec::new(),
+                    keyframes: vec![
+                        position_keyframe(0, [100, 100]),
+                        position_keyframe(1000, [600, 300]),
+                    ],
+                    depth: 0,
+                }],
+            }],
+            active_text_items: Vec::new(),
+            active_image_items: Vec::new(),
+            active_video_items: Vec::new(),
+            groups: Vec::new(),
+        }
+    }
+
+    fn keyframes(copied: &CopiedObject) -> Vec<(String, KeyframeValue)> {
+        copied
+            .motion_path
+            .iter()
+            .flat_map(|m| m.properties.iter())
+            .flat_map(|p| p.keyframes.iter())
+            .map(|k| (k.id.clone(), k.value.clone()))
+            .collect()
+    }
+
+    #[test]
+    fn copies_skip_objects_missing_from_the_sequence() {
+        let polygon_id = Uuid::new_v4();
+        let sequence = sequence(&polygon_id.to_string());
+
+        let copied = copy_objects(
+            &sequence,
+            &[
+                (polygon_id, ObjectType::Polygon),
+                (Uuid::new_v4(), ObjectType::TextItem),
+            ],
+        );
+
+        assert_eq!(copied.len(), 1);
+        assert_eq!(copied[0].object.id(), polygon_id.to_string());
+        assert!(copied[0].motion_path.is_some());
+    }
+
+    #[test]
+    fn fresh_copies_get_new_ids() {
+        let polygon_id = Uuid::new_v4();
+        let original = copy_objects(
+            &sequence(&polygon_id.to_string()),
+            &[(polygon_id, ObjectType::Polygon)],
+        )
+        .remove(0);
+
+        let copy = fresh_copy(&original, COPY_OFFSET);
+        let motion_path = copy.motion_path.as_ref().expect("Couldn't get motion path");
+        let original_path = original
+            .motion_path
+            .as_ref()
+            .expect("Couldn't get motion path");
+
+        assert_ne!(copy.object.id(), original.object.id());
+        assert_ne!(motion_path.id, original_path.id);
+        // the motion path follows the new object
+        assert_eq!(motion_path.polygon_id, copy.object.id());
+
+        for ((copy_id, _), (original_id, _)) in keyframes(&copy).iter().zip(keyframes(&original)) {
+            assert_ne!(*copy_id, original_id);
+        }
+    }
+
+    #[test]
+    fn fresh_copies_are_shifted_by_the_offset() {
+        let polygon_id = Uuid::new_v4();
+        let original = copy_objects(
+            &sequence(&polygon_id.to_string()),
+            &[(polygon_id, ObjectType::Polygon)],
+        )
+        .remove(0);
+
+        let copy = fresh_copy(&original, COPY_OFFSET);
+
+        let SavedObject::Polygon(polygon) = &copy.object else {
+            panic!("Copy isn't a polygon");
+        };
+        assert_eq!(
+            (polygon.position.x, polygon.position.y),
+            (100 + COPY_OFFSET.0, 100 + COPY_OFFSET.1)
+        );
+
+        let positions: Vec<[i32; 2]> = keyframes(&copy)
+            .into_iter()
+            .filter_map(|(_, value)| match value {
+                KeyframeValue::Position(position) => Some(position),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            positions,
+            vec![
+                [100 + COPY_OFFSET.0, 100 + COPY_OFFSET.1],
+                [600 + COPY_OFFSET.0, 300 + COPY_OFFSET.1],
+            ]
+        );
+    }
+}
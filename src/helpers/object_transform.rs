@@ -0,0 +1,284 @@
+use std::str::FromStr;
+
+use stunts_engine::animations::ObjectType;
+use stunts_engine::editor::{Editor, Point};
+use uuid::Uuid;
+
+/// Where an object sits on the canvas, in world coordinates
+#[derive(Clone, Debug, PartialEq)]
+pub struct ObjectBounds {
+    pub id: Uuid,
+    pub object_type: ObjectType,
+    pub center: Point,
+    pub dimensions: (f32, f32),
+    /// degrees, clockwise
+    pub rotation: f32,
+    pub layer: i32,
+}
+
+fn rotate(point: Point, degrees: f32) -> Point {
+    let (sin, cos) = degrees.to_radians().sin_cos();
+
+    Point {
+        x: point.x * cos - point.y * sin,
+        y: point.x * sin + point.y * cos,
+    }
+}
+
+impl ObjectBounds {
+    /// `point` relative to the center, with the object's rotation undone
+    pub fn to_local(&self, point: Point) -> Point {
+        rotate(
+            Point {
+                x: point.x - self.center.x,
+                y: point.y - self.center.y,
+            },
+            -self.rotation,
+        )
+    }
+
+    pub fn to_world(&self, local: Point) -> Point {
+        let rotated = rotate(local, self.rotation);
+
+        Point {
+            x: self.center.x + rotated.x,
+            y: self.center.y + rotated.y,
+        }
+    }
+
+    pub fn contains(&self, point: Point) -> bool {
+        let local = self.to_local(point);
+
+        local.x.abs() <= self.dimensions.0 / 2.0 && local.y.abs() <= self.dimensions.1 / 2.0
+    }
+
+    /// Upright box around the (maybe rotated) object, as its min and max corners
+    pub fn extent(&self) -> (Point, Point) {
+        let corners = self.corners();
+
+        corners.iter().skip(1).fold((corners[0], corners[0]), |(min, max), c| {
+            (
+                Point {
+                    x: min.x.min(c.x),
+                    y: min.y.min(c.y),
+                },
+                Point {
+                    x: max.x.max(c.x),
+                    y: max.y.max(c.y),
+                },
+            )
+        })
+    }
+
+    /// top left, top right, bottom right, bottom left
+    pub fn corners(&self) -> [Point; 4] {
+        let (half_w, half_h) = (self.dimensions.0 / 2.0, self.dimensions.1 / 2.0);
+
+        [
+            self.to_world(Point { x: -half_w, y: -half_h }),
+            self.to_world(Point { x: half_w, y: -half_h }),
+            self.to_world(Point { x: half_w, y: half_h }),
+            self.to_world(Point { x: -half_w, y: half_h }),
+        ]
+    }
+}
+
+/// Visible objects, topmost first
+pub fn visible_objects(editor: &Editor) -> Vec<ObjectBounds> {
+    let mut objects = Vec::new();
+
+    for polygon in editor.polygons.iter().filter(|p| !p.hidden) {
+        objects.push(ObjectBounds {
+            id: polygon.id,
+            object_type: ObjectType::Polygon,
+            center: Point {
+                x: polygon.transform.position.x,
+                y: polygon.transform.position.y,
+            },
+            dimensions: polygon.dimensions,
+            rotation: polygon.transform.rotation.to_degrees(),
+            layer: polygon.layer,
+        });
+    }
+
+    for text in editor.text_items.iter().filter(|t| !t.hidden) {
+        objects.push(ObjectBounds {
+            id: text.id,
+            object_type: ObjectType::TextItem,
+            center: Point {
+                x: text.transform.position.x,
+                y: text.transform.position.y,
+            },
+            dimensions: text.dimensions,
+            rotation: text.transform.rotation.to_degrees(),
+            layer: text.layer,
+        });
+    }
+
+    for image in editor.image_items.iter().filter(|i| !i.hidden) {
+        let Ok(id) = Uuid::from_str(&image.id.to_string()) else {
+            continue;
+        };
+
+        objects.push(ObjectBounds {
+            id,
+            object_type: ObjectType::ImageItem,
+            center: Point {
+                x: image.transform.position.x,
+                y: image.transform.position.y,
+            },
+            dimensions: (image.dimensions.0 as f32, image.dimensions.1 as f32),
+            rotation: image.transform.rotation.to_degrees(),
+            layer: image.layer,
+        });
+    }
+
+    for video in editor.video_items.iter().filter(|v| !v.hidden) {
+        let Ok(id) = Uuid::from_str(&video.id.to_string()) else {
+            continue;
+        };
+
+        objects.push(ObjectBounds {
+            id,
+            object_type: ObjectType::VideoItem,
+            center: Point {
+                x: video.transform.position.x,
+                y: video.transform.position.y,
+            },
+            dimensions: (video.dimensions.0 as f32, video.dimensions.1 as f32),
+            rotation: video.transform.rotation.to_degrees(),
+            layer: video.layer,
+        });
+    }
+
+    // higher layers draw over lower ones
+    objects.sort_by(|a, b| b.layer.cmp(&a.layer));
+
+    objects
+}
+
+pub fn object_bounds(editor: &Editor, object_id: Uuid) -> Option<ObjectBounds> {
+    visible_objects(editor)
+        .into_iter()
+        .find(|o| o.id == object_id)
+}
+
+/// Topmost object under a world point
+pub fn hit_test(editor: &Editor, point: Point) -> Option<ObjectBounds> {
+    visible_objects(editor)
+        .into_iter()
+        .find(|o| o.contains(point))
+}
+
+/// Moves an object's transform and uploads it, so the change shows without playback
+pub fn set_object_transform(
+    editor: &mut Editor,
+    object_id: Uuid,
+    object_type: &ObjectType,
+    center: Point,
+    rotation: f32,
+) {
+    let Some(camera) = editor.camera else {
+        return;
+    };
+    let Some(gpu_resources) = editor.gpu_resources.clone() else {
+        return;
+    };
+
+    let window_size = camera.window_size;
+    let position = [center.x, center.y];
+    let rotation = rotation.to_radians();
+
+    match object_type {
+        ObjectType::Polygon => {
+            if let Some(polygon) = editor.polygons.iter_mut().find(|p| p.id == object_id) {
+                polygon.transform.update_position(position, &window_size);
+                polygon.transform.update_rotation(rotation);
+                polygon
+                    .transform
+                    .update_uniform_buffer(&gpu_resources.queue, &window_size);
+            }
+        }
+        ObjectType::TextItem => {
+            if let Some(text) = editor.text_items.iter_mut().find(|t| t.id == object_id) {
+                text.transform.update_position(position, &window_size);
+                text.transform.update_rotation(rotation);
+                text.transform
+                    .update_uniform_buffer(&gpu_resources.queue, &window_size);
+
+                let background = &mut text.background_polygon.transform;
+                background.update_position(position, &window_size);
+                background.update_rotation(rotation);
+                background.update_uniform_buffer(&gpu_resources.queue, &window_size);
+            }
+        }
+        ObjectType::ImageItem => {
+            let image = editor
+                .image_items
+                .iter_mut()
+                .find(|i| i.id.to_string() == object_id.to_string());
+
+            if let Some(image) = image {
+                image.transform.update_position(position, &window_size);
+                image.transform.update_rotation(rotation);
+                image
+                    .transform
+                    .update_uniform_buffer(&gpu_resources.queue, &window_size);
+            }
+        }
+        ObjectType::VideoItem => {
+            let video = editor
+                .video_items
+                .iter_mut()
+                .find(|v| v.id.to_string() == object_id.to_string());
+
+            if let Some(video) = video {
+                video.transform.update_position(position, &window_size);
+                video.transform.update_rotation(rotation);
+                video
+                    .transform
+                    .update_uniform_buffer(&gpu_resources.queue, &window_size);
+            }
+        }
+    }
+}
+
+/// Hidden objects aren't drawn or hit, used to take deleted objects off the canvas.
+/// Returns false when the object isn't in the editor.
+pub fn set_object_hidden(
+    editor: &mut Editor,
+    object_id: Uuid,
+    object_type: &ObjectType,
+    hidden: bool,
+) -> bool {
+    let object_hidden = match object_type {
+        ObjectType::Polygon => editor
+            .polygons
+            .iter_mut()
+            .find(|p| p.id == object_id)
+            .map(|polygon| &mut polygon.hidden),
+        ObjectType::TextItem => editor
+            .text_items
+            .iter_mut()
+            .find(|t| t.id == object_id)
+            .map(|text| &mut text.hidden),
+        ObjectType::ImageItem => editor
+            .image_items
+            .iter_mut()
+            .find(|i| i.id.to_string() == object_id.to_string())
+            .map(|image| &mut image.hidden),
+        ObjectType::VideoItem => editor
+            .video_items
+            .iter_mut()
+            .find(|v| v.id.to_string() == object_id.to_string())
+            .map(|video| &mut video.hidden),
+    };
+
+    match object_hidden {
+        Some(object_hidden) => {
+            *object_hidden = hidden;
+            true
+        }
+        None => false,
+    }
+}
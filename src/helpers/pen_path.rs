@@ -0,0 +1,491 @@
+use stunts_engine::editor::Point;
+use stunts_engine::polygon::{SavedPathAnchor, SavedShape};
+
+// points sampled along each curved segment
+const CURVE_STEPS: usize = 16;
+
+/// A point on a pen path. Handles sit on the point itself for sharp corners.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PenAnchor {
+    pub point: Point,
+    pub handle_in: Point,
+    pub handle_out: Point,
+}
+
+impl PenAnchor {
+    pub fn corner(point: Point) -> Self {
+        PenAnchor {
+            point,
+            handle_in: point,
+            handle_out: point,
+        }
+    }
+
+    pub fn is_corner(&self) -> bool {
+        self.handle_in == self.point && self.handle_out == self.point
+    }
+
+    /// Drags out a smooth handle pair, the in handle mirroring the out handle
+    pub fn set_smooth_handle(&mut self, handle_out: Point) {
+        self.handle_out = handle_out;
+        self.handle_in = Point {
+            x: 2.0 * self.point.x - handle_out.x,
+            y: 2.0 * self.point.y - handle_out.y,
+        };
+    }
+
+    pub fn move_to(&mut self, point: Point) {
+        let dx = point.x - self.point.x;
+        let dy = point.y - self.point.y;
+
+        self.point = point;
+        self.handle_in = Point {
+            x: self.handle_in.x + dx,
+            y: self.handle_in.y + dy,
+        };
+        self.handle_out = Point {
+            x: self.handle_out.x + dx,
+            y: self.handle_out.y + dy,
+        };
+    }
+}
+
+/// A path of cubic bezier segments drawn with the pen tool
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PenPath {
+    pub anchors: Vec<PenAnchor>,
+    pub closed: bool,
+}
+
+fn lerp(a: Point, b: Point, t: f32) -> Point {
+    Point {
+        x: a.x + (b.x - a.x) * t,
+        y: a.y + (b.y - a.y) * t,
+    }
+}
+
+fn distance(a: Point, b: Point) -> f32 {
+    ((b.x - a.x).powi(2) + (b.y - a.y).powi(2)).sqrt()
+}
+
+fn cubic(p0: Point, p1: Point, p2: Point, p3: Point, t: f32) -> Point {
+    let a = lerp(p0, p1, t);
+    let b = lerp(p1, p2, t);
+    let c = lerp(p2, p3, t);
+
+    lerp(lerp(a, b, t), lerp(b, c, t), t)
+}
+
+impl PenPath {
+    /// Index pairs of the anchors each segment joins, including the closing segment
+    pub fn segments(&self) -> Vec<(usize, usize)> {
+        let count = self.anchors.len();
+        let mut segments: Vec<(usize, usize)> = (1..count).map(|i| (i - 1, i)).collect();
+
+        if self.closed && count > 2 {
+            segments.push((count - 1, 0));
+        }
+
+        segments
+    }
+
+    fn segment_point(&self, segment: (usize, usize), t: f32) -> Point {
+        let a = &self.anchors[segment.0];
+        let b = &self.anchors[segment.1];
+
+        cubic(a.point, a.handle_out, b.handle_in, b.point, t)
+    }
+
+    fn segment_is_straight(&self, segment: (usize, usize)) -> bool {
+        let a = &self.anchors[segment.0];
+        let b = &self.anchors[segment.1];
+
+        a.handle_out == a.point && b.handle_in == b.point
+    }
+
+    /// The path as straight lines, curves sampled into short steps
+    pub fn flatten(&self) -> Vec<Point> {
+        let Some(first) = self.anchors.first() else {
+            return Vec::new();
+        };
+
+        let mut points = vec![first.point];
+
+        for segment in self.segments() {
+            if !self.segment_is_straight(segment) {
+                points.extend(
+                    (1..CURVE_STEPS).map(|i| self.segment_point(segment, i as f32 / CURVE_STEPS as f32)),
+                );
+            }
+            points.push(self.anchors[segment.1].point);
+        }
+
+        // the closing segment ends back on the first anchor
+        if self.closed && points.len() > 1 {
+            points.pop();
+        }
+
+        points
+    }
+
+    /// Polygon outline of the path. Open paths have no area, so they trace back along
+    /// themselves and only show their stroke.
+    pub fn outline(&self) -> Vec<Point> {
+        let mut points = self.flatten();
+
+        if !self.closed && points.len() > 2 {
+            let back: Vec<Point> = points[1..points.len() - 1].iter().rev().copied().collect();
+            points.extend(back);
+        }
+
+        points
+    }
+
+    /// Center and size of the flattened path
+    pub fn bounds(&self) -> (Point, (f32, f32)) {
+        let points = self.flatten();
+
+        if points.is_empty() {
+            return (Point { x: 0.0, y: 0.0 }, (1.0, 1.0));
+        }
+
+        let min_x = points.iter().map(|p| p.x).fold(f32::MAX, f32::min);
+        let min_y = points.iter().map(|p| p.y).fold(f32::MAX, f32::min);
+        let max_x = points.iter().map(|p| p.x).fold(f32::MIN, f32::max);
+        let max_y = points.iter().map(|p| p.y).fold(f32::MIN, f32::max);
+
+        (
+            Point {
+                x: (min_x + max_x) / 2.0,
+                y: (min_y + max_y) / 2.0,
+            },
+            ((max_x - min_x).max(1.0), (max_y - min_y).max(1.0)),
+        )
+    }
+
+    pub fn nearest_anchor(&self, point: Point, radius: f32) -> Option<usize> {
+        self.anchors
+            .iter()
+            .enumerate()
+            .map(|(i, a)| (i, distance(a.point, point)))
+            .filter(|(_, d)| *d <= radius)
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(i, _)| i)
+    }
+
+    /// Segment passing within `radius` of the point, with the curve parameter there
+    pub fn nearest_segment(&self, point: Point, radius: f32) -> Option<((usize, usize), f32)> {
+        self.segments()
+            .into_iter()
+            .flat_map(|segment| {
+                (0..=CURVE_STEPS).map(move |i| {
+                    let t = i as f32 / CURVE_STEPS as f32;
+                    (segment, t)
+                })
+            })
+            .map(|(segment, t)| (segment, t, distance(self.segment_point(segment, t), point)))
+            .filter(|(_, _, d)| *d <= radius)
+            .min_by(|a, b| a.2.total_cmp(&b.2))
+            .map(|(segment, t, _)| (segment, t))
+    }
+
+    /// Splits a segment at `t` without changing the curve's shape, returns the new anchor
+    pub fn insert_anchor(&mut self, segment: (usize, usize), t: f32) -> usize {
+        let a = self.anchors[segment.0];
+        let b = self.anchors[segment.1];
+
+        // de casteljau
+        let p01 = lerp(a.point, a.handle_out, t);
+        let p12 = lerp(a.handle_out, b.handle_in, t);
+        let p23 = lerp(b.handle_in, b.point, t);
+        let p012 = lerp(p01, p12, t);
+        let p123 = lerp(p12, p23, t);
+
+        let new_anchor = PenAnchor {
+            point: lerp(p012, p123, t),
+            handle_in: p012,
+            handle_out: p123,
+        };
+
+        self.anchors[segment.0].handle_out = p01;
+        self.anchors[segment.1].handle_in = p23;
+
+        // the closing segment inserts at the end
+        let index = segment.0 + 1;
+        self.anchors.insert(index, new_anchor);
+
+        index
+    }
+
+    pub fn remove_anchor(&mut self, index: usize) {
+        if index < self.anchors.len() {
+            self.anchors.remove(index);
+        }
+
+        if self.anchors.len() < 3 {
+            self.closed = false;
+        }
+    }
+
+    /// Saved form, relative to the polygon's box (0.0 - 1.0)
+    pub fn to_saved(&self, center: Point, dimensions: (f32, f32)) -> SavedShape {
+        let left = center.x - dimensions.0 / 2.0;
+        let top = center.y - dimensions.1 / 2.0;
+
+        let normalize = |p: Point| ((p.x - left) / dimensions.0, (p.y - top) / dimensions.1);
+
+        SavedShape::Path {
+            anchors: self
+                .anchors
+                .iter()
+                .map(|a| {
+                    let (x, y) = normalize(a.point);
+                    let (in_x, in_y) = normalize(a.handle_in);
+                    let (out_x, out_y) = normalize(a.handle_out);
+
+                    SavedPathAnchor {
+                        x,
+                        y,
+                        in_x,
+                        in_y,
+                        out_x,
+                        out_y,
+                    }
+                })
+                .collect(),
+            closed: self.closed,
+        }
+    }
+
+    /// Path placed in the box with the given center and size
+    pub fn from_saved(
+        anchors: &[SavedPathAnchor],
+        closed: bool,
+        center: Point,
+        dimensions: (f32, f32),
+    ) -> Self {
+        let left = center.x - dimensions.0 / 2.0;
+        let top = center.y - dimensions.1 / 2.0;
+
+        let place = |x: f32, y: f32| Point {
+            x: left + x * dimensions.0,
+            y: top + y * dimensions.1,
+        };
+
+        PenPath {
+            anchors: anchors
+                .iter()
+                .map(|a| PenAnchor {
+                    point: place(a.x, a.y),
+                    handle_in: place(a.in_x, a.in_y),
+                    handle_out: place(a.out_x, a.out_y),
+                })
+                .collect(),
+            closed,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn point(x: f32, y: f32) -> Point {
+        Point { x, y }
+    }
+
+    fn close(a: Point, b: Point) -> bool {
+        (a.x - b.x).abs() < 1e-3 && (a.y - b.y).abs() < 1e-3
+    }
+
+    fn corners(points: &[(f32, f32)], closed: bool) -> PenPath {
+        PenPath {
+            anchors: points
+                .iter()
+                .map(|(x, y)| PenAnchor::corner(point(*x, *y)))
+                .collect(),
+            closed,
+        }
+    }
+
+    #[test]
+    fn only_paths_with_three_anchors_close() {
+        assert!(corners(&[], true).segments().is_empty());
+        assert!(corners(&[(0.0, 0.0)], true).segments().is_empty());
+        assert_eq!(
+            corners(&[(0.0, 0.0), (10.0, 0.0)], true).segments(),
+            vec![(0, 1)]
+        );
+        assert_eq!(
+            corners(&[(0.0, 0.0), (10.0, 0.0), (10.0, 10.0)], true).segments(),
+            vec![(0, 1), (1, 2), (2, 0)]
+        );
+    }
+
+    #[test]
+    fn straight_paths_flatten_to_their_anchors() {
+        let triangle = corners(&[(0.0, 0.0), (10.0, 0.0), (10.0, 10.0)], true);
+
+        // the closing segment doesn't repeat the first anchor
+        assert_eq!(
+            triangle.flatten(),
+            vec![point(0.0, 0.0), point(10.0, 0.0), point(10.0, 10.0)]
+        );
+        assert!(PenPath::default().flatten().is_empty());
+    }
+
+    #[test]
+    fn curved_segments_are_sampled() {
+        let mut path = corners(&[(0.0, 0.0), (100.0, 0.0)], false);
+        path.anchors[0].set_smooth_handle(point(0.0, 50.0));
+
+        let points = path.flatten();
+
+        assert_eq!(points.len(), CURVE_STEPS + 1);
+        assert_eq!(points[0], point(0.0, 0.0));
+        assert_eq!(points[CURVE_STEPS], point(100.0, 0.0));
+        // pulled toward the handle
+        assert!(points[CURVE_STEPS / 2].y > 0.0);
+    }
+
+    #[test]
+    fn open_paths_trace_back_along_themselves() {
+        let path = corners(&[(0.0, 0.0), (10.0, 0.0), (20.0, 5.0)], false);
+
+        assert_eq!(
+            path.outline(),
+            vec![
+                point(0.0, 0.0),
+                point(10.0, 0.0),
+                point(20.0, 5.0),
+                point(10.0, 0.0)
+            ]
+        );
+        // closed paths have area of their own
+        let mut closed = path.clone();
+        closed.closed = true;
+        assert_eq!(closed.outline(), closed.flatten());
+    }
+
+    #[test]
+    fn bounds_never_collapse_to_zero() {
+        assert_eq!(PenPath::default().bounds(), (point(0.0, 0.0), (1.0, 1.0)));
+        assert_eq!(
+            corners(&[(5.0, 5.0)], false).bounds(),
+            (point(5.0, 5.0), (1.0, 1.0))
+        );
+        assert_eq!(
+            corners(&[(0.0, 0.0), (20.0, 10.0)], false).bounds(),
+            (point(10.0, 5.0), (20.0, 10.0))
+        );
+    }
+
+    #[test]
+    fn smooth_handles_mirror_and_follow_their_anchor() {
+        let mut anchor = PenAnchor::corner(point(10.0, 10.0));
+        assert!(anchor.is_corner());
+
+        anchor.set_smooth_handle(point(15.0, 12.0));
+        assert_eq!(anchor.handle_in, point(5.0, 8.0));
+        assert!(!anchor.is_corner());
+
+        anchor.move_to(point(20.0, 20.0));
+        assert_eq!(anchor.handle_in, point(15.0, 18.0));
+        assert_eq!(anchor.handle_out, point(25.0, 22.0));
+    }
+
+    #[test]
+    fn inserting_an_anchor_keeps_the_curve() {
+        let mut path = corners(&[(0.0, 0.0), (100.0, 0.0)], false);
+        path.anchors[0].set_smooth_handle(point(30.0, 60.0));
+        let original = path.clone();
+
+        let index = path.insert_anchor((0, 1), 0.25);
+
+        assert_eq!(index, 1);
+        assert_eq!(path.anchors.len(), 3);
+        assert!(close(
+            path.anchors[1].point,
+            original.segment_point((0, 1), 0.25)
+        ));
+        // both halves still run along the original curve
+        assert!(close(
+            path.segment_point((0, 1), 0.5),
+            original.segment_point((0, 1), 0.125)
+        ));
+        assert!(close(
+            path.segment_point((1, 2), 0.5),
+            original.segment_point((0, 1), 0.625)
+        ));
+    }
+
+    #[test]
+    fn inserting_on_the_closing_segment_appends() {
+        let mut path = corners(&[(0.0, 0.0), (10.0, 0.0), (10.0, 10.0)], true);
+
+        let index = path.insert_anchor((2, 0), 0.5);
+
+        assert_eq!(index, 3);
+        assert_eq!(path.anchors[3].point, point(5.0, 5.0));
+        assert_eq!(path.segments().last(), Some(&(3, 0)));
+    }
+
+    #[test]
+    fn removing_anchors_opens_short_paths() {
+        let mut path = corners(&[(0.0, 0.0), (10.0, 0.0), (10.0, 10.0)], true);
+
+        // out of range is ignored
+        path.remove_anchor(7);
+        assert_eq!(path.anchors.len(), 3);
+        assert!(path.closed);
+
+        path.remove_anchor(1);
+        assert_eq!(path.anchors.len(), 2);
+        assert!(!path.closed);
+    }
+
+    #[test]
+    fn nearest_anchor_and_segment_respect_the_radius() {
+        let path = corners(&[(0.0, 0.0), (100.0, 0.0)], false);
+
+        assert_eq!(path.nearest_anchor(point(98.0, 1.0), 5.0), Some(1));
+        assert_eq!(path.nearest_anchor(point(50.0, 0.0), 5.0), None);
+
+        let (segment, t) = path
+            .nearest_segment(point(50.0, 2.0), 5.0)
+            .expect("Couldn't find segment");
+        assert_eq!(segment, (0, 1));
+        assert!((t - 0.5).abs() < 1e-6);
+        assert_eq!(path.nearest_segment(point(50.0, 20.0), 5.0), None);
+        assert_eq!(
+            PenPath::default().nearest_segment(point(0.0, 0.0), 5.0),
+            None
+        );
+    }
+
+    #[test]
+    fn saved_paths_come_back_in_their_box() {
+        let mut path = corners(&[(100.0, 50.0), (300.0, 50.0), (200.0, 170.0)], true);
+        path.anchors[1].set_smooth_handle(point(333.3, 61.7));
+        let (center, dimensions) = path.bounds();
+
+        let SavedShape::Path { anchors, closed } = path.to_saved(center, dimensions) else {
+            panic!("Pen paths save as paths");
+        };
+        let restored = PenPath::from_saved(&anchors, closed, center, dimensions);
+
+        assert!(restored.closed);
+        for (a, b) in restored.anchors.iter().zip(path.anchors.iter()) {
+            assert!(close(a.point, b.point));
+            assert!(close(a.handle_in, b.handle_in));
+            assert!(close(a.handle_out, b.handle_out));
+        }
+
+        // a unit box gives the normalized outline the shape library uses
+        let unit = PenPath::from_saved(&anchors, closed, point(0.5, 0.5), (1.0, 1.0));
+        assert!(unit
+            .flatten()
+            .iter()
+            .all(|p| (-1e-3..=1.001).contains(&p.x) && (-1e-3..=1.001).contains(&p.y)));
+    }
+}
@@ -0,0 +1,221 @@
+use std::sync::{Arc, Mutex};
+
+use stunts_engine::animations::Sequence;
+use stunts_engine::editor::{Editor, Point};
+use stunts_engine::polygon::{
+    PolygonConfig, SavedPoint, SavedPolygonConfig, SavedShape, SavedStroke,
+};
+
+use crate::editor_state::EditorState;
+use crate::helpers::utilities::SavedState;
+
+pub fn square_points() -> Vec<Point> {
+    vec![
+        Point { x: 0.0, y: 0.0 },
+        Point { x: 1.0, y: 0.0 },
+        Point { x: 1.0, y: 1.0 },
+        Point { x: 0.0, y: 1.0 },
+    ]
+}
+
+pub fn to_saved_polygon_config(
+    config: &PolygonConfig,
+    rotation: i32,
+    shape: Option<SavedShape>,
+) -> SavedPolygonConfig {
+    SavedPolygonConfig {
+        id: config.id.to_string(),
+        name: config.name.clone(),
+        // kept as floats, edited shapes come back exactly as they were saved
+        points: config.points.clone(),
+        rotation,
+        shape,
+        dimensions: (config.dimensions.0 as i32, config.dimensions.1 as i32),
+        fill: [
+            config.fill[0] as i32,
+            config.fill[1] as i32,
+            config.fill[2] as i32,
+            config.fill[3] as i32,
+        ],
+        border_radius: config.border_radius as i32,
+        position: SavedPoint {
+            x: config.position.x as i32,
+            y: config.position.y as i32,
+        },
+        stroke: SavedStroke {
+            thickness: config.stroke.thickness as i32,
+            fill: [
+                config.stroke.fill[0] as i32,
+                config.stroke.fill[1] as i32,
+                config.stroke.fill[2] as i32,
+                config.stroke.fill[3] as i32,
+            ],
+        },
+        layer: config.layer,
+    }
+}
+
+/// Polygons saved before points were persisted were always squares
+pub fn migrate_saved_state(saved_state: &mut SavedState) -> bool {
+    let mut migrated = false;
+
+    saved_state
+        .sequences
+        .iter_mut()
+        .flat_map(|s| s.active_polygons.iter_mut())
+        .filter(|p| p.points.is_empty())
+        .for_each(|p| {
+            p.points = square_points();
+            migrated = true;
+        });
+
+    migrated
+}
+
+/// Adds a polygon to the editor and the saved sequence, returning the updated sequences
+pub fn add_polygon_to_sequence(
+    editor_m: Arc<Mutex<Editor>>,
+    editor_state: Arc<Mutex<EditorState>>,
+    sequence_id: String,
+    polygon_config: PolygonConfig,
+    shape: Option<SavedShape>,
+) -> Vec<Sequence> {
+    let mut editor = editor_m.lock().unwrap();
+    editor.add_polygon(
+        polygon_config.clone(),
+        "Polygon".to_string(),
+        polygon_config.id,
+        sequence_id.clone(),
+    );
+    drop(editor);
+
+    let mut editor_state = editor_state.lock().unwrap();
+    editor_state.add_saved_polygon(
+        sequence_id.clone(),
+        to_saved_polygon_config(&polygon_config, 0, shape),
+    );
+
+    let saved_state = editor_state
+        .record_state
+        .saved_state
+        .as_ref()
+        .expect("Couldn't get saved state");
+    let sequences = saved_state.sequences.clone();
+
+    drop(editor_state);
+
+    let updated_sequence = sequences
+        .iter()
+        .find(|s| s.id == sequence_id)
+        .expect("Couldn't get updated sequence");
+
+    let mut editor = editor_m.lock().unwrap();
+    editor.current_sequence_data = Some(updated_sequence.clone());
+    editor.update_motion_paths(updated_sequence);
+
+    sequences
+}
+
+#[cfg(test)]
+mod tests {
+    use stunts_engine::polygon::Stroke;
+    use stunts_engine::timelines::SavedTimelineStateConfig;
+    use uuid::Uuid;
+
+    use super::*;
+
+    fn polygon_config(points: Vec<Point>) -> PolygonConfig {
+        PolygonConfig {
+            id: Uuid::new_v4(),
+            name: "Polygon".to_string(),
+            points,
+            dimensions: (120.0, 80.0),
+            position: Point { x: 300.0, y: 200.0 },
+            border_radius: 0.0,
+            fill: [1.0, 1.0, 1.0, 1.0],
+            stroke: Stroke {
+                fill: [1.0, 1.0, 1.0, 1.0],
+                thickness: 2.0,
+            },
+            layer: -2,
+        }
+    }
+
+    fn saved_state(points: Vec<Vec<Point>>) -> SavedState {
+        SavedState {
+            sequences: vec![Sequence {
+                id: Uuid::new_v4().to_string(),
+                name: "Sequence".to_string(),
+                background_fill: None,
+                duration_ms: 20000,
+                active_polygons: points
+                    .into_iter()
+                    .map(|points| to_saved_polygon_config(&polygon_config(points), 0, None))
+                    .collect(),
+                polygon_motion_paths: Vec::new(),
+                active_text_items: Vec::new(),
+                active_image_items: Vec::new(),
+                active_video_items: Vec::new(),
+                groups: Vec::new(),
+            }],
+            timeline_state: SavedTimelineStateConfig {
+                timeline_sequences: Vec::new(),
+            },
+        }
+    }
+
+    fn saved_points(saved_state: &SavedState) -> Vec<Vec<Point>> {
+        saved_state.sequences[0]
+            .active_polygons
+            .iter()
+            .map(|p| p.points.clone())
+            .collect()
+    }
+
+    #[test]
+    fn edited_points_round_trip_exactly() {
+        // none of these land on a thousandth
+        let points = vec![
+            Point { x: 0.0, y: 0.0 },
+            Point {
+                x: 1.0 / 3.0,
+                y: 0.12345,
+            },
+            Point {
+                x: 0.99991,
+                y: 2.0 / 7.0,
+            },
+        ];
+
+        let saved = to_saved_polygon_config(&polygon_config(points.clone()), 37, None);
+        let json = serde_json::to_string(&saved).expect("Couldn't serialize polygon");
+        let restored: SavedPolygonConfig =
+            serde_json::from_str(&json).expect("Couldn't read polygon");
+
+        assert_eq!(restored.points, points);
+        assert_eq!(restored.rotation, 37);
+    }
+
+    #[test]
+    fn migration_gives_pointless_polygons_square_points() {
+        let triangle = vec![
+            Point { x: 0.5, y: 0.0 },
+            Point { x: 1.0, y: 1.0 },
+            Point { x: 0.0, y: 1.0 },
+        ];
+        let mut state = saved_state(vec![Vec::new(), triangle.clone()]);
+
+        assert!(migrate_saved_state(&mut state));
+        assert_eq!(saved_points(&state), vec![square_points(), triangle]);
+    }
+
+    #[test]
+    fn migration_leaves_saved_points_alone() {
+        let mut squares = saved_state(vec![square_points()]);
+        let mut empty = saved_state(Vec::new());
+
+        assert!(!migrate_saved_state(&mut squares));
+        assert_eq!(saved_points(&squares), vec![square_points()]);
+        assert!(!migrate_saved_state(&mut empty));
+    }
+}
@@ -0,0 +1,422 @@
+use std::{cell::RefCell, rc::Rc};
+
+use js_sys::{Array, Reflect, Uint8Array};
+use log::info;
+use stunts_engine::st_video::{MousePosition, PointType, SourceData};
+use wasm_bindgen::{prelude::*, JsCast};
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{
+    window, Blob, BlobEvent, BlobPropertyBag, DisplayMediaStreamConstraints, MediaRecorder,
+    MediaRecorderOptions, MediaStream, MediaStreamTrack, PointerEvent,
+};
+
+use crate::helpers::video_probe::wait_for_event;
+
+// pointer samples closer together than this are skipped
+const SAMPLE_INTERVAL_MS: f64 = 10.0;
+// ask the recorder for data every second so a long capture isn't one huge blob
+const TIMESLICE_MS: i32 = 1000;
+
+pub struct CaptureResult {
+    pub file_data: Vec<u8>,
+    pub mime_type: String,
+    pub mouse_positions: Vec<MousePosition>,
+    /// milliseconds from the start of the recording
+    pub clicks: Vec<u128>,
+    pub source_data: SourceData,
+}
+
+#[derive(Default)]
+struct PointerLog {
+    positions: Vec<MousePosition>,
+    clicks: Vec<u128>,
+    last_sample: Option<f64>,
+}
+
+impl PointerLog {
+    /// `elapsed_ms` is from the start of the recording, `(x, y)` in frame pixels
+    fn record_move(&mut self, elapsed_ms: f64, (x, y): (f32, f32)) {
+        if self
+            .last_sample
+            .is_some_and(|last| elapsed_ms - last < SAMPLE_INTERVAL_MS)
+        {
+            return;
+        }
+        self.last_sample = Some(elapsed_ms);
+
+        self.push(elapsed_ms, (x, y));
+    }
+
+    /// clicks are always kept, with a position so focus can find them
+    fn record_click(&mut self, elapsed_ms: f64, (x, y): (f32, f32)) {
+        self.clicks.push(elapsed_ms.max(0.0) as u128);
+        self.push(elapsed_ms, (x, y));
+    }
+
+    fn push(&mut self, elapsed_ms: f64, (x, y): (f32, f32)) {
+        self.positions.push(MousePosition {
+            x,
+            y,
+            timestamp: elapsed_ms.max(0.0) as u128,
+            point_type: PointType::Point,
+        });
+    }
+}
+
+/// Records a display surface with MediaRecorder while logging the pointer.
+/// The pointer can only be seen over this page, so positions are recorded
+/// when capturing the current tab and left empty for other windows.
+pub struct ScreenRecorder {
+    stream: MediaStream,
+    recorder: MediaRecorder,
+    mime_type: String,
+    chunks: Rc<RefCell<Vec<Blob>>>,
+    pointer_log: Rc<RefCell<PointerLog>>,
+    source_data: SourceData,
+    _on_data: Closure<dyn FnMut(BlobEvent)>,
+    on_pointer_move: Closure<dyn FnMut(PointerEvent)>,
+    on_pointer_down: Closure<dyn FnMut(PointerEvent)>,
+    _on_ended: Closure<dyn FnMut()>,
+}
+
+fn now_ms() -> f64 {
+    window()
+        .and_then(|w| w.performance())
+        .map(|p| p.now())
+        .unwrap_or(0.0)
+}
+
+fn recording_mime_type() -> String {
+    ["video/webm;codecs=vp9", "video/webm;codecs=vp8", "video/webm"]
+        .into_iter()
+        .find(|t| MediaRecorder::is_type_supported(t))
+        .unwrap_or("video/webm")
+        .to_string()
+}
+
+fn track_size(track: &MediaStreamTrack) -> (u32, u32) {
+    let settings = track.get_settings();
+    let width = settings.get_width().unwrap_or(0);
+    let height = settings.get_height().unwrap_or(0);
+
+    (width.max(0) as u32, height.max(0) as u32)
+}
+
+impl ScreenRecorder {
+    /// Prompts for a surface and starts recording. `on_ended` runs if the
+    /// browser's own "stop sharing" control ends the capture.
+    pub async fn start(on_ended: impl Fn() + 'static) -> Result<ScreenRecorder, String> {
+        let window = window().expect("Couldn't get window");
+        let media_devices = window
+            .navigator()
+            .media_devices()
+            .map_err(|_| "Screen capture isn't available in this browser")?;
+
+        let constraints = DisplayMediaStreamConstraints::new();
+        constraints.set_video(&JsValue::TRUE);
+        constraints.set_audio(&JsValue::FALSE);
+        // lets the user pick this tab, the only surface we can track the pointer over
+        let _ = Reflect::set(&constraints, &"preferCurrentTab".into(), &JsValue::TRUE);
+
+        let stream: MediaStream = JsFuture::from(
+            media_devices
+                .get_display_media_with_constraints(&constraints)
+                .map_err(|_| "Couldn't request screen capture")?,
+        )
+        .await
+        .map_err(|_| "Screen capture was cancelled")?
+        .dyn_into()
+        .map_err(|_| "Couldn't get capture stream")?;
+
+        let track: MediaStreamTrack = stream
+            .get_video_tracks()
+            .get(0)
+            .dyn_into()
+            .map_err(|_| "Capture has no video track")?;
+        let (width, height) = track_size(&track);
+
+        let source_data = SourceData {
+            id: 0,
+            name: track.label(),
+            width,
+            height,
+            x: 0,
+            y: 0,
+            scale_factor: 1.0,
+        };
+
+        let mime_type = recording_mime_type();
+        let options = MediaRecorderOptions::new();
+        options.set_mime_type(&mime_type);
+
+        let recorder = MediaRecorder::new_with_media_stream_and_media_recorder_options(
+            &stream, &options,
+        )
+        .map_err(|_| "Couldn't create recorder")?;
+
+        let chunks = Rc::new(RefCell::new(Vec::new()));
+        let on_data = Closure::<dyn FnMut(BlobEvent)>::new({
+            let chunks = chunks.clone();
+            move |ev: BlobEvent| {
+                if let Some(data) = ev.data() {
+                    chunks.borrow_mut().push(data);
+                }
+            }
+        });
+        recorder.set_ondataavailable(Some(on_data.as_ref().unchecked_ref()));
+
+        let started_at = now_ms();
+        let pointer_log = Rc::new(RefCell::new(PointerLog::default()));
+
+        // client coordinates scaled to the captured frame
+        let to_frame = move |ev: &PointerEvent| {
+            let window = web_sys::window().expect("Couldn't get window");
+            let inner_width = window.inner_width().ok().and_then(|w| w.as_f64()).unwrap_or(1.0);
+            let inner_height = window.inner_height().ok().and_then(|h| h.as_f64()).unwrap_or(1.0);
+
+            (
+                (ev.client_x() as f64 * width as f64 / inner_width) as f32,
+                (ev.client_y() as f64 * height as f64 / inner_height) as f32,
+            )
+        };
+
+        let on_pointer_move = Closure::<dyn FnMut(PointerEvent)>::new({
+            let pointer_log = pointer_log.clone();
+            move |ev: PointerEvent| {
+                pointer_log
+                    .borrow_mut()
+                    .record_move(now_ms() - started_at, to_frame(&ev));
+            }
+        });
+
+        let on_pointer_down = Closure::<dyn FnMut(PointerEvent)>::new({
+            let pointer_log = pointer_log.clone();
+            move |ev: PointerEvent| {
+                pointer_log
+                    .borrow_mut()
+                    .record_click(now_ms() - started_at, to_frame(&ev));
+            }
+        });
+
+        window
+            .add_event_listener_with_callback("pointermove", on_pointer_move.as_ref().unchecked_ref())
+            .expect("Couldn't add pointermove listener");
+        window
+            .add_event_listener_with_callback("pointerdown", on_pointer_down.as_ref().unchecked_ref())
+            .expect("Couldn't add pointerdown listener");
+
+        let on_ended = Closure::<dyn FnMut()>::new(move || on_ended());
+        track.set_onended(Some(on_ended.as_ref().unchecked_ref()));
+
+        recorder
+            .start_with_time_slice(TIMESLICE_MS)
+            .map_err(|_| "Couldn't start recording")?;
+
+        info!("Screen capture started {}x{} ({})", width, height, mime_type);
+
+        Ok(ScreenRecorder {
+            stream,
+            recorder,
+            mime_type,
+            chunks,
+            pointer_log,
+            source_data,
+            _on_data: on_data,
+            on_pointer_move,
+            on_pointer_down,
+            _on_ended: on_ended,
+        })
+    }
+
+    /// Stops recording and hands back the video along with the pointer data
+    pub async fn stop(self) -> Result<CaptureResult, String> {
+        if self.recorder.state() != web_sys::RecordingState::Inactive {
+            let stopped = wait_for_event(&self.recorder, "stop");
+            self.recorder.stop().map_err(|_| "Couldn't stop recorder")?;
+            stopped.await?;
+        }
+
+        self.stream
+            .get_tracks()
+            .iter()
+            .filter_map(|t| t.dyn_into::<MediaStreamTrack>().ok())
+            .for_each(|t| {
+                t.set_onended(None);
+                t.stop();
+            });
+
+        let window = window().expect("Couldn't get window");
+        let _ = window.remove_event_listener_with_callback(
+            "pointermove",
+            self.on_pointer_move.as_ref().unchecked_ref(),
+        );
+        let _ = window.remove_event_listener_with_callback(
+            "pointerdown",
+            self.on_pointer_down.as_ref().unchecked_ref(),
+        );
+        self.recorder.set_ondataavailable(None);
+
+        let parts = Array::new();
+        self.chunks.borrow().iter().for_each(|chunk| {
+            parts.push(chunk);
+        });
+
+        // drop codec parameters, the upload only cares about the container
+        let mime_type = self
+            .mime_type
+            .split(';')
+            .next()
+            .unwrap_or("video/webm")
+            .to_string();
+
+        let options = BlobPropertyBag::new();
+        options.set_type(&mime_type);
+        let blob = Blob::new_with_blob_sequence_and_options(&parts, &options)
+            .map_err(|_| "Couldn't combine recording")?;
+
+        let buffer = JsFuture::from(blob.array_buffer())
+            .await
+            .map_err(|_| "Couldn't read recording")?;
+        let file_data = Uint8Array::new(&buffer).to_vec();
+
+        let pointer_log = self.pointer_log.borrow();
+
+        info!(
+            "Screen capture stopped, {} bytes, {} pointer samples",
+            file_data.len(),
+            pointer_log.positions.len()
+        );
+
+        Ok(CaptureResult {
+            file_data,
+            mime_type,
+            mouse_positions: pointer_log.positions.clone(),
+            clicks: pointer_log.clicks.clone(),
+            source_data: self.source_data.clone(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::helpers::auto_zoom::{generate_zoom_keyframes, AutoZoomSettings};
+    use stunts_engine::animations::{KeyframeValue, UIKeyframe};
+
+    // (ms, zoom) of each zoom keyframe
+    fn zoom_track(keyframes: &[UIKeyframe]) -> Vec<(u128, i32)> {
+        keyframes
+            .iter()
+            .filter_map(|k| match k.value {
+                KeyframeValue::Zoom(zoom) => Some((k.time.as_millis(), zoom)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn keyframes_for(log: &PointerLog, duration_ms: i64) -> Vec<(u128, i32)> {
+        zoom_track(&generate_zoom_keyframes(
+            &log.positions,
+            &log.clicks,
+            duration_ms,
+            (1920, 1080),
+            &AutoZoomSettings::default(),
+        )
+        .zoom)
+    }
+
+    // jumps in, jitters in place until 1500ms, then jumps away
+    fn dwell_log() -> PointerLog {
+        let mut log = PointerLog::default();
+        log.record_move(0.0, (100.0, 100.0));
+
+        for step in 0..=28 {
+            let jitter = (step % 3) as f32;
+            log.record_move(100.0 + step as f64 * 50.0, (500.0 + jitter, 300.0 - jitter));
+        }
+
+        log.record_move(1600.0, (900.0, 500.0));
+        log
+    }
+
+    #[test]
+    fn moves_closer_than_the_sample_interval_are_skipped() {
+        let mut log = PointerLog::default();
+        log.record_move(0.0, (0.0, 0.0));
+        log.record_move(4.0, (1.0, 0.0));
+        log.record_move(10.0, (2.0, 0.0));
+        log.record_click(12.0, (3.0, 0.0));
+
+        assert_eq!(
+            log.positions
+                .iter()
+                .map(|p| p.timestamp)
+                .collect::<Vec<_>>(),
+            vec![0, 10, 12]
+        );
+        assert_eq!(log.clicks, vec![12]);
+    }
+
+    #[test]
+    fn dwell_zooms_in_until_the_pointer_leaves() {
+        assert_eq!(
+            keyframes_for(&dwell_log(), 2500),
+            vec![(0, 100), (100, 135), (1500, 135), (2100, 100), (2500, 100)]
+        );
+    }
+
+    #[test]
+    fn dwell_holds_through_the_exit_without_fast_zoom_out() {
+        let log = dwell_log();
+        let settings = AutoZoomSettings {
+            zoom_out_on_fast_movement: false,
+            ..AutoZoomSettings::default()
+        };
+
+        assert_eq!(
+            zoom_track(&generate_zoom_keyframes(
+                &log.positions,
+                &log.clicks,
+                2500,
+                (1920, 1080),
+                &settings
+            )
+            .zoom),
+            vec![(0, 100), (100, 135), (1600, 135), (2200, 100), (2500, 100)]
+        );
+    }
+
+    #[test]
+    fn click_zooms_in_ahead_of_time_and_holds() {
+        let mut log = PointerLog::default();
+
+        // steady movement that never settles
+        for step in 0..4 {
+            log.record_move(step as f64 * 500.0, (step as f32 * 200.0, 0.0));
+        }
+        log.record_click(2000.0, (800.0, 0.0));
+        for step in 1..4 {
+            log.record_move(
+                2000.0 + step as f64 * 500.0,
+                (800.0 + step as f32 * 200.0, 0.0),
+            );
+        }
+
+        assert_eq!(
+            keyframes_for(&log, 3700),
+            vec![(0, 100), (1100, 100), (1700, 135), (3200, 135), (3700, 100)]
+        );
+    }
+
+    #[test]
+    fn fast_sweep_stays_zoomed_out() {
+        let mut log = PointerLog::default();
+
+        // 100px every 20ms
+        for step in 0..=25 {
+            log.record_move(step as f64 * 20.0, (step as f32 * 100.0, 400.0));
+        }
+
+        assert_eq!(keyframes_for(&log, 1400), vec![(0, 100), (1400, 100)]);
+    }
+}
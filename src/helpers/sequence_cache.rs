@@ -0,0 +1,201 @@
+use std::collections::{HashSet, VecDeque};
+
+use stunts_engine::{animations::Sequence, editor::Editor};
+
+// sequences kept materialized in the editor at once
+pub const SEQUENCE_CACHE_CAPACITY: usize = 3;
+
+/// Tracks which sequences currently have their objects in the `Editor`,
+/// most recently opened first. A sequence only counts as ready once its
+/// restore has finished.
+#[derive(Clone, Debug)]
+pub struct SequenceCache {
+    capacity: usize,
+    recent: VecDeque<String>,
+    restoring: HashSet<String>,
+    // the sequence the user has open, restores only reveal this one
+    open: Option<String>,
+}
+
+impl Default for SequenceCache {
+    fn default() -> Self {
+        SequenceCache::new(SEQUENCE_CACHE_CAPACITY)
+    }
+}
+
+impl SequenceCache {
+    pub fn new(capacity: usize) -> Self {
+        SequenceCache {
+            capacity: capacity.max(1),
+            recent: VecDeque::new(),
+            restoring: HashSet::new(),
+            open: None,
+        }
+    }
+
+    pub fn contains(&self, sequence_id: &str) -> bool {
+        self.recent.iter().any(|id| id == sequence_id)
+    }
+
+    /// Objects are all in the editor and can be revealed right away
+    pub fn is_ready(&self, sequence_id: &str) -> bool {
+        self.contains(sequence_id) && !self.is_restoring(sequence_id)
+    }
+
+    pub fn is_restoring(&self, sequence_id: &str) -> bool {
+        self.restoring.contains(sequence_id)
+    }
+
+    /// Remembers the sequence as the open one, replacing whatever was open before
+    pub fn open(&mut self, sequence_id: String) {
+        self.open = Some(sequence_id);
+    }
+
+    pub fn is_open(&self, sequence_id: &str) -> bool {
+        self.open.as_deref() == Some(sequence_id)
+    }
+
+    /// Returns false when a restore for the sequence is already running,
+    /// that one will finish it
+    pub fn begin_restore(&mut self, sequence_id: String) -> bool {
+        self.restoring.insert(sequence_id)
+    }
+
+    /// Ends a restore and returns whether its objects should stay. They shouldn't
+    /// when the sequence was evicted or the cache cleared while it ran.
+    pub fn finish_restore(&mut self, sequence_id: &str) -> bool {
+        self.restoring.remove(sequence_id);
+        self.contains(sequence_id)
+    }
+
+    /// Marks the sequence as most recently used and returns the sequences
+    /// pushed out to make room for it. Ones still restoring aren't returned,
+    /// `finish_restore` drops their objects once they're all in.
+    pub fn touch(&mut self, sequence_id: String) -> Vec<String> {
+        self.recent.retain(|id| *id != sequence_id);
+        self.recent.push_front(sequence_id);
+
+        let mut evicted = Vec::new();
+        while self.recent.len() > self.capacity {
+            if let Some(id) = self.recent.pop_back() {
+                if !self.restoring.contains(&id) {
+                    evicted.push(id);
+                }
+            }
+        }
+
+        evicted
+    }
+
+    /// Forgets every sequence. Restores still running drop their objects when they finish.
+    pub fn clear(&mut self) {
+        self.recent.clear();
+        self.open = None;
+    }
+}
+
+/// Drops a sequence's objects from the editor, freeing their GPU buffers and textures.
+/// The saved state is untouched so the sequence can be restored again later.
+pub fn release_sequence_objects(editor: &mut Editor, sequence: &Sequence) {
+    editor.polygons.retain(|p| {
+        !sequence
+            .active_polygons
+            .iter()
+            .any(|ap| ap.id == p.id.to_string())
+    });
+    editor.text_items.retain(|t| {
+        !sequence
+            .active_text_items
+            .iter()
+            .any(|at| at.id == t.id.to_string())
+    });
+    editor.image_items.retain(|i| {
+        !sequence
+            .active_image_items
+            .iter()
+            .any(|ai| ai.id == i.id.to_string())
+    });
+    editor.video_items.retain(|v| {
+        !sequence
+            .active_video_items
+            .iter()
+            .any(|av| av.id == v.id.to_string())
+    });
+
+    log::info!("Released sequence {}", sequence.id);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ids(ids: &[&str]) -> Vec<String> {
+        ids.iter().map(|id| id.to_string()).collect()
+    }
+
+    #[test]
+    fn touch_evicts_the_least_recently_opened() {
+        let mut cache = SequenceCache::new(2);
+
+        assert!(cache.touch("a".into()).is_empty());
+        assert!(cache.touch("b".into()).is_empty());
+        assert!(cache.touch("a".into()).is_empty());
+        assert_eq!(cache.touch("c".into()), ids(&["b"]));
+        assert!(cache.contains("a") && cache.contains("c"));
+    }
+
+    #[test]
+    fn sequences_are_ready_once_their_restore_finishes() {
+        let mut cache = SequenceCache::new(2);
+
+        cache.touch("a".into());
+        assert!(cache.begin_restore("a".into()));
+        assert!(!cache.is_ready("a"));
+
+        // opening it again doesn't start a second restore
+        assert!(!cache.begin_restore("a".into()));
+
+        assert!(cache.finish_restore("a"));
+        assert!(cache.is_ready("a"));
+    }
+
+    #[test]
+    fn restores_evicted_while_running_are_dropped_when_they_finish() {
+        let mut cache = SequenceCache::new(1);
+
+        cache.touch("a".into());
+        cache.begin_restore("a".into());
+
+        // still restoring, so the caller must not release it yet
+        assert!(cache.touch("b".into()).is_empty());
+
+        assert!(!cache.finish_restore("a"));
+        assert!(!cache.is_ready("a"));
+    }
+
+    #[test]
+    fn restores_reopened_while_running_are_kept() {
+        let mut cache = SequenceCache::new(1);
+
+        cache.touch("a".into());
+        cache.begin_restore("a".into());
+        cache.touch("b".into());
+        cache.touch("a".into());
+
+        assert!(cache.finish_restore("a"));
+        assert!(cache.is_ready("a"));
+    }
+
+    #[test]
+    fn only_the_open_sequence_is_revealed() {
+        let mut cache = SequenceCache::new(2);
+
+        cache.open("a".into());
+        cache.open("b".into());
+        assert!(!cache.is_open("a"));
+        assert!(cache.is_open("b"));
+
+        cache.clear();
+        assert!(!cache.is_open("b"));
+    }
+}
@@ -0,0 +1,342 @@
+use std::f32::consts::PI;
+
+use stunts_engine::editor::Point;
+use stunts_engine::polygon::SavedShape;
+
+use crate::helpers::pen_path::PenPath;
+use crate::helpers::polygons::square_points;
+
+// enough segments that the edge doesn't look faceted at typical sizes
+const ELLIPSE_SEGMENTS: usize = 48;
+
+/// Shapes offered in the palette, with their default parameters
+pub fn shape_library() -> Vec<SavedShape> {
+    vec![
+        SavedShape::Rectangle,
+        SavedShape::RoundedRect { radius: 16 },
+        SavedShape::Ellipse,
+        SavedShape::Triangle,
+        SavedShape::RegularPolygon { sides: 6 },
+        SavedShape::Star {
+            points: 5,
+            inner_radius: 45,
+        },
+        SavedShape::Arrow {
+            head_length: 35,
+            shaft_width: 40,
+        },
+        SavedShape::Line { thickness: 20 },
+    ]
+}
+
+pub fn shape_label(shape: &SavedShape) -> &'static str {
+    match shape {
+        SavedShape::Rectangle => "Rectangle",
+        SavedShape::RoundedRect { .. } => "Rounded Rect",
+        SavedShape::Ellipse => "Ellipse",
+        SavedShape::Triangle => "Triangle",
+        SavedShape::RegularPolygon { .. } => "Polygon",
+        SavedShape::Star { .. } => "Star",
+        SavedShape::Arrow { .. } => "Arrow",
+        SavedShape::Line { .. } => "Line",
+        SavedShape::Path { .. } => "Path",
+    }
+}
+
+pub fn shape_icon(shape: &SavedShape) -> &'static str {
+    match shape {
+        SavedShape::Rectangle | SavedShape::RoundedRect { .. } => "square",
+        SavedShape::Ellipse => "dot",
+        SavedShape::Triangle => "triangle",
+        SavedShape::RegularPolygon { .. } => "octagon",
+        SavedShape::Star { .. } => "shapes",
+        SavedShape::Arrow { .. } => "motion-arrow",
+        SavedShape::Line { .. } => "minus",
+        SavedShape::Path { .. } => "brush",
+    }
+}
+
+/// Size a shape is created at
+pub fn shape_dimensions(shape: &SavedShape) -> (f32, f32) {
+    match shape {
+        SavedShape::Arrow { .. } => (160.0, 80.0),
+        SavedShape::Line { .. } => (200.0, 20.0),
+        _ => (100.0, 100.0),
+    }
+}
+
+/// Rounded corners come from the polygon's border radius rather than extra points
+pub fn shape_border_radius(shape: &SavedShape) -> f32 {
+    match shape {
+        SavedShape::RoundedRect { radius } => *radius as f32,
+        _ => 0.0,
+    }
+}
+
+/// Editable parameters as (name, value, min, max)
+pub fn shape_params(shape: &SavedShape) -> Vec<(&'static str, i32, i32, i32)> {
+    match shape {
+        SavedShape::RoundedRect { radius } => vec![("Radius", *radius, 0, 200)],
+        SavedShape::RegularPolygon { sides } => vec![("Sides", *sides, 3, 24)],
+        SavedShape::Star {
+            points,
+            inner_radius,
+        } => vec![("Points", *points, 3, 24), ("Inner %", *inner_radius, 5, 95)],
+        SavedShape::Arrow {
+            head_length,
+            shaft_width,
+        } => vec![("Head %", *head_length, 5, 95), ("Shaft %", *shaft_width, 5, 100)],
+        SavedShape::Line { thickness } => vec![("Thickness %", *thickness, 1, 100)],
+        // paths are edited point by point with the pen tool
+        SavedShape::Rectangle
+        | SavedShape::Ellipse
+        | SavedShape::Triangle
+        | SavedShape::Path { .. } => Vec::new(),
+    }
+}
+
+/// Copy of `shape` with the named parameter set, clamped to its range
+pub fn with_shape_param(shape: &SavedShape, name: &str, value: i32) -> SavedShape {
+    let value = shape_params(shape)
+        .iter()
+        .find(|(param, _, _, _)| *param == name)
+        .map(|(_, _, min, max)| value.clamp(*min, *max))
+        .unwrap_or(value);
+
+    let mut shape = shape.clone();
+
+    match (&mut shape, name) {
+        (SavedShape::RoundedRect { radius }, "Radius") => *radius = value,
+        (SavedShape::RegularPolygon { sides }, "Sides") => *sides = value,
+        (SavedShape::Star { points, .. }, "Points") => *points = value,
+        (SavedShape::Star { inner_radius, .. }, "Inner %") => *inner_radius = value,
+        (SavedShape::Arrow { head_length, .. }, "Head %") => *head_length = value,
+        (SavedShape::Arrow { shaft_width, .. }, "Shaft %") => *shaft_width = value,
+        (SavedShape::Line { thickness }, "Thickness %") => *thickness = value,
+        _ => log::warn!("Unknown shape parameter {}", name),
+    }
+
+    shape
+}
+
+/// points evenly spaced around the unit box's inscribed ellipse, starting at the top.
+/// `radius` gives each point's distance from the center, 1.0 being the box edge
+fn ring_points(count: usize, radius: impl Fn(usize) -> f32) -> Vec<Point> {
+    (0..count)
+        .map(|i| {
+            let angle = -PI / 2.0 + 2.0 * PI * i as f32 / count as f32;
+            let r = radius(i) * 0.5;
+
+            Point {
+                x: 0.5 + r * angle.cos(),
+                y: 0.5 + r * angle.sin(),
+            }
+        })
+        .collect()
+}
+
+/// Normalized (0.0 - 1.0) outline of a shape, scaled to the polygon's dimensions by the engine
+pub fn shape_points(shape: &SavedShape) -> Vec<Point> {
+    match shape {
+        SavedShape::Rectangle | SavedShape::RoundedRect { .. } => square_points(),
+        SavedShape::Ellipse => ring_points(ELLIPSE_SEGMENTS, |_| 1.0),
+        SavedShape::Triangle => vec![
+            Point { x: 0.5, y: 0.0 },
+            Point { x: 1.0, y: 1.0 },
+            Point { x: 0.0, y: 1.0 },
+        ],
+        SavedShape::RegularPolygon { sides } => ring_points((*sides).max(3) as usize, |_| 1.0),
+        SavedShape::Star {
+            points,
+            inner_radius,
+        } => {
+            let inner = (*inner_radius).clamp(5, 95) as f32 / 100.0;
+
+            // outer and inner points alternate
+            ring_points((*points).max(3) as usize * 2, |i| {
+                if i % 2 == 0 {
+                    1.0
+                } else {
+                    inner
+                }
+            })
+        }
+        SavedShape::Arrow {
+            head_length,
+            shaft_width,
+        } => {
+            let neck = 1.0 - (*head_length).clamp(5, 95) as f32 / 100.0;
+            let half_shaft = (*shaft_width).clamp(5, 100) as f32 / 200.0;
+
+            vec![
+                Point {
+                    x: 0.0,
+                    y: 0.5 - half_shaft,
+                },
+                Point {
+                    x: neck,
+                    y: 0.5 - half_shaft,
+                },
+                Point { x: neck, y: 0.0 },
+                Point { x: 1.0, y: 0.5 },
+                Point { x: neck, y: 1.0 },
+                Point {
+                    x: neck,
+                    y: 0.5 + half_shaft,
+                },
+                Point {
+                    x: 0.0,
+                    y: 0.5 + half_shaft,
+                },
+            ]
+        }
+        SavedShape::Line { thickness } => {
+            let half = (*thickness).clamp(1, 100) as f32 / 200.0;
+
+            vec![
+                Point { x: 0.0, y: 0.5 - half },
+                Point { x: 1.0, y: 0.5 - half },
+                Point { x: 1.0, y: 0.5 + half },
+                Point { x: 0.0, y: 0.5 + half },
+            ]
+        }
+        SavedShape::Path { anchors, closed } => {
+            PenPath::from_saved(anchors, *closed, Point { x: 0.5, y: 0.5 }, (1.0, 1.0)).outline()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(a: Point, b: Point) -> bool {
+        (a.x - b.x).abs() < 1e-5 && (a.y - b.y).abs() < 1e-5
+    }
+
+    fn in_unit_box(points: &[Point]) -> bool {
+        points
+            .iter()
+            .all(|p| (-1e-5..=1.0 + 1e-5).contains(&p.x) && (-1e-5..=1.0 + 1e-5).contains(&p.y))
+    }
+
+    #[test]
+    fn library_shapes_fill_the_unit_box() {
+        for shape in shape_library() {
+            let points = shape_points(&shape);
+
+            assert!(
+                points.len() >= 3,
+                "{} has {} points",
+                shape_label(&shape),
+                points.len()
+            );
+            assert!(
+                in_unit_box(&points),
+                "{} leaves the box",
+                shape_label(&shape)
+            );
+        }
+    }
+
+    #[test]
+    fn regular_polygons_start_at_the_top() {
+        let points = shape_points(&SavedShape::RegularPolygon { sides: 6 });
+
+        assert_eq!(points.len(), 6);
+        assert!(close(points[0], Point { x: 0.5, y: 0.0 }));
+        // opposite the first point
+        assert!(close(points[3], Point { x: 0.5, y: 1.0 }));
+    }
+
+    #[test]
+    fn too_few_sides_or_points_make_a_triangle() {
+        assert_eq!(
+            shape_points(&SavedShape::RegularPolygon { sides: 0 }).len(),
+            3
+        );
+        assert_eq!(
+            shape_points(&SavedShape::Star {
+                points: -2,
+                inner_radius: 45,
+            })
+            .len(),
+            6
+        );
+    }
+
+    #[test]
+    fn star_points_alternate_outer_and_inner() {
+        let points = shape_points(&SavedShape::Star {
+            points: 5,
+            inner_radius: 40,
+        });
+        let center = Point { x: 0.5, y: 0.5 };
+        let radius = |p: &Point| ((p.x - center.x).powi(2) + (p.y - center.y).powi(2)).sqrt();
+
+        assert_eq!(points.len(), 10);
+        for (i, point) in points.iter().enumerate() {
+            let expected = if i % 2 == 0 { 0.5 } else { 0.2 };
+            assert!((radius(point) - expected).abs() < 1e-5);
+        }
+    }
+
+    #[test]
+    fn arrow_points_at_the_right_edge() {
+        let points = shape_points(&SavedShape::Arrow {
+            head_length: 35,
+            shaft_width: 40,
+        });
+
+        assert!(points.iter().any(|p| close(*p, Point { x: 1.0, y: 0.5 })));
+        // shaft spans 40% of the height around the middle
+        assert!(close(points[0], Point { x: 0.0, y: 0.3 }));
+        assert!(close(points[6], Point { x: 0.0, y: 0.7 }));
+    }
+
+    #[test]
+    fn params_are_clamped_to_their_range() {
+        let hexagon = SavedShape::RegularPolygon { sides: 6 };
+
+        assert!(matches!(
+            with_shape_param(&hexagon, "Sides", 100),
+            SavedShape::RegularPolygon { sides: 24 }
+        ));
+        assert!(matches!(
+            with_shape_param(&hexagon, "Sides", 1),
+            SavedShape::RegularPolygon { sides: 3 }
+        ));
+        assert!(matches!(
+            with_shape_param(
+                &SavedShape::Star {
+                    points: 5,
+                    inner_radius: 45,
+                },
+                "Inner %",
+                0,
+            ),
+            SavedShape::Star {
+                points: 5,
+                inner_radius: 5,
+            }
+        ));
+    }
+
+    #[test]
+    fn unknown_params_leave_the_shape_alone() {
+        assert!(shape_params(&SavedShape::Rectangle).is_empty());
+        assert!(matches!(
+            with_shape_param(&SavedShape::Line { thickness: 20 }, "Sides", 8),
+            SavedShape::Line { thickness: 20 }
+        ));
+    }
+
+    #[test]
+    fn only_rounded_rects_have_a_border_radius() {
+        assert_eq!(
+            shape_border_radius(&SavedShape::RoundedRect { radius: 16 }),
+            16.0
+        );
+        assert_eq!(shape_border_radius(&SavedShape::Rectangle), 0.0);
+    }
+}
@@ -0,0 +1,166 @@
+use serde::{Deserialize, Serialize};
+use stunts_engine::editor::Editor;
+use stunts_engine::text_due::{SavedTextRendererConfig, TextAlignment};
+use uuid::Uuid;
+
+// for text saved before line height and letter spacing could be set
+pub const DEFAULT_LINE_HEIGHT: i32 = 120;
+pub const DEFAULT_LETTER_SPACING: i32 = 0;
+
+/// Everything the text editor can change about a text item
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TextStyle {
+    pub text: String,
+    pub font_family: String,
+    pub font_size: i32,
+    /// percent of the font size
+    pub line_height: i32,
+    /// pixels added between letters
+    pub letter_spacing: i32,
+    pub alignment: TextAlignment,
+    pub color: [i32; 4],
+}
+
+impl TextStyle {
+    pub fn from_saved(t: &SavedTextRendererConfig) -> Self {
+        TextStyle {
+            text: t.text.clone(),
+            font_family: t.font_family.clone(),
+            font_size: t.font_size,
+            line_height: t.line_height.unwrap_or(DEFAULT_LINE_HEIGHT),
+            letter_spacing: t.letter_spacing.unwrap_or(DEFAULT_LETTER_SPACING),
+            alignment: t.text_alignment.unwrap_or_default(),
+            color: t.color,
+        }
+    }
+
+    pub fn write_saved(&self, t: &mut SavedTextRendererConfig) {
+        t.text = self.text.clone();
+        t.font_family = self.font_family.clone();
+        t.font_size = self.font_size;
+        t.line_height = Some(self.line_height);
+        t.letter_spacing = Some(self.letter_spacing);
+        t.text_alignment = Some(self.alignment);
+        t.color = self.color;
+    }
+}
+
+/// Fonts the editor has loaded, in the order the font manager keeps them
+pub fn font_names(editor: &Editor) -> Vec<String> {
+    editor
+        .font_manager
+        .font_data
+        .iter()
+        .map(|(name, _)| name.clone())
+        .collect()
+}
+
+/// Puts a style on the text item in the editor and lays its glyphs out again
+pub fn apply_text_style(
+    editor: &mut Editor,
+    text_id: Uuid,
+    style: &TextStyle,
+) -> Result<(), String> {
+    let font_data = editor
+        .font_manager
+        .get_font_by_name(&style.font_family)
+        .ok_or(format!("Unknown font \"{}\"", style.font_family))?;
+
+    let gpu_resources = editor
+        .gpu_resources
+        .as_ref()
+        .ok_or("Couldn't get gpu resources")?;
+
+    let text = editor
+        .text_items
+        .iter_mut()
+        .find(|t| t.id == text_id)
+        .ok_or("Couldn't find text item")?;
+
+    if text.font_family != style.font_family {
+        text.set_font(font_data);
+        text.font_family = style.font_family.clone();
+    }
+
+    text.text = style.text.clone();
+    text.font_size = style.font_size;
+    text.line_height = style.line_height;
+    text.letter_spacing = style.letter_spacing;
+    text.text_alignment = style.alignment;
+    text.color = style.color;
+
+    text.render_text(&gpu_resources.device, &gpu_resources.queue);
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use stunts_engine::polygon::SavedPoint;
+
+    use super::*;
+
+    // as saved before line height, letter spacing and alignment could be set
+    fn saved_text() -> SavedTextRendererConfig {
+        SavedTextRendererConfig {
+            id: Uuid::new_v4().to_string(),
+            name: "Title".to_string(),
+            text: "Hello".to_string(),
+            font_family: "Aleo".to_string(),
+            dimensions: (200, 50),
+            position: SavedPoint { x: 40, y: 60 },
+            layer: 1,
+            color: [20, 20, 200, 255],
+            font_size: 28,
+            background_fill: None,
+            line_height: None,
+            letter_spacing: None,
+            text_alignment: None,
+        }
+    }
+
+    #[test]
+    fn older_saves_get_the_default_spacing() {
+        let style = TextStyle::from_saved(&saved_text());
+
+        assert_eq!(style.line_height, DEFAULT_LINE_HEIGHT);
+        assert_eq!(style.letter_spacing, DEFAULT_LETTER_SPACING);
+        assert_eq!(style.alignment, TextAlignment::default());
+        assert_eq!(style.font_size, 28);
+        assert_eq!(style.color, [20, 20, 200, 255]);
+    }
+
+    #[test]
+    fn styles_round_trip_through_the_saved_text() {
+        let style = TextStyle {
+            text: "Hello\nthere".to_string(),
+            font_family: "Basic".to_string(),
+            font_size: 40,
+            line_height: 150,
+            letter_spacing: -2,
+            alignment: TextAlignment::Right,
+            color: [255, 0, 0, 255],
+        };
+        let mut saved = saved_text();
+
+        style.write_saved(&mut saved);
+
+        assert_eq!(TextStyle::from_saved(&saved), style);
+        assert_eq!(saved.line_height, Some(150));
+        assert_eq!(saved.text_alignment, Some(TextAlignment::Right));
+    }
+
+    #[test]
+    fn writing_a_style_leaves_the_rest_of_the_text_alone() {
+        let original = saved_text();
+        let mut saved = original.clone();
+
+        TextStyle::from_saved(&original).write_saved(&mut saved);
+
+        assert_eq!(saved.id, original.id);
+        assert_eq!(saved.name, original.name);
+        assert_eq!(saved.dimensions, original.dimensions);
+        assert_eq!((saved.position.x, saved.position.y), (40, 60));
+        assert_eq!(saved.layer, original.layer);
+    }
+}
@@ -68,3 +68,16 @@ impl AuthState {
         }
     }
 }
+
+/// Token the login page left in local storage, for code that runs outside of components
+pub fn stored_token() -> Option<String> {
+    let auth_token = web_sys::window()?
+        .local_storage()
+        .ok()??
+        .get_item("auth-token")
+        .ok()??;
+
+    serde_json::from_str::<AuthToken>(&auth_token)
+        .ok()
+        .map(|auth_token| auth_token.token)
+}
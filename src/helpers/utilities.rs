@@ -1,6 +1,8 @@
+use leptos::wasm_bindgen::JsCast;
 use reactive_stores::Store;
 use serde::{Deserialize, Serialize};
 use stunts_engine::{animations::Sequence, timelines::SavedTimelineStateConfig};
+use web_sys::{window, Blob, BlobPropertyBag, HtmlAnchorElement, Url};
 
 #[derive(Store, Clone, PartialEq, Eq, Hash, Serialize, Deserialize, Debug)]
 pub struct SavedState {
@@ -20,3 +22,33 @@ pub struct ProjectData {
 pub struct ProjectsDataFile {
     pub projects: Vec<ProjectData>,
 }
+
+// hands generated bytes (exports and such) to the browser as a file download
+pub fn download_bytes(bytes: &[u8], file_name: &str, mime_type: &str) {
+    let array = js_sys::Uint8Array::from(bytes);
+    let parts = js_sys::Array::new();
+    parts.push(&array.buffer());
+
+    let options = BlobPropertyBag::new();
+    options.set_type(mime_type);
+
+    let blob = Blob::new_with_buffer_source_sequence_and_options(&parts, &options)
+        .expect("Couldn't create blob");
+    let url = Url::create_object_url_with_blob(&blob).expect("Couldn't create object url");
+
+    let document = window()
+        .expect("Couldn't get window")
+        .document()
+        .expect("Couldn't get document");
+    let anchor = document
+        .create_element("a")
+        .expect("Couldn't create anchor")
+        .dyn_into::<HtmlAnchorElement>()
+        .expect("Couldn't convert anchor");
+
+    anchor.set_href(&url);
+    anchor.set_download(file_name);
+    anchor.click();
+
+    let _ = Url::revoke_object_url(&url);
+}
@@ -0,0 +1,411 @@
+use std::{cell::RefCell, rc::Rc, sync::atomic::Ordering};
+
+use js_sys::{Object, Reflect, Uint8Array};
+use log::info;
+use wasm_bindgen::{prelude::*, Clamped, JsCast};
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{
+    window, CanvasRenderingContext2d, EncodedVideoChunk, EncodedVideoChunkType,
+    HtmlCanvasElement, ImageData, VideoEncoder, VideoEncoderConfig, VideoEncoderEncodeOptions,
+    VideoEncoderInit, VideoFrame, VideoFrameInit,
+};
+
+use crate::canvas_renderer::FrameCapture;
+use crate::helpers::video_mux::{mux_webm, plan_mp4_samples, EncodedChunk, WebmTrack};
+
+// samples are timed in microseconds, same as WebCodecs
+const MP4_TIMESCALE: u32 = 1_000_000;
+// frames allowed to queue up in the encoder before we wait on it
+const MAX_ENCODE_QUEUE: u32 = 4;
+
+#[wasm_bindgen]
+extern "C" {
+    // mp4box.js is loaded globally from index.html
+    type Mp4File;
+
+    #[wasm_bindgen(js_namespace = MP4Box, js_name = createFile)]
+    fn mp4box_create_file() -> Mp4File;
+
+    #[wasm_bindgen(method, js_name = addTrack)]
+    fn add_track(this: &Mp4File, options: &Object) -> u32;
+
+    #[wasm_bindgen(method, js_name = addSample)]
+    fn add_sample(this: &Mp4File, track_id: u32, data: &Uint8Array, options: &Object);
+
+    #[wasm_bindgen(method, js_name = getBuffer)]
+    fn get_buffer(this: &Mp4File) -> js_sys::ArrayBuffer;
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum VideoContainer {
+    Mp4,
+    WebM,
+}
+
+impl VideoContainer {
+    /// WebCodecs codec string
+    pub fn codec(&self) -> &'static str {
+        match self {
+            // H.264 high profile, level 5.0, 1080p at the canvas's 2:1 is past level 4's frame size
+            VideoContainer::Mp4 => "avc1.640032",
+            // VP9 profile 0, level 5.0, 8 bit
+            VideoContainer::WebM => "vp09.00.50.08",
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            VideoContainer::Mp4 => "mp4",
+            VideoContainer::WebM => "webm",
+        }
+    }
+
+    pub fn mime_type(&self) -> &'static str {
+        match self {
+            VideoContainer::Mp4 => "video/mp4",
+            VideoContainer::WebM => "video/webm",
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct VideoExportSettings {
+    pub container: VideoContainer,
+    /// output height in pixels, width follows the canvas aspect ratio
+    pub height: u32,
+    pub fps: u32,
+    /// bits per second
+    pub bitrate: u32,
+    pub keyframe_interval_s: f32,
+}
+
+impl Default for VideoExportSettings {
+    fn default() -> Self {
+        Self {
+            container: VideoContainer::Mp4,
+            height: 1080,
+            fps: 30,
+            bitrate: 8_000_000,
+            keyframe_interval_s: 2.0,
+        }
+    }
+}
+
+impl VideoExportSettings {
+    /// codecs want even dimensions
+    pub fn output_size(&self, width: u32, height: u32) -> (u32, u32) {
+        let out_height = self.height.max(2);
+        let out_width = (width as f32 * out_height as f32 / height.max(1) as f32).round() as u32;
+
+        ((out_width.max(2) + 1) & !1, (out_height + 1) & !1)
+    }
+
+    pub fn frame_count(&self, duration_ms: i32) -> u32 {
+        ((duration_ms.max(0) as f32 / 1000.0) * self.fps as f32).ceil() as u32
+    }
+
+    pub fn frame_duration_us(&self) -> i64 {
+        1_000_000 / self.fps.max(1) as i64
+    }
+
+    fn keyframe_every(&self) -> u32 {
+        ((self.keyframe_interval_s * self.fps as f32).round() as u32).max(1)
+    }
+}
+
+fn set_field(object: &Object, key: &str, value: &JsValue) {
+    Reflect::set(object, &JsValue::from_str(key), value).expect("Couldn't set field");
+}
+
+fn scratch_canvas(width: u32, height: u32) -> (HtmlCanvasElement, CanvasRenderingContext2d) {
+    let document = window()
+        .expect("Couldn't get window")
+        .document()
+        .expect("Couldn't get document");
+
+    let canvas = document
+        .create_element("canvas")
+        .expect("Couldn't create canvas")
+        .dyn_into::<HtmlCanvasElement>()
+        .expect("Couldn't cast canvas");
+    canvas.set_width(width);
+    canvas.set_height(height);
+
+    let context = canvas
+        .get_context("2d")
+        .expect("Couldn't get context")
+        .expect("Couldn't find 2d context")
+        .dyn_into::<CanvasRenderingContext2d>()
+        .expect("Couldn't cast context");
+
+    (canvas, context)
+}
+
+pub(crate) async fn sleep_ms(ms: i32) {
+    let promise = js_sys::Promise::new(&mut |resolve, _| {
+        window()
+            .expect("Couldn't get window")
+            .set_timeout_with_callback_and_timeout_and_arguments_0(&resolve, ms)
+            .expect("Couldn't set timeout");
+    });
+
+    let _ = JsFuture::from(promise).await;
+}
+
+fn read_chunk(chunk: &EncodedVideoChunk) -> EncodedChunk {
+    let buffer = Uint8Array::new_with_length(chunk.byte_length());
+    chunk
+        .copy_to_with_buffer_source(&buffer)
+        .expect("Couldn't copy encoded chunk");
+
+    EncodedChunk {
+        data: buffer.to_vec(),
+        timestamp_us: chunk.timestamp() as i64,
+        duration_us: chunk.duration().map(|d| d as i64),
+        is_key: chunk.type_() == EncodedVideoChunkType::Key,
+    }
+}
+
+// the avcC record arrives with the first chunk's metadata
+fn read_description(metadata: &JsValue) -> Option<Vec<u8>> {
+    let decoder_config = Reflect::get(metadata, &JsValue::from_str("decoderConfig")).ok()?;
+    if decoder_config.is_undefined() || decoder_config.is_null() {
+        return None;
+    }
+
+    let description = Reflect::get(&decoder_config, &JsValue::from_str("description")).ok()?;
+    if description.is_undefined() || description.is_null() {
+        return None;
+    }
+
+    Some(Uint8Array::new(&description).to_vec())
+}
+
+fn mux_mp4(
+    chunks: &[EncodedChunk],
+    description: Option<Vec<u8>>,
+    width: u32,
+    height: u32,
+    frame_duration_us: i64,
+) -> Result<Vec<u8>, String> {
+    let description = description.ok_or("Encoder didn't provide an avc decoder config")?;
+
+    let file = mp4box_create_file();
+
+    let track_options = Object::new();
+    set_field(&track_options, "timescale", &MP4_TIMESCALE.into());
+    set_field(&track_options, "width", &width.into());
+    set_field(&track_options, "height", &height.into());
+    set_field(&track_options, "nb_samples", &(chunks.len() as u32).into());
+    set_field(
+        &track_options,
+        "avcDecoderConfigRecord",
+        &Uint8Array::from(&description[..]).buffer(),
+    );
+
+    let track_id = file.add_track(&track_options);
+
+    let samples = plan_mp4_samples(chunks, MP4_TIMESCALE, frame_duration_us);
+
+    for (chunk, sample) in chunks.iter().zip(samples) {
+        let sample_options = Object::new();
+        set_field(&sample_options, "duration", &sample.duration.into());
+        set_field(&sample_options, "dts", &(sample.dts as f64).into());
+        set_field(&sample_options, "cts", &(sample.cts as f64).into());
+        set_field(&sample_options, "is_sync", &sample.is_sync.into());
+
+        file.add_sample(track_id, &Uint8Array::from(&chunk.data[..]), &sample_options);
+    }
+
+    Ok(Uint8Array::new(&file.get_buffer()).to_vec())
+}
+
+/// Closes the encoder however the export ends. The callbacks it was created with
+/// have to outlive it, it can still call them until it's closed.
+struct EncoderGuard {
+    encoder: VideoEncoder,
+    _on_output: Closure<dyn FnMut(EncodedVideoChunk, JsValue)>,
+    _on_error: Closure<dyn FnMut(JsValue)>,
+}
+
+impl Drop for EncoderGuard {
+    fn drop(&mut self) {
+        // already closed if the encoder hit an error
+        let _ = self.encoder.close();
+    }
+}
+
+/// Renders the open sequence at a fixed timestep, encodes it with WebCodecs and muxes
+/// the result. Frames are encoded at the capture's size, create it with `output_size()`.
+/// Setting `capture.cancelled` stops the export at the next frame and returns `Ok(None)`.
+/// `on_progress` receives 0.0 - 1.0.
+pub async fn export_sequence_video(
+    capture: &FrameCapture,
+    duration_ms: i32,
+    settings: &VideoExportSettings,
+    on_progress: impl Fn(f32),
+) -> Result<Option<Vec<u8>>, String> {
+    let (out_width, out_height) = (capture.width, capture.height);
+    let frame_count = settings.frame_count(duration_ms).max(1);
+    let frame_duration_us = settings.frame_duration_us();
+
+    // the live canvas would step the same editor and buffers between our frames
+    let _session = capture.begin();
+
+    info!(
+        "Exporting {} {}x{} with {} frames...",
+        settings.container.extension(),
+        out_width,
+        out_height,
+        frame_count
+    );
+
+    let chunks: Rc<RefCell<Vec<EncodedChunk>>> = Rc::new(RefCell::new(Vec::new()));
+    let description: Rc<RefCell<Option<Vec<u8>>>> = Rc::new(RefCell::new(None));
+    let encode_error: Rc<RefCell<Option<String>>> = Rc::new(RefCell::new(None));
+
+    let on_output = {
+        let chunks = chunks.clone();
+        let description = description.clone();
+
+        Closure::<dyn FnMut(EncodedVideoChunk, JsValue)>::new(
+            move |chunk: EncodedVideoChunk, metadata: JsValue| {
+                if let Some(config) = read_description(&metadata) {
+                    description.borrow_mut().replace(config);
+                }
+
+                chunks.borrow_mut().push(read_chunk(&chunk));
+            },
+        )
+    };
+
+    let on_error = {
+        let encode_error = encode_error.clone();
+
+        Closure::<dyn FnMut(JsValue)>::new(move |e: JsValue| {
+            encode_error.borrow_mut().replace(format!("{:?}", e));
+        })
+    };
+
+    let init = VideoEncoderInit::new(
+        on_error.as_ref().unchecked_ref(),
+        on_output.as_ref().unchecked_ref(),
+    );
+    let guard = EncoderGuard {
+        encoder: VideoEncoder::new(&init)
+            .map_err(|e| format!("Couldn't create encoder: {:?}", e))?,
+        _on_output: on_output,
+        _on_error: on_error,
+    };
+    let encoder = &guard.encoder;
+
+    let config = VideoEncoderConfig::new(settings.container.codec(), out_height, out_width);
+    config.set_bitrate(settings.bitrate as f64);
+    config.set_framerate(settings.fps as f64);
+
+    let support = JsFuture::from(VideoEncoder::is_config_supported(&config))
+        .await
+        .map_err(|e| format!("Couldn't check encoder support: {:?}", e))?;
+    let supported = Reflect::get(&support, &JsValue::from_str("supported"))
+        .ok()
+        .and_then(|s| s.as_bool())
+        .unwrap_or(false);
+
+    if !supported {
+        return Err(format!(
+            "This browser can't encode {} at {}x{}",
+            settings.container.codec(),
+            out_width,
+            out_height
+        ));
+    }
+
+    encoder
+        .configure(&config)
+        .map_err(|e| format!("Couldn't configure encoder: {:?}", e))?;
+
+    // VideoFrame reads the pixels back out of a 2d canvas
+    let (output_canvas, output_context) = scratch_canvas(out_width, out_height);
+
+    let keyframe_every = settings.keyframe_every();
+
+    for frame_index in 0..frame_count {
+        if capture.cancelled.load(Ordering::SeqCst) {
+            info!("Video export cancelled at frame {}", frame_index);
+            return Ok(None);
+        }
+
+        if let Some(e) = encode_error.borrow().clone() {
+            return Err(format!("Encoder failed: {}", e));
+        }
+
+        let timestamp_us = frame_index as i64 * frame_duration_us;
+        let pixels = capture
+            .capture_frame(timestamp_us as f32 / 1_000_000.0)
+            .await?;
+
+        let image_data =
+            ImageData::new_with_u8_clamped_array_and_sh(Clamped(&pixels), out_width, out_height)
+                .map_err(|e| format!("Couldn't create image data: {:?}", e))?;
+        output_context
+            .put_image_data(&image_data, 0.0, 0.0)
+            .map_err(|e| format!("Couldn't draw frame: {:?}", e))?;
+
+        let frame_init = VideoFrameInit::new();
+        frame_init.set_timestamp(timestamp_us as f64);
+        frame_init.set_duration(frame_duration_us as f64);
+
+        let frame = VideoFrame::new_with_html_canvas_element_and_video_frame_init(
+            &output_canvas,
+            &frame_init,
+        )
+        .map_err(|e| format!("Couldn't create video frame: {:?}", e))?;
+
+        let encode_options = VideoEncoderEncodeOptions::new();
+        encode_options.set_key_frame(frame_index % keyframe_every == 0);
+
+        let encoded = encoder.encode_with_options(&frame, &encode_options);
+        frame.close();
+        encoded.map_err(|e| format!("Couldn't encode frame: {:?}", e))?;
+
+        while encoder.encode_queue_size() > MAX_ENCODE_QUEUE {
+            sleep_ms(5).await;
+        }
+
+        on_progress((frame_index + 1) as f32 / frame_count as f32);
+    }
+
+    JsFuture::from(encoder.flush())
+        .await
+        .map_err(|e| format!("Couldn't flush encoder: {:?}", e))?;
+
+    if let Some(e) = encode_error.borrow().clone() {
+        return Err(format!("Encoder failed: {}", e));
+    }
+
+    let chunks = chunks.borrow();
+
+    let output = match settings.container {
+        VideoContainer::Mp4 => mux_mp4(
+            &chunks,
+            description.borrow().clone(),
+            out_width,
+            out_height,
+            frame_duration_us,
+        )?,
+        VideoContainer::WebM => mux_webm(
+            &WebmTrack {
+                codec_id: "V_VP9".to_string(),
+                codec_private: None,
+                width: out_width,
+                height: out_height,
+            },
+            &chunks,
+            frame_duration_us,
+        ),
+    };
+
+    info!("Video exported, {} bytes", output.len());
+
+    Ok(Some(output))
+}
@@ -0,0 +1,489 @@
+// Container muxing for encoded video chunks.
+// Kept free of web-sys so it can be fed synthetic chunks outside the browser.
+
+/// An encoded frame as it comes out of the VideoEncoder
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct EncodedChunk {
+    pub data: Vec<u8>,
+    pub timestamp_us: i64,
+    pub duration_us: Option<i64>,
+    pub is_key: bool,
+}
+
+/// Per-sample timing handed to mp4box, in `timescale` units
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Mp4Sample {
+    pub dts: u64,
+    pub cts: u64,
+    pub duration: u32,
+    pub is_sync: bool,
+}
+
+/// Resolves sample timing for the mp4 track. Timestamps are shifted so the first
+/// sample starts at zero, and missing durations fall back to the gap to the next
+/// chunk (or `frame_duration_us` for the last one).
+pub fn plan_mp4_samples(
+    chunks: &[EncodedChunk],
+    timescale: u32,
+    frame_duration_us: i64,
+) -> Vec<Mp4Sample> {
+    let start_us = chunks.first().map(|c| c.timestamp_us).unwrap_or(0);
+    let to_units = |us: i64| (us.max(0) as u128 * timescale as u128 / 1_000_000) as u64;
+
+    chunks
+        .iter()
+        .enumerate()
+        .map(|(i, chunk)| {
+            let duration_us = chunk.duration_us.unwrap_or_else(|| {
+                chunks
+                    .get(i + 1)
+                    .map(|next| next.timestamp_us - chunk.timestamp_us)
+                    .unwrap_or(frame_duration_us)
+            });
+            let time = to_units(chunk.timestamp_us - start_us);
+
+            // no b-frames from the encoder settings we use, so decode order == presentation order
+            Mp4Sample {
+                dts: time,
+                cts: time,
+                duration: to_units(duration_us).max(1) as u32,
+                is_sync: chunk.is_key,
+            }
+        })
+        .collect()
+}
+
+// EBML element ids used by the WebM writer
+const EBML: u32 = 0x1A45DFA3;
+const EBML_VERSION: u32 = 0x4286;
+const EBML_READ_VERSION: u32 = 0x42F7;
+const EBML_MAX_ID_LENGTH: u32 = 0x42F2;
+const EBML_MAX_SIZE_LENGTH: u32 = 0x42F3;
+const DOC_TYPE: u32 = 0x4282;
+const DOC_TYPE_VERSION: u32 = 0x4287;
+const DOC_TYPE_READ_VERSION: u32 = 0x4285;
+const SEGMENT: u32 = 0x18538067;
+const INFO: u32 = 0x1549A966;
+const TIMECODE_SCALE: u32 = 0x2AD7B1;
+const MUXING_APP: u32 = 0x4D80;
+const WRITING_APP: u32 = 0x5741;
+const DURATION: u32 = 0x4489;
+const TRACKS: u32 = 0x1654AE6B;
+const TRACK_ENTRY: u32 = 0xAE;
+const TRACK_NUMBER: u32 = 0xD7;
+const TRACK_UID: u32 = 0x73C5;
+const TRACK_TYPE: u32 = 0x83;
+const CODEC_ID: u32 = 0x86;
+const CODEC_PRIVATE: u32 = 0x63A2;
+const VIDEO: u32 = 0xE0;
+const PIXEL_WIDTH: u32 = 0xB0;
+const PIXEL_HEIGHT: u32 = 0xBA;
+const CLUSTER: u32 = 0x1F43B675;
+const TIMECODE: u32 = 0xE7;
+const SIMPLE_BLOCK: u32 = 0xA3;
+const CUES: u32 = 0x1C53BB6B;
+const CUE_POINT: u32 = 0xBB;
+const CUE_TIME: u32 = 0xB3;
+const CUE_TRACK_POSITIONS: u32 = 0xB7;
+const CUE_TRACK: u32 = 0xF7;
+const CUE_CLUSTER_POSITION: u32 = 0xF1;
+
+// timecodes are in milliseconds
+const WEBM_TIMECODE_SCALE: u64 = 1_000_000;
+const VIDEO_TRACK_NUMBER: u64 = 1;
+
+pub struct WebmTrack {
+    /// matroska codec id, e.g. "V_VP9"
+    pub codec_id: String,
+    pub codec_private: Option<Vec<u8>>,
+    pub width: u32,
+    pub height: u32,
+}
+
+fn write_id(out: &mut Vec<u8>, id: u32) {
+    let bytes = id.to_be_bytes();
+    let skip = bytes.iter().position(|b| *b != 0).unwrap_or(3);
+    out.extend_from_slice(&bytes[skip..]);
+}
+
+/// EBML variable size integer, using the shortest length that fits
+pub fn encode_vint(value: u64) -> Vec<u8> {
+    let mut length = 1;
+
+    // all ones is reserved for "unknown size"
+    while length < 8 && value >= (1u64 << (7 * length)) - 1 {
+        length += 1;
+    }
+
+    let marked = value | (1u64 << (7 * length));
+    marked.to_be_bytes()[8 - length..].to_vec()
+}
+
+fn write_element(out: &mut Vec<u8>, id: u32, payload: &[u8]) {
+    write_id(out, id);
+    out.extend_from_slice(&encode_vint(payload.len() as u64));
+    out.extend_from_slice(payload);
+}
+
+fn write_uint(out: &mut Vec<u8>, id: u32, value: u64) {
+    let bytes = value.to_be_bytes();
+    let skip = bytes.iter().position(|b| *b != 0).unwrap_or(7);
+    write_element(out, id, &bytes[skip..]);
+}
+
+fn write_float(out: &mut Vec<u8>, id: u32, value: f64) {
+    write_element(out, id, &value.to_be_bytes());
+}
+
+fn write_string(out: &mut Vec<u8>, id: u32, value: &str) {
+    write_element(out, id, value.as_bytes());
+}
+
+fn simple_block(chunk: &EncodedChunk, relative_ms: i16) -> Vec<u8> {
+    let mut block = encode_vint(VIDEO_TRACK_NUMBER);
+    block.extend_from_slice(&relative_ms.to_be_bytes());
+    block.push(if chunk.is_key { 0x80 } else { 0x00 });
+    block.extend_from_slice(&chunk.data);
+    block
+}
+
+/// Splits chunks into clusters. A new cluster starts on every keyframe and whenever
+/// the block offset would overflow the signed 16 bit relative timecode.
+/// Returns (cluster timecode ms, [(chunk index, relative ms)]).
+pub fn plan_webm_clusters(chunks: &[EncodedChunk]) -> Vec<(u64, Vec<(usize, i16)>)> {
+    let start_us = chunks.first().map(|c| c.timestamp_us).unwrap_or(0);
+    let mut clusters: Vec<(u64, Vec<(usize, i16)>)> = Vec::new();
+
+    for (i, chunk) in chunks.iter().enumerate() {
+        let time_ms = ((chunk.timestamp_us - start_us).max(0) / 1000) as u64;
+
+        let needs_cluster = match clusters.last() {
+            None => true,
+            Some((cluster_ms, _)) => chunk.is_key || time_ms - cluster_ms > i16::MAX as u64,
+        };
+
+        if needs_cluster {
+            clusters.push((time_ms, Vec::new()));
+        }
+
+        let (cluster_ms, blocks) = clusters.last_mut().expect("Couldn't get cluster");
+        blocks.push((i, (time_ms - *cluster_ms) as i16));
+    }
+
+    clusters
+}
+
+/// Writes a complete WebM file holding a single video track
+pub fn mux_webm(track: &WebmTrack, chunks: &[EncodedChunk], frame_duration_us: i64) -> Vec<u8> {
+    let mut header = Vec::new();
+    write_uint(&mut header, EBML_VERSION, 1);
+    write_uint(&mut header, EBML_READ_VERSION, 1);
+    write_uint(&mut header, EBML_MAX_ID_LENGTH, 4);
+    write_uint(&mut header, EBML_MAX_SIZE_LENGTH, 8);
+    write_string(&mut header, DOC_TYPE, "webm");
+    write_uint(&mut header, DOC_TYPE_VERSION, 4);
+    write_uint(&mut header, DOC_TYPE_READ_VERSION, 2);
+
+    let duration_ms = match (chunks.first(), chunks.last()) {
+        (Some(first), Some(last)) => {
+            (last.timestamp_us - first.timestamp_us + last.duration_us.unwrap_or(frame_duration_us))
+                as f64
+                / 1000.0
+        }
+        _ => 0.0,
+    };
+
+    let mut info = Vec::new();
+    write_uint(&mut info, TIMECODE_SCALE, WEBM_TIMECODE_SCALE);
+    write_string(&mut info, MUXING_APP, "stunts");
+    write_string(&mut info, WRITING_APP, "stunts");
+    write_float(&mut info, DURATION, duration_ms);
+
+    let mut video = Vec::new();
+    write_uint(&mut video, PIXEL_WIDTH, track.width as u64);
+    write_uint(&mut video, PIXEL_HEIGHT, track.height as u64);
+
+    let mut track_entry = Vec::new();
+    write_uint(&mut track_entry, TRACK_NUMBER, VIDEO_TRACK_NUMBER);
+    write_uint(&mut track_entry, TRACK_UID, 1);
+    write_uint(&mut track_entry, TRACK_TYPE, 1);
+    write_string(&mut track_entry, CODEC_ID, &track.codec_id);
+    if let Some(codec_private) = &track.codec_private {
+        write_element(&mut track_entry, CODEC_PRIVATE, codec_private);
+    }
+    write_element(&mut track_entry, VIDEO, &video);
+
+    let mut tracks = Vec::new();
+    write_element(&mut tracks, TRACK_ENTRY, &track_entry);
+
+    let mut segment = Vec::new();
+    write_element(&mut segment, INFO, &info);
+    write_element(&mut segment, TRACKS, &tracks);
+
+    // cue positions are relative to the start of the segment payload
+    let mut cues = Vec::new();
+
+    for (cluster_ms, blocks) in plan_webm_clusters(chunks) {
+        let mut cluster = Vec::new();
+        write_uint(&mut cluster, TIMECODE, cluster_ms);
+
+        for (chunk_index, relative_ms) in &blocks {
+            write_element(
+                &mut cluster,
+                SIMPLE_BLOCK,
+                &simple_block(&chunks[*chunk_index], *relative_ms),
+            );
+        }
+
+        if chunks[blocks[0].0].is_key {
+            let mut positions = Vec::new();
+            write_uint(&mut positions, CUE_TRACK, VIDEO_TRACK_NUMBER);
+            write_uint(&mut positions, CUE_CLUSTER_POSITION, segment.len() as u64);
+
+            let mut cue_point = Vec::new();
+            write_uint(&mut cue_point, CUE_TIME, cluster_ms);
+            write_element(&mut cue_point, CUE_TRACK_POSITIONS, &positions);
+
+            write_element(&mut cues, CUE_POINT, &cue_point);
+        }
+
+        write_element(&mut segment, CLUSTER, &cluster);
+    }
+
+    if !cues.is_empty() {
+        write_element(&mut segment, CUES, &cues);
+    }
+
+    let mut output = Vec::new();
+    write_element(&mut output, EBML, &header);
+    write_element(&mut output, SEGMENT, &segment);
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chunk(timestamp_ms: i64, is_key: bool, data: &[u8]) -> EncodedChunk {
+        EncodedChunk {
+            data: data.to_vec(),
+            timestamp_us: timestamp_ms * 1000,
+            duration_us: None,
+            is_key,
+        }
+    }
+
+    // (id, payload) for each element directly inside `bytes`
+    fn read_elements(bytes: &[u8]) -> Vec<(u32, &[u8])> {
+        let mut elements = Vec::new();
+        let mut pos = 0;
+
+        while pos < bytes.len() {
+            let id_length = bytes[pos].leading_zeros() as usize + 1;
+            let id = bytes[pos..pos + id_length]
+                .iter()
+                .fold(0u32, |id, b| id << 8 | *b as u32);
+            pos += id_length;
+
+            let size_length = bytes[pos].leading_zeros() as usize + 1;
+            let size = bytes[pos..pos + size_length]
+                .iter()
+                .fold(0u64, |size, b| size << 8 | *b as u64)
+                & ((1u64 << (7 * size_length)) - 1);
+            pos += size_length;
+
+            elements.push((id, &bytes[pos..pos + size as usize]));
+            pos += size as usize;
+        }
+
+        elements
+    }
+
+    #[test]
+    fn vint_uses_the_shortest_width() {
+        assert_eq!(encode_vint(0), vec![0x80]);
+        assert_eq!(encode_vint(0x7E), vec![0xFE]);
+        // 0x7F in one byte would read as "unknown size"
+        assert_eq!(encode_vint(0x7F), vec![0x40, 0x7F]);
+        assert_eq!(encode_vint(0x3FFE), vec![0x7F, 0xFE]);
+        assert_eq!(encode_vint(0x3FFF), vec![0x20, 0x3F, 0xFF]);
+        assert_eq!(encode_vint(0x1F_FFFE), vec![0x3F, 0xFF, 0xFE]);
+        assert_eq!(encode_vint(0x1F_FFFF), vec![0x10, 0x1F, 0xFF, 0xFF]);
+        assert_eq!(encode_vint(0x0FFF_FFFF), vec![0x08, 0x0F, 0xFF, 0xFF, 0xFF]);
+    }
+
+    #[test]
+    fn mp4_samples_start_at_zero_and_keep_keyframes() {
+        let chunks = [
+            chunk(1_000, true, &[]),
+            chunk(1_033, false, &[]),
+            chunk(1_066, true, &[]),
+        ];
+
+        let samples = plan_mp4_samples(&chunks, 1_000_000, 33_333);
+
+        assert_eq!(
+            samples
+                .iter()
+                .map(|s| (s.dts, s.cts, s.is_sync))
+                .collect::<Vec<_>>(),
+            vec![
+                (0, 0, true),
+                (33_000, 33_000, false),
+                (66_000, 66_000, true)
+            ]
+        );
+    }
+
+    #[test]
+    fn mp4_sample_durations_fall_back_to_the_next_chunk() {
+        let mut chunks = [
+            chunk(0, true, &[]),
+            chunk(40, false, &[]),
+            chunk(100, false, &[]),
+        ];
+        // an encoder provided duration wins over the gap
+        chunks[1].duration_us = Some(50_000);
+
+        let samples = plan_mp4_samples(&chunks, 1_000_000, 33_333);
+
+        assert_eq!(
+            samples.iter().map(|s| s.duration).collect::<Vec<_>>(),
+            // the last chunk has no next one, it gets a frame
+            vec![40_000, 50_000, 33_333]
+        );
+    }
+
+    #[test]
+    fn mp4_samples_scale_to_the_timescale() {
+        let chunks = [chunk(0, true, &[]), chunk(500, false, &[])];
+
+        let samples = plan_mp4_samples(&chunks, 90_000, 500_000);
+
+        assert_eq!(samples[1].dts, 45_000);
+        assert_eq!(samples[1].duration, 45_000);
+        // too short to show up in the timescale still lasts a unit
+        assert_eq!(plan_mp4_samples(&chunks, 1, 1)[1].duration, 1);
+        assert!(plan_mp4_samples(&[], 1_000_000, 33_333).is_empty());
+    }
+
+    #[test]
+    fn clusters_start_on_keyframes() {
+        let chunks = [
+            chunk(0, true, &[]),
+            chunk(33, false, &[]),
+            chunk(66, true, &[]),
+            chunk(100, false, &[]),
+        ];
+
+        assert_eq!(
+            plan_webm_clusters(&chunks),
+            vec![(0, vec![(0, 0), (1, 33)]), (66, vec![(2, 0), (3, 34)])]
+        );
+    }
+
+    #[test]
+    fn clusters_split_before_the_timecode_overflows() {
+        let chunks = [
+            chunk(0, true, &[]),
+            chunk(32_767, false, &[]),
+            chunk(32_768, false, &[]),
+        ];
+
+        assert_eq!(
+            plan_webm_clusters(&chunks),
+            vec![(0, vec![(0, 0), (1, i16::MAX)]), (32_768, vec![(2, 0)])]
+        );
+    }
+
+    #[test]
+    fn clusters_are_relative_to_the_first_chunk() {
+        let chunks = [chunk(5_000, true, &[]), chunk(5_040, false, &[])];
+
+        assert_eq!(
+            plan_webm_clusters(&chunks),
+            vec![(0, vec![(0, 0), (1, 40)])]
+        );
+        assert!(plan_webm_clusters(&[]).is_empty());
+    }
+
+    #[test]
+    fn webm_output_has_header_segment_and_blocks() {
+        let track = WebmTrack {
+            codec_id: "V_VP9".to_string(),
+            codec_private: None,
+            width: 640,
+            height: 360,
+        };
+        let chunks = [
+            chunk(0, true, &[1, 2, 3]),
+            chunk(40, false, &[4, 5]),
+            chunk(80, true, &[6]),
+        ];
+
+        let output = mux_webm(&track, &chunks, 40_000);
+        assert_eq!(&output[..4], &[0x1A, 0x45, 0xDF, 0xA3]);
+
+        let top = read_elements(&output);
+        assert_eq!(
+            top.iter().map(|(id, _)| *id).collect::<Vec<_>>(),
+            vec![EBML, SEGMENT]
+        );
+
+        let header = read_elements(top[0].1);
+        assert!(header.contains(&(DOC_TYPE, b"webm".as_slice())));
+
+        let segment = read_elements(top[1].1);
+        assert_eq!(
+            segment.iter().map(|(id, _)| *id).collect::<Vec<_>>(),
+            vec![INFO, TRACKS, CLUSTER, CLUSTER, CUES]
+        );
+
+        let info = read_elements(segment[0].1);
+        let duration = info
+            .iter()
+            .find(|(id, _)| *id == DURATION)
+            .map(|(_, payload)| f64::from_be_bytes(payload.to_vec().try_into().unwrap()));
+        assert_eq!(duration, Some(120.0));
+
+        // (timecode, [(relative ms, flags, data)])
+        let clusters = segment[2..4]
+            .iter()
+            .map(|(_, payload)| {
+                let elements = read_elements(payload);
+                assert_eq!(elements[0].0, TIMECODE);
+
+                let blocks = elements[1..]
+                    .iter()
+                    .map(|(id, block)| {
+                        assert_eq!(*id, SIMPLE_BLOCK);
+                        // track 1 as a one byte vint
+                        assert_eq!(block[0], 0x81);
+
+                        (
+                            i16::from_be_bytes([block[1], block[2]]),
+                            block[3],
+                            block[4..].to_vec(),
+                        )
+                    })
+                    .collect::<Vec<_>>();
+
+                (elements[0].1.to_vec(), blocks)
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            clusters,
+            vec![
+                (
+                    vec![0],
+                    vec![(0, 0x80, vec![1, 2, 3]), (40, 0x00, vec![4, 5])]
+                ),
+                (vec![80], vec![(0, 0x80, vec![6])]),
+            ]
+        );
+
+        // one cue per cluster, both start on a keyframe
+        assert_eq!(read_elements(segment[4].1).len(), 2);
+    }
+}
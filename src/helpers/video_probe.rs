@@ -0,0 +1,175 @@
+use std::future::Future;
+
+use js_sys::{Array, Promise, Uint8Array};
+use wasm_bindgen::JsCast;
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{
+    window, AddEventListenerOptions, Blob, BlobPropertyBag, CanvasRenderingContext2d,
+    EventTarget, HtmlCanvasElement, HtmlVideoElement, Url,
+};
+
+// width of the first-frame thumbnail shown while uploading
+const PREVIEW_WIDTH: u32 = 160;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct VideoProbe {
+    pub duration_ms: i64,
+    pub width: u32,
+    pub height: u32,
+    /// jpeg data url of the first frame
+    pub preview_url: String,
+}
+
+pub fn video_mime_type(file_name: &str) -> &'static str {
+    if file_name.to_lowercase().ends_with(".webm") {
+        "video/webm"
+    } else {
+        "video/mp4"
+    }
+}
+
+/// Fits the video inside `max_width` x `max_height` keeping its aspect ratio
+pub fn fit_dimensions(width: u32, height: u32, max_width: u32, max_height: u32) -> (u32, u32) {
+    if width == 0 || height == 0 {
+        return (max_width, max_height);
+    }
+
+    let scale = (max_width as f32 / width as f32).min(max_height as f32 / height as f32);
+
+    (
+        ((width as f32 * scale).round() as u32).max(1),
+        ((height as f32 * scale).round() as u32).max(1),
+    )
+}
+
+/// Resolves on the next `event` (or fails on "error"). The listeners are added
+/// right away so an event fired before the first poll isn't missed.
+pub fn wait_for_event(
+    target: &EventTarget,
+    event: &str,
+) -> impl Future<Output = Result<(), String>> {
+    let promise = Promise::new(&mut |resolve, reject| {
+        let options = AddEventListenerOptions::new();
+        options.set_once(true);
+
+        let _ = target.add_event_listener_with_callback_and_add_event_listener_options(
+            event, &resolve, &options,
+        );
+        let _ = target.add_event_listener_with_callback_and_add_event_listener_options(
+            "error", &reject, &options,
+        );
+    });
+    let event = event.to_string();
+
+    async move {
+        JsFuture::from(promise)
+            .await
+            .map(|_| ())
+            .map_err(|_| format!("Couldn't load video (waiting for {})", event))
+    }
+}
+
+async fn seek(video: &HtmlVideoElement, time_s: f64) -> Result<(), String> {
+    let seeked = wait_for_event(video, "seeked");
+    video.set_current_time(time_s);
+    seeked.await
+}
+
+fn draw_preview(video: &HtmlVideoElement) -> Result<String, String> {
+    let (width, height) = fit_dimensions(
+        video.video_width(),
+        video.video_height(),
+        PREVIEW_WIDTH,
+        PREVIEW_WIDTH,
+    );
+
+    let document = window()
+        .expect("Couldn't get window")
+        .document()
+        .expect("Couldn't get document");
+    let canvas = document
+        .create_element("canvas")
+        .expect("Couldn't create canvas")
+        .dyn_into::<HtmlCanvasElement>()
+        .expect("Couldn't convert canvas");
+    canvas.set_width(width);
+    canvas.set_height(height);
+
+    let context = canvas
+        .get_context("2d")
+        .ok()
+        .flatten()
+        .and_then(|c| c.dyn_into::<CanvasRenderingContext2d>().ok())
+        .ok_or("Couldn't get preview context")?;
+
+    context
+        .draw_image_with_html_video_element_and_dw_and_dh(
+            video,
+            0.0,
+            0.0,
+            width as f64,
+            height as f64,
+        )
+        .map_err(|_| "Couldn't draw preview frame")?;
+
+    canvas
+        .to_data_url_with_type("image/jpeg")
+        .map_err(|_| "Couldn't encode preview frame".to_string())
+}
+
+/// Loads the video into a detached `<video>` element to read its duration and size,
+/// and grabs the first frame for a preview
+pub async fn probe_video(file_data: &[u8], mime_type: &str) -> Result<VideoProbe, String> {
+    let parts = Array::new();
+    parts.push(&Uint8Array::from(file_data).buffer());
+
+    let options = BlobPropertyBag::new();
+    options.set_type(mime_type);
+
+    let blob = Blob::new_with_buffer_source_sequence_and_options(&parts, &options)
+        .map_err(|_| "Couldn't create video blob")?;
+    let url = Url::create_object_url_with_blob(&blob).map_err(|_| "Couldn't create video url")?;
+
+    let document = window()
+        .expect("Couldn't get window")
+        .document()
+        .expect("Couldn't get document");
+    let video = document
+        .create_element("video")
+        .expect("Couldn't create video")
+        .dyn_into::<HtmlVideoElement>()
+        .expect("Couldn't convert video");
+    video.set_muted(true);
+    video.set_preload("auto");
+
+    let result = async {
+        let loaded = wait_for_event(&video, "loadeddata");
+        video.set_src(&url);
+        loaded.await?;
+
+        // recorded webm often has no duration until the end has been seen
+        let mut duration = video.duration();
+        if !duration.is_finite() {
+            seek(&video, 1.0e7).await?;
+            duration = video.duration();
+            seek(&video, 0.0).await?;
+        }
+
+        if !duration.is_finite() || duration <= 0.0 {
+            return Err("Couldn't read video duration".to_string());
+        }
+
+        Ok(VideoProbe {
+            duration_ms: (duration * 1000.0).round() as i64,
+            width: video.video_width(),
+            height: video.video_height(),
+            preview_url: draw_preview(&video)?,
+        })
+    }
+    .await;
+
+    video.remove_attribute("src").ok();
+    let _ = Url::revoke_object_url(&url);
+
+    result
+}
@@ -8,29 +8,59 @@ use rand::Rng;
 use reactive_stores::Store;
 use rfd::AsyncFileDialog;
 use std::str::FromStr;
-use std::sync::{Arc, Mutex};
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc, Mutex,
+};
 use stunts_engine::animations::{BackgroundFill, ObjectType, Sequence};
 use stunts_engine::editor::{
-    init_editor_with_model, rgb_to_wgpu, wgpu_to_human, Point, Viewport, WindowSize,
+    init_editor_with_model, wgpu_to_human, Point, Viewport, WindowSize,
     CANVAS_HORIZ_OFFSET, CANVAS_VERT_OFFSET,
 };
-use stunts_engine::polygon::{PolygonConfig, SavedPoint, SavedPolygonConfig, SavedStroke, Stroke};
+use stunts_engine::polygon::{PolygonConfig, SavedPoint, SavedShape, Stroke};
 use stunts_engine::st_image::{SavedStImageConfig, StImageConfig};
-use stunts_engine::st_video::StVideoConfig;
-use stunts_engine::text_due::{SavedTextRendererConfig, TextRendererConfig};
+use stunts_engine::text_due::{SavedTextRendererConfig, TextAlignment, TextRendererConfig};
 use undo::Record;
 use uuid::Uuid;
 use wasm_bindgen_futures::spawn_local;
 
-use crate::canvas_renderer::CanvasRenderer;
+use crate::canvas_renderer::{active_render_loops, CanvasRenderer, RedrawFlag};
+use crate::components::arrange_panel::ArrangePanel;
+use crate::components::auto_zoom_panel::AutoZoomPanel;
+use crate::components::background_panel::BackgroundPanel;
+use crate::components::export_panel::ExportPanel;
+use crate::components::group_panel::GroupPanel;
+use crate::components::history_panel::HistoryPanel;
 use crate::components::icon::CreateIcon;
-use crate::components::items::{DebouncedInput, NavButton, OptionButton};
-use crate::components::layers::{Layer, LayerPanel};
+use crate::components::inspector::PropertyInspector;
+use crate::components::items::{NavButton, OptionButton};
+use crate::components::layers::{layers_from_editor, Layer, LayerPanel};
+use crate::components::pen_tool::{PenMode, PenOverlay};
+use crate::components::selection_overlay::SelectionOverlay;
+use crate::components::shape_panel::ShapePanel;
+use crate::components::shortcuts::EditorShortcuts;
+use crate::components::text_editor::TextEditor;
 use crate::editor_state::EditorState;
-use crate::fetchers::projects::{get_single_project, save_image, update_sequences};
-use crate::helpers::media::restore_sequence_objects;
+use crate::fetchers::projects::{
+    get_single_project, save_image, save_media_data, save_video, update_sequences,
+};
+use crate::helpers::alignment::SnapSettings;
+use crate::helpers::auto_zoom::{generate_zoom_keyframes, AutoZoomSettings};
+use crate::helpers::backgrounds::apply_background_fill;
+use crate::helpers::history::load_session_history;
+use crate::helpers::media_cache::remember_image;
+use crate::helpers::media::{add_video_to_sequence, restore_sequence_objects, RestoreProgress};
+use crate::helpers::object_copy::{copy_objects, paste_objects, COPY_OFFSET};
+use crate::helpers::polygons::{add_polygon_to_sequence, migrate_saved_state};
+use crate::helpers::screen_capture::ScreenRecorder;
+use crate::helpers::sequence_cache::{release_sequence_objects, SequenceCache};
+use crate::helpers::shapes::{
+    shape_border_radius, shape_dimensions, shape_icon, shape_label, shape_library, shape_points,
+};
+use crate::helpers::text_style::{DEFAULT_LETTER_SPACING, DEFAULT_LINE_HEIGHT};
 use crate::helpers::users::AuthToken;
 use crate::helpers::utilities::{SavedState, SavedStateStoreFields};
+use crate::helpers::video_probe::{probe_video, video_mime_type};
 
 use leptos::Params;
 use leptos_router::hooks::{use_navigate, use_params, use_query};
@@ -41,6 +71,16 @@ struct ProjectParams {
     project_id: Option<String>,
 }
 
+// sequences saved before backgrounds could be set
+fn default_background_fill() -> BackgroundFill {
+    BackgroundFill::Color([
+        wgpu_to_human(0.8) as i32,
+        wgpu_to_human(0.8) as i32,
+        wgpu_to_human(0.8) as i32,
+        255,
+    ])
+}
+
 #[derive(Clone, PartialEq)]
 enum Sections {
     SequenceList,
@@ -49,32 +89,70 @@ enum Sections {
 
 #[component]
 pub fn Project() -> impl IntoView {
+    // set when the page unmounts, the renderer may still be on its way
+    let disposed = Arc::new(AtomicBool::new(false));
+
     let renderer: LocalResource<(Arc<Mutex<CanvasRenderer>>, Arc<Mutex<EditorState>>)> =
-        LocalResource::new(
+        LocalResource::new({
+            let disposed = disposed.clone();
+
             // || (),
-            || async move {
-                let viewport = Arc::new(Mutex::new(Viewport::new(900.0 as f32, 450.0 as f32)));
+            move || {
+                let disposed = disposed.clone();
 
-                let editor = Arc::new(Mutex::new(init_editor_with_model(viewport)));
+                async move {
+                    let viewport = Arc::new(Mutex::new(Viewport::new(900.0 as f32, 450.0 as f32)));
 
-                let record = Arc::new(Mutex::new(Record::new()));
-                let editor_state = Arc::new(Mutex::new(EditorState::new(editor.clone(), record)));
+                    let editor = Arc::new(Mutex::new(init_editor_with_model(viewport)));
 
-                let mut renderer = Arc::new(Mutex::new(CanvasRenderer::new(editor).await));
+                    // shared so edits outside the render loop can ask for a redraw
+                    let redraw = RedrawFlag::default();
 
-                let mut renderer_guard = renderer.lock().unwrap();
+                    let record = Arc::new(Mutex::new(Record::new()));
+                    let editor_state = Arc::new(Mutex::new(EditorState::new(
+                        editor.clone(),
+                        record,
+                        redraw.clone(),
+                    )));
 
-                renderer_guard.recreate_depth_view(900, 450);
+                    let mut renderer =
+                        Arc::new(Mutex::new(CanvasRenderer::new(editor, redraw).await));
 
-                // better to start in Effect?
-                info!("Begin rendering...");
-                renderer_guard.begin_rendering();
+                    let mut renderer_guard = renderer.lock().unwrap();
 
-                drop(renderer_guard);
+                    renderer_guard.recreate_depth_view(900, 450);
 
-                (renderer, editor_state)
-            },
-        );
+                    // left before the gpu device came back, on_cleanup had nothing to tear down
+                    if disposed.load(Ordering::SeqCst) {
+                        renderer_guard.teardown();
+                    } else {
+                        // better to start in Effect?
+                        info!("Begin rendering...");
+                        renderer_guard.begin_rendering();
+                    }
+
+                    info!("Active render loops: {}", active_render_loops());
+
+                    drop(renderer_guard);
+
+                    (renderer, editor_state)
+                }
+            }
+        });
+
+    // leaving the project (route change) must not leave a render loop or gpu device behind
+    on_cleanup(move || {
+        disposed.store(true, Ordering::SeqCst);
+
+        if let Some(renderer) = renderer.try_get_untracked().flatten() {
+            let (canvas_renderer, _) = renderer.take();
+            let mut canvas_renderer = canvas_renderer.lock().unwrap();
+
+            canvas_renderer.teardown();
+
+            info!("Renderer torn down, active render loops: {}", active_render_loops());
+        }
+    });
 
     let (auth_state, set_auth_state, _) =
         use_local_storage::<AuthToken, JsonSerdeCodec>("auth-token");
@@ -97,6 +175,7 @@ pub fn Project() -> impl IntoView {
     };
 
     let (section, set_section) = signal(Sections::SequenceList);
+    let pen_mode = RwSignal::new(PenMode::Off);
 
     let navigate = use_navigate();
 
@@ -107,10 +186,68 @@ pub fn Project() -> impl IntoView {
     let (is_curved, set_is_curved) = signal(false);
     let (auto_choreograph, set_auto_choreograph) = signal(true);
     let (auto_fade, set_auto_fade) = signal(true);
+    let (video_preview, set_video_preview) = signal(Option::<String>::None);
+    let (video_error, set_video_error) = signal(Option::<String>::None);
+    let (capturing, set_capturing) = signal(false);
+    let capture_recorder = StoredValue::new_local(Option::<(ScreenRecorder, String)>::None);
+
+    let restore_progress = RwSignal::new(Option::<RestoreProgress>::None);
+    let restore_warnings = RwSignal::new(Vec::<String>::new());
+    let sequence_cache = StoredValue::new(SequenceCache::default());
 
     let layers: RwSignal<Vec<Layer>> = create_rw_signal(Vec::new());
 
     let dragger_id = create_rw_signal(Uuid::nil());
+    let selection = RwSignal::new(Vec::<(Uuid, ObjectType)>::new());
+    let snapping = RwSignal::new(SnapSettings::default());
+    // text item open in the inline text editor
+    let editing_text = RwSignal::new(None::<Uuid>);
+
+    // keep the scene list in step with objects being added, deleted and restored by undo
+    Effect::new(move |_| {
+        sequences.track();
+
+        let Sections::SequenceView(sequence_id) = section.get_untracked() else {
+            return;
+        };
+        let Some(renderer) = renderer.get_untracked() else {
+            return;
+        };
+        let (canvas_renderer, editor_state) = renderer.take();
+        let canvas_renderer = canvas_renderer.lock().unwrap();
+        let editor_m = canvas_renderer.editor.clone();
+
+        layers.set(layers_from_editor(&editor_m.lock().unwrap()));
+
+        // group pivots follow their children around
+        if let Some(sequence) = sequences.get_untracked().iter().find(|s| s.id == sequence_id) {
+            canvas_renderer.set_groups(sequence);
+        }
+
+        // undo and redo can swap the background fill
+        let background_changed = editor_state
+            .lock()
+            .unwrap()
+            .record_state
+            .background_changed
+            .take();
+
+        if background_changed.as_ref() == Some(&sequence_id) {
+            let fill = sequences.with_untracked(|sequences| {
+                sequences
+                    .iter()
+                    .find(|s| s.id == sequence_id)
+                    .and_then(|s| s.background_fill.clone())
+            });
+
+            apply_background_fill(
+                &canvas_renderer,
+                sequence_id.clone(),
+                fill.unwrap_or_else(default_background_fill),
+                auth_state.get_untracked().token,
+            );
+        }
+    });
 
     Effect::new(move |_| {
         set_loading.set(true);
@@ -122,35 +259,33 @@ pub fn Project() -> impl IntoView {
         if let Some(renderer) = renderer {
             info!("Got renderer!");
 
-            let (canvas_renderer, editor_state) = renderer.take();
+            let (_, editor_state) = renderer.take();
 
             spawn_local({
                 async move {
-                    let response = get_single_project(auth_state.token.clone(), project_id()).await;
+                    let mut response = get_single_project(auth_state.token.clone(), project_id()).await;
+
+                    if migrate_saved_state(&mut response.project.file_data) {
+                        info!("Migrated saved polygons to stored points");
+                    }
 
                     let mut editor_state = editor_state.lock().unwrap();
 
                     editor_state.record_state.saved_state =
                         Some(response.project.file_data.clone());
 
-                    let cloned_sequences = response.project.file_data.sequences.clone();
+                    // undo survives reloads within the session
+                    if let Some(stored) = load_session_history(&project_id()) {
+                        editor_state.restore_history(stored);
+                    }
 
                     sequences.set(response.project.file_data.sequences);
                     timeline_state.set(response.project.file_data.timeline_state);
 
                     drop(editor_state);
 
-                    let canvas_renderer = canvas_renderer.lock().unwrap();
-                    let editor = canvas_renderer.editor.clone();
-
-                    info!("Restoring objects...");
-
-                    restore_sequence_objects(
-                        editor.clone(),
-                        cloned_sequences,
-                        true,
-                        auth_state.token,
-                    );
+                    // objects are restored per sequence as they're opened
+                    sequence_cache.update_value(|cache| cache.clear());
 
                     set_loading.set(false);
                 }
@@ -178,6 +313,7 @@ pub fn Project() -> impl IntoView {
                 active_text_items: Vec::new(),
                 active_image_items: Vec::new(),
                 active_video_items: Vec::new(),
+                groups: Vec::new(),
             });
 
             sequences.set(new_sequences.clone());
@@ -198,12 +334,11 @@ pub fn Project() -> impl IntoView {
         }
     };
 
-    let on_open_sequence = move |sequence_id: String| {
-        set_section.set(Sections::SequenceView(sequence_id.clone()));
-
+    // shows a sequence whose objects are already in the editor and hides the rest
+    let reveal_sequence = move |sequence_id: String| {
         println!("Open Sequence...");
 
-        let renderer = renderer.get().expect("Couldn't get renderer");
+        let renderer = renderer.get_untracked().expect("Couldn't get renderer");
         let (canvas_renderer, editor_state) = renderer.take();
         let canvas_renderer = canvas_renderer.lock().unwrap();
         let editor = canvas_renderer.editor.clone();
@@ -222,20 +357,16 @@ pub fn Project() -> impl IntoView {
             .expect("Couldn't find matching sequence")
             .clone();
 
-        let mut background_fill = Some(BackgroundFill::Color([
-            wgpu_to_human(0.8) as i32,
-            wgpu_to_human(0.8) as i32,
-            wgpu_to_human(0.8) as i32,
-            255,
-        ]));
+        let mut background_fill = Some(default_background_fill());
 
         if saved_sequence.background_fill.is_some() {
             background_fill = saved_sequence.background_fill.clone();
         }
 
-        // for the background polygon and its signal
-        editor_state.selected_polygon_id =
-            Uuid::from_str(&saved_sequence.id).expect("Couldn't convert string to uuid");
+        editor_state.clear_selection();
+        selection.set(Vec::new());
+
+        editor_state.record_state.open_sequence = Some(sequence_id.clone());
 
         drop(editor_state);
 
@@ -271,50 +402,49 @@ pub fn Project() -> impl IntoView {
         });
 
         saved_sequence.active_polygons.iter().for_each(|ap| {
-            let polygon = editor
+            match editor
                 .polygons
                 .iter_mut()
                 .find(|p| p.id.to_string() == ap.id)
-                .expect("Couldn't find polygon");
-            polygon.hidden = false;
+            {
+                Some(polygon) => polygon.hidden = false,
+                None => log::warn!("Couldn't find polygon {}", ap.id),
+            }
         });
         saved_sequence.active_image_items.iter().for_each(|si| {
-            let image = editor
+            // media may still be loading or failed to fetch
+            match editor
                 .image_items
                 .iter_mut()
                 .find(|i| i.id.to_string() == si.id)
-                .expect("Couldn't find image");
-            image.hidden = false;
-            info!("Image revealed... {:?}", si.id);
+            {
+                Some(image) => {
+                    image.hidden = false;
+                    info!("Image revealed... {:?}", si.id);
+                }
+                None => log::warn!("Couldn't find image {}", si.id),
+            }
         });
         saved_sequence.active_text_items.iter().for_each(|tr| {
-            let text = editor
+            match editor
                 .text_items
                 .iter_mut()
                 .find(|t| t.id.to_string() == tr.id)
-                .expect("Couldn't find image");
-            text.hidden = false;
+            {
+                Some(text) => text.hidden = false,
+                None => log::warn!("Couldn't find text {}", tr.id),
+            }
         });
         saved_sequence.active_video_items.iter().for_each(|tr| {
-            let video = editor
+            match editor
                 .video_items
                 .iter_mut()
                 .find(|t| t.id.to_string() == tr.id)
-                .expect("Couldn't find image");
-            video.hidden = false;
-        });
-
-        match background_fill.expect("Couldn't get default background fill") {
-            BackgroundFill::Color(fill) => {
-                editor.replace_background(
-                    Uuid::from_str(&saved_sequence.id).expect("Couldn't convert string to uuid"),
-                    rgb_to_wgpu(fill[0] as u8, fill[1] as u8, fill[2] as u8, fill[3] as f32),
-                );
+            {
+                Some(video) => video.hidden = false,
+                None => log::warn!("Couldn't find video {}", tr.id),
             }
-            _ => {
-                println!("Not supported yet...");
-            }
-        }
+        });
 
         println!("Objects restored!");
 
@@ -324,43 +454,21 @@ pub fn Project() -> impl IntoView {
 
         info!("Restoring layers...");
 
-        let mut new_layers = Vec::new();
-        editor.polygons.iter().for_each(|polygon| {
-            if !polygon.hidden {
-                let polygon_config: PolygonConfig = polygon.to_config();
-                let new_layer: Layer = Layer::from_polygon_config(&polygon_config);
-                new_layers.push(new_layer);
-            }
-        });
-        editor.text_items.iter().for_each(|text| {
-            if !text.hidden {
-                let text_config: TextRendererConfig = text.to_config();
-                let new_layer: Layer = Layer::from_text_config(&text_config);
-                new_layers.push(new_layer);
-            }
-        });
-        editor.image_items.iter().for_each(|image| {
-            if !image.hidden {
-                let image_config: StImageConfig = image.to_config();
-                let new_layer: Layer = Layer::from_image_config(&image_config);
-                new_layers.push(new_layer);
-            }
-        });
-        editor.video_items.iter().for_each(|video| {
-            if !video.hidden {
-                let video_config: StVideoConfig = video.to_config();
-                let new_layer: Layer = Layer::from_video_config(&video_config);
-                new_layers.push(new_layer);
-            }
-        });
+        layers.set(layers_from_editor(&editor));
 
-        // sort layers by layer_index property, lower values should come first in the list
-        // but reverse the order because the UI outputs the first one first, thus it displays last
-        new_layers.sort_by(|a, b| b.initial_layer_index.cmp(&a.initial_layer_index));
+        drop(editor);
 
-        layers.set(new_layers);
+        canvas_renderer.set_groups(&saved_sequence);
 
-        drop(editor);
+        // gradients and images are rasterized async, colors apply right away
+        apply_background_fill(
+            &canvas_renderer,
+            saved_sequence.id.clone(),
+            background_fill.expect("Couldn't get default background fill"),
+            auth_state.get_untracked().token,
+        );
+
+        canvas_renderer.redraw.request();
 
         // drop(editor);
 
@@ -369,20 +477,134 @@ pub fn Project() -> impl IntoView {
         // sequence_selected.set(true);
     };
 
+    let on_open_sequence = move |sequence_id: String| {
+        set_section.set(Sections::SequenceView(sequence_id.clone()));
+
+        sequence_cache.update_value(|cache| cache.open(sequence_id.clone()));
+
+        // recently opened, objects are still in the editor
+        if sequence_cache.with_value(|cache| cache.is_ready(&sequence_id)) {
+            sequence_cache.update_value(|cache| {
+                cache.touch(sequence_id.clone());
+            });
+            reveal_sequence(sequence_id);
+            set_loading.set(false);
+            return;
+        }
+
+        // opened again before its restore finished, that restore reveals it
+        if sequence_cache.with_value(|cache| cache.is_restoring(&sequence_id)) {
+            sequence_cache.update_value(|cache| {
+                cache.touch(sequence_id.clone());
+            });
+            set_loading.set(true);
+            return;
+        }
+
+        let renderer = renderer.get_untracked().expect("Couldn't get renderer");
+        let (canvas_renderer, editor_state) = renderer.take();
+        let editor_m = canvas_renderer.lock().unwrap().editor.clone();
+
+        let saved_sequences = editor_state
+            .lock()
+            .unwrap()
+            .record_state
+            .saved_state
+            .as_ref()
+            .expect("Couldn't get Saved State")
+            .sequences
+            .clone();
+
+        let Some(saved_sequence) = saved_sequences
+            .iter()
+            .find(|s| s.id == sequence_id)
+            .cloned()
+        else {
+            log::error!("Couldn't find sequence {}", sequence_id);
+            return;
+        };
+
+        let evicted = sequence_cache
+            .try_update_value(|cache| {
+                cache.begin_restore(sequence_id.clone());
+                cache.touch(sequence_id.clone())
+            })
+            .unwrap_or_default();
+
+        {
+            let mut editor = editor_m.lock().unwrap();
+
+            saved_sequences
+                .iter()
+                .filter(|s| evicted.contains(&s.id))
+                .for_each(|s| release_sequence_objects(&mut editor, s));
+        }
+
+        set_loading.set(true);
+
+        let token = auth_state.get_untracked().token;
+
+        spawn_local(async move {
+            info!("Restoring objects...");
+
+            let progress_sequence_id = sequence_id.clone();
+            let warnings = restore_sequence_objects(
+                editor_m.clone(),
+                vec![saved_sequence.clone()],
+                true,
+                token,
+                move |progress| {
+                    let open = sequence_cache
+                        .try_with_value(|cache| cache.is_open(&progress_sequence_id))
+                        .unwrap_or(false);
+                    if open {
+                        restore_progress.set(Some(progress));
+                    }
+                },
+            )
+            .await;
+
+            let keep = sequence_cache
+                .try_update_value(|cache| cache.finish_restore(&sequence_id))
+                .unwrap_or(false);
+
+            // evicted while restoring, nothing else will free these
+            if !keep {
+                let mut editor = editor_m.lock().unwrap();
+                release_sequence_objects(&mut editor, &saved_sequence);
+                return;
+            }
+
+            // another sequence was opened meanwhile, leave its view alone
+            let open = sequence_cache
+                .try_with_value(|cache| cache.is_open(&sequence_id))
+                .unwrap_or(false);
+            if !open {
+                return;
+            }
+
+            restore_warnings.set(warnings);
+            restore_progress.set(None);
+
+            reveal_sequence(sequence_id);
+
+            // ready once every asset is in
+            set_loading.set(false);
+        });
+    };
+
     // Add to renderer Editor
     // Then add save to db
     // Update the context signal
     // Finally persist it in certain structs
-    let on_add_square = move |sequence_id: String| {
-        info!("Adding Square...");
+    let on_add_shape = move |sequence_id: String, shape: SavedShape| {
+        info!("Adding {}...", shape_label(&shape));
 
         let renderer = renderer.get().expect("Couldn't get renderer");
         let (canvas_renderer, editor_state) = renderer.take();
         let canvas_renderer = canvas_renderer.lock().unwrap();
         let editor_m = canvas_renderer.editor.clone();
 
-        let mut editor = editor_m.lock().unwrap();
-
         let mut rng = rand::thread_rng();
         let random_number_800 = rng.gen_range(0..=800);
         let random_number_450 = rng.gen_range(0..=450);
@@ -391,19 +613,14 @@ pub fn Project() -> impl IntoView {
 
         let polygon_config = PolygonConfig {
             id: new_id.clone(),
-            name: "Square".to_string(),
-            points: vec![
-                Point { x: 0.0, y: 0.0 },
-                Point { x: 1.0, y: 0.0 },
-                Point { x: 1.0, y: 1.0 },
-                Point { x: 0.0, y: 1.0 },
-            ],
-            dimensions: (100.0, 100.0),
+            name: shape_label(&shape).to_string(),
+            points: shape_points(&shape),
+            dimensions: shape_dimensions(&shape),
             position: Point {
                 x: random_number_800 as f32,
                 y: random_number_450 as f32,
             },
-            border_radius: 0.0,
+            border_radius: shape_border_radius(&shape),
             fill: [1.0, 1.0, 1.0, 1.0],
             stroke: Stroke {
                 fill: [1.0, 1.0, 1.0, 1.0],
@@ -412,77 +629,19 @@ pub fn Project() -> impl IntoView {
             layer: -2,
         };
 
-        editor.add_polygon(
-            polygon_config.clone(),
-            "Polygon".to_string(),
-            new_id,
-            sequence_id.clone(),
-        );
-
-        drop(editor);
-
-        let mut editor_state = editor_state.lock().unwrap();
-
-        editor_state.add_saved_polygon(
-            sequence_id.clone(),
-            SavedPolygonConfig {
-                id: polygon_config.id.to_string().clone(),
-                name: polygon_config.name.clone(),
-                dimensions: (
-                    polygon_config.dimensions.0 as i32,
-                    polygon_config.dimensions.1 as i32,
-                ),
-                fill: [
-                    polygon_config.fill[0] as i32,
-                    polygon_config.fill[1] as i32,
-                    polygon_config.fill[2] as i32,
-                    polygon_config.fill[3] as i32,
-                ],
-                border_radius: polygon_config.border_radius as i32,
-                position: SavedPoint {
-                    x: polygon_config.position.x as i32,
-                    y: polygon_config.position.y as i32,
-                },
-                stroke: SavedStroke {
-                    thickness: polygon_config.stroke.thickness as i32,
-                    fill: [
-                        polygon_config.stroke.fill[0] as i32,
-                        polygon_config.stroke.fill[1] as i32,
-                        polygon_config.stroke.fill[2] as i32,
-                        polygon_config.stroke.fill[3] as i32,
-                    ],
-                },
-                layer: polygon_config.layer.clone(),
-            },
+        let updated_sequences = add_polygon_to_sequence(
+            editor_m,
+            editor_state,
+            sequence_id,
+            polygon_config,
+            Some(shape),
         );
 
-        let saved_state = editor_state
-            .record_state
-            .saved_state
-            .as_ref()
-            .expect("Couldn't get saved state");
-
-        let updated_sequence = saved_state
-            .sequences
-            .iter()
-            .find(|s| s.id == sequence_id.clone())
-            .expect("Couldn't get updated sequence");
-
-        let sequence_cloned = updated_sequence.clone();
-
-        sequences.set(saved_state.sequences.clone());
-
-        drop(editor_state);
-
-        let mut editor = editor_m.lock().unwrap();
-
-        editor.current_sequence_data = Some(sequence_cloned.clone());
-
-        editor.update_motion_paths(&sequence_cloned);
+        sequences.set(updated_sequences);
 
-        drop(editor);
+        canvas_renderer.redraw.request();
 
-        info!("Square added!");
+        info!("Shape added!");
     };
 
     let on_add_text = move |sequence_id: String| {
@@ -518,6 +677,9 @@ pub fn Project() -> impl IntoView {
             color: [20, 20, 200, 255],
             font_size: 28,
             background_fill: [200, 200, 200, 255],
+            line_height: DEFAULT_LINE_HEIGHT,
+            letter_spacing: DEFAULT_LETTER_SPACING,
+            text_alignment: TextAlignment::Left,
         };
 
         editor.add_text_item(
@@ -549,6 +711,9 @@ pub fn Project() -> impl IntoView {
                 color: text_config.color.clone(),
                 font_size: text_config.font_size.clone(),
                 background_fill: Some(text_config.background_fill),
+                line_height: Some(text_config.line_height),
+                letter_spacing: Some(text_config.letter_spacing),
+                text_alignment: Some(text_config.text_alignment),
             },
         );
 
@@ -567,6 +732,10 @@ pub fn Project() -> impl IntoView {
 
         sequences.set(saved_state.sequences.clone());
 
+        // straight into the text editor, so the placeholder can be typed over
+        editor_state.select_object(new_id, ObjectType::TextItem);
+        selection.set(editor_state.selected_objects.clone());
+
         drop(editor_state);
 
         let mut editor = editor_m.lock().unwrap();
@@ -575,6 +744,10 @@ pub fn Project() -> impl IntoView {
         editor.update_motion_paths(&sequence_cloned);
 
         drop(editor);
+
+        canvas_renderer.redraw.request();
+
+        editing_text.set(Some(new_id));
     };
 
     let on_add_image = move |sequence_id: String| {
@@ -603,6 +776,9 @@ pub fn Project() -> impl IntoView {
 
                         info!("File url: {:?}", url);
 
+                        // restoring later won't need to download it again
+                        remember_image(url.clone(), file_data.clone());
+
                         let canvas_renderer = canvas_renderer.lock().unwrap();
                         let editor_m = canvas_renderer.editor.clone();
 
@@ -685,6 +861,8 @@ pub fn Project() -> impl IntoView {
 
                         drop(editor);
 
+                        canvas_renderer.redraw.request();
+
                         info!("Image added!");
                     }
                 }
@@ -692,13 +870,372 @@ pub fn Project() -> impl IntoView {
         });
     };
 
-    let on_add_video = move |sequence_id: String| {};
+    let on_add_video = move |sequence_id: String| {
+        let auth_state = auth_state.get_untracked();
+        let renderer = renderer.get().expect("Couldn't get renderer");
+        let (canvas_renderer, editor_state) = renderer.take();
+
+        spawn_local({
+            async move {
+                if let Some(file) = AsyncFileDialog::new()
+                    .add_filter("videos", &["mp4", "webm"])
+                    .pick_file()
+                    .await
+                {
+                    let file_name = file.file_name();
+                    info!("File name: {:?}", file_name);
+
+                    let file_data = file.read().await;
+
+                    set_video_error.set(None);
+
+                    // check the file plays before uploading it
+                    let probe = match probe_video(&file_data, video_mime_type(&file_name)).await {
+                        Ok(probe) => probe,
+                        Err(e) => {
+                            log::error!("Video probe failed: {}", e);
+                            set_video_error.set(Some(e));
+                            return;
+                        }
+                    };
+
+                    info!("Video duration: {:?}ms", probe.duration_ms);
+
+                    set_video_preview.set(Some(probe.preview_url.clone()));
+
+                    let save_response =
+                        save_video(auth_state.token, file_name, file_data.clone()).await;
+
+                    let Some(response) = save_response else {
+                        set_video_preview.set(None);
+                        set_video_error.set(Some("Couldn't upload video".to_string()));
+                        return;
+                    };
+
+                    let canvas_renderer = canvas_renderer.lock().unwrap();
+
+                    let added = add_video_to_sequence(
+                        canvas_renderer.editor.clone(),
+                        editor_state.clone(),
+                        sequence_id.clone(),
+                        response.url,
+                        &file_data,
+                        &probe,
+                        None,
+                    );
+
+                    match added {
+                        Some((_, updated_sequences)) => {
+                            sequences.set(updated_sequences);
+                            info!("Video added!");
+                        }
+                        None => set_video_error.set(Some("Couldn't add video".to_string())),
+                    }
+
+                    set_video_preview.set(None);
+
+                    canvas_renderer.redraw.request();
+                }
+            }
+        });
+    };
+
+    // uploads a finished capture and drops it into the sequence it was started from
+    let on_stop_capture = Callback::new(move |_: ()| {
+        let Some((recorder, sequence_id)) = capture_recorder
+            .try_update_value(|recorder| recorder.take())
+            .flatten()
+        else {
+            return;
+        };
+
+        set_capturing.set(false);
+
+        let token = auth_state.get_untracked().token;
+        let renderer = renderer.get_untracked().expect("Couldn't get renderer");
+        let (canvas_renderer, editor_state) = renderer.take();
+
+        spawn_local(async move {
+            let capture = match recorder.stop().await {
+                Ok(capture) => capture,
+                Err(e) => {
+                    log::error!("Screen capture failed: {}", e);
+                    set_video_error.set(Some(e));
+                    return;
+                }
+            };
+
+            let probe = match probe_video(&capture.file_data, &capture.mime_type).await {
+                Ok(probe) => probe,
+                Err(e) => {
+                    log::error!("Capture probe failed: {}", e);
+                    set_video_error.set(Some(e));
+                    return;
+                }
+            };
+
+            set_video_preview.set(Some(probe.preview_url.clone()));
+
+            let capture_id = Uuid::new_v4().to_string();
+
+            let Some(video_response) = save_video(
+                token.clone(),
+                format!("capture-{}.webm", capture_id),
+                capture.file_data.clone(),
+            )
+            .await
+            else {
+                set_video_preview.set(None);
+                set_video_error.set(Some("Couldn't upload capture".to_string()));
+                return;
+            };
+
+            // pointer data sits in one folder per capture, see capture_file_path
+            let mouse_path = save_media_data(
+                token.clone(),
+                format!("{}/mousePositions.json", capture_id),
+                &capture.mouse_positions,
+            )
+            .await
+            .map(|response| response.url);
+            save_media_data(
+                token.clone(),
+                format!("{}/sourceData.json", capture_id),
+                &capture.source_data,
+            )
+            .await;
+            save_media_data(
+                token,
+                format!("{}/clicks.json", capture_id),
+                &capture.clicks,
+            )
+            .await;
+
+            let canvas_renderer = canvas_renderer.lock().unwrap();
+            let editor_m = canvas_renderer.editor.clone();
+
+            let added = add_video_to_sequence(
+                editor_m.clone(),
+                editor_state.clone(),
+                sequence_id.clone(),
+                video_response.url,
+                &capture.file_data,
+                &probe,
+                mouse_path,
+            );
+
+            set_video_preview.set(None);
+
+            let Some((video_id, updated_sequences)) = added else {
+                set_video_error.set(Some("Couldn't add capture".to_string()));
+                return;
+            };
+
+            {
+                let mut editor = editor_m.lock().unwrap();
+
+                if let Some(video) = editor
+                    .video_items
+                    .iter_mut()
+                    .find(|v| v.id.to_string() == video_id.to_string())
+                {
+                    video.mouse_positions = Some(capture.mouse_positions.clone());
+                    video.source_data = Some(capture.source_data.clone());
+                }
+            }
+
+            // nothing to follow when another window was captured, keep the default zoom
+            if capture.mouse_positions.is_empty() {
+                sequences.set(updated_sequences);
+                canvas_renderer.redraw.request();
+                return;
+            }
+
+            let keyframes = generate_zoom_keyframes(
+                &capture.mouse_positions,
+                &capture.clicks,
+                probe.duration_ms,
+                (capture.source_data.width, capture.source_data.height),
+                &AutoZoomSettings::default(),
+            );
+
+            let mut editor_state = editor_state.lock().unwrap();
+            editor_state.replace_property_keyframes(
+                sequence_id.clone(),
+                video_id.to_string(),
+                "zoom",
+                "Zoom / Popout",
+                keyframes.zoom,
+            );
+            editor_state.replace_property_keyframes(
+                sequence_id.clone(),
+                video_id.to_string(),
+                "zoom_focus",
+                "Zoom Focus",
+                keyframes.focus,
+            );
+
+            let saved_state = editor_state
+                .record_state
+                .saved_state
+                .as_ref()
+                .expect("Couldn't get saved state");
+            let updated_sequence = saved_state
+                .sequences
+                .iter()
+                .find(|s| s.id == sequence_id.clone())
+                .expect("Couldn't get updated sequence")
+                .clone();
+
+            sequences.set(saved_state.sequences.clone());
+
+            drop(editor_state);
+
+            let mut editor = editor_m.lock().unwrap();
+
+            editor.current_sequence_data = Some(updated_sequence.clone());
+            editor.update_motion_paths(&updated_sequence);
+
+            drop(editor);
+
+            canvas_renderer.redraw.request();
+
+            info!("Capture added!");
+        });
+    });
+
+    let on_open_capture = move |sequence_id: String| {
+        if capturing.get_untracked() {
+            return;
+        }
+
+        set_video_error.set(None);
 
-    let on_open_capture = move |sequence_id: String| {};
+        spawn_local(async move {
+            // the browser's own "stop sharing" button ends up here too
+            match ScreenRecorder::start(move || on_stop_capture.run(())).await {
+                Ok(recorder) => {
+                    capture_recorder.set_value(Some((recorder, sequence_id)));
+                    set_capturing.set(true);
+                }
+                Err(e) => {
+                    log::error!("Couldn't start screen capture: {}", e);
+                    set_video_error.set(Some(e));
+                }
+            }
+        });
+    };
 
     let on_items_updated = move || {};
-    let on_item_duplicated = move |object_id: Uuid, object_type: ObjectType| {};
-    let on_item_deleted = move |object_id: Uuid, object_type: ObjectType| {};
+    let on_item_duplicated = move |object_id: Uuid, object_type: ObjectType| {
+        let Sections::SequenceView(sequence_id) = section.get_untracked() else {
+            return;
+        };
+
+        let renderer = renderer.get_untracked().expect("Couldn't get renderer");
+        let (canvas_renderer, editor_state) = renderer.take();
+        let editor_m = canvas_renderer.lock().unwrap().editor.clone();
+
+        let copied = {
+            let editor_state = editor_state.lock().unwrap();
+            let saved_state = editor_state
+                .record_state
+                .saved_state
+                .as_ref()
+                .expect("Couldn't get saved state");
+
+            saved_state
+                .sequences
+                .iter()
+                .find(|s| s.id == sequence_id)
+                .map(|sequence| copy_objects(sequence, &[(object_id, object_type)]))
+                .unwrap_or_default()
+        };
+
+        let token = auth_state.get_untracked().token;
+
+        spawn_local(async move {
+            let result = paste_objects(
+                editor_m,
+                editor_state.clone(),
+                sequence_id,
+                copied,
+                COPY_OFFSET,
+                token,
+            )
+            .await;
+
+            if let Err(e) = result {
+                log::error!("Couldn't duplicate object: {}", e);
+                return;
+            }
+
+            let editor_state = editor_state.lock().unwrap();
+            let saved_state = editor_state
+                .record_state
+                .saved_state
+                .as_ref()
+                .expect("Couldn't get saved state");
+
+            sequences.set(saved_state.sequences.clone());
+            selection.set(editor_state.selected_objects.clone());
+
+            drop(editor_state);
+
+            canvas_renderer.lock().unwrap().redraw.request();
+        });
+    };
+    let on_item_deleted = move |object_id: Uuid, object_type: ObjectType| {
+        let Sections::SequenceView(sequence_id) = section.get_untracked() else {
+            return;
+        };
+
+        let renderer = renderer.get_untracked().expect("Couldn't get renderer");
+        let (canvas_renderer, editor_state) = renderer.take();
+
+        let mut editor_state = editor_state.lock().unwrap();
+
+        if let Err(e) = editor_state.delete_objects(sequence_id, vec![(object_id, object_type)]) {
+            log::error!("Couldn't delete object: {}", e);
+            return;
+        }
+
+        let saved_state = editor_state
+            .record_state
+            .saved_state
+            .as_ref()
+            .expect("Couldn't get saved state");
+
+        sequences.set(saved_state.sequences.clone());
+        selection.set(editor_state.selected_objects.clone());
+
+        drop(editor_state);
+
+        canvas_renderer.lock().unwrap().redraw.request();
+    };
+    let on_item_selected = move |object_id: Uuid, object_type: ObjectType, additive: bool| {
+        let renderer = renderer.get_untracked().expect("Couldn't get renderer");
+        let (_, editor_state) = renderer.take();
+
+        let mut editor_state = editor_state.lock().unwrap();
+
+        if additive {
+            editor_state.toggle_selected(object_id, object_type);
+        } else {
+            editor_state.select_object(object_id, object_type);
+        }
+
+        selection.set(editor_state.selected_objects.clone());
+    };
+    let on_group_selected = move |objects: Vec<(Uuid, ObjectType)>| {
+        let renderer = renderer.get_untracked().expect("Couldn't get renderer");
+        let (_, editor_state) = renderer.take();
+
+        let mut editor_state = editor_state.lock().unwrap();
+        editor_state.set_selection(objects);
+
+        selection.set(editor_state.selected_objects.clone());
+    };
 
     let aside_width = 260.0;
     let quarters = (aside_width / 4.0) + (5.0 * 4.0);
@@ -947,16 +1484,29 @@ pub fn Project() -> impl IntoView {
                                                     }}
                                                 </button>
                                                 <div class="flex flex-row flex-wrap gap-2">
-                                                    <OptionButton
-                                                        style="".to_string()
-                                                        label="Add Square".to_string()
-                                                        icon="square".to_string()
-                                                        callback=Box::new({
-                                                            let sequence_id = sequence_id.clone();
-                                                            move || {
-                                                                on_add_square(sequence_id.clone());
+                                                    {shape_library()
+                                                        .into_iter()
+                                                        .map(|shape| {
+                                                            view! {
+                                                                <OptionButton
+                                                                    style="".to_string()
+                                                                    label=format!("Add {}", shape_label(&shape))
+                                                                    icon=shape_icon(&shape).to_string()
+                                                                    callback=Box::new({
+                                                                        let sequence_id = sequence_id.clone();
+                                                                        move || {
+                                                                            on_add_shape(sequence_id.clone(), shape.clone());
+                                                                        }
+                                                                    })
+                                                                />
                                                             }
                                                         })
+                                                        .collect_view()}
+                                                    <OptionButton
+                                                        style="".to_string()
+                                                        label="Pen".to_string()
+                                                        icon="brush".to_string()
+                                                        callback=Box::new(move || pen_mode.set(PenMode::Draw))
                                                     />
                                                     <OptionButton
                                                         style="".to_string()
@@ -1003,6 +1553,43 @@ pub fn Project() -> impl IntoView {
                                                         })
                                                     />
                                                 </div>
+                                                {move || {
+                                                    capturing
+                                                        .get()
+                                                        .then(|| {
+                                                            view! {
+                                                                <div class="flex flex-row gap-2 items-center">
+                                                                    <span class="w-2 h-2 rounded-full bg-red-500"></span>
+                                                                    <span class="text-xs">"Recording..."</span>
+                                                                    <button
+                                                                        class="text-xs rounded-md border px-2 py-1 hover:bg-gray-200"
+                                                                        on:click=move |_| on_stop_capture.run(())
+                                                                    >
+                                                                        "Stop Capture"
+                                                                    </button>
+                                                                </div>
+                                                            }
+                                                        })
+                                                }}
+                                                {move || {
+                                                    video_preview
+                                                        .get()
+                                                        .map(|preview| {
+                                                            view! {
+                                                                <div class="flex flex-row gap-2 items-center">
+                                                                    <img src=preview class="w-20 rounded border" />
+                                                                    <span class="text-xs">"Uploading video..."</span>
+                                                                </div>
+                                                            }
+                                                        })
+                                                }}
+                                                {move || {
+                                                    video_error
+                                                        .get()
+                                                        .map(|err| {
+                                                            view! { <div class="text-red-500 text-xs">{err}</div> }
+                                                        })
+                                                }}
                                                 <div class="flex flex-row flex-wrap gap-2">
                                                     {themes
                                                         .into_iter()
@@ -1043,46 +1630,210 @@ pub fn Project() -> impl IntoView {
                                                         })
                                                         .collect_view()}
                                                 </div>
-                                                <label class="text-sm">"Background Color"</label>
-                                                <div class="flex flex-row gap-2">
-                                                    <DebouncedInput
-                                                        id="background_red".to_string()
-                                                        label="Red".to_string()
-                                                        placeholder="Red".to_string()
-                                                    />
-                                                    <DebouncedInput
-                                                        id="background_green".to_string()
-                                                        label="Green".to_string()
-                                                        placeholder="Green".to_string()
-                                                    />
-                                                    <DebouncedInput
-                                                        id="background_blue".to_string()
-                                                        label="Blue".to_string()
-                                                        placeholder="Blue".to_string()
-                                                    />
-                                                </div>
+                                                <BackgroundPanel renderer sequence_id=sequence_id.clone() />
                                             </div>
                                         </div>
                                         <div class="flex max-w-[315px] w-full max-h-[50vh] overflow-y-scroll overflow-x-hidden p-4 border-0 rounded-[15px] shadow-[0_0_15px_4px_rgba(0,0,0,0.16)]">
                                             <LayerPanel
                                                 // renderer
+                                                sequence_id=sequence_id.clone()
                                                 layers
                                                 dragger_id
                                                 on_items_updated
                                                 on_item_duplicated
                                                 on_item_deleted
+                                                on_item_selected
+                                                on_group_selected
+                                                selection
+                                            />
+                                        </div>
+                                        <div class="flex max-w-[315px] w-full p-4 border-0 rounded-[15px] shadow-[0_0_15px_4px_rgba(0,0,0,0.16)]">
+                                            <PropertyInspector
+                                                renderer
+                                                sequence_id=sequence_id.clone()
+                                                selection
+                                            />
+                                        </div>
+                                        <div class="flex max-w-[315px] w-full p-4 border-0 rounded-[15px] shadow-[0_0_15px_4px_rgba(0,0,0,0.16)]">
+                                            <ArrangePanel
+                                                renderer
+                                                sequence_id=sequence_id.clone()
+                                                selection
+                                                snapping
                                             />
                                         </div>
+                                        <div class="flex max-w-[315px] w-full p-4 border-0 rounded-[15px] shadow-[0_0_15px_4px_rgba(0,0,0,0.16)]">
+                                            <GroupPanel
+                                                renderer
+                                                sequence_id=sequence_id.clone()
+                                                selection
+                                            />
+                                        </div>
+                                        <div class="flex max-w-[315px] w-full p-4 border-0 rounded-[15px] shadow-[0_0_15px_4px_rgba(0,0,0,0.16)]">
+                                            <HistoryPanel
+                                                renderer
+                                                project_id=project_id()
+                                                sequence_id=sequence_id.clone()
+                                            />
+                                        </div>
+                                        <div class="flex max-w-[315px] w-full p-4 border-0 rounded-[15px] shadow-[0_0_15px_4px_rgba(0,0,0,0.16)]">
+                                            <ExportPanel renderer sequence_id=sequence_id.clone() />
+                                        </div>
+                                        <div class="flex max-w-[315px] w-full p-4 border-0 rounded-[15px] shadow-[0_0_15px_4px_rgba(0,0,0,0.16)]">
+                                            <AutoZoomPanel renderer sequence_id=sequence_id.clone() />
+                                        </div>
+                                        <div class="flex max-w-[315px] w-full p-4 border-0 rounded-[15px] shadow-[0_0_15px_4px_rgba(0,0,0,0.16)]">
+                                            <ShapePanel renderer sequence_id=sequence_id.clone() pen_mode />
+                                        </div>
                                     </div>
                                 }
                                     .into_any()
                             }
                         }
                     }} <div>
-                        <canvas id="scene-canvas" class="w-[900px] h-[450px] border border-black" />
+                        {move || {
+                            restore_progress
+                                .get()
+                                .map(|progress| {
+                                    view! {
+                                        <div class="flex flex-col gap-1 mb-2 w-[900px]">
+                                            <span class="text-xs">
+                                                {format!(
+                                                    "Loading project... {} of {} ({})",
+                                                    progress.loaded,
+                                                    progress.total,
+                                                    progress.current,
+                                                )}
+                                            </span>
+                                            <div class="w-full h-2 rounded bg-gray-200">
+                                                <div
+                                                    class="h-2 rounded stunts-gradient"
+                                                    style:width=format!("{:.0}%", progress.fraction() * 100.0)
+                                                ></div>
+                                            </div>
+                                        </div>
+                                    }
+                                })
+                        }}
+                        <Show when=move || !restore_warnings.get().is_empty()>
+                            <div class="flex flex-col gap-1 mb-2 p-2 w-[900px] rounded border border-yellow-400 bg-yellow-50">
+                                <div class="flex flex-row justify-between items-center">
+                                    <span class="text-xs">
+                                        "Some objects couldn't be restored and were skipped:"
+                                    </span>
+                                    <button
+                                        class="text-xs px-2 rounded hover:bg-gray-200"
+                                        on:click=move |_| restore_warnings.set(Vec::new())
+                                    >
+                                        "Dismiss"
+                                    </button>
+                                </div>
+                                <ul class="text-xs list-disc pl-4">
+                                    {move || {
+                                        restore_warnings
+                                            .get()
+                                            .into_iter()
+                                            .map(|warning| view! { <li>{warning}</li> })
+                                            .collect_view()
+                                    }}
+                                </ul>
+                            </div>
+                        </Show>
+                        <div class="relative w-[900px] h-[450px]">
+                            <canvas id="scene-canvas" class="w-[900px] h-[450px] border border-black" />
+                            {move || match section.get() {
+                                Sections::SequenceView(sequence_id) => {
+                                    Some(
+                                        view! {
+                                            <SelectionOverlay
+                                                renderer
+                                                sequence_id=sequence_id.clone()
+                                                pen_mode
+                                                selection
+                                                snapping
+                                                editing_text
+                                            />
+                                            <TextEditor
+                                                renderer
+                                                sequence_id=sequence_id.clone()
+                                                selection
+                                                editing_text
+                                            />
+                                            <PenOverlay
+                                                renderer
+                                                sequence_id=sequence_id.clone()
+                                                pen_mode
+                                            />
+                                            <EditorShortcuts renderer sequence_id pen_mode selection />
+                                        },
+                                    )
+                                }
+                                Sections::SequenceList => None,
+                            }}
+                        </div>
                     </div>
                 </div>
             </div>
         </ErrorBoundary>
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use leptos::mount::mount_to;
+    use leptos::prelude::*;
+    use wasm_bindgen::JsValue;
+    use wasm_bindgen_test::*;
+
+    use crate::canvas_renderer::active_render_loops;
+    use crate::helpers::video_export::sleep_ms;
+    use crate::App;
+
+    // needs a browser with WebGPU, e.g. `wasm-pack test --chrome`
+    wasm_bindgen_test_configure!(run_in_browser);
+
+    fn open_project_route() {
+        window()
+            .history()
+            .expect("Couldn't get history")
+            .replace_state_with_url(&JsValue::NULL, "", Some("/project/test-project"))
+            .expect("Couldn't set url");
+    }
+
+    async fn wait_for_render_loops(count: usize) -> bool {
+        for _ in 0..100 {
+            if active_render_loops() == count {
+                return true;
+            }
+            sleep_ms(50).await;
+        }
+
+        false
+    }
+
+    #[wasm_bindgen_test]
+    async fn unmounting_the_project_stops_its_render_loop() {
+        open_project_route();
+
+        let handle = mount_to(document().body().expect("Couldn't get body"), App);
+        assert!(wait_for_render_loops(1).await, "Renderer never started");
+
+        drop(handle);
+        assert_eq!(active_render_loops(), 0);
+    }
+
+    #[wasm_bindgen_test]
+    async fn unmounting_before_the_renderer_is_ready_starts_no_loop() {
+        open_project_route();
+
+        let handle = mount_to(document().body().expect("Couldn't get body"), App);
+        // lets the renderer find the canvas and start waiting on the gpu
+        sleep_ms(0).await;
+
+        drop(handle);
+
+        // long enough for the gpu device to come back
+        assert!(!wait_for_render_loops(1).await);
+        assert_eq!(active_render_loops(), 0);
+    }
+}
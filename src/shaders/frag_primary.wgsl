@@ -1,6 +1,12 @@
 @group(1) @binding(1) var texture: texture_2d<f32>;
 @group(1) @binding(2) var texture_sampler: sampler;
 
+struct GroupStyle {
+    opacity: f32,
+};
+
+@group(4) @binding(0) var<uniform> group_style: GroupStyle;
+
 struct FragmentInput {
     @location(0) tex_coords: vec2<f32>,
     @location(1) color: vec4<f32>,
@@ -9,5 +15,6 @@ struct FragmentInput {
 @fragment
 fn fs_main(in: FragmentInput) -> @location(0) vec4<f32> {
     let tex_color = textureSample(texture, texture_sampler, in.tex_coords);
-    return tex_color * in.color;
-}
\ No newline at end of file
+    // fade whole groups without touching their children's colors
+    return tex_color * in.color * vec4<f32>(1.0, 1.0, 1.0, group_style.opacity);
+}